@@ -1,32 +1,44 @@
 use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
-use kimchi::bench::BenchmarkCtx;
+use kimchi::bench::{costs::OpeningAggregation, BenchmarkCtx};
 
 const PROOFS: usize = 10;
 
 pub fn amortization(c: &mut Criterion) {
-    let mut group = c.benchmark_group("amortization");
-
     let ctx = BenchmarkCtx::new(16);
     let proof_and_public = ctx.create_proof();
     let proofs: Vec<_> = std::iter::repeat(proof_and_public)
         .take(1 << PROOFS)
         .collect();
 
-    group.sample_size(10);
-    for size in 0..=PROOFS {
-        group.throughput(criterion::Throughput::Elements(1 << size));
-        group.bench_with_input(
-            BenchmarkId::from_parameter(format!("2^{size}")),
-            &(),
-            |b, _| {
-                b.iter_batched(
-                    || &proofs[0..(1 << size)],
-                    |input| ctx.batch_verification(black_box(input)),
-                    BatchSize::SmallInput,
-                );
-            },
-        );
+    // the same proofs, verified with their openings aggregated, and one by one, each paying for its own opening
+    for (name, aggregation) in [
+        ("amortization", OpeningAggregation::Batched),
+        ("no amortization", OpeningAggregation::PerProof),
+    ] {
+        let mut group = c.benchmark_group(name);
+        group.sample_size(10);
+        for size in 0..=PROOFS {
+            group.throughput(criterion::Throughput::Elements(1 << size));
+            group.bench_with_input(
+                BenchmarkId::from_parameter(format!("2^{size}")),
+                &(),
+                |b, _| {
+                    b.iter_batched(
+                        || &proofs[0..(1 << size)],
+                        |input| ctx.verify_with(black_box(input), aggregation),
+                        BatchSize::SmallInput,
+                    );
+                },
+            );
+        }
+        group.finish();
     }
+
+    let report = ctx.aggregation_report(&proofs);
+    println!(
+        "{} proofs of {} bytes: verified in {:?} one by one, and in {:?} with their openings aggregated",
+        report.proofs, report.proof_size, report.per_proof, report.batched
+    );
 }
 
 criterion_group!(benches, amortization);
@@ -0,0 +1,260 @@
+//! A/B comparisons of the proving time of two configurations.
+//!
+//! A difference of a few percent between two runs is often noise: the machine warms up, throttles,
+//! or gets busy with something else. The two configurations are therefore run interleaved,
+//! in a random order within each round so that neither benefits from a drift of the machine's state,
+//! and the difference is only reported as significant if two tests agree on it:
+//! a Mann-Whitney U test, which makes no assumption on the distributions of the timings,
+//! and a bootstrap confidence interval of the difference of the medians, which must exclude zero.
+
+use std::time::Instant;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+
+use super::BenchmarkCtx;
+
+/// The p-value (and one minus the confidence of the bootstrap interval) below which a difference is significant.
+pub const SIGNIFICANCE: f64 = 0.05;
+
+/// The number of resamplings of the bootstrap.
+pub const BOOTSTRAP_RESAMPLES: usize = 2000;
+
+fn median(sorted: &[f64]) -> f64 {
+    let n = sorted.len();
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+    }
+}
+
+fn sorted(samples: &[f64]) -> Vec<f64> {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    sorted
+}
+
+/// The complementary error function, with a fractional error below `1.2e-7` (Numerical Recipes' `erfcc`).
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.5 * x.abs());
+    let poly = [
+        -1.26551223,
+        1.00002368,
+        0.37409196,
+        0.09678418,
+        -0.18628806,
+        0.27886807,
+        -1.13520398,
+        1.48851587,
+        -0.82215223,
+        0.17087277,
+    ]
+    .iter()
+    .rev()
+    .fold(0.0, |acc, c| c + t * acc);
+    let erfc = t * (-x * x + poly).exp();
+    if x >= 0.0 {
+        erfc
+    } else {
+        2.0 - erfc
+    }
+}
+
+/// The Mann-Whitney U statistic of the samples `a` against `b`, and its two-sided p-value,
+/// from the normal approximation with a continuity and tie correction.
+pub fn mann_whitney(a: &[f64], b: &[f64]) -> (f64, f64) {
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let n = na + nb;
+    let mut all: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&x| (x, true))
+        .chain(b.iter().map(|&x| (x, false)))
+        .collect();
+    all.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    // tied values share the average of their ranks
+    let mut rank_sum = 0.0;
+    let mut ties = 0.0;
+    let mut start = 0;
+    while start < all.len() {
+        let end = start
+            + all[start..]
+                .iter()
+                .take_while(|x| x.0 == all[start].0)
+                .count();
+        let rank = (start + end + 1) as f64 / 2.0;
+        rank_sum += rank * all[start..end].iter().filter(|x| x.1).count() as f64;
+        let t = (end - start) as f64;
+        ties += t * t * t - t;
+        start = end;
+    }
+
+    let u = rank_sum - na * (na + 1.0) / 2.0;
+    let mean = na * nb / 2.0;
+    let variance = na * nb / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return (u, 1.0);
+    }
+    let z = ((u - mean).abs() - 0.5).max(0.0) / variance.sqrt();
+    (u, erfc(z / std::f64::consts::SQRT_2))
+}
+
+/// A bootstrap confidence interval, at `1 - SIGNIFICANCE`, of the median of `b` minus the median of `a`.
+pub fn bootstrap_median_difference(a: &[f64], b: &[f64], rng: &mut impl Rng) -> (f64, f64) {
+    let mut resample = |samples: &[f64]| {
+        let resampled: Vec<f64> = (0..samples.len())
+            .map(|_| samples[rng.gen_range(0..samples.len())])
+            .collect();
+        median(&sorted(&resampled))
+    };
+    let differences: Vec<f64> = (0..BOOTSTRAP_RESAMPLES)
+        .map(|_| resample(b) - resample(a))
+        .collect();
+    let differences = sorted(&differences);
+    let percentile = |p: f64| {
+        differences[((p * BOOTSTRAP_RESAMPLES as f64) as usize).min(BOOTSTRAP_RESAMPLES - 1)]
+    };
+    (
+        percentile(SIGNIFICANCE / 2.0),
+        percentile(1.0 - SIGNIFICANCE / 2.0),
+    )
+}
+
+/// The result of an A/B comparison.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct AbReport {
+    /// The number of measurements of each configuration.
+    pub samples: (usize, usize),
+    /// The median duration of each configuration, in nanoseconds.
+    pub median_ns: (f64, f64),
+    /// The Mann-Whitney U statistic of the first configuration.
+    pub u_statistic: f64,
+    pub p_value: f64,
+    /// The confidence interval of the second median minus the first one, in nanoseconds.
+    pub difference_ci_ns: (f64, f64),
+}
+
+impl AbReport {
+    /// Tests the durations, in nanoseconds, measured for each configuration.
+    /// The bootstrap is seeded with `seed`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if a configuration has no measurements.
+    pub fn new(a: &[f64], b: &[f64], seed: u64) -> Self {
+        assert!(
+            !a.is_empty() && !b.is_empty(),
+            "a configuration has no measurements"
+        );
+        let (u_statistic, p_value) = mann_whitney(a, b);
+        let mut rng = StdRng::seed_from_u64(seed);
+        AbReport {
+            samples: (a.len(), b.len()),
+            median_ns: (median(&sorted(a)), median(&sorted(b))),
+            u_statistic,
+            p_value,
+            difference_ci_ns: bootstrap_median_difference(a, b, &mut rng),
+        }
+    }
+
+    /// Returns whether the two configurations differ significantly, according to both tests.
+    pub fn significant(&self) -> bool {
+        let (low, high) = self.difference_ci_ns;
+        self.p_value < SIGNIFICANCE && (low > 0.0 || high < 0.0)
+    }
+}
+
+/// Times `a` and `b` once per round for `rounds` rounds, in a random order within each round,
+/// and returns the durations of each in nanoseconds.
+pub fn interleave<A, B>(
+    rounds: usize,
+    mut a: A,
+    mut b: B,
+    rng: &mut impl Rng,
+) -> (Vec<f64>, Vec<f64>)
+where
+    A: FnMut(),
+    B: FnMut(),
+{
+    let mut times = (Vec::with_capacity(rounds), Vec::with_capacity(rounds));
+    let mut time = |op: &mut dyn FnMut()| {
+        let start = Instant::now();
+        op();
+        start.elapsed().as_nanos() as f64
+    };
+    for _ in 0..rounds {
+        if rng.gen() {
+            times.0.push(time(&mut a));
+            times.1.push(time(&mut b));
+        } else {
+            times.1.push(time(&mut b));
+            times.0.push(time(&mut a));
+        }
+    }
+    times
+}
+
+/// A configuration of the benchmark circuit.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AbConfig {
+    pub srs_size_log2: u32,
+    /// The number of threads of the prover, or 0 for the default of rayon.
+    pub threads: usize,
+}
+
+/// Compares the proving time of the benchmark circuit in two configurations, over `rounds` interleaved proofs of each.
+/// The order of the rounds and the bootstrap are seeded with `seed`.
+pub fn ab_test(a: AbConfig, b: AbConfig, rounds: usize, seed: u64) -> AbReport {
+    let setup = |config: AbConfig| {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(config.threads)
+            .build()
+            .expect("failed to create the thread pool");
+        let ctx = pool.install(|| BenchmarkCtx::new(config.srs_size_log2));
+        (pool, ctx)
+    };
+    let (pool_a, ctx_a) = setup(a);
+    let (pool_b, ctx_b) = setup(b);
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (times_a, times_b) = interleave(
+        rounds,
+        || {
+            pool_a.install(|| ctx_a.create_proof());
+        },
+        || {
+            pool_b.install(|| ctx_b.create_proof());
+        },
+        &mut rng,
+    );
+    AbReport::new(&times_a, &times_b, seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ab_significance() {
+        let a: Vec<f64> = (0..40).map(|i| 1000.0 + (i * 37 % 40) as f64).collect();
+
+        // the same distribution, in another order
+        let mut b = a.clone();
+        b.reverse();
+        let report = AbReport::new(&a, &b, 0);
+        assert!(report.p_value > 0.9, "{report:?}");
+        assert!(!report.significant(), "{report:?}");
+
+        // every measurement of the second configuration is slower
+        let b: Vec<f64> = a.iter().map(|x| x + 50.0).collect();
+        let report = AbReport::new(&a, &b, 0);
+        assert!(report.significant(), "{report:?}");
+        assert!(report.difference_ci_ns.0 > 0.0);
+        assert_eq!(report.u_statistic, 0.0);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let (a, b) = interleave(5, || {}, || {}, &mut rng);
+        assert_eq!((a.len(), b.len()), (5, 5));
+    }
+}
@@ -0,0 +1,124 @@
+//! The baseline backend of a benchmark: integrity without zero knowledge.
+//!
+//! The baseline publishes a Blake2b digest of the weights, the inputs and the outputs of an inference,
+//! and verifying it recomputes the digest from the revealed values.
+//! It hides nothing, and only binds the values together without proving that the outputs were computed from the inputs,
+//! so it is the cheapest way to check the integrity of a published inference.
+//! [IntegrityReport] compares it with a proof of the same model,
+//! telling what zero knowledge and soundness cost on top of integrity.
+
+use std::time::{Duration, Instant};
+
+use ark_serialize::CanonicalSerialize;
+use blake2::{Blake2b512, Digest};
+use mina_curves::pasta::Fp;
+use serde::Serialize;
+
+use super::costs::RecurringCosts;
+
+/// Hashes the weights, the inputs and the outputs of an inference.
+/// Each of them is prefixed with its length, so that values cannot move from one to the other.
+pub fn digest(weights: &[Fp], inputs: &[Fp], outputs: &[Fp]) -> [u8; 64] {
+    let mut h = Blake2b512::new();
+    let mut bytes = vec![];
+    for values in [weights, inputs, outputs] {
+        h.update((values.len() as u64).to_le_bytes());
+        for value in values {
+            bytes.clear();
+            value
+                .serialize(&mut bytes)
+                .expect("a field element serializes to a vector");
+            h.update(&bytes);
+        }
+    }
+    let mut digest = [0u8; 64];
+    digest.copy_from_slice(&h.finalize());
+    digest
+}
+
+/// Checks a published digest against the revealed weights, inputs and outputs.
+pub fn verify_digest(published: &[u8; 64], weights: &[Fp], inputs: &[Fp], outputs: &[Fp]) -> bool {
+    digest(weights, inputs, outputs) == *published
+}
+
+/// Measures the recurring costs of the baseline for an inference:
+/// publishing the digest stands for proving, and checking it for verifying.
+/// The witness costs nothing, as evaluating the model is not part of the baseline.
+pub fn baseline_costs(weights: &[Fp], inputs: &[Fp], outputs: &[Fp]) -> RecurringCosts {
+    let start = Instant::now();
+    let published = digest(weights, inputs, outputs);
+    let prove = start.elapsed();
+
+    let start = Instant::now();
+    assert!(verify_digest(&published, weights, inputs, outputs));
+    let verify = start.elapsed();
+
+    RecurringCosts {
+        witness: Duration::ZERO,
+        prove,
+        verify,
+    }
+}
+
+/// The integrity section of a benchmark report:
+/// the recurring costs of a model with the baseline and with zero knowledge, side by side.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct IntegrityReport {
+    pub model: String,
+    pub baseline: RecurringCosts,
+    pub zk: RecurringCosts,
+}
+
+/// The ratio of two durations, [None] if the baseline is too small to be measured.
+fn overhead(zk: Duration, baseline: Duration) -> Option<f64> {
+    (!baseline.is_zero()).then(|| zk.as_secs_f64() / baseline.as_secs_f64())
+}
+
+impl IntegrityReport {
+    /// How many times slower proving is than publishing the digest.
+    pub fn prove_overhead(&self) -> Option<f64> {
+        overhead(self.zk.prove, self.baseline.prove)
+    }
+
+    /// How many times slower verifying the proof is than checking the digest.
+    pub fn verify_overhead(&self) -> Option<f64> {
+        overhead(self.zk.verify, self.baseline.verify)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::BenchmarkCtx;
+
+    #[test]
+    fn test_baseline() {
+        let weights = [1u64, 2, 3].map(Fp::from);
+        let inputs = [4u64, 5, 6].map(Fp::from);
+        let outputs = [Fp::from(32u64)];
+
+        let published = digest(&weights, &inputs, &outputs);
+        assert!(verify_digest(&published, &weights, &inputs, &outputs));
+        assert!(!verify_digest(
+            &published,
+            &weights,
+            &inputs,
+            &[Fp::from(33u64)]
+        ));
+        // moving a value from the inputs to the weights changes the digest
+        assert!(!verify_digest(
+            &published,
+            &[1u64, 2, 3, 4].map(Fp::from),
+            &[5u64, 6].map(Fp::from),
+            &outputs
+        ));
+
+        let ctx = BenchmarkCtx::new(4);
+        let report = IntegrityReport {
+            model: "bench".to_string(),
+            baseline: baseline_costs(&weights, &inputs, &outputs),
+            zk: ctx.recurring_costs(),
+        };
+        println!("integrity: {}", serde_json::to_string(&report).unwrap());
+    }
+}
@@ -0,0 +1,148 @@
+//! The optional proof-compression stage of a benchmark.
+//!
+//! A compressor wraps a kimchi proof into a smaller final proof
+//! (for example with a recursive verifier circuit, or by proving the kimchi verifier in another SNARK),
+//! trading some prover latency for a smaller proof and a cheaper verification.
+//! Compressors are plugged in through the [ProofCompressor] trait,
+//! and [compress] measures what each of them costs and saves for a model.
+//!
+//! This module is only the extension point: the crate ships no compressor,
+//! as wrapping a kimchi proof needs a verifier of kimchi proofs in a circuit over the other curve of the cycle,
+//! which lives outside of this crate.
+//! Until one is plugged in, the benchmarks report no compression,
+//! and the passthrough compressor of the tests only checks the measurements.
+
+use std::time::{Duration, Instant};
+
+use mina_curves::pasta::{Fp, Vesta};
+use poly_commitment::evaluation_proof::OpeningProof;
+use serde::Serialize;
+
+use super::BenchmarkCtx;
+use crate::{error::CompressionError, proof::ProverProof};
+
+/// A stage turning a kimchi proof into a smaller final proof.
+pub trait ProofCompressor {
+    /// The final proof.
+    type Compressed: Serialize;
+
+    /// The name of the compressor, used in the reports.
+    fn name(&self) -> &str;
+
+    /// Wraps a kimchi proof, with its public input, into a final proof.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the proof cannot be wrapped (for example if it does not verify).
+    fn compress(
+        &self,
+        proof: &ProverProof<Vesta, OpeningProof<Vesta>>,
+        public: &[Fp],
+    ) -> Result<Self::Compressed, CompressionError>;
+
+    /// Verifies a final proof.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the final proof does not verify.
+    fn verify(&self, compressed: &Self::Compressed) -> Result<(), CompressionError>;
+}
+
+/// The compression section of a benchmark report.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct CompressionReport {
+    pub model: String,
+    pub compressor: String,
+    /// The size in bytes of the kimchi proof.
+    pub original_size: usize,
+    /// The size in bytes of the final proof.
+    pub compressed_size: usize,
+    /// The latency added to the prover by the compression.
+    pub latency: Duration,
+    /// The time it takes to verify the final proof.
+    pub verify: Duration,
+}
+
+impl CompressionReport {
+    /// The size of the final proof relative to the kimchi proof.
+    pub fn ratio(&self) -> f64 {
+        self.compressed_size as f64 / self.original_size as f64
+    }
+}
+
+/// Compresses a proof of a model, checks the final proof, and reports the sizes and latencies.
+///
+/// # Errors
+///
+/// Will give error if the compressor fails, or if the final proof does not verify.
+pub fn compress<C: ProofCompressor>(
+    model: String,
+    compressor: &C,
+    proof: &ProverProof<Vesta, OpeningProof<Vesta>>,
+    public: &[Fp],
+) -> Result<(C::Compressed, CompressionReport), CompressionError> {
+    let start = Instant::now();
+    let compressed = compressor.compress(proof, public)?;
+    let latency = start.elapsed();
+
+    let start = Instant::now();
+    compressor.verify(&compressed)?;
+    let verify = start.elapsed();
+
+    let compressed_size = rmp_serde::to_vec(&compressed)
+        .map_err(|e| CompressionError::Compress(e.to_string()))?
+        .len();
+    let report = CompressionReport {
+        model,
+        compressor: compressor.name().to_string(),
+        original_size: BenchmarkCtx::proof_size(proof),
+        compressed_size,
+        latency,
+        verify,
+    };
+    Ok((compressed, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keeps the kimchi proof as it is: not a compressor, but the baseline the measurements are checked against.
+    struct Passthrough<'a> {
+        ctx: &'a BenchmarkCtx,
+    }
+
+    impl ProofCompressor for Passthrough<'_> {
+        type Compressed = (ProverProof<Vesta, OpeningProof<Vesta>>, Vec<Fp>);
+
+        fn name(&self) -> &str {
+            "passthrough"
+        }
+
+        fn compress(
+            &self,
+            proof: &ProverProof<Vesta, OpeningProof<Vesta>>,
+            public: &[Fp],
+        ) -> Result<Self::Compressed, CompressionError> {
+            Ok((proof.clone(), public.to_vec()))
+        }
+
+        fn verify(&self, compressed: &Self::Compressed) -> Result<(), CompressionError> {
+            self.ctx
+                .batch_verification(std::slice::from_ref(compressed));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_compress() {
+        let ctx = BenchmarkCtx::new(4);
+        let (proof, public) = ctx.create_proof();
+
+        let compressor = Passthrough { ctx: &ctx };
+        let (_, report) = compress("bench".to_string(), &compressor, &proof, &public).unwrap();
+        assert_eq!(report.compressor, "passthrough");
+        assert!(report.compressed_size >= report.original_size);
+        println!("compression: {}", serde_json::to_string(&report).unwrap());
+    }
+}
@@ -0,0 +1,149 @@
+//! The costs section of a benchmark report.
+//!
+//! One-time costs (compiling the circuit, the setup, generating the keys) are paid once per model,
+//! while recurring costs (computing the witness, proving, verifying) are paid for every inference.
+//! Keeping them apart tells whether a model is dominated by its setup or by its proofs,
+//! and after how many inferences the setup is amortized.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::security::SecurityLevel;
+
+/// The costs paid once per model.
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OneTimeCosts {
+    /// Building the circuit.
+    pub compile: Duration,
+    /// Creating the SRS, the constraint system and the prover index.
+    pub setup: Duration,
+    /// Creating the verifier index.
+    pub key_gen: Duration,
+}
+
+impl OneTimeCosts {
+    /// The sum of the costs.
+    pub fn total(&self) -> Duration {
+        self.compile + self.setup + self.key_gen
+    }
+}
+
+/// The costs paid for every inference.
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RecurringCosts {
+    /// Computing the witness.
+    pub witness: Duration,
+    /// Creating the proof.
+    pub prove: Duration,
+    /// Verifying the proof.
+    pub verify: Duration,
+}
+
+impl RecurringCosts {
+    /// The sum of the costs.
+    pub fn total(&self) -> Duration {
+        self.witness + self.prove + self.verify
+    }
+}
+
+/// The costs of a model.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct CostReport {
+    pub model: String,
+    pub one_time: OneTimeCosts,
+    pub recurring: RecurringCosts,
+    /// See [CostReport::break_even].
+    pub break_even: Option<u64>,
+    /// The security level the costs were measured at, if known, so that comparisons tell whether it differs.
+    pub security: Option<SecurityLevel>,
+}
+
+impl CostReport {
+    /// Creates the report of a model, and computes its break-even point.
+    pub fn new(model: String, one_time: OneTimeCosts, recurring: RecurringCosts) -> Self {
+        Self {
+            model,
+            one_time,
+            recurring,
+            break_even: Self::break_even(&one_time, &recurring),
+            security: None,
+        }
+    }
+
+    /// Records the security level the costs were measured at.
+    pub fn with_security(self, security: SecurityLevel) -> Self {
+        Self {
+            security: Some(security),
+            ..self
+        }
+    }
+
+    /// The number of inferences after which the recurring costs reach the one-time costs,
+    /// i.e. after which the setup accounts for at most half of the total cost.
+    /// [None] if the recurring costs are too small to be measured.
+    pub fn break_even(one_time: &OneTimeCosts, recurring: &RecurringCosts) -> Option<u64> {
+        let recurring = recurring.total().as_nanos();
+        if recurring == 0 {
+            return None;
+        }
+        let inferences = (one_time.total().as_nanos() + recurring - 1) / recurring;
+        Some(inferences.try_into().unwrap_or(u64::MAX))
+    }
+}
+
+/// How the verifier checks the openings of a batch of proofs.
+/// Each proof opens its polynomials at two evaluation points, `zeta` and `zeta * omega`, in one opening proof.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpeningAggregation {
+    /// Each proof is verified on its own, and pays for the check of its opening.
+    PerProof,
+    /// The openings of all the proofs, at all their evaluation points, are combined into a single check,
+    /// so that the batch pays for one multi-scalar multiplication.
+    Batched,
+}
+
+/// The cost of verifying a batch of proofs, without and with aggregating their openings.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AggregationReport {
+    /// The number of proofs of the batch.
+    pub proofs: usize,
+    /// The size in bytes of a proof.
+    /// The openings are aggregated by the verifier, so the size is the same with both aggregations.
+    pub proof_size: usize,
+    /// Verifying the batch with [OpeningAggregation::PerProof].
+    pub per_proof: Duration,
+    /// Verifying the batch with [OpeningAggregation::Batched].
+    pub batched: Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_break_even() {
+        let one_time = OneTimeCosts {
+            compile: Duration::from_millis(100),
+            setup: Duration::from_millis(800),
+            key_gen: Duration::from_millis(100),
+        };
+        let recurring = RecurringCosts {
+            witness: Duration::from_millis(10),
+            prove: Duration::from_millis(80),
+            verify: Duration::from_millis(10),
+        };
+        let report = CostReport::new("test".to_string(), one_time, recurring);
+        assert_eq!(report.break_even, Some(10));
+
+        let slower_setup = OneTimeCosts {
+            setup: Duration::from_millis(801),
+            ..one_time
+        };
+        assert_eq!(CostReport::break_even(&slower_setup, &recurring), Some(11));
+        assert_eq!(
+            CostReport::break_even(&one_time, &RecurringCosts::default()),
+            None
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Continuous benchmarking: a daemon running the suite whenever its inputs change.
+//!
+//! The daemon polls a [WatchSource], a directory of model files and configs or a git ref,
+//! and turns its current contents into jobs: one per file, identified by the hash of its contents,
+//! or one per commit of the ref.
+//! Each job that has not run yet is run, and its report is appended to an [ArtifactStore],
+//! where [super::query] can track the results over time.
+//! A job that fails appends a [Failure] instead, so that the failures can be triaged with the results.
+//! The jobs that ran are recorded in a [SuiteState], so that restarting the daemon does not run them again.
+//!
+//! The daemon only decides when to run and how to name the results: how a job is run is up to its caller.
+//! The `bench-daemon` binary runs the built-in circuit of [super::BenchmarkCtx] for every job,
+//! as the crate has no importer building a circuit from a model file,
+//! so its results track the prover over the changes of the source rather than the models themselves.
+
+use std::{
+    convert::Infallible,
+    fs,
+    path::PathBuf,
+    process::Command,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use super::{
+    failure::Failure,
+    manifest::hash_bytes,
+    state::{JobId, SuiteState},
+    store::{ArtifactKind, ArtifactStore},
+};
+use crate::error::StoreError;
+
+/// The backend of the jobs of the daemon.
+const BACKEND: &str = "kimchi";
+
+/// What the daemon watches.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatchSource {
+    /// The files of a directory, subdirectories excluded.
+    Directory(PathBuf),
+    /// The commit a git ref points to, in a repository.
+    GitRef { repo: PathBuf, reference: String },
+}
+
+impl WatchSource {
+    /// The jobs for the current contents of the source.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the directory cannot be read, or if git cannot resolve the ref.
+    pub fn jobs(&self) -> Result<Vec<JobId>, StoreError> {
+        match self {
+            WatchSource::Directory(dir) => {
+                let mut jobs = vec![];
+                for entry in fs::read_dir(dir).map_err(|e| StoreError::Io(e.to_string()))? {
+                    let path = entry.map_err(|e| StoreError::Io(e.to_string()))?.path();
+                    if !path.is_file() {
+                        continue;
+                    }
+                    let bytes = fs::read(&path).map_err(|e| StoreError::Io(e.to_string()))?;
+                    jobs.push(JobId {
+                        model: path.file_name().unwrap().to_string_lossy().into_owned(),
+                        backend: BACKEND.to_string(),
+                        config: hash_bytes(&bytes),
+                    });
+                }
+                jobs.sort();
+                Ok(jobs)
+            }
+            WatchSource::GitRef { repo, reference } => {
+                let output = Command::new("git")
+                    .arg("-C")
+                    .arg(repo)
+                    .args(["rev-parse", "--verify"])
+                    .arg(format!("{reference}^{{commit}}"))
+                    .output()
+                    .map_err(|e| StoreError::Io(e.to_string()))?;
+                if !output.status.success() {
+                    return Err(StoreError::Io(
+                        String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                    ));
+                }
+                Ok(vec![JobId {
+                    model: reference.clone(),
+                    backend: BACKEND.to_string(),
+                    config: String::from_utf8_lossy(&output.stdout).trim().to_string(),
+                }])
+            }
+        }
+    }
+}
+
+/// Formats a time as an ISO 8601 UTC date, as expected by [super::query::Query].
+fn iso_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, secs) = ((secs / 86400) as i64, secs % 86400);
+
+    // the civil date of a number of days since 1970-01-01, in the proleptic Gregorian calendar
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// The report of a job, as appended to the store.
+#[derive(Serialize, Clone, Debug)]
+pub struct DaemonReport<R> {
+    pub model: String,
+    pub backend: String,
+    /// The hash of the file, or the commit, the job ran for.
+    pub config: String,
+    /// When the job completed.
+    pub date: String,
+    /// The report of the job, or its failure.
+    pub report: R,
+}
+
+impl<R> DaemonReport<R> {
+    /// The report of a job that just completed.
+    fn new(job: &JobId, report: R) -> Self {
+        Self {
+            model: job.model.clone(),
+            backend: job.backend.clone(),
+            config: job.config.clone(),
+            date: iso_date(SystemTime::now()),
+            report,
+        }
+    }
+}
+
+/// A daemon running the jobs of a source.
+pub struct Daemon<S> {
+    source: WatchSource,
+    store: S,
+    state: SuiteState,
+    interval: Duration,
+}
+
+impl<S> Daemon<S>
+where
+    S: ArtifactStore,
+{
+    /// Creates a daemon polling `source` every `interval`, and recording the jobs that ran in the state file at `state`.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the state file exists and cannot be read.
+    pub fn new(
+        source: WatchSource,
+        store: S,
+        state: impl Into<PathBuf>,
+        interval: Duration,
+    ) -> Result<Self, StoreError> {
+        Ok(Self {
+            source,
+            store,
+            state: SuiteState::open(state)?,
+            interval,
+        })
+    }
+
+    /// Runs the jobs of the source that have not run yet, appending their reports to the store.
+    /// Returns the jobs that ran.
+    ///
+    /// A job that fails does not stop the other jobs: its failure is appended to the store in place of its report,
+    /// under a name ending in `-failed.json`, and it only runs again once its input changes.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the source cannot be read, or if a report cannot be stored.
+    pub fn poll<R, FUNC>(&mut self, mut run_job: FUNC) -> Result<Vec<JobId>, StoreError>
+    where
+        R: Serialize,
+        FUNC: FnMut(&JobId) -> Result<R, Failure>,
+    {
+        let mut ran = vec![];
+        for job in self.source.jobs()? {
+            if self.state.is_completed(&job) {
+                continue;
+            }
+            let (bytes, suffix) = match run_job(&job) {
+                Ok(report) => (
+                    serde_json::to_vec_pretty(&DaemonReport::new(&job, report)),
+                    "",
+                ),
+                Err(failure) => (
+                    serde_json::to_vec_pretty(&DaemonReport::new(&job, failure)),
+                    "-failed",
+                ),
+            };
+            let bytes =
+                bytes.map_err(|e| StoreError::Malformed(job.model.clone(), e.to_string()))?;
+            // refs may contain slashes, which stores take for directories
+            let name = format!(
+                "{}-{}{suffix}.json",
+                job.model.replace('/', "_"),
+                &job.config[..job.config.len().min(16)]
+            );
+            self.store.put(ArtifactKind::Report, &name, &bytes)?;
+
+            self.state.mark_completed(job.clone())?;
+            ran.push(job);
+        }
+        Ok(ran)
+    }
+
+    /// Polls the source forever, every interval.
+    ///
+    /// # Errors
+    ///
+    /// Returns at the first error of [Self::poll].
+    pub fn watch<R, FUNC>(&mut self, mut run_job: FUNC) -> Result<Infallible, StoreError>
+    where
+        R: Serialize,
+        FUNC: FnMut(&JobId) -> Result<R, Failure>,
+    {
+        loop {
+            self.poll(&mut run_job)?;
+            thread::sleep(self.interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::{
+        failure::{FailureCategory, Stage},
+        query::load_results,
+        store::LocalStore,
+    };
+
+    #[test]
+    fn test_daemon() {
+        let root = std::env::temp_dir().join(format!("kimchi-daemon-{}", std::process::id()));
+        let models = root.join("models");
+        fs::create_dir_all(&models).unwrap();
+        fs::write(models.join("mlp.json"), r#"{"layers": 2}"#).unwrap();
+        fs::write(models.join("cnn.json"), r#"{"layers": 3}"#).unwrap();
+
+        let mut daemon = Daemon::new(
+            WatchSource::Directory(models.clone()),
+            LocalStore::new(root.join("store")),
+            root.join("state.json"),
+            Duration::ZERO,
+        )
+        .unwrap();
+        let run = |job: &JobId| {
+            if job.model == "broken.json" {
+                return Err(Failure::new(
+                    FailureCategory::UnsupportedOperation,
+                    Stage::Compile,
+                    "unknown op Gather",
+                ));
+            }
+            Ok(serde_json::json!({ "num_gates": job.model.len() }))
+        };
+
+        assert_eq!(daemon.poll(run).unwrap().len(), 2);
+        assert!(daemon.poll(run).unwrap().is_empty());
+
+        // a changed model runs again, and a failing one is recorded without stopping it
+        fs::write(models.join("mlp.json"), r#"{"layers": 4}"#).unwrap();
+        fs::write(models.join("broken.json"), "").unwrap();
+        let ran = daemon.poll(run).unwrap();
+        assert_eq!(ran.len(), 2);
+        assert!(daemon.poll(run).unwrap().is_empty());
+
+        let records = load_results(&LocalStore::new(root.join("store"))).unwrap();
+        assert_eq!(records.len(), 4);
+        let (failed, succeeded): (Vec<_>, Vec<_>) = records
+            .iter()
+            .partition(|record| record.name.ends_with("-failed.json"));
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].model, "broken.json");
+        assert!(succeeded
+            .iter()
+            .all(|record| record.values["report.num_gates"] == 8.0));
+
+        assert_eq!(iso_date(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            iso_date(UNIX_EPOCH + Duration::from_secs(1_709_210_096)),
+            "2024-02-29T12:34:56Z"
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
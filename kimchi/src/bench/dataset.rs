@@ -0,0 +1,260 @@
+//! Test sets stored on disk in shards, for benchmarks whose test set does not fit in memory.
+//!
+//! A dataset is a directory of shards named `shard-00000.json`, `shard-00001.json`, ...,
+//! each holding a JSON array of [Sample]s.
+//! [Dataset::open] reads the shards once to build the Merkle tree of the digests of the samples
+//! (see [sample_digest] and [crate::snarky::merkle]),
+//! and [Dataset::samples] then streams the samples one shard at a time, along with their [MerklePath],
+//! so that circuits can check that each sample they use belongs to the committed dataset.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ark_ff::Zero;
+use mina_curves::pasta::{Fp, Vesta};
+
+use super::leaderboard::{sample_digest, Sample};
+use crate::{
+    curve::KimchiCurve,
+    error::StoreError,
+    snarky::merkle::{node, root_from_path},
+};
+
+fn shard_name(shard: usize) -> String {
+    format!("shard-{shard:05}.json")
+}
+
+/// Writes the samples to `dir`, in shards of `shard_size` samples.
+///
+/// # Errors
+///
+/// Will give error if `shard_size` is zero, or if the shards cannot be written.
+pub fn write_shards(dir: &Path, samples: &[Sample], shard_size: usize) -> Result<(), StoreError> {
+    if shard_size == 0 {
+        return Err(StoreError::EmptyShards);
+    }
+    fs::create_dir_all(dir).map_err(|e| StoreError::Io(e.to_string()))?;
+    for (shard, samples) in samples.chunks(shard_size).enumerate() {
+        let json = serde_json::to_vec(samples).map_err(|e| StoreError::Io(e.to_string()))?;
+        fs::write(dir.join(shard_name(shard)), json).map_err(|e| StoreError::Io(e.to_string()))?;
+    }
+    Ok(())
+}
+
+fn read_shard(path: &Path) -> Result<Vec<Sample>, StoreError> {
+    let json = fs::read(path).map_err(|e| StoreError::Io(e.to_string()))?;
+    serde_json::from_slice(&json)
+        .map_err(|e| StoreError::Malformed(path.display().to_string(), e.to_string()))
+}
+
+/// The path of a sample in the Merkle tree of a [Dataset].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerklePath {
+    /// The index of the sample.
+    pub index: usize,
+    /// The siblings of the nodes on the path, from the leaves to the root.
+    pub siblings: Vec<Fp>,
+}
+
+impl MerklePath {
+    /// Computes the root of the tree from the digest of the sample.
+    pub fn root(&self, leaf: Fp) -> Fp {
+        root_from_path(Vesta::sponge_params(), leaf, self.index, &self.siblings)
+    }
+}
+
+/// A sharded dataset, and the Merkle tree of its samples.
+#[derive(Debug)]
+pub struct Dataset {
+    shards: Vec<PathBuf>,
+    /// The layers of the tree, from the leaves (padded with zeros to a power of two) to the root.
+    layers: Vec<Vec<Fp>>,
+    len: usize,
+}
+
+impl Dataset {
+    /// Opens the dataset in `dir` and builds its Merkle tree.
+    /// Only the digests of the samples are kept in memory.
+    pub fn open(dir: &Path) -> Result<Self, StoreError> {
+        let mut shards = vec![];
+        for entry in fs::read_dir(dir).map_err(|e| StoreError::Io(e.to_string()))? {
+            let path = entry.map_err(|e| StoreError::Io(e.to_string()))?.path();
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("");
+            if name.starts_with("shard-") && name.ends_with(".json") {
+                shards.push(path);
+            }
+        }
+        shards.sort();
+
+        let params = Vesta::sponge_params();
+        let mut leaves = vec![];
+        for shard in &shards {
+            leaves.extend(
+                read_shard(shard)?
+                    .iter()
+                    .map(|sample| sample_digest(params, sample)),
+            );
+        }
+        let len = leaves.len();
+        leaves.resize(len.next_power_of_two(), Fp::zero());
+
+        let mut layers = vec![leaves];
+        while layers.last().unwrap().len() > 1 {
+            let layer = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| node(params, pair[0], pair[1]))
+                .collect();
+            layers.push(layer);
+        }
+
+        Ok(Self {
+            shards,
+            layers,
+            len,
+        })
+    }
+
+    /// The number of samples.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the dataset has no samples.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The depth of the tree, which is the length of the paths.
+    pub fn depth(&self) -> usize {
+        self.layers.len() - 1
+    }
+
+    /// The root of the tree, which commits to the dataset.
+    pub fn root(&self) -> Fp {
+        self.layers[self.depth()][0]
+    }
+
+    /// The path of the sample at `index`, if the dataset has that many samples.
+    pub fn path(&self, index: usize) -> Option<MerklePath> {
+        if index >= self.len {
+            return None;
+        }
+        let siblings = self.layers[..self.depth()]
+            .iter()
+            .enumerate()
+            .map(|(level, layer)| layer[(index >> level) ^ 1])
+            .collect();
+        Some(MerklePath { index, siblings })
+    }
+
+    /// Streams the samples and their paths, reading one shard at a time.
+    pub fn samples(&self) -> Samples<'_> {
+        Samples {
+            dataset: self,
+            next_shard: 0,
+            shard: vec![].into_iter(),
+            index: 0,
+        }
+    }
+}
+
+/// An iterator over the samples of a [Dataset] (see [Dataset::samples]).
+pub struct Samples<'a> {
+    dataset: &'a Dataset,
+    next_shard: usize,
+    shard: std::vec::IntoIter<Sample>,
+    index: usize,
+}
+
+impl Iterator for Samples<'_> {
+    type Item = Result<(Sample, MerklePath), StoreError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(sample) = self.shard.next() {
+                let Some(path) = self.dataset.path(self.index) else {
+                    // a shard grew since the dataset was opened
+                    let shard = &self.dataset.shards[self.next_shard - 1];
+                    self.next_shard = self.dataset.shards.len();
+                    self.shard = vec![].into_iter();
+                    return Some(Err(StoreError::Malformed(
+                        shard.display().to_string(),
+                        "the shard has more samples than when the dataset was opened".to_string(),
+                    )));
+                };
+                self.index += 1;
+                return Some(Ok((sample, path)));
+            }
+
+            let shard = self.dataset.shards.get(self.next_shard)?;
+            self.next_shard += 1;
+            match read_shard(shard) {
+                Ok(samples) => self.shard = samples.into_iter(),
+                Err(e) => {
+                    // stop after reporting the error
+                    self.next_shard = self.dataset.shards.len();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dataset() {
+        let dir = std::env::temp_dir().join(format!("kimchi-dataset-{}", std::process::id()));
+        let samples: Vec<_> = (0..5u64)
+            .map(|i| Sample {
+                features: vec![Fp::from(i), Fp::from(i * i)],
+                label: Fp::from(i % 2),
+            })
+            .collect();
+        assert!(matches!(
+            write_shards(&dir, &samples, 0),
+            Err(StoreError::EmptyShards)
+        ));
+        write_shards(&dir, &samples, 2).unwrap();
+
+        let dataset = Dataset::open(&dir).unwrap();
+        assert_eq!(dataset.len(), 5);
+        assert_eq!(dataset.depth(), 3);
+
+        let params = Vesta::sponge_params();
+        let streamed: Vec<_> = dataset.samples().map(Result::unwrap).collect();
+        assert_eq!(streamed.len(), 5);
+        for (i, (sample, path)) in streamed.iter().enumerate() {
+            assert_eq!(sample, &samples[i]);
+            assert_eq!(path.index, i);
+            assert_eq!(path.root(sample_digest(params, sample)), dataset.root());
+        }
+
+        // a sample does not belong at another index
+        let path = dataset.path(1).unwrap();
+        assert_ne!(
+            path.root(sample_digest(params, &samples[0])),
+            dataset.root()
+        );
+        // nor past the samples, in the padding of the tree
+        assert!(dataset.path(5).is_none());
+
+        // a corrupted shard is malformed, not an I/O error
+        fs::write(dir.join(shard_name(0)), "[{").unwrap();
+        assert!(matches!(
+            Dataset::open(&dir),
+            Err(StoreError::Malformed(..))
+        ));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,275 @@
+//! Structured records of the benchmark jobs that failed.
+//!
+//! A job of a large run (many models on many backends) can fail for reasons that call for different fixes:
+//! running out of memory, an operation the backend does not support, a witness that does not satisfy the constraints,
+//! or a timeout. Instead of an error string, a [Failure] records the [FailureCategory], the [Stage] of the job that failed,
+//! and the context needed to reproduce it, so that the failures of a run can be grouped and triaged automatically (see [triage]).
+
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    fmt::Display,
+    panic::{self, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::state::JobId;
+use crate::error::{
+    CapabilityError, CustomGateError, ProverError, SetupError, VerifierIndexError, VerifyError,
+};
+
+/// Why a job failed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// An allocation failed.
+    OutOfMemory,
+    /// The circuit does not fit the SRS, the domain or the limits of the backend.
+    TooLarge,
+    /// The model uses an operation, a gate or a lookup the backend does not support.
+    UnsupportedOperation,
+    /// The witness does not satisfy the constraints, or the proof does not verify.
+    Unsatisfied,
+    /// The stage ran for longer than allowed.
+    Timeout,
+    /// The inputs of the stage are inconsistent or malformed.
+    Invalid,
+    /// Anything else, including panics.
+    Internal,
+}
+
+/// The stage of a job.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// Building the circuit of the model.
+    Compile,
+    /// Creating the SRS and the indexes.
+    Setup,
+    Witness,
+    Prove,
+    Verify,
+}
+
+/// Errors that belong to a [FailureCategory].
+pub trait Categorize: Display {
+    fn category(&self) -> FailureCategory;
+}
+
+impl Categorize for ProverError {
+    fn category(&self) -> FailureCategory {
+        use ProverError::*;
+        match self {
+            NoRoomForZkInWitness | NotZeroKnowledge(..) => FailureCategory::TooLarge,
+            Permutation(_) | ValueNotInTable(_) => FailureCategory::Unsatisfied,
+            WitnessCsInconsistent | RuntimeTablesInconsistent | WrongBlinders(_) => {
+                FailureCategory::Invalid
+            }
+            Prover(_) => FailureCategory::Internal,
+        }
+    }
+}
+
+impl Categorize for VerifyError {
+    fn category(&self) -> FailureCategory {
+        match self {
+            VerifyError::SRSTooSmall => FailureCategory::TooLarge,
+            VerifyError::OpenProof => FailureCategory::Unsatisfied,
+            _ => FailureCategory::Invalid,
+        }
+    }
+}
+
+impl Categorize for SetupError {
+    fn category(&self) -> FailureCategory {
+        match self {
+            SetupError::DomainCreation(_) => FailureCategory::TooLarge,
+            SetupError::ConstraintSystem(_) | SetupError::LookupCreation(_) => {
+                FailureCategory::Invalid
+            }
+        }
+    }
+}
+
+impl Categorize for VerifierIndexError {
+    fn category(&self) -> FailureCategory {
+        FailureCategory::Invalid
+    }
+}
+
+impl Categorize for CapabilityError {
+    fn category(&self) -> FailureCategory {
+        match self {
+            CapabilityError::UnsupportedGate(_) | CapabilityError::LookupTooWide(..) => {
+                FailureCategory::UnsupportedOperation
+            }
+            CapabilityError::TooManyRows(..) => FailureCategory::TooLarge,
+        }
+    }
+}
+
+impl Categorize for CustomGateError {
+    fn category(&self) -> FailureCategory {
+        match self {
+            CustomGateError::UnknownGate(_) => FailureCategory::UnsupportedOperation,
+            CustomGateError::Unsatisfied(..) => FailureCategory::Unsatisfied,
+            CustomGateError::AlreadyRegistered(_) | CustomGateError::ConstraintCount(..) => {
+                FailureCategory::Invalid
+            }
+        }
+    }
+}
+
+/// Why and where a job failed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Failure {
+    pub category: FailureCategory,
+    pub stage: Stage,
+    pub message: String,
+    /// Anything needed to reproduce the failure, such as the size of the circuit or the row that failed.
+    pub context: BTreeMap<String, String>,
+}
+
+impl Failure {
+    pub fn new(category: FailureCategory, stage: Stage, message: impl Display) -> Self {
+        Self {
+            category,
+            stage,
+            message: message.to_string(),
+            context: BTreeMap::new(),
+        }
+    }
+
+    /// Records an error of a stage, in its category.
+    pub fn from_error(stage: Stage, error: &impl Categorize) -> Self {
+        Self::new(error.category(), stage, error)
+    }
+
+    /// Records a panic of a stage.
+    /// Panics do not tell their cause, so only failed allocations are told apart from internal errors.
+    pub fn from_panic(stage: Stage, payload: Box<dyn Any + Send>) -> Self {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "a panic without a message".to_string());
+        let category =
+            if message.contains("capacity overflow") || message.contains("memory allocation") {
+                FailureCategory::OutOfMemory
+            } else {
+                FailureCategory::Internal
+            };
+        Self::new(category, stage, message)
+    }
+
+    /// Adds a value to the context of the failure.
+    pub fn with_context(mut self, key: impl Into<String>, value: impl Display) -> Self {
+        self.context.insert(key.into(), value.to_string());
+        self
+    }
+}
+
+/// Runs a stage of a job, turning its panics into failures.
+///
+/// The stage is not interrupted when it exceeds `timeout`, as the provers cannot be cancelled,
+/// but its result is then discarded and recorded as a [FailureCategory::Timeout].
+pub fn run_stage<T, FUNC>(stage: Stage, timeout: Option<Duration>, run: FUNC) -> Result<T, Failure>
+where
+    FUNC: FnOnce() -> Result<T, Failure>,
+{
+    let start = Instant::now();
+    let result = panic::catch_unwind(AssertUnwindSafe(run))
+        .unwrap_or_else(|payload| Err(Failure::from_panic(stage, payload)))?;
+    let elapsed = start.elapsed();
+    match timeout {
+        Some(timeout) if elapsed > timeout => Err(Failure::new(
+            FailureCategory::Timeout,
+            stage,
+            format!("the stage took {elapsed:?}, more than {timeout:?}"),
+        )
+        .with_context("timeout_ms", timeout.as_millis())),
+        _ => Ok(result),
+    }
+}
+
+/// The failure of a job.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FailureRecord {
+    pub job: JobId,
+    pub failure: Failure,
+}
+
+/// Groups failures by category and stage.
+pub fn triage(
+    records: &[FailureRecord],
+) -> BTreeMap<(FailureCategory, Stage), Vec<&FailureRecord>> {
+    let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for record in records {
+        groups
+            .entry((record.failure.category, record.failure.stage))
+            .or_default()
+            .push(record);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failures() {
+        let job = |model: &str| JobId {
+            model: model.to_string(),
+            backend: "kimchi".to_string(),
+            config: "default".to_string(),
+        };
+
+        let unsat = run_stage::<(), _>(Stage::Prove, None, || {
+            Err(
+                Failure::from_error(Stage::Prove, &ProverError::ValueNotInTable(3))
+                    .with_context("row", 3),
+            )
+        })
+        .unwrap_err();
+        assert_eq!(unsat.category, FailureCategory::Unsatisfied);
+        assert_eq!(unsat.context["row"], "3");
+
+        let panicked =
+            run_stage::<(), _>(Stage::Compile, None, || panic!("unknown op Gather")).unwrap_err();
+        assert_eq!(panicked.category, FailureCategory::Internal);
+        assert_eq!(panicked.message, "unknown op Gather");
+
+        let slow = run_stage(Stage::Setup, Some(Duration::ZERO), || {
+            std::thread::sleep(Duration::from_millis(1));
+            Ok(())
+        })
+        .unwrap_err();
+        assert_eq!(slow.category, FailureCategory::Timeout);
+        assert_eq!(run_stage(Stage::Setup, None, || Ok(1)), Ok(1));
+
+        let records = vec![
+            FailureRecord {
+                job: job("mlp"),
+                failure: unsat.clone(),
+            },
+            FailureRecord {
+                job: job("lenet"),
+                failure: unsat,
+            },
+            FailureRecord {
+                job: job("gpt"),
+                failure: panicked,
+            },
+        ];
+        let groups = triage(&records);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            groups[&(FailureCategory::Unsatisfied, Stage::Prove)].len(),
+            2
+        );
+        println!("failures: {}", serde_json::to_string(&records).unwrap());
+    }
+}
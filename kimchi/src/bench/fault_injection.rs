@@ -0,0 +1,136 @@
+//! Fault injection, to check that the verifier rejects corrupted proofs, public inputs and keys.
+//!
+//! Each [FaultClass] corrupts a single part of an otherwise valid `(proof, public input, verifier index)` triple.
+//! A robust verifier must reject every one of them.
+
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{One, PrimeField};
+use mina_poseidon::FqSponge;
+use poly_commitment::{evaluation_proof::OpeningProof, PolyComm};
+use serde::Serialize;
+
+use crate::{
+    curve::KimchiCurve, plonk_sponge::FrSponge, proof::ProverProof, verifier::verify,
+    verifier_index::VerifierIndex,
+};
+
+/// The parts of a proving artifact that can be corrupted.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaultClass {
+    /// A public input value is modified (or added, if there is none).
+    PublicInput,
+    /// A witness column commitment is shifted by the generator.
+    WitnessCommitment,
+    /// A witness column evaluation is modified.
+    Evaluation,
+    /// The evaluation used by Maller's optimization is modified.
+    FtEval1,
+    /// The opening proof is modified.
+    OpeningProof,
+    /// The generic gate commitment of the verifier index is shifted by the generator.
+    VerifierKey,
+}
+
+impl FaultClass {
+    /// All the fault classes.
+    pub const ALL: [FaultClass; 6] = [
+        FaultClass::PublicInput,
+        FaultClass::WitnessCommitment,
+        FaultClass::Evaluation,
+        FaultClass::FtEval1,
+        FaultClass::OpeningProof,
+        FaultClass::VerifierKey,
+    ];
+}
+
+/// Whether the verifier rejected a fault.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct FaultOutcome {
+    pub class: FaultClass,
+    pub rejected: bool,
+}
+
+/// The robustness section of a benchmark report.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct RobustnessReport {
+    pub outcomes: Vec<FaultOutcome>,
+}
+
+impl RobustnessReport {
+    /// Returns whether every injected fault was rejected.
+    pub fn all_rejected(&self) -> bool {
+        self.outcomes.iter().all(|outcome| outcome.rejected)
+    }
+}
+
+/// Shifts the first chunk of a commitment by the generator.
+fn corrupt_commitment<G: AffineCurve>(comm: &mut PolyComm<G>) {
+    let shifted = comm.elems[0].into_projective() + G::prime_subgroup_generator().into_projective();
+    comm.elems[0] = shifted.into_affine();
+}
+
+/// Injects every [FaultClass] in turn into a valid proof, and records whether the verifier rejected it.
+///
+/// # Panics
+///
+/// Will panic if the given proof does not verify in the first place.
+pub fn inject_faults<G, EFqSponge, EFrSponge>(
+    group_map: &G::Map,
+    verifier_index: &VerifierIndex<G, OpeningProof<G>>,
+    proof: &ProverProof<G, OpeningProof<G>>,
+    public_input: &[G::ScalarField],
+) -> RobustnessReport
+where
+    G: KimchiCurve,
+    G::BaseField: PrimeField,
+    EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+    EFrSponge: FrSponge<G::ScalarField>,
+{
+    let verifies = |verifier_index: &VerifierIndex<G, OpeningProof<G>>,
+                    proof: &ProverProof<G, OpeningProof<G>>,
+                    public_input: &[G::ScalarField]| {
+        verify::<G, EFqSponge, EFrSponge, OpeningProof<G>>(
+            group_map,
+            verifier_index,
+            proof,
+            public_input,
+        )
+        .is_ok()
+    };
+
+    assert!(
+        verifies(verifier_index, proof, public_input),
+        "faults must be injected into a valid proof"
+    );
+
+    let one = G::ScalarField::one();
+    let outcomes = FaultClass::ALL
+        .into_iter()
+        .map(|class| {
+            let mut verifier_index = verifier_index.clone();
+            let mut proof = proof.clone();
+            let mut public_input = public_input.to_vec();
+
+            match class {
+                FaultClass::PublicInput => match public_input.first_mut() {
+                    Some(x) => *x += one,
+                    None => public_input.push(one),
+                },
+                FaultClass::WitnessCommitment => {
+                    corrupt_commitment(&mut proof.commitments.w_comm[0]);
+                }
+                FaultClass::Evaluation => proof.evals.w[0].zeta[0] += one,
+                FaultClass::FtEval1 => proof.ft_eval1 += one,
+                FaultClass::OpeningProof => proof.proof.z1 += one,
+                FaultClass::VerifierKey => corrupt_commitment(&mut verifier_index.generic_comm),
+            };
+
+            FaultOutcome {
+                class,
+                rejected: !verifies(&verifier_index, &proof, &public_input),
+            }
+        })
+        .collect();
+
+    RobustnessReport { outcomes }
+}
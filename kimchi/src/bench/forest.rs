@@ -0,0 +1,191 @@
+//! A benchmark of random forest inference, sweeping over the number of trees and their depth.
+//!
+//! Each forest (see [crate::snarky::decision_tree::predict_forest]) is drawn at random from a fixed seed,
+//! so that sweeps are reproducible, and is proven on a random input.
+//! The cost of a tree grows with `2^depth` (the selections among the nodes of each level, and the hash of the nodes),
+//! while the aggregation adds a comparison per class for a majority vote, and nothing for an average.
+
+use std::time::Duration;
+
+use mina_curves::pasta::{Fp, Vesta};
+use poly_commitment::evaluation_proof::OpeningProof;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+
+use super::prove_and_verify;
+use crate::{
+    curve::KimchiCurve,
+    loc,
+    snarky::{
+        api::SnarkyCircuit,
+        decision_tree::{predict_forest, Aggregation, DecisionTree, DecisionTreeVar, RandomForest},
+        prelude::{FieldVar, RunState, SnarkyResult},
+    },
+};
+
+/// The number of bits of the features and the thresholds.
+pub const FEATURE_WIDTH: usize = 16;
+
+/// The shape of a forest.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ForestShape {
+    pub trees: usize,
+    pub depth: usize,
+    /// The number of features of the input.
+    pub features: usize,
+    /// The number of classes the leaves are drawn from.
+    pub classes: usize,
+}
+
+impl ForestShape {
+    /// Draws a forest of this shape, with leaves in `0..classes`.
+    pub fn random(&self, aggregation: Aggregation, rng: &mut impl Rng) -> RandomForest<Fp> {
+        let nodes = (1 << self.depth) - 1;
+        let trees = (0..self.trees)
+            .map(|_| DecisionTree {
+                features: (0..nodes)
+                    .map(|_| rng.gen_range(0..self.features))
+                    .collect(),
+                thresholds: (0..nodes)
+                    .map(|_| rng.gen_range(0..1 << FEATURE_WIDTH))
+                    .collect(),
+                leaves: (0..=nodes)
+                    .map(|_| Fp::from(rng.gen_range(0..self.classes) as u64))
+                    .collect(),
+            })
+            .collect();
+        RandomForest { trees, aggregation }
+    }
+}
+
+/// The circuit predicting the aggregated prediction of a private forest of a given shape on a private input.
+struct ForestCircuit {
+    shape: ForestShape,
+    aggregation: Aggregation,
+}
+
+impl SnarkyCircuit for ForestCircuit {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = (RandomForest<Fp>, Vec<u64>);
+    type PublicInput = FieldVar<Fp>;
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        commitment: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let nodes = (1 << self.shape.depth) - 1;
+        let mut trees = Vec::with_capacity(self.shape.trees);
+        for t in 0..self.shape.trees {
+            let tree = move || &private.unwrap().0.trees[t];
+            let mut var = DecisionTreeVar {
+                features: Vec::with_capacity(nodes),
+                thresholds: Vec::with_capacity(nodes),
+                leaves: Vec::with_capacity(nodes + 1),
+            };
+            for i in 0..nodes {
+                var.features
+                    .push(sys.compute(loc!(), |_| Fp::from(tree().features[i] as u64))?);
+                var.thresholds
+                    .push(sys.compute(loc!(), |_| Fp::from(tree().thresholds[i]))?);
+            }
+            for i in 0..=nodes {
+                var.leaves.push(sys.compute(loc!(), |_| tree().leaves[i])?);
+            }
+            trees.push(var);
+        }
+
+        let mut x = Vec::with_capacity(self.shape.features);
+        for i in 0..self.shape.features {
+            let value: FieldVar<Fp> = sys.compute(loc!(), |_| Fp::from(private.unwrap().1[i]))?;
+            x.push(value);
+        }
+
+        predict_forest(
+            sys,
+            loc!(),
+            &commitment,
+            &trees,
+            &x,
+            FEATURE_WIDTH,
+            self.aggregation,
+        )
+    }
+}
+
+/// The costs of proving the inference of a forest.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ForestReport {
+    pub shape: ForestShape,
+    pub aggregation: String,
+    pub gates: usize,
+    pub gates_per_tree: f64,
+    pub prove: Duration,
+    pub verify: Duration,
+}
+
+/// Proves and verifies the inference of a random forest of each shape, aggregated with `aggregation`.
+///
+/// The forests and the inputs are drawn from `seed`.
+pub fn sweep_forests(
+    shapes: &[ForestShape],
+    aggregation: Aggregation,
+    seed: u64,
+) -> Vec<ForestReport> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    shapes
+        .iter()
+        .map(|&shape| {
+            let forest = shape.random(aggregation, &mut rng);
+            let x: Vec<u64> = (0..shape.features)
+                .map(|_| rng.gen_range(0..1 << FEATURE_WIDTH))
+                .collect();
+            let commitment = forest.commitment(Vesta::sponge_params());
+            let expected = forest.predict(&x);
+
+            let circuit = ForestCircuit { shape, aggregation };
+            let run = prove_and_verify(circuit, commitment, (forest, x)).unwrap();
+            assert_eq!(run.output, expected, "the forest predicts incorrectly");
+
+            ForestReport {
+                shape,
+                aggregation: format!("{aggregation:?}"),
+                gates: run.gates,
+                gates_per_tree: run.gates as f64 / shape.trees.max(1) as f64,
+                prove: run.prove,
+                verify: run.verify,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sweep_forests() {
+        let shape = ForestShape {
+            trees: 1,
+            depth: 2,
+            features: 4,
+            classes: 3,
+        };
+        let shapes = [shape, ForestShape { trees: 3, ..shape }];
+        let reports = sweep_forests(&shapes, Aggregation::MajorityVote(3), 0);
+        assert!(reports[0].gates < reports[1].gates);
+
+        let reports = sweep_forests(
+            &[ForestShape { depth: 3, ..shape }],
+            Aggregation::Average,
+            0,
+        );
+        assert_eq!(reports.len(), 1);
+        println!("forests: {}", serde_json::to_string(&reports).unwrap());
+    }
+}
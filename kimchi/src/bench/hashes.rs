@@ -0,0 +1,138 @@
+//! A benchmark comparing the algebraic hashes available to circuits, for commitment hashing.
+//!
+//! Each hash gadget absorbs the same chain of values (`digest = hash(digest, value)`, as commitments to weights or datasets do),
+//! and the report gives its number of gates, and its proving and verification times.
+//! Poseidon has a dedicated kimchi gate computing 5 rounds per row,
+//! while Rescue-Prime (see [crate::snarky::rescue]) is built from generic multiplications:
+//! the comparison tells how much of the cost of a commitment comes from the hash.
+
+use std::time::Duration;
+
+use ark_ff::Zero;
+use mina_curves::pasta::{Fp, Vesta};
+use poly_commitment::evaluation_proof::OpeningProof;
+use serde::Serialize;
+
+use super::prove_and_verify;
+use crate::{
+    curve::KimchiCurve,
+    loc,
+    snarky::{
+        api::SnarkyCircuit,
+        prelude::{FieldVar, RunState, SnarkyResult},
+        prf::poseidon_native,
+        rescue::{rescue_hash, rescue_hash_native, RescueParams},
+    },
+};
+
+/// A hash gadget.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashGadget {
+    Poseidon,
+    RescuePrime,
+}
+
+impl HashGadget {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashGadget::Poseidon => "poseidon",
+            HashGadget::RescuePrime => "rescue-prime",
+        }
+    }
+
+    /// Hashes a chain of values, out of circuit.
+    fn hash_chain(&self, rescue: &RescueParams<Fp>, values: &[Fp]) -> Fp {
+        values.iter().fold(Fp::zero(), |digest, &value| match self {
+            HashGadget::Poseidon => poseidon_native(Vesta::sponge_params(), digest, value),
+            HashGadget::RescuePrime => rescue_hash_native(rescue, digest, value),
+        })
+    }
+}
+
+/// The circuit hashing a chain of private values.
+struct ChainCircuit {
+    hash: HashGadget,
+    length: usize,
+    rescue: RescueParams<Fp>,
+}
+
+impl SnarkyCircuit for ChainCircuit {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = Vec<Fp>;
+    type PublicInput = ();
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        _: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let mut digest = FieldVar::zero();
+        for i in 0..self.length {
+            let value: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap()[i])?;
+            digest = match self.hash {
+                HashGadget::Poseidon => sys.poseidon(loc!(), (digest, value)).0,
+                HashGadget::RescuePrime => rescue_hash(sys, loc!(), &self.rescue, digest, value)?,
+            };
+        }
+        Ok(digest)
+    }
+}
+
+/// The costs of a hash gadget on a chain of values.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct HashReport {
+    pub hash: String,
+    /// The number of values hashed.
+    pub hashes: usize,
+    pub gates: usize,
+    pub gates_per_hash: f64,
+    pub prove: Duration,
+    pub verify: Duration,
+}
+
+/// Proves and verifies a chain of `length` values with each hash gadget.
+pub fn compare_hashes(length: usize) -> Vec<HashReport> {
+    let values: Vec<Fp> = (1..=length as u64).map(Fp::from).collect();
+    let rescue = RescueParams::default_params();
+
+    [HashGadget::Poseidon, HashGadget::RescuePrime]
+        .into_iter()
+        .map(|hash| {
+            let expected = hash.hash_chain(&rescue, &values);
+            let circuit = ChainCircuit {
+                hash,
+                length,
+                rescue: rescue.clone(),
+            };
+            let run = prove_and_verify(circuit, (), values.clone()).unwrap();
+            assert_eq!(run.output, expected, "{} hashes incorrectly", hash.name());
+
+            HashReport {
+                hash: hash.name().to_string(),
+                hashes: length,
+                gates: run.gates,
+                gates_per_hash: run.gates as f64 / length.max(1) as f64,
+                prove: run.prove,
+                verify: run.verify,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_hashes() {
+        let reports = compare_hashes(4);
+        assert_eq!(reports.len(), 2);
+        // the dedicated gate of Poseidon makes it cheaper than generic multiplications
+        assert!(reports[0].gates < reports[1].gates);
+        println!("hashes: {}", serde_json::to_string(&reports).unwrap());
+    }
+}
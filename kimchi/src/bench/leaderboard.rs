@@ -0,0 +1,307 @@
+//! A benchmark scenario proving the accuracy of a model over a committed test set,
+//! as a leaderboard would require from its submissions.
+//!
+//! The test set is split in batches of a fixed size, and each batch is proven separately.
+//! A batch proof takes the running commitment to the test set and the running number of correct predictions,
+//! and outputs both after absorbing its samples.
+//! Chaining the batch proofs yields a single [AccuracyClaim]:
+//! the model classifies `correct` samples out of `total` correctly, on the test set committed as `test_set`.
+
+use std::borrow::Cow;
+
+use ark_ff::{PrimeField, Zero};
+use mina_curves::pasta::{Fp, Vesta};
+use mina_poseidon::poseidon::ArithmeticSpongeParams;
+use poly_commitment::evaluation_proof::OpeningProof;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use super::{BaseSponge, ScalarSponge};
+use crate::{
+    curve::KimchiCurve,
+    error::{AccuracyClaimError, AccuracyProofError},
+    loc,
+    proof::ProverProof,
+    snarky::{
+        api::{ProverIndexWrapper, SnarkyCircuit, VerifierIndexWrapper},
+        poseidon::{hash_slice, hash_slice_native},
+        prelude::{FieldVar, RunState, SnarkyResult},
+        prf::poseidon_native,
+    },
+};
+
+/// A model evaluated by the leaderboard.
+pub trait Classifier {
+    /// The number of features of a sample.
+    fn features(&self) -> usize;
+
+    /// Predicts the label of a sample in the circuit.
+    fn predict(
+        &self,
+        sys: &mut RunState<Fp>,
+        loc: Cow<'static, str>,
+        features: &[FieldVar<Fp>],
+    ) -> SnarkyResult<FieldVar<Fp>>;
+}
+
+/// A labelled sample of the test set.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sample {
+    #[serde_as(as = "Vec<o1_utils::serialization::SerdeAs>")]
+    pub features: Vec<Fp>,
+    #[serde_as(as = "o1_utils::serialization::SerdeAs")]
+    pub label: Fp,
+}
+
+/// Hashes a sample, out of circuit, as the [hash_slice] of its features followed by its label.
+pub fn sample_digest(params: &ArithmeticSpongeParams<Fp>, sample: &Sample) -> Fp {
+    let values: Vec<_> = sample
+        .features
+        .iter()
+        .chain([&sample.label])
+        .copied()
+        .collect();
+    hash_slice_native(params, &values)
+}
+
+/// Commits to a test set, out of circuit.
+/// The digests of the samples are chained rather than hashed as a slice, so that the batch proofs can extend the
+/// commitment one batch at a time; the number of samples is part of the [AccuracyClaim].
+pub fn commit_test_set(samples: &[Sample]) -> Fp {
+    let params = Vesta::sponge_params();
+    samples.iter().fold(Fp::zero(), |commitment, sample| {
+        poseidon_native(params, commitment, sample_digest(params, sample))
+    })
+}
+
+/// The circuit proving the predictions of a batch of samples.
+struct BatchCircuit<M> {
+    model: M,
+    batch_size: usize,
+}
+
+impl<M: Classifier> SnarkyCircuit for BatchCircuit<M> {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = Vec<Sample>;
+    /// The commitment to the previous samples, and the number of correct predictions on them.
+    type PublicInput = (FieldVar<Fp>, FieldVar<Fp>);
+    /// The same values, after the samples of the batch.
+    type PublicOutput = (FieldVar<Fp>, FieldVar<Fp>);
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        (mut commitment, mut correct): Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        for i in 0..self.batch_size {
+            let mut features = Vec::with_capacity(self.model.features());
+            for j in 0..self.model.features() {
+                let feature: FieldVar<Fp> =
+                    sys.compute(loc!(), |_| private.unwrap()[i].features[j])?;
+                features.push(feature);
+            }
+            let label: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap()[i].label)?;
+
+            let values: Vec<_> = features.iter().chain([&label]).cloned().collect();
+            let digest = hash_slice(sys, loc!(), &values);
+            commitment = sys.poseidon(loc!(), (commitment, digest)).0;
+
+            let prediction = self.model.predict(sys, loc!(), &features)?;
+            let hit = prediction.equal(sys, loc!(), &label)?;
+            correct = correct + hit.to_field_var();
+        }
+
+        Ok((commitment, correct))
+    }
+}
+
+/// The accuracy of a model on a committed test set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccuracyClaim {
+    /// The commitment to the test set (see [commit_test_set]).
+    pub test_set: Fp,
+    /// The number of correct predictions.
+    pub correct: u64,
+    /// The number of samples.
+    pub total: u64,
+}
+
+impl AccuracyClaim {
+    /// The accuracy, between 0 and 1.
+    pub fn accuracy(&self) -> f64 {
+        self.correct as f64 / self.total as f64
+    }
+}
+
+/// The proof of a batch, with its public input and output.
+pub struct BatchProof {
+    pub proof: ProverProof<Vesta, OpeningProof<Vesta>>,
+    pub input: (Fp, Fp),
+    pub output: (Fp, Fp),
+}
+
+/// Proves and checks accuracy claims for a model.
+pub struct Leaderboard<M: Classifier> {
+    batch_size: usize,
+    prover_index: ProverIndexWrapper<BatchCircuit<M>>,
+    verifier_index: VerifierIndexWrapper<BatchCircuit<M>>,
+}
+
+impl<M: Classifier> Leaderboard<M> {
+    /// Compiles the batch circuit of a model.
+    pub fn new(model: M, batch_size: usize) -> SnarkyResult<Self> {
+        let (prover_index, verifier_index) =
+            BatchCircuit { model, batch_size }.compile_to_indexes()?;
+        Ok(Self {
+            batch_size,
+            prover_index,
+            verifier_index,
+        })
+    }
+
+    /// Proves the accuracy of the model on a test set,
+    /// whose size must be a multiple of the batch size.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the test set is not a whole number of batches, or if a batch cannot be proven.
+    pub fn prove(
+        &mut self,
+        samples: &[Sample],
+    ) -> Result<(AccuracyClaim, Vec<BatchProof>), AccuracyProofError> {
+        if self.batch_size == 0 || samples.len() % self.batch_size != 0 {
+            return Err(AccuracyProofError::PartialBatch(
+                samples.len(),
+                self.batch_size,
+            ));
+        }
+
+        let mut state = (Fp::zero(), Fp::zero());
+        let mut batches = vec![];
+        for (i, batch) in samples.chunks(self.batch_size).enumerate() {
+            let debug = false;
+            let (proof, output) = self
+                .prover_index
+                .prove::<BaseSponge, ScalarSponge>(state, batch.to_vec(), debug)
+                .map_err(|e| AccuracyProofError::Prove(i, e.source.to_string()))?;
+            batches.push(BatchProof {
+                proof,
+                input: state,
+                output: *output,
+            });
+            state = *output;
+        }
+
+        let claim = AccuracyClaim {
+            test_set: state.0,
+            correct: state.1.into_repr().as_ref()[0],
+            total: samples.len() as u64,
+        };
+        Ok((claim, batches))
+    }
+
+    /// Checks that the batch proofs chain into the claim.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if a proof does not verify,
+    /// if a batch does not start where the previous one ended,
+    /// or if the last batch does not end with the claimed values.
+    pub fn verify(
+        &self,
+        claim: &AccuracyClaim,
+        batches: &[BatchProof],
+    ) -> Result<(), AccuracyClaimError> {
+        let samples = batches.len() * self.batch_size;
+        if samples as u64 != claim.total {
+            return Err(AccuracyClaimError::SampleCount(samples, claim.total));
+        }
+
+        let mut state = (Fp::zero(), Fp::zero());
+        for (i, batch) in batches.iter().enumerate() {
+            if batch.input != state {
+                return Err(AccuracyClaimError::BrokenChain(i));
+            }
+            self.verifier_index
+                .try_verify::<BaseSponge, ScalarSponge>(&batch.proof, &batch.input, &batch.output)
+                .map_err(|e| AccuracyClaimError::InvalidProof(i, e))?;
+            state = batch.output;
+        }
+
+        if state != (claim.test_set, Fp::from(claim.correct)) {
+            return Err(AccuracyClaimError::ClaimMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Predicts class 1 if the second feature is set, assuming the features are one-hot encoded.
+    struct OneHot;
+
+    impl Classifier for OneHot {
+        fn features(&self) -> usize {
+            2
+        }
+
+        fn predict(
+            &self,
+            _: &mut RunState<Fp>,
+            _: Cow<'static, str>,
+            features: &[FieldVar<Fp>],
+        ) -> SnarkyResult<FieldVar<Fp>> {
+            Ok(features[1].clone())
+        }
+    }
+
+    fn sample(features: [u64; 2], label: u64) -> Sample {
+        Sample {
+            features: features.into_iter().map(Fp::from).collect(),
+            label: Fp::from(label),
+        }
+    }
+
+    #[test]
+    fn test_leaderboard() {
+        let samples = vec![
+            sample([1, 0], 0),
+            sample([0, 1], 1),
+            sample([0, 1], 0),
+            sample([1, 0], 0),
+        ];
+        let mut leaderboard = Leaderboard::new(OneHot, 2).unwrap();
+
+        assert!(matches!(
+            leaderboard.prove(&samples[..3]),
+            Err(AccuracyProofError::PartialBatch(3, 2))
+        ));
+        let (claim, batches) = leaderboard.prove(&samples).unwrap();
+        assert_eq!(claim.test_set, commit_test_set(&samples));
+        assert_eq!((claim.correct, claim.total), (3, 4));
+        leaderboard.verify(&claim, &batches).unwrap();
+
+        // the claim must match the proofs
+        let inflated = AccuracyClaim {
+            correct: 4,
+            ..claim
+        };
+        assert!(matches!(
+            leaderboard.verify(&inflated, &batches),
+            Err(AccuracyClaimError::ClaimMismatch)
+        ));
+
+        // the batches must be chained
+        let reordered: Vec<_> = batches.into_iter().rev().collect();
+        assert!(matches!(
+            leaderboard.verify(&claim, &reordered),
+            Err(AccuracyClaimError::BrokenChain(0))
+        ));
+    }
+}
@@ -0,0 +1,217 @@
+//! A benchmark scenario for similarity search: proving the locality-sensitive-hash bucket of a private input.
+//!
+//! The hash is made of random projections: bit `j` of the bucket is set
+//! if the inner product of the input with the projection vector `j` is at least the threshold `j`.
+//! The projection vectors are private, but committed: the circuit takes a commitment to them as public input,
+//! so that every bucket is computed against the same projections.
+//! Inputs and projections are unsigned fixed-point values of `width` bits
+//! (signed projections can be centered by the thresholds).
+
+use mina_curves::pasta::{Fp, Vesta};
+use poly_commitment::evaluation_proof::OpeningProof;
+
+use crate::{
+    curve::KimchiCurve,
+    loc,
+    snarky::{
+        api::SnarkyCircuit,
+        bits::{from_bits, to_bits, Endianness},
+        errors::{RealSnarkyError, SnarkyCompilationError, SnarkyError},
+        poseidon::{hash_slice, hash_slice_native},
+        prelude::{FieldVar, RunState, SnarkyResult},
+        statistics::at_least,
+    },
+};
+
+/// Commits to projection vectors, out of circuit, as the [hash_slice] of their values one vector after the other.
+pub fn commit_projections(projections: &[Vec<u64>]) -> Fp {
+    let values: Vec<_> = projections
+        .iter()
+        .flatten()
+        .map(|&value| Fp::from(value))
+        .collect();
+    hash_slice_native(Vesta::sponge_params(), &values)
+}
+
+/// The private input of the [LshCircuit].
+pub struct LshWitness {
+    pub input: Vec<u64>,
+    pub projections: Vec<Vec<u64>>,
+}
+
+/// The circuit proving the bucket of an input.
+pub struct LshCircuit {
+    /// The number of dimensions of the inputs and of the projections.
+    dims: usize,
+    /// The threshold of each projection, i.e. of each bit of the bucket.
+    thresholds: Vec<u64>,
+    /// The number of bits of the inputs and of the projections.
+    width: usize,
+}
+
+impl LshCircuit {
+    /// Creates the circuit hashing inputs of `dims` dimensions with a projection per threshold.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if a threshold is larger than `2^product_width`, the bound of the comparisons of the inner products.
+    pub fn new(dims: usize, thresholds: Vec<u64>, width: usize) -> SnarkyResult<Self> {
+        let circuit = Self {
+            dims,
+            thresholds,
+            width,
+        };
+        let product_width = circuit.product_width();
+        for (j, &threshold) in circuit.thresholds.iter().enumerate() {
+            if product_width < 64 && threshold > 1 << product_width {
+                return Err(Box::new(RealSnarkyError::new(
+                    SnarkyError::CompilationError(SnarkyCompilationError::ValueTooWide(
+                        format!("threshold {j}"),
+                        threshold,
+                        product_width,
+                    )),
+                )));
+            }
+        }
+        Ok(circuit)
+    }
+
+    /// The number of bits of an inner product.
+    fn product_width(&self) -> usize {
+        let dims_bits = (usize::BITS - self.dims.leading_zeros()) as usize;
+        2 * self.width + dims_bits
+    }
+
+    /// Computes the bits of the bucket of an input, out of circuit, bit `j` being the one of projection `j`.
+    pub fn bucket_native(&self, witness: &LshWitness) -> Vec<bool> {
+        witness
+            .projections
+            .iter()
+            .zip(&self.thresholds)
+            .map(|(projection, &threshold)| {
+                let product: u128 = projection
+                    .iter()
+                    .zip(&witness.input)
+                    .map(|(&r, &x)| r as u128 * x as u128)
+                    .sum();
+                product >= threshold as u128
+            })
+            .collect()
+    }
+}
+
+impl SnarkyCircuit for LshCircuit {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = LshWitness;
+    /// The commitment to the projections.
+    type PublicInput = FieldVar<Fp>;
+    /// The bucket, bit `j` being the one of projection `j`.
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        commitment: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let mut input = Vec::with_capacity(self.dims);
+        for i in 0..self.dims {
+            let value: FieldVar<Fp> =
+                sys.compute(loc!(), |_| Fp::from(private.unwrap().input[i]))?;
+            to_bits(sys, loc!(), &value, self.width, Endianness::Little)?;
+            input.push(value);
+        }
+
+        let mut opened = Vec::with_capacity(self.thresholds.len() * self.dims);
+        let mut bits = Vec::with_capacity(self.thresholds.len());
+        for (j, &threshold) in self.thresholds.iter().enumerate() {
+            let mut projection = Vec::with_capacity(self.dims);
+            for i in 0..self.dims {
+                let value: FieldVar<Fp> =
+                    sys.compute(loc!(), |_| Fp::from(private.unwrap().projections[j][i]))?;
+                to_bits(sys, loc!(), &value, self.width, Endianness::Little)?;
+                opened.push(value.clone());
+                projection.push(value);
+            }
+
+            let product = FieldVar::dot_product(&input, &projection, None, loc!(), sys)?;
+            bits.push(at_least(
+                sys,
+                loc!(),
+                &product,
+                self.product_width(),
+                threshold,
+            )?);
+        }
+
+        let opened = hash_slice(sys, loc!(), &opened);
+        sys.assert_eq(Some("lsh.projections".into()), loc!(), opened, commitment)?;
+        Ok(from_bits(&bits, Endianness::Little))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_ff::Field;
+
+    use super::*;
+    use crate::bench::{prove_and_verify, BenchCircuit};
+
+    #[test]
+    fn test_lsh() {
+        let circuit = LshCircuit::new(3, vec![20, 100], 4).unwrap();
+        let projections = vec![vec![1, 2, 3], vec![7, 0, 5]];
+        let commitment = commit_projections(&projections);
+        let low = LshWitness {
+            input: vec![4, 5, 1],
+            projections: projections.clone(),
+        };
+        // 4 + 10 + 3 = 17 < 20, and 28 + 0 + 5 = 33 < 100
+        assert_eq!(circuit.bucket_native(&low), vec![false, false]);
+        let high = LshWitness {
+            input: vec![9, 5, 15],
+            projections,
+        };
+        // 9 + 10 + 45 = 64 >= 20, and 63 + 0 + 75 = 138 >= 100
+        assert_eq!(circuit.bucket_native(&high), vec![true, true]);
+
+        // the circuit is compiled once, and proven on both inputs
+        let mut bench = BenchCircuit::compile(circuit).unwrap();
+        let run = bench.prove_and_verify(commitment, low).unwrap();
+        assert_eq!(run.output, Fp::from(0u64));
+        let run = bench.prove_and_verify(commitment, high).unwrap();
+        assert_eq!(run.output, Fp::from(0b11u64));
+
+        // buckets can have more bits than a word
+        let wide = LshCircuit::new(1, (0..100).collect(), 7).unwrap();
+        let projections = vec![vec![50]; 100];
+        let commitment = commit_projections(&projections);
+        let witness = LshWitness {
+            input: vec![1],
+            projections,
+        };
+        let bits = wide.bucket_native(&witness);
+        assert_eq!(bits.len(), 100);
+        assert_eq!(bits.iter().filter(|&&bit| bit).count(), 51);
+
+        let run = prove_and_verify(wide, commitment, witness).unwrap();
+        // the projections 0 to 50 are at least their thresholds
+        let expected = (0..=50).fold(Fp::from(0u64), |bucket, j| {
+            bucket + Fp::from(2u64).pow([j as u64])
+        });
+        assert_eq!(run.output, expected);
+    }
+
+    #[test]
+    fn test_lsh_validates_thresholds() {
+        // inner products of 1 dimension of 2 bits fit in 2 * 2 + 1 bits
+        assert!(LshCircuit::new(1, vec![32], 2).is_ok());
+        let err = LshCircuit::new(1, vec![1, 33], 2).err().unwrap();
+        assert!(matches!(
+            err.source,
+            SnarkyError::CompilationError(SnarkyCompilationError::ValueTooWide(_, 33, 5))
+        ));
+    }
+}
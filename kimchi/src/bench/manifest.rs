@@ -0,0 +1,267 @@
+//! Reproducible experiment manifests.
+//!
+//! A manifest pins everything a benchmark result depends on in a single file:
+//! the model and the dataset (by path and hash), the quantization configuration,
+//! the circuit and the SRS (by hash), and the versions of the crates.
+//! It also records the deterministic results of the run (the sizes of the circuit and of a proof that verified),
+//! so that re-running a manifest tells whether the same inputs still give the same results,
+//! and if not, which of the inputs changed (see [Manifest::diff]).
+//! Timings are not part of the results, as they depend on the host.
+//!
+//! The model and the dataset are metadata: they are hashed, so that a manifest pins them,
+//! but the benchmark always runs the built-in circuit of [BenchmarkCtx] for the SRS size of the manifest,
+//! whatever the model, as the crate has no importer building a circuit from a model file.
+//! The results therefore tell whether the prover and its dependencies behave the same,
+//! and the hashes whether the run was made with the same files.
+//!
+//! Hashes are hex-encoded Blake2b-512 digests.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::PathBuf,
+};
+
+use ark_serialize::CanonicalSerialize;
+use blake2::{Blake2b512, Digest};
+use serde::{Deserialize, Serialize};
+
+use super::BenchmarkCtx;
+
+/// Hashes bytes.
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    let mut h = Blake2b512::new();
+    h.update(bytes);
+    hex::encode(h.finalize())
+}
+
+/// A file an experiment depends on.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HashedFile {
+    pub path: PathBuf,
+    pub hash: String,
+}
+
+impl HashedFile {
+    /// Reads and hashes a file.
+    pub fn new(path: PathBuf) -> io::Result<Self> {
+        let hash = hash_bytes(&fs::read(&path)?);
+        Ok(Self { path, hash })
+    }
+}
+
+/// The deterministic results of a run.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RunResults {
+    pub num_gates: usize,
+    pub domain_size: usize,
+    /// The size in bytes of a serialized proof, which verified.
+    pub proof_size: usize,
+}
+
+/// The versions of the crates pinned by the contents of a `Cargo.lock`.
+pub fn crate_versions(lock: &str) -> BTreeMap<String, String> {
+    let mut versions = BTreeMap::new();
+    let mut name = None;
+    for line in lock.lines() {
+        if let Some(value) = line.strip_prefix("name = ") {
+            name = Some(value.trim_matches('"').to_string());
+        } else if let Some(value) = line.strip_prefix("version = ") {
+            if let Some(name) = name.take() {
+                versions.insert(name, value.trim_matches('"').to_string());
+            }
+        }
+    }
+    versions
+}
+
+impl BenchmarkCtx {
+    /// Hashes the gates of the circuit.
+    pub fn circuit_hash(&self) -> String {
+        hash_bytes(&rmp_serde::to_vec(&self.index.cs.gates).unwrap())
+    }
+
+    /// Hashes the points of the SRS.
+    pub fn srs_hash(&self) -> String {
+        let mut bytes = vec![];
+        self.index.srs.g.serialize(&mut bytes).unwrap();
+        self.index.srs.h.serialize(&mut bytes).unwrap();
+        hash_bytes(&bytes)
+    }
+}
+
+/// A manifest of a benchmark run.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Manifest {
+    pub model: HashedFile,
+    pub dataset: HashedFile,
+    pub quantization: serde_json::Value,
+    pub srs_size_log2: u32,
+    pub circuit_hash: String,
+    pub srs_hash: String,
+    /// The version of this crate, and of its dependencies if a `Cargo.lock` was given.
+    pub versions: BTreeMap<String, String>,
+    pub results: RunResults,
+}
+
+impl Manifest {
+    /// Runs the benchmark for an SRS of `2^srs_size_log2` points and records its manifest.
+    /// The model and the dataset are only hashed: the benchmarked circuit is the built-in one of [BenchmarkCtx].
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the model, the dataset or the `Cargo.lock` cannot be read.
+    pub fn run(
+        model: PathBuf,
+        dataset: PathBuf,
+        quantization: serde_json::Value,
+        srs_size_log2: u32,
+        lock: Option<PathBuf>,
+    ) -> io::Result<Self> {
+        let model = HashedFile::new(model)?;
+        let dataset = HashedFile::new(dataset)?;
+        let mut versions = match lock {
+            Some(lock) => crate_versions(&fs::read_to_string(lock)?),
+            None => BTreeMap::new(),
+        };
+        versions.insert(
+            env!("CARGO_PKG_NAME").to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+        );
+
+        let ctx = BenchmarkCtx::new(srs_size_log2);
+        let proof = ctx.create_proof();
+        ctx.batch_verification(std::slice::from_ref(&proof));
+        let results = RunResults {
+            num_gates: ctx.num_gates,
+            domain_size: ctx.index.cs.domain.d1.size as usize,
+            proof_size: BenchmarkCtx::proof_size(&proof.0),
+        };
+
+        Ok(Self {
+            model,
+            dataset,
+            quantization,
+            srs_size_log2,
+            circuit_hash: ctx.circuit_hash(),
+            srs_hash: ctx.srs_hash(),
+            versions,
+            results,
+        })
+    }
+
+    /// Re-runs the benchmark with the inputs of the manifest, reading the model and the dataset again.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the model, the dataset or the `Cargo.lock` cannot be read.
+    pub fn reproduce(&self, lock: Option<PathBuf>) -> io::Result<Self> {
+        Self::run(
+            self.model.path.clone(),
+            self.dataset.path.clone(),
+            self.quantization.clone(),
+            self.srs_size_log2,
+            lock,
+        )
+    }
+
+    /// Describes the fields that differ between two manifests, empty if they are the same.
+    pub fn diff(&self, other: &Self) -> Vec<String> {
+        let mut diffs = vec![];
+        let mut compare = |field: &str, left: String, right: String| {
+            if left != right {
+                diffs.push(format!("{field}: {left} != {right}"));
+            }
+        };
+        compare("model", self.model.hash.clone(), other.model.hash.clone());
+        compare(
+            "dataset",
+            self.dataset.hash.clone(),
+            other.dataset.hash.clone(),
+        );
+        compare(
+            "quantization",
+            self.quantization.to_string(),
+            other.quantization.to_string(),
+        );
+        compare(
+            "srs size",
+            self.srs_size_log2.to_string(),
+            other.srs_size_log2.to_string(),
+        );
+        compare(
+            "circuit",
+            self.circuit_hash.clone(),
+            other.circuit_hash.clone(),
+        );
+        compare("srs", self.srs_hash.clone(), other.srs_hash.clone());
+        compare(
+            "results",
+            format!("{:?}", self.results),
+            format!("{:?}", other.results),
+        );
+
+        let names: BTreeSet<_> = self.versions.keys().chain(other.versions.keys()).collect();
+        for name in names {
+            let version = |versions: &BTreeMap<String, String>| {
+                versions
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_else(|| "none".to_string())
+            };
+            compare(
+                &format!("version of {name}"),
+                version(&self.versions),
+                version(&other.versions),
+            );
+        }
+        diffs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest() {
+        let dir = std::env::temp_dir().join("kimchi_test_manifest");
+        fs::create_dir_all(&dir).unwrap();
+        let model = dir.join("model.json");
+        let dataset = dir.join("dataset.json");
+        fs::write(&model, r#"{"weights": [1, 2]}"#).unwrap();
+        fs::write(&dataset, "[[3, 4]]").unwrap();
+        let quantization = serde_json::json!({ "scale_bits": 16 });
+
+        let manifest = Manifest::run(model, dataset.clone(), quantization, 4, None).unwrap();
+        let json = serde_json::to_string(&manifest).unwrap();
+        let manifest: Manifest = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            manifest.diff(&manifest.reproduce(None).unwrap()),
+            Vec::<String>::new()
+        );
+
+        // changing the dataset is the only difference
+        fs::write(&dataset, "[[3, 5]]").unwrap();
+        let diffs = manifest.diff(&manifest.reproduce(None).unwrap());
+        assert_eq!(diffs.len(), 1);
+        assert!(diffs[0].starts_with("dataset"));
+
+        // the model is metadata: changing it does not change the benchmarked circuit
+        fs::write(&dataset, "[[3, 4]]").unwrap();
+        fs::write(&manifest.model.path, r#"{"weights": [1, 2, 3]}"#).unwrap();
+        let diffs = manifest.diff(&manifest.reproduce(None).unwrap());
+        assert_eq!(diffs.len(), 1);
+        assert!(diffs[0].starts_with("model"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_crate_versions() {
+        let lock = "[[package]]\nname = \"ark-ff\"\nversion = \"0.3.0\"\nsource = \"registry\"\n\n[[package]]\nname = \"kimchi\"\nversion = \"0.1.0\"\n";
+        let versions = crate_versions(lock);
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions["ark-ff"], "0.3.0");
+    }
+}
@@ -0,0 +1,178 @@
+//! A benchmark scenario for weights protected at rest: proving an inference with weights stored masked.
+//!
+//! Each weight is masked with a one-time pad: the `i`-th pseudo-random value derived from a key (see [crate::snarky::prf]).
+//! The circuit only embeds the masked weights, and unmasks them with the private key,
+//! whose commitment is a public input so that every proof uses the same weights.
+//! Comparing the masked circuit with the same model in the clear quantifies the overhead of the protection:
+//! a permutation per weight, and a multiplication per weight, as the unmasked weights are no longer constants.
+
+use std::time::Duration;
+
+use mina_curves::pasta::{Fp, Vesta};
+use poly_commitment::evaluation_proof::OpeningProof;
+use serde::Serialize;
+
+use super::prove_and_verify;
+use crate::{
+    curve::KimchiCurve,
+    loc,
+    snarky::{
+        api::SnarkyCircuit,
+        prelude::{FieldVar, RunState, SnarkyResult},
+        prf::{commit_seed, prf, Prf},
+    },
+};
+
+/// Masks weights with the pseudo-random values derived from a key, out of circuit.
+pub fn mask_weights(key: Fp, weights: &[u64]) -> Vec<Fp> {
+    let params = Vesta::sponge_params();
+    weights
+        .iter()
+        .enumerate()
+        .map(|(i, &weight)| Fp::from(weight) + prf(params, key, i as u64))
+        .collect()
+}
+
+/// A linear model with its weights in the clear.
+pub struct PlainLinear {
+    pub weights: Vec<u64>,
+}
+
+impl SnarkyCircuit for PlainLinear {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    /// The input of the model.
+    type PrivateInput = Vec<u64>;
+    type PublicInput = ();
+    /// The score of the input.
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        _: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let mut terms = Vec::with_capacity(self.weights.len());
+        for (i, &weight) in self.weights.iter().enumerate() {
+            let x: FieldVar<Fp> = sys.compute(loc!(), |_| Fp::from(private.unwrap()[i]))?;
+            terms.push((Fp::from(weight), x));
+        }
+        Ok(FieldVar::linear_combination(&terms))
+    }
+}
+
+/// The private input of the [MaskedLinear] circuit.
+pub struct MaskedWitness {
+    pub key: Fp,
+    pub input: Vec<u64>,
+}
+
+/// The same linear model as [PlainLinear], with its weights masked.
+pub struct MaskedLinear {
+    /// The weights masked by [mask_weights].
+    pub masked: Vec<Fp>,
+}
+
+impl SnarkyCircuit for MaskedLinear {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = MaskedWitness;
+    /// The commitment to the key (see [commit_seed]).
+    type PublicInput = FieldVar<Fp>;
+    /// The score of the input.
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        commitment: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let key: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap().key)?;
+        let pad = Prf::new(sys, loc!(), key);
+        sys.assert_eq(
+            Some("masked_weights.key".into()),
+            loc!(),
+            pad.commitment().clone(),
+            commitment,
+        )?;
+
+        let mut weights = Vec::with_capacity(self.masked.len());
+        let mut input = Vec::with_capacity(self.masked.len());
+        for (i, &masked) in self.masked.iter().enumerate() {
+            let mask = pad.eval(sys, loc!(), i as u64);
+            weights.push(&FieldVar::constant(masked) - &mask);
+
+            let x: FieldVar<Fp> = sys.compute(loc!(), |_| Fp::from(private.unwrap().input[i]))?;
+            input.push(x);
+        }
+        FieldVar::dot_product(&weights, &input, None, loc!(), sys)
+    }
+}
+
+/// The cost of masking the weights of a model.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct MaskingOverhead {
+    /// The number of gates of the model in the clear.
+    pub plain_gates: usize,
+    /// The number of gates of the masked model.
+    pub masked_gates: usize,
+    /// Proving with the model in the clear.
+    pub plain_prove: Duration,
+    /// Proving with the masked model.
+    pub masked_prove: Duration,
+}
+
+impl MaskingOverhead {
+    /// The number of gates of the masked model, relative to the model in the clear.
+    pub fn gates_ratio(&self) -> f64 {
+        self.masked_gates as f64 / self.plain_gates as f64
+    }
+}
+
+/// Proves an inference of a linear model in the clear and with its weights masked by `key`,
+/// and checks that both proofs output the same score.
+pub fn measure_overhead(weights: &[u64], key: Fp, input: &[u64]) -> SnarkyResult<MaskingOverhead> {
+    let plain = PlainLinear {
+        weights: weights.to_vec(),
+    };
+    let plain = prove_and_verify(plain, (), input.to_vec())?;
+
+    let masked = MaskedLinear {
+        masked: mask_weights(key, weights),
+    };
+    let commitment = commit_seed(Vesta::sponge_params(), key);
+    let witness = MaskedWitness {
+        key,
+        input: input.to_vec(),
+    };
+    let masked = prove_and_verify(masked, commitment, witness)?;
+    assert_eq!(plain.output, masked.output, "masking changed the score");
+
+    Ok(MaskingOverhead {
+        plain_gates: plain.gates,
+        masked_gates: masked.gates,
+        plain_prove: plain.prove,
+        masked_prove: masked.prove,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_masked_weights() {
+        let key = Fp::from(1234u64);
+        let weights = [3, 0, 7, 1];
+        let masked = mask_weights(key, &weights);
+        assert!(masked.iter().zip(weights).all(|(m, w)| *m != Fp::from(w)));
+
+        let overhead = measure_overhead(&weights, key, &[1, 2, 3, 4]).unwrap();
+        assert!(overhead.gates_ratio() > 1.);
+        println!("masking: {}", serde_json::to_string(&overhead).unwrap());
+    }
+}
@@ -0,0 +1,519 @@
+pub mod ab;
+pub mod baseline;
+pub mod compression;
+pub mod costs;
+pub mod daemon;
+pub mod dataset;
+pub mod failure;
+pub mod fault_injection;
+pub mod forest;
+pub mod hashes;
+pub mod leaderboard;
+pub mod lsh;
+pub mod manifest;
+pub mod masked_weights;
+pub mod plugin;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod query;
+pub mod recommendation;
+pub mod reference;
+pub mod roofline;
+pub mod scaffold;
+pub mod security;
+pub mod session;
+pub mod size;
+pub mod state;
+pub mod store;
+pub mod tee;
+pub mod timing;
+
+use std::{
+    array,
+    time::{Duration, Instant},
+};
+
+use groupmap::{BWParameters, GroupMap};
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use o1_utils::math;
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof};
+
+use self::{
+    costs::{AggregationReport, OneTimeCosts, OpeningAggregation, RecurringCosts},
+    fault_injection::{inject_faults, RobustnessReport},
+    roofline::{analyze, HostPeaks, RooflineReport},
+    security::SecurityLevel,
+    size::BackendSize,
+    timing::{audit, TimingAudit},
+};
+use crate::{
+    circuits::{
+        gate::CircuitGate,
+        polynomials::generic::GenericGateSpec,
+        wires::{Wire, COLUMNS},
+    },
+    error::TimingAuditError,
+    proof::ProverProof,
+    prover_index::{testing::new_index_for_test, ProverIndex},
+    snarky::{
+        api::{ProverIndexWrapper, SnarkyCircuit, VerifierIndexWrapper},
+        errors::SnarkyResult,
+        snarky_type::SnarkyType,
+    },
+    verifier::{batch_verify, verify, Context},
+    verifier_index::VerifierIndex,
+};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+pub struct BenchmarkCtx {
+    pub num_gates: usize,
+    group_map: BWParameters<VestaParameters>,
+    index: ProverIndex<Vesta, OpeningProof<Vesta>>,
+    verifier_index: VerifierIndex<Vesta, OpeningProof<Vesta>>,
+}
+
+impl BenchmarkCtx {
+    pub fn srs_size(&self) -> usize {
+        math::ceil_log2(self.index.srs.max_degree())
+    }
+
+    /// This will create a context that allows for benchmarks of `num_gates` gates (multiplication gates).
+    pub fn new(srs_size_log2: u32) -> Self {
+        Self::with_costs(srs_size_log2).0
+    }
+
+    /// Same as [Self::new], but also measures the one-time costs of the context.
+    pub fn with_costs(srs_size_log2: u32) -> (Self, OneTimeCosts) {
+        // there's some overhead that we need to remove (e.g. zk rows)
+
+        let num_gates = ((1 << srs_size_log2) - 10) as usize;
+
+        // create the circuit
+        let start = Instant::now();
+        let mut gates = vec![];
+
+        #[allow(clippy::explicit_counter_loop)]
+        for row in 0..num_gates {
+            let wires = Wire::for_row(row);
+            gates.push(CircuitGate::create_generic_gadget(
+                wires,
+                GenericGateSpec::Const(1u32.into()),
+                None,
+            ));
+        }
+        let compile = start.elapsed();
+
+        // group map
+        let start = Instant::now();
+        let group_map = <Vesta as CommitmentCurve>::Map::setup();
+
+        // create the index
+        let index = new_index_for_test(gates, 0);
+        let setup = start.elapsed();
+
+        assert_eq!(index.cs.domain.d1.log_size_of_group, srs_size_log2, "the test wanted to use an SRS of size {srs_size_log2} but the domain size ended up being {}", index.cs.domain.d1.log_size_of_group);
+
+        // create the verifier index
+        let start = Instant::now();
+        let verifier_index = index.verifier_index();
+        let key_gen = start.elapsed();
+
+        //
+        let ctx = BenchmarkCtx {
+            num_gates,
+            group_map,
+            index,
+            verifier_index,
+        };
+        let costs = OneTimeCosts {
+            compile,
+            setup,
+            key_gen,
+        };
+        (ctx, costs)
+    }
+
+    /// Measures the recurring costs of an inference: computing the witness, proving and verifying.
+    pub fn recurring_costs(&self) -> RecurringCosts {
+        let start = Instant::now();
+        let witness = self.witness();
+        let witness_time = start.elapsed();
+
+        let public_input = witness[0][0..self.index.cs.public].to_vec();
+        let start = Instant::now();
+        let proof = ProverProof::create::<BaseSponge, ScalarSponge>(
+            &self.group_map,
+            witness,
+            &[],
+            &self.index,
+        )
+        .unwrap();
+        let prove = start.elapsed();
+
+        let start = Instant::now();
+        self.individual_verification(&[(proof, public_input)]);
+        let verify = start.elapsed();
+
+        RecurringCosts {
+            witness: witness_time,
+            prove,
+            verify,
+        }
+    }
+
+    /// Computes the values that the prover and the verifier otherwise compute lazily on their first use
+    /// (the domain precomputations of the constraint system, and the zero-knowledge polynomials of the verifier index),
+    /// and returns the time it took.
+    /// Latency benchmarks call this before measuring, so that the first measured proof does not pay for the setup.
+    pub fn preload(&self) -> Duration {
+        let start = Instant::now();
+        self.index.cs.precomputations();
+        self.verifier_index.permutation_vanishing_polynomial_m();
+        self.verifier_index.w();
+        start.elapsed()
+    }
+
+    /// Creates and verifies `proofs` proofs that are thrown away,
+    /// to warm up the caches, the allocator and the thread pool before steady-state measurements,
+    /// and returns the time it took.
+    pub fn warm(&self, proofs: usize) -> Duration {
+        let start = Instant::now();
+        for _ in 0..proofs {
+            let proof = self.create_proof();
+            self.batch_verification(std::slice::from_ref(&proof));
+        }
+        start.elapsed()
+    }
+
+    fn witness(&self) -> [Vec<Fp>; COLUMNS] {
+        array::from_fn(|_| vec![1u32.into(); self.num_gates])
+    }
+
+    /// Produces a proof
+    pub fn create_proof(&self) -> (ProverProof<Vesta, OpeningProof<Vesta>>, Vec<Fp>) {
+        // create witness
+        let witness = self.witness();
+
+        let public_input = witness[0][0..self.index.cs.public].to_vec();
+
+        // add the proof to the batch
+        (
+            ProverProof::create::<BaseSponge, ScalarSponge>(
+                &self.group_map,
+                witness,
+                &[],
+                &self.index,
+            )
+            .unwrap(),
+            public_input,
+        )
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn batch_verification(&self, batch: &[(ProverProof<Vesta, OpeningProof<Vesta>>, Vec<Fp>)]) {
+        // verify the proof
+        let batch: Vec<_> = batch
+            .iter()
+            .map(|(proof, public)| Context {
+                verifier_index: &self.verifier_index,
+                proof,
+                public_input: public,
+            })
+            .collect();
+        batch_verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+            &self.group_map,
+            &batch,
+        )
+        .unwrap();
+    }
+
+    /// Verifies a batch of proofs, checking their openings as configured.
+    #[allow(clippy::type_complexity)]
+    pub fn verify_with(
+        &self,
+        batch: &[(ProverProof<Vesta, OpeningProof<Vesta>>, Vec<Fp>)],
+        aggregation: OpeningAggregation,
+    ) {
+        match aggregation {
+            OpeningAggregation::PerProof => self.individual_verification(batch),
+            OpeningAggregation::Batched => self.batch_verification(batch),
+        }
+    }
+
+    /// Measures the size of the proofs of a batch, and the time to verify them with each [OpeningAggregation].
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the batch is empty, or if a proof does not verify.
+    #[allow(clippy::type_complexity)]
+    pub fn aggregation_report(
+        &self,
+        batch: &[(ProverProof<Vesta, OpeningProof<Vesta>>, Vec<Fp>)],
+    ) -> AggregationReport {
+        let proof_size = Self::proof_size(&batch.first().expect("the batch is empty").0);
+        let [per_proof, batched] =
+            [OpeningAggregation::PerProof, OpeningAggregation::Batched].map(|aggregation| {
+                let start = Instant::now();
+                self.verify_with(batch, aggregation);
+                start.elapsed()
+            });
+        AggregationReport {
+            proofs: batch.len(),
+            proof_size,
+            per_proof,
+            batched,
+        }
+    }
+
+    /// Verifies each proof of the batch on its own,
+    /// so that every proof pays for its own opening check.
+    /// This is the baseline that [Self::batch_verification] amortizes against.
+    #[allow(clippy::type_complexity)]
+    pub fn individual_verification(
+        &self,
+        batch: &[(ProverProof<Vesta, OpeningProof<Vesta>>, Vec<Fp>)],
+    ) {
+        for (proof, public) in batch {
+            verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+                &self.group_map,
+                &self.verifier_index,
+                proof,
+                public,
+            )
+            .unwrap();
+        }
+    }
+
+    /// Checks that the verifier rejects every class of corruption of the given proof.
+    pub fn fault_injection(
+        &self,
+        (proof, public): &(ProverProof<Vesta, OpeningProof<Vesta>>, Vec<Fp>),
+    ) -> RobustnessReport {
+        inject_faults::<Vesta, BaseSponge, ScalarSponge>(
+            &self.group_map,
+            &self.verifier_index,
+            proof,
+            public,
+        )
+    }
+
+    /// Audits whether the verification time depends on which proof is verified,
+    /// by interleaving verifications of `fixed` with verifications of proofs picked from `varying`.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if there are no varying proofs, or if a class got fewer than 2 measurements.
+    #[allow(clippy::type_complexity)]
+    pub fn verification_timing_audit(
+        &self,
+        fixed: &(ProverProof<Vesta, OpeningProof<Vesta>>, Vec<Fp>),
+        varying: &[(ProverProof<Vesta, OpeningProof<Vesta>>, Vec<Fp>)],
+        measurements: usize,
+    ) -> Result<TimingAudit, TimingAuditError> {
+        if varying.is_empty() {
+            return Err(TimingAuditError::NoVaryingInputs);
+        }
+        let mut next = 0;
+        audit(
+            measurements,
+            |class| {
+                if class {
+                    next = (next + 1) % varying.len();
+                    &varying[next]
+                } else {
+                    fixed
+                }
+            },
+            |proof| self.batch_verification(std::slice::from_ref(proof)),
+        )
+    }
+
+    /// Measures how close the FFTs and MSMs of the prover get to the limits of the host,
+    /// at the sizes used by this circuit.
+    pub fn roofline(&self, peaks: HostPeaks) -> RooflineReport {
+        analyze(
+            self.index.cs.domain.d1.size as usize,
+            &self.index.srs.g,
+            peaks,
+        )
+    }
+
+    /// Measures the size of the circuit, in the units shared by all backends.
+    pub fn size(&self) -> BackendSize {
+        BackendSize::kimchi(&self.index.cs.gates)
+    }
+
+    /// Estimates the security level of the proofs of the circuit.
+    pub fn security(&self) -> SecurityLevel {
+        SecurityLevel::kimchi(
+            self.index.cs.domain.d1.log_size_of_group,
+            self.index.cs.lookup_constraint_system.is_some(),
+        )
+    }
+
+    /// Returns the size in bytes of a serialized proof.
+    pub fn proof_size(proof: &ProverProof<Vesta, OpeningProof<Vesta>>) -> usize {
+        rmp_serde::to_vec(proof).unwrap().len()
+    }
+}
+
+/// A circuit proven once and verified, by [prove_and_verify] or [BenchCircuit::prove_and_verify].
+pub struct CircuitRun<C: SnarkyCircuit> {
+    /// The number of gates of the circuit.
+    pub gates: usize,
+    /// The proof.
+    pub proof: ProverProof<Vesta, OpeningProof<Vesta>>,
+    /// The public output of the proof.
+    pub output: <C::PublicOutput as SnarkyType<Fp>>::OutOfCircuit,
+    pub prove: Duration,
+    pub verify: Duration,
+}
+
+/// A circuit compiled once, to be proven and verified on many inputs.
+pub struct BenchCircuit<C: SnarkyCircuit> {
+    pub prover_index: ProverIndexWrapper<C>,
+    pub verifier_index: VerifierIndexWrapper<C>,
+}
+
+impl<C> BenchCircuit<C>
+where
+    C: SnarkyCircuit<Curve = Vesta, Proof = OpeningProof<Vesta>>,
+    <C::PublicInput as SnarkyType<Fp>>::OutOfCircuit: Clone,
+{
+    /// Compiles a circuit.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the circuit does not compile.
+    pub fn compile(circuit: C) -> SnarkyResult<Self> {
+        let (prover_index, verifier_index) = circuit.compile_to_indexes()?;
+        Ok(BenchCircuit {
+            prover_index,
+            verifier_index,
+        })
+    }
+
+    /// Proves the circuit on the given inputs and verifies the proof,
+    /// measuring the proof and the verification.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the inputs do not satisfy the circuit.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the proof does not verify.
+    pub fn prove_and_verify(
+        &mut self,
+        public: <C::PublicInput as SnarkyType<Fp>>::OutOfCircuit,
+        private: C::PrivateInput,
+    ) -> SnarkyResult<CircuitRun<C>> {
+        let gates = self.prover_index.num_gates();
+
+        let start = Instant::now();
+        let (proof, output) =
+            self.prover_index
+                .prove::<BaseSponge, ScalarSponge>(public.clone(), private, false)?;
+        let prove = start.elapsed();
+
+        let start = Instant::now();
+        self.verifier_index
+            .try_verify::<BaseSponge, ScalarSponge>(&proof, &public, &output)
+            .expect("the proof does not verify");
+        let verify = start.elapsed();
+
+        Ok(CircuitRun {
+            gates,
+            proof,
+            output: *output,
+            prove,
+            verify,
+        })
+    }
+}
+
+/// Compiles a circuit, proves it on the given inputs and verifies the proof,
+/// measuring the proof and the verification.
+/// Circuits proven on many inputs are compiled once with [BenchCircuit::compile] instead.
+///
+/// # Errors
+///
+/// Will give error if the circuit does not compile, or if the inputs do not satisfy it.
+///
+/// # Panics
+///
+/// Will panic if the proof does not verify.
+pub fn prove_and_verify<C>(
+    circuit: C,
+    public: <C::PublicInput as SnarkyType<Fp>>::OutOfCircuit,
+    private: C::PrivateInput,
+) -> SnarkyResult<CircuitRun<C>>
+where
+    C: SnarkyCircuit<Curve = Vesta, Proof = OpeningProof<Vesta>>,
+    <C::PublicInput as SnarkyType<Fp>>::OutOfCircuit: Clone,
+{
+    BenchCircuit::compile(circuit)?.prove_and_verify(public, private)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::costs::CostReport;
+
+    #[test]
+    fn test_bench() {
+        // context created in 21.2235 ms
+        let start = Instant::now();
+        let srs_size = 4;
+        let (ctx, one_time) = BenchmarkCtx::with_costs(srs_size);
+        println!("testing bench code for SRS of size {srs_size}");
+        println!("context created in {}s", start.elapsed().as_secs());
+
+        // one-time costs, kept out of the steady-state measurements below
+        println!("preloaded in {:?}", ctx.preload());
+        println!("warmed up in {:?}", ctx.warm(1));
+
+        let roofline = ctx.roofline(HostPeaks::measure());
+        println!("roofline: {}", serde_json::to_string(&roofline).unwrap());
+
+        let report = CostReport::new("bench".to_string(), one_time, ctx.recurring_costs())
+            .with_security(ctx.security());
+        println!("costs: {}", serde_json::to_string(&report).unwrap());
+        println!("size: {}", serde_json::to_string(&ctx.size()).unwrap());
+
+        // proof created in 7.1227 ms
+        let start = Instant::now();
+        let (proof, public_input) = ctx.create_proof();
+        println!("proof created in {}s", start.elapsed().as_secs());
+
+        // proof verified in 1.710 ms
+        let start = Instant::now();
+        let batch = vec![(proof, public_input)];
+        ctx.batch_verification(&batch);
+        println!("proof verified in {}", start.elapsed().as_secs());
+
+        // the same proofs verified with and without aggregating their openings
+        let proofs = vec![batch[0].clone(); 4];
+        let aggregation = ctx.aggregation_report(&proofs);
+        assert_eq!(aggregation.proofs, 4);
+        assert_eq!(
+            aggregation.proof_size,
+            BenchmarkCtx::proof_size(&batch[0].0)
+        );
+        println!(
+            "aggregation: {}",
+            serde_json::to_string(&aggregation).unwrap()
+        );
+
+        // every corruption of the proof must be rejected
+        let robustness = ctx.fault_injection(&batch[0]);
+        assert!(robustness.all_rejected(), "{robustness:?}");
+    }
+}
@@ -0,0 +1,205 @@
+//! Plugins contributing circuits and backends to the benchmark suite from other crates.
+//!
+//! A crate implements [CircuitPlugin] for a circuit proven with kimchi, or [BackendPlugin] for another proof system,
+//! and registers it with [register_circuit] or [register_backend] before running the suite:
+//!
+//! ```ignore
+//! fn main() {
+//!     kimchi::bench::plugin::register_circuit(MyCircuit).unwrap();
+//!     let results = kimchi::bench::plugin::run_suite(12);
+//! }
+//! ```
+//!
+//! [run_suite] then enumerates the registered plugins along with the circuits of this crate,
+//! so that contributing a model or a backend does not require modifying this crate.
+//! Plugins are identified by their name, which must be unique among the plugins of their kind.
+
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use super::{costs::CostReport, BenchmarkCtx};
+use crate::error::PluginError;
+
+/// The name of kimchi among the backends of [SuiteResult]s.
+pub const KIMCHI: &str = "kimchi";
+
+/// A circuit proven with kimchi.
+pub trait CircuitPlugin: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Compiles the circuit, sets it up with an SRS of `2^srs_size_log2` points, and proves and verifies it.
+    fn run(&self, srs_size_log2: u32) -> CostReport;
+}
+
+/// Another proof system, running circuits of its own.
+pub trait BackendPlugin: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// The names of the circuits the backend runs.
+    fn circuits(&self) -> Vec<String>;
+
+    /// Runs one of the circuits of the backend, at the size closest to `2^srs_size_log2`,
+    /// or returns [None] if the backend does not have it.
+    fn run(&self, circuit: &str, srs_size_log2: u32) -> Option<CostReport>;
+}
+
+/// The circuit of [BenchmarkCtx], which this crate contributes to the suite.
+struct BenchmarkCircuit;
+
+impl CircuitPlugin for BenchmarkCircuit {
+    fn name(&self) -> &str {
+        "bench"
+    }
+
+    fn run(&self, srs_size_log2: u32) -> CostReport {
+        let (ctx, one_time) = BenchmarkCtx::with_costs(srs_size_log2);
+        CostReport::new(self.name().to_string(), one_time, ctx.recurring_costs())
+            .with_security(ctx.security())
+    }
+}
+
+/// The registered plugins.
+struct Registry {
+    circuits: Vec<Arc<dyn CircuitPlugin>>,
+    backends: Vec<Arc<dyn BackendPlugin>>,
+}
+
+static REGISTRY: Lazy<RwLock<Registry>> = Lazy::new(|| {
+    RwLock::new(Registry {
+        circuits: vec![Arc::new(BenchmarkCircuit)],
+        backends: vec![],
+    })
+});
+
+/// Registers a circuit to be run by the suite.
+///
+/// # Errors
+///
+/// Will give error if a circuit of the same name is already registered.
+pub fn register_circuit(plugin: impl CircuitPlugin + 'static) -> Result<(), PluginError> {
+    let mut registry = REGISTRY.write().unwrap();
+    if registry.circuits.iter().any(|c| c.name() == plugin.name()) {
+        return Err(PluginError::Duplicate("circuit", plugin.name().to_string()));
+    }
+    registry.circuits.push(Arc::new(plugin));
+    Ok(())
+}
+
+/// Registers a backend whose circuits are run by the suite.
+///
+/// # Errors
+///
+/// Will give error if a backend of the same name is already registered, or if it is named [KIMCHI].
+pub fn register_backend(plugin: impl BackendPlugin + 'static) -> Result<(), PluginError> {
+    let mut registry = REGISTRY.write().unwrap();
+    if plugin.name() == KIMCHI || registry.backends.iter().any(|b| b.name() == plugin.name()) {
+        return Err(PluginError::Duplicate("backend", plugin.name().to_string()));
+    }
+    registry.backends.push(Arc::new(plugin));
+    Ok(())
+}
+
+/// The registered circuits, this crate's first, in the order of registration.
+pub fn circuits() -> Vec<Arc<dyn CircuitPlugin>> {
+    REGISTRY.read().unwrap().circuits.clone()
+}
+
+/// The registered backends, in the order of registration.
+pub fn backends() -> Vec<Arc<dyn BackendPlugin>> {
+    REGISTRY.read().unwrap().backends.clone()
+}
+
+/// The costs of a circuit of the suite on a backend.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SuiteResult {
+    pub backend: String,
+    pub report: CostReport,
+}
+
+/// Runs every registered circuit on kimchi, then every circuit of every registered backend,
+/// with SRSs of `2^srs_size_log2` points.
+/// Backends may not reach the same security level at the same size, which
+/// [check_equal_security](super::security::check_equal_security) tells from the reports of the results.
+pub fn run_suite(srs_size_log2: u32) -> Vec<SuiteResult> {
+    let mut results = vec![];
+    for circuit in circuits() {
+        results.push(SuiteResult {
+            backend: KIMCHI.to_string(),
+            report: circuit.run(srs_size_log2),
+        });
+    }
+    for backend in backends() {
+        for circuit in backend.circuits() {
+            if let Some(report) = backend.run(&circuit, srs_size_log2) {
+                results.push(SuiteResult {
+                    backend: backend.name().to_string(),
+                    report,
+                });
+            }
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::costs::{OneTimeCosts, RecurringCosts};
+
+    struct Constant(&'static str);
+
+    fn report(name: &str) -> CostReport {
+        CostReport::new(
+            name.to_string(),
+            OneTimeCosts::default(),
+            RecurringCosts::default(),
+        )
+    }
+
+    impl CircuitPlugin for Constant {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn run(&self, _: u32) -> CostReport {
+            report(self.0)
+        }
+    }
+
+    impl BackendPlugin for Constant {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn circuits(&self) -> Vec<String> {
+            vec!["constant".to_string()]
+        }
+
+        fn run(&self, circuit: &str, _: u32) -> Option<CostReport> {
+            (circuit == "constant").then(|| report(circuit))
+        }
+    }
+
+    #[test]
+    fn test_plugin_registry() {
+        register_circuit(Constant("plugin_circuit")).unwrap();
+        assert!(matches!(
+            register_circuit(Constant("bench")),
+            Err(PluginError::Duplicate("circuit", _))
+        ));
+        let names: Vec<_> = circuits().iter().map(|c| c.name().to_string()).collect();
+        assert_eq!(names[0], "bench");
+        assert!(names.contains(&"plugin_circuit".to_string()));
+
+        register_backend(Constant("plugin_backend")).unwrap();
+        assert!(register_backend(Constant(KIMCHI)).is_err());
+        let backend = backends()
+            .into_iter()
+            .find(|b| b.name() == "plugin_backend")
+            .unwrap();
+        assert_eq!(backend.run("constant", 10), Some(report("constant")));
+        assert_eq!(backend.run("other", 10), None);
+    }
+}
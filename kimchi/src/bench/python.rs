@@ -0,0 +1,170 @@
+//! Python bindings of the [query](super::query) API, for notebooks.
+//!
+//! With the `python` feature, the `kimchi_bench` extension module is built with
+//!
+//! ```console
+//! $ cargo rustc --release --features python --crate-type cdylib
+//! ```
+//!
+//! and renaming the library to `kimchi_bench.so` makes it importable:
+//!
+//! ```python
+//! import kimchi_bench
+//! records = kimchi_bench.load_results("artifacts", models=["mlp"], since="2026-01-01")
+//! kimchi_bench.aggregate(records, "recurring.prove", "mean")
+//! rows, columns, cells = kimchi_bench.pivot(records, "model", "backend", "recurring.prove", "max")
+//! ```
+//!
+//! Records are loaded from a [LocalStore]; aggregations and groupings are named in lower case.
+
+use std::collections::BTreeMap;
+
+use pyo3::{
+    exceptions::{PyIOError, PyUserWarning, PyValueError},
+    prelude::*,
+};
+
+use super::{
+    query::{self, Aggregate, GroupBy, Query, ResultRecord},
+    store::LocalStore,
+};
+
+/// A [ResultRecord], as seen from Python.
+#[pyclass(name = "ResultRecord", get_all)]
+#[derive(Clone)]
+struct PyResultRecord {
+    name: String,
+    model: String,
+    backend: String,
+    date: Option<String>,
+    values: BTreeMap<String, f64>,
+}
+
+impl From<ResultRecord> for PyResultRecord {
+    fn from(record: ResultRecord) -> Self {
+        let ResultRecord {
+            name,
+            model,
+            backend,
+            date,
+            values,
+        } = record;
+        Self {
+            name,
+            model,
+            backend,
+            date,
+            values,
+        }
+    }
+}
+
+impl From<PyResultRecord> for ResultRecord {
+    fn from(record: PyResultRecord) -> Self {
+        let PyResultRecord {
+            name,
+            model,
+            backend,
+            date,
+            values,
+        } = record;
+        Self {
+            name,
+            model,
+            backend,
+            date,
+            values,
+        }
+    }
+}
+
+fn parse_aggregate(how: &str) -> PyResult<Aggregate> {
+    match how {
+        "count" => Ok(Aggregate::Count),
+        "sum" => Ok(Aggregate::Sum),
+        "mean" => Ok(Aggregate::Mean),
+        "min" => Ok(Aggregate::Min),
+        "max" => Ok(Aggregate::Max),
+        _ => Err(PyValueError::new_err(format!("unknown aggregation {how}"))),
+    }
+}
+
+fn parse_group_by(field: &str) -> PyResult<GroupBy> {
+    match field {
+        "model" => Ok(GroupBy::Model),
+        "backend" => Ok(GroupBy::Backend),
+        "date" => Ok(GroupBy::Date),
+        _ => Err(PyValueError::new_err(format!("cannot group by {field}"))),
+    }
+}
+
+/// Loads the reports stored under `root` that match the filters (see [Query]).
+/// The reports that cannot be loaded are skipped, with a warning each.
+#[pyfunction]
+#[pyo3(signature = (root, models = vec![], backends = vec![], since = None, until = None))]
+fn load_results(
+    py: Python<'_>,
+    root: &str,
+    models: Vec<String>,
+    backends: Vec<String>,
+    since: Option<String>,
+    until: Option<String>,
+) -> PyResult<Vec<PyResultRecord>> {
+    let loaded = query::load_results_lenient(&LocalStore::new(root))
+        .map_err(|e| PyIOError::new_err(e.to_string()))?;
+    for (name, e) in &loaded.skipped {
+        PyErr::warn(
+            py,
+            py.get_type::<PyUserWarning>(),
+            &format!("skipped the report {name}: {e}"),
+            1,
+        )?;
+    }
+    let query = Query {
+        models,
+        backends,
+        since,
+        until,
+    };
+    Ok(loaded
+        .records
+        .into_iter()
+        .filter(|record| query.matches(record))
+        .map(PyResultRecord::from)
+        .collect())
+}
+
+/// Aggregates a value over records (see [query::aggregate]).
+#[pyfunction]
+fn aggregate(records: Vec<PyResultRecord>, value: &str, how: &str) -> PyResult<Option<f64>> {
+    let how = parse_aggregate(how)?;
+    let records: Vec<ResultRecord> = records.into_iter().map(ResultRecord::from).collect();
+    let records: Vec<_> = records.iter().collect();
+    Ok(query::aggregate(&records, value, how))
+}
+
+/// Pivots records (see [query::pivot]), returning the rows, the columns and the cells of the table.
+#[pyfunction]
+fn pivot(
+    records: Vec<PyResultRecord>,
+    rows: &str,
+    columns: &str,
+    value: &str,
+    how: &str,
+) -> PyResult<(Vec<String>, Vec<String>, Vec<Vec<Option<f64>>>)> {
+    let (rows, columns) = (parse_group_by(rows)?, parse_group_by(columns)?);
+    let how = parse_aggregate(how)?;
+    let records: Vec<ResultRecord> = records.into_iter().map(ResultRecord::from).collect();
+    let records: Vec<_> = records.iter().collect();
+    let table = query::pivot(&records, rows, columns, value, how);
+    Ok((table.rows, table.columns, table.cells))
+}
+
+#[pymodule]
+fn kimchi_bench(_py: Python<'_>, module: &PyModule) -> PyResult<()> {
+    module.add_class::<PyResultRecord>()?;
+    module.add_function(wrap_pyfunction!(load_results, module)?)?;
+    module.add_function(wrap_pyfunction!(aggregate, module)?)?;
+    module.add_function(wrap_pyfunction!(pivot, module)?)?;
+    Ok(())
+}
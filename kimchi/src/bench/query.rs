@@ -0,0 +1,351 @@
+//! Querying the benchmark reports of an [ArtifactStore], for analysis.
+//!
+//! Every report is loaded as a [ResultRecord]: its model, backend and date,
+//! and its numeric values flattened into dotted paths (`recurring.prove` for the proving time of a [super::costs::CostReport]),
+//! durations being converted to seconds.
+//! Records are then filtered with a [Query], and aggregated or pivoted on one of their values,
+//! so that notebooks and scripts can compare runs without parsing the reports themselves.
+//! The same API is exposed to Python with the `python` feature (see `bench::python`).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::store::{ArtifactKind, ArtifactStore};
+use crate::error::StoreError;
+
+/// The values of a stored report.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ResultRecord {
+    /// The name of the report in the store.
+    pub name: String,
+    pub model: String,
+    /// The backend of the report, `kimchi` if it does not say.
+    pub backend: String,
+    /// The date of the report in ISO 8601, if it has one.
+    pub date: Option<String>,
+    pub values: BTreeMap<String, f64>,
+}
+
+/// Flattens the numeric values of a JSON value into `values`, under `path`.
+fn flatten(path: &str, value: &Value, values: &mut BTreeMap<String, f64>) {
+    let key = |field: &str| {
+        if path.is_empty() {
+            field.to_string()
+        } else {
+            format!("{path}.{field}")
+        }
+    };
+    match value {
+        Value::Number(number) => {
+            if let Some(number) = number.as_f64() {
+                values.insert(path.to_string(), number);
+            }
+        }
+        Value::Bool(flag) => {
+            values.insert(path.to_string(), if *flag { 1.0 } else { 0.0 });
+        }
+        Value::Object(fields) => {
+            // a serialized duration
+            if let (Some(secs), Some(nanos), 2) = (
+                fields.get("secs").and_then(Value::as_f64),
+                fields.get("nanos").and_then(Value::as_f64),
+                fields.len(),
+            ) {
+                values.insert(path.to_string(), secs + nanos * 1e-9);
+                return;
+            }
+            for (field, value) in fields {
+                flatten(&key(field), value, values);
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                flatten(&key(&i.to_string()), item, values);
+            }
+        }
+        Value::Null | Value::String(_) => (),
+    }
+}
+
+impl ResultRecord {
+    /// Reads a report.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the report is not a JSON object with a `model`.
+    pub fn parse(name: &str, bytes: &[u8]) -> Result<Self, StoreError> {
+        let malformed = |reason: &str| StoreError::Malformed(name.to_string(), reason.to_string());
+        let report: Value = serde_json::from_slice(bytes).map_err(|e| malformed(&e.to_string()))?;
+        let text = |field: &str| {
+            report
+                .get(field)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+
+        let model = text("model").ok_or_else(|| malformed("the report has no model"))?;
+        let mut values = BTreeMap::new();
+        flatten("", &report, &mut values);
+        Ok(Self {
+            name: name.to_string(),
+            model,
+            backend: text("backend").unwrap_or_else(|| "kimchi".to_string()),
+            date: text("date"),
+            values,
+        })
+    }
+}
+
+/// Loads all the reports of a store.
+///
+/// # Errors
+///
+/// Will give error if the reports cannot be retrieved, or if one of them is malformed
+/// (see [load_results_lenient] to skip them instead).
+pub fn load_results(store: &impl ArtifactStore) -> Result<Vec<ResultRecord>, StoreError> {
+    store
+        .list(ArtifactKind::Report)?
+        .iter()
+        .map(|name| ResultRecord::parse(name, &store.get(ArtifactKind::Report, name)?))
+        .collect()
+}
+
+/// The reports of a store that could be loaded, and those that could not.
+#[derive(Debug, Default)]
+pub struct LoadedResults {
+    pub records: Vec<ResultRecord>,
+    /// The names of the reports that could not be retrieved or parsed, and why.
+    pub skipped: Vec<(String, StoreError)>,
+}
+
+/// Loads all the reports of a store, skipping the ones that cannot be retrieved or that are malformed,
+/// so that one bad report does not hide all the others (see [load_results] to fail instead).
+///
+/// # Errors
+///
+/// Will give error if the reports cannot be listed.
+pub fn load_results_lenient(store: &impl ArtifactStore) -> Result<LoadedResults, StoreError> {
+    let mut loaded = LoadedResults::default();
+    for name in store.list(ArtifactKind::Report)? {
+        match store
+            .get(ArtifactKind::Report, &name)
+            .and_then(|bytes| ResultRecord::parse(&name, &bytes))
+        {
+            Ok(record) => loaded.records.push(record),
+            Err(e) => loaded.skipped.push((name, e)),
+        }
+    }
+    Ok(loaded)
+}
+
+/// A filter over records. Empty filters match every record.
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+    pub models: Vec<String>,
+    pub backends: Vec<String>,
+    /// The first date included, in ISO 8601.
+    pub since: Option<String>,
+    /// The first date excluded, in ISO 8601.
+    pub until: Option<String>,
+}
+
+impl Query {
+    /// Returns true if the record matches the query.
+    /// Records without a date only match queries without dates.
+    pub fn matches(&self, record: &ResultRecord) -> bool {
+        let in_list = |list: &[String], value: &String| list.is_empty() || list.contains(value);
+        // ISO 8601 dates sort lexicographically
+        let in_range = match &record.date {
+            Some(date) => {
+                self.since.as_ref().map_or(true, |since| date >= since)
+                    && self.until.as_ref().map_or(true, |until| date < until)
+            }
+            None => self.since.is_none() && self.until.is_none(),
+        };
+        in_list(&self.models, &record.model) && in_list(&self.backends, &record.backend) && in_range
+    }
+
+    /// Returns the records matching the query.
+    pub fn filter<'a>(&self, records: &'a [ResultRecord]) -> Vec<&'a ResultRecord> {
+        records
+            .iter()
+            .filter(|record| self.matches(record))
+            .collect()
+    }
+}
+
+/// How the values of several records are combined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aggregate {
+    Count,
+    Sum,
+    Mean,
+    Min,
+    Max,
+}
+
+/// Aggregates a value over records, ignoring the records without it.
+/// [None] if none of the records has the value.
+pub fn aggregate(records: &[&ResultRecord], value: &str, how: Aggregate) -> Option<f64> {
+    let values: Vec<f64> = records
+        .iter()
+        .filter_map(|record| record.values.get(value).copied())
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+    let sum: f64 = values.iter().sum();
+    Some(match how {
+        Aggregate::Count => values.len() as f64,
+        Aggregate::Sum => sum,
+        Aggregate::Mean => sum / values.len() as f64,
+        Aggregate::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+        Aggregate::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+    })
+}
+
+/// A field by which records are grouped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    Model,
+    Backend,
+    Date,
+}
+
+impl GroupBy {
+    fn key(self, record: &ResultRecord) -> String {
+        match self {
+            GroupBy::Model => record.model.clone(),
+            GroupBy::Backend => record.backend.clone(),
+            GroupBy::Date => record.date.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// A table of a value aggregated by two fields of the records.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Pivot {
+    pub rows: Vec<String>,
+    pub columns: Vec<String>,
+    /// The aggregated value of each row, one per column, [None] if no record of the row and column has the value.
+    pub cells: Vec<Vec<Option<f64>>>,
+}
+
+/// Pivots records, for example the mean proving time of each model (rows) on each backend (columns).
+pub fn pivot(
+    records: &[&ResultRecord],
+    rows: GroupBy,
+    columns: GroupBy,
+    value: &str,
+    how: Aggregate,
+) -> Pivot {
+    let mut groups: BTreeMap<(String, String), Vec<&ResultRecord>> = BTreeMap::new();
+    for record in records {
+        groups
+            .entry((rows.key(record), columns.key(record)))
+            .or_default()
+            .push(record);
+    }
+    let row_keys: BTreeSet<_> = groups.keys().map(|(row, _)| row.clone()).collect();
+    let column_keys: BTreeSet<_> = groups.keys().map(|(_, column)| column.clone()).collect();
+
+    let cells = row_keys
+        .iter()
+        .map(|row| {
+            column_keys
+                .iter()
+                .map(|column| {
+                    groups
+                        .get(&(row.clone(), column.clone()))
+                        .and_then(|group| aggregate(group, value, how))
+                })
+                .collect()
+        })
+        .collect();
+    Pivot {
+        rows: row_keys.into_iter().collect(),
+        columns: column_keys.into_iter().collect(),
+        cells,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::store::LocalStore;
+
+    #[test]
+    fn test_query() {
+        let root = std::env::temp_dir().join(format!("kimchi-query-{}", std::process::id()));
+        let store = LocalStore::new(&root);
+        let reports = [
+            (
+                "a.json",
+                r#"{"model": "mlp", "date": "2026-01-01", "recurring": {"prove": {"secs": 1, "nanos": 500000000}}}"#,
+            ),
+            (
+                "b.json",
+                r#"{"model": "mlp", "date": "2026-02-01", "recurring": {"prove": {"secs": 2, "nanos": 500000000}}}"#,
+            ),
+            (
+                "c.json",
+                r#"{"model": "mlp", "backend": "r1cs", "date": "2026-02-01", "recurring": {"prove": {"secs": 4, "nanos": 0}}}"#,
+            ),
+            ("d.json", r#"{"model": "cnn", "num_gates": 10}"#),
+        ];
+        for (name, report) in reports {
+            store
+                .put(ArtifactKind::Report, name, report.as_bytes())
+                .unwrap();
+        }
+
+        let records = load_results(&store).unwrap();
+        assert_eq!(records.len(), 4);
+
+        let kimchi = Query {
+            models: vec!["mlp".to_string()],
+            backends: vec!["kimchi".to_string()],
+            ..Query::default()
+        };
+        let matching = kimchi.filter(&records);
+        assert_eq!(
+            aggregate(&matching, "recurring.prove", Aggregate::Mean),
+            Some(2.0)
+        );
+
+        let february = Query {
+            since: Some("2026-02-01".to_string()),
+            ..Query::default()
+        };
+        assert_eq!(february.filter(&records).len(), 2);
+
+        let all: Vec<_> = records.iter().collect();
+        let table = pivot(
+            &all,
+            GroupBy::Model,
+            GroupBy::Backend,
+            "recurring.prove",
+            Aggregate::Max,
+        );
+        assert_eq!(table.rows, ["cnn", "mlp"]);
+        assert_eq!(table.columns, ["kimchi", "r1cs"]);
+        assert_eq!(table.cells, [[None, None], [Some(2.5), Some(4.0)]]);
+
+        // a malformed report fails the whole load, unless it is skipped
+        store
+            .put(ArtifactKind::Report, "e.json", br#"{"num_gates": 10}"#)
+            .unwrap();
+        assert!(matches!(
+            load_results(&store),
+            Err(StoreError::Malformed(..))
+        ));
+        let loaded = load_results_lenient(&store).unwrap();
+        assert_eq!(loaded.records, records);
+        assert_eq!(loaded.skipped.len(), 1);
+        assert_eq!(loaded.skipped[0].0, "e.json");
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
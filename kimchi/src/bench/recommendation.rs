@@ -0,0 +1,263 @@
+//! A benchmark scenario for private recommendation: proving the top-k items of a matrix factorization model.
+//!
+//! The score of an item is the inner product of the user embedding with the item embedding.
+//! The user embedding is private, but committed: the circuit takes a commitment to it as public input.
+//! The item embeddings are the catalog of the model and are fixed in the circuit,
+//! so that the scores are linear combinations of the user embedding and cost no multiplication.
+//! Embeddings are unsigned fixed-point values of `width` bits.
+//! The recommended items are public as a commitment to their flags (see [commit_recommendations]),
+//! so that catalogs can have more items than the bits of a field element.
+
+use ark_ff::One;
+use mina_curves::pasta::{Fp, Vesta};
+use poly_commitment::evaluation_proof::OpeningProof;
+
+use crate::{
+    curve::KimchiCurve,
+    loc,
+    snarky::{
+        api::SnarkyCircuit,
+        bits::{to_bits, Endianness},
+        boolean::Boolean,
+        errors::{RealSnarkyError, SnarkyCompilationError, SnarkyError},
+        poseidon::{hash_slice, hash_slice_native},
+        prelude::{FieldVar, RunState, SnarkyResult},
+    },
+};
+
+/// Commits to a user embedding, out of circuit.
+pub fn commit_embedding(embedding: &[u64]) -> Fp {
+    let values: Vec<_> = embedding.iter().map(|&value| Fp::from(value)).collect();
+    hash_slice_native(Vesta::sponge_params(), &values)
+}
+
+/// Commits to the recommended items, out of circuit, as the [RecommendationCircuit] outputs them.
+pub fn commit_recommendations(taken: &[bool]) -> Fp {
+    let flags: Vec<_> = taken.iter().map(|&flag| Fp::from(flag)).collect();
+    hash_slice_native(Vesta::sponge_params(), &flags)
+}
+
+/// The circuit proving the top-k items of a user.
+pub struct RecommendationCircuit {
+    /// The embedding of each item of the catalog.
+    items: Vec<Vec<u64>>,
+    /// The number of items recommended.
+    k: usize,
+    /// The number of bits of the embeddings.
+    width: usize,
+}
+
+impl RecommendationCircuit {
+    /// Creates the circuit recommending `k` items of a catalog.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the item embeddings do not all have the same number of dimensions,
+    /// or if a value of an item does not fit in `width` bits.
+    pub fn new(items: Vec<Vec<u64>>, k: usize, width: usize) -> SnarkyResult<Self> {
+        let error = |error| {
+            Err(Box::new(RealSnarkyError::new(
+                SnarkyError::CompilationError(error),
+            )))
+        };
+        let dims = items.first().map_or(0, Vec::len);
+        for (i, item) in items.iter().enumerate() {
+            if item.len() != dims {
+                return error(SnarkyCompilationError::ShapeMismatch(
+                    "values",
+                    format!("item {i}"),
+                    item.len(),
+                    dims,
+                ));
+            }
+            if let Some(&value) = item
+                .iter()
+                .find(|&&value| width < 64 && value >> width != 0)
+            {
+                return error(SnarkyCompilationError::ValueTooWide(
+                    format!("item {i}"),
+                    value,
+                    width,
+                ));
+            }
+        }
+        Ok(Self { items, k, width })
+    }
+
+    /// The number of bits of a score.
+    fn score_width(&self) -> usize {
+        let dims = self.items.first().map_or(0, Vec::len);
+        let dims_bits = (usize::BITS - dims.leading_zeros()) as usize;
+        2 * self.width + dims_bits
+    }
+
+    /// Computes the score of each item, out of circuit.
+    pub fn scores_native(&self, user: &[u64]) -> Vec<u128> {
+        self.items
+            .iter()
+            .map(|item| {
+                item.iter()
+                    .zip(user)
+                    .map(|(&v, &u)| v as u128 * u as u128)
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Computes the top-k items, out of circuit,
+    /// as a flag per item which is set if the item is recommended.
+    /// Ties are broken in favor of the first items.
+    pub fn top_k_native(&self, user: &[u64]) -> Vec<bool> {
+        let scores = self.scores_native(user);
+        let mut ranking: Vec<usize> = (0..scores.len()).collect();
+        ranking.sort_by(|&a, &b| scores[b].cmp(&scores[a]));
+        let mut taken = vec![false; scores.len()];
+        for &i in &ranking[..self.k] {
+            taken[i] = true;
+        }
+        taken
+    }
+}
+
+impl SnarkyCircuit for RecommendationCircuit {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    /// The user embedding.
+    type PrivateInput = Vec<u64>;
+    /// The commitment to the user embedding.
+    type PublicInput = FieldVar<Fp>;
+    /// The commitment to the recommended items, see [commit_recommendations].
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        commitment: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        assert!(
+            self.k <= self.items.len(),
+            "more recommendations than items"
+        );
+        let dims = self.items.first().map_or(0, Vec::len);
+
+        let mut user = Vec::with_capacity(dims);
+        for j in 0..dims {
+            let value: FieldVar<Fp> = sys.compute(loc!(), |_| Fp::from(private.unwrap()[j]))?;
+            to_bits(sys, loc!(), &value, self.width, Endianness::Little)?;
+            user.push(value);
+        }
+        let opened = hash_slice(sys, loc!(), &user);
+        sys.assert_eq(
+            Some("recommendation.user".into()),
+            loc!(),
+            opened,
+            commitment,
+        )?;
+
+        let scores: Vec<_> = self
+            .items
+            .iter()
+            .map(|item| {
+                let terms: Vec<_> = item
+                    .iter()
+                    .zip(&user)
+                    .map(|(&v, u)| (Fp::from(v), u.clone()))
+                    .collect();
+                FieldVar::linear_combination(&terms)
+            })
+            .collect();
+
+        let taken = private.map(|user| self.top_k_native(user));
+        let mut selected = Vec::with_capacity(scores.len());
+        for i in 0..scores.len() {
+            let flag: Boolean<Fp> = sys.compute(loc!(), |_| taken.as_ref().unwrap()[i])?;
+            selected.push(flag.to_field_var());
+        }
+
+        // the threshold is the score of the last recommended item:
+        // recommended items score at least the threshold, and the others at most
+        let _: FieldVar<Fp> = sys.hint(
+            "recommendation.top_k".into(),
+            loc!(),
+            |_| {
+                let user = private.unwrap();
+                let mut scores = self.scores_native(user);
+                scores.sort_unstable_by(|a, b| b.cmp(a));
+                Fp::from(scores[self.k - 1])
+            },
+            |sys, threshold| {
+                let count = FieldVar::constant(Fp::from(self.k as u64));
+                sys.assert_eq(None, loc!(), FieldVar::sum_many(&selected), count)?;
+
+                for (flag, score) in selected.iter().zip(&scores) {
+                    // (2 * flag - 1) * (score - threshold) is non-negative
+                    let sign = &flag.scale(Fp::from(2u64)) - &FieldVar::constant(Fp::one());
+                    let margin = sign.mul(&(score - threshold), None, loc!(), sys)?;
+                    to_bits(sys, loc!(), &margin, self.score_width(), Endianness::Little)?;
+                }
+                Ok(())
+            },
+        )?;
+
+        Ok(hash_slice(sys, loc!(), &selected))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::prove_and_verify;
+
+    #[test]
+    fn test_recommendation() {
+        let circuit = RecommendationCircuit::new(
+            vec![vec![1, 0, 2], vec![3, 3, 0], vec![0, 1, 1], vec![2, 2, 2]],
+            2,
+            2,
+        )
+        .unwrap();
+        let user = vec![3, 1, 2];
+        // scores 7, 12, 3 and 12
+        assert_eq!(circuit.scores_native(&user), vec![7, 12, 3, 12]);
+        assert_eq!(circuit.top_k_native(&user), vec![false, true, false, true]);
+
+        let commitment = commit_embedding(&user);
+        let run = prove_and_verify(circuit, commitment, user).unwrap();
+        assert_eq!(
+            run.output,
+            commit_recommendations(&[false, true, false, true])
+        );
+
+        // catalogs can have more items than the bits of a field element
+        let large = RecommendationCircuit::new((0..300).map(|i| vec![i]).collect(), 1, 9).unwrap();
+        let top_k = large.top_k_native(&[1]);
+        assert_eq!(top_k.iter().position(|&taken| taken), Some(299));
+        assert_eq!(top_k.iter().filter(|&&taken| taken).count(), 1);
+
+        let commitment = commit_embedding(&[1]);
+        let run = prove_and_verify(large, commitment, vec![1]).unwrap();
+        assert_eq!(run.output, commit_recommendations(&top_k));
+    }
+
+    #[test]
+    fn test_recommendation_validates_items() {
+        let err = RecommendationCircuit::new(vec![vec![1, 2], vec![3]], 1, 2)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.source,
+            SnarkyError::CompilationError(SnarkyCompilationError::ShapeMismatch(_, _, 1, 2))
+        ));
+
+        // 4 does not fit in 2 bits, so the scores could overflow their range checks
+        let err = RecommendationCircuit::new(vec![vec![1, 4]], 1, 2)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.source,
+            SnarkyError::CompilationError(SnarkyCompilationError::ValueTooWide(_, 4, 2))
+        ));
+    }
+}
@@ -0,0 +1,271 @@
+//! Reference results of the benchmark on common machines, bundled with the crate.
+//!
+//! Before trusting their own numbers, users compare the proving and verification times of their environment
+//! with those of a machine like theirs (see the `compare-to-baseline` binary):
+//! a debug build, a throttled CPU or a busy host shows up as times far from the reference.
+//! The references are the [recurring costs](super::costs::RecurringCosts) of [BenchmarkCtx] circuits of a few sizes,
+//! recorded with [Platform::record] on each machine and added to `reference_results.json`
+//! (`compare-to-baseline --record <name>` does both).
+//!
+//! The file is compiled into the crate as [BUNDLE], so a new entry only reaches [Platform::bundled] after a rebuild;
+//! [Platform::load] reads it at runtime instead, which `compare-to-baseline` does when the file is there.
+//! No platform is recorded yet: until one is, `compare-to-baseline` has nothing to compare with.
+
+use std::{fs, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use super::BenchmarkCtx;
+use crate::error::StoreError;
+
+/// The path of the reference results in the sources of the crate, which [BUNDLE] is compiled from.
+pub const BUNDLE_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/bench/reference_results.json"
+);
+
+/// The bundled reference results, a JSON array of [Platform]s.
+pub const BUNDLE: &str = include_str!("reference_results.json");
+
+/// The sizes of the circuits that references are recorded for, as powers of two.
+pub const REFERENCE_SIZES: [u32; 3] = [10, 12, 14];
+
+/// The times of a [BenchmarkCtx] circuit on a machine.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReferenceResult {
+    pub srs_size_log2: u32,
+    pub prove: Duration,
+    pub verify: Duration,
+}
+
+/// The reference results of a machine.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Platform {
+    pub name: String,
+    /// The model name of the CPU, as given by the host (see [host_cpu]).
+    pub cpu: String,
+    pub threads: usize,
+    pub results: Vec<ReferenceResult>,
+}
+
+/// The model name of the CPU of the host, if it can tell.
+pub fn host_cpu() -> Option<String> {
+    let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").ok()?;
+    cpuinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("model name"))
+        .and_then(|line| line.split_once(':'))
+        .map(|(_, name)| name.trim().to_string())
+}
+
+/// Measures the times of the circuits of the given sizes on the host.
+pub fn measure(sizes: &[u32]) -> Vec<ReferenceResult> {
+    sizes
+        .iter()
+        .map(|&srs_size_log2| {
+            let costs = BenchmarkCtx::new(srs_size_log2).recurring_costs();
+            ReferenceResult {
+                srs_size_log2,
+                prove: costs.prove,
+                verify: costs.verify,
+            }
+        })
+        .collect()
+}
+
+impl Platform {
+    /// Parses the bundled platforms.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the bundle is malformed, which the tests check.
+    pub fn bundled() -> Vec<Self> {
+        serde_json::from_str(BUNDLE).expect("the reference results are malformed")
+    }
+
+    /// Reads the platforms of a file of reference results, as [BUNDLE] but at runtime.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the file cannot be read, or is malformed.
+    pub fn load(path: &Path) -> Result<Vec<Self>, StoreError> {
+        let json = fs::read_to_string(path).map_err(|e| StoreError::Io(e.to_string()))?;
+        serde_json::from_str(&json)
+            .map_err(|e| StoreError::Malformed(path.display().to_string(), e.to_string()))
+    }
+
+    /// Records the reference results of the host under a name, on the circuits of [REFERENCE_SIZES].
+    pub fn record(name: String) -> Self {
+        Self {
+            name,
+            cpu: host_cpu().unwrap_or_else(|| "unknown".to_string()),
+            threads: rayon::current_num_threads(),
+            results: measure(&REFERENCE_SIZES),
+        }
+    }
+
+    /// Adds a platform to `platforms`, replacing the platform of the same name if there is one,
+    /// and keeps them sorted by name.
+    pub fn insert(platforms: &mut Vec<Self>, platform: Self) {
+        platforms.retain(|other| other.name != platform.name);
+        platforms.push(platform);
+        platforms.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// The platform closest to the host among `platforms`:
+    /// one with the same CPU if any, and otherwise the one with the closest number of threads.
+    pub fn closest(platforms: &[Self]) -> Option<&Self> {
+        let cpu = host_cpu();
+        let threads = rayon::current_num_threads();
+        platforms
+            .iter()
+            .find(|platform| Some(&platform.cpu) == cpu.as_ref())
+            .or_else(|| {
+                platforms
+                    .iter()
+                    .min_by_key(|platform| platform.threads.abs_diff(threads))
+            })
+    }
+}
+
+/// How a time of the host compares with the reference.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// Within the tolerance of the reference.
+    Expected,
+    Slower,
+    Faster,
+}
+
+/// The comparison of a time of the host with the reference.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Comparison {
+    pub srs_size_log2: u32,
+    /// `"prove"` or `"verify"`.
+    pub operation: &'static str,
+    pub measured: Duration,
+    pub reference: Duration,
+    /// The measured time divided by the reference.
+    pub ratio: f64,
+    pub verdict: Verdict,
+}
+
+/// Compares measured times with the references of a platform, for the sizes both have.
+/// A time is [Verdict::Expected] if its ratio to the reference is within `1 ± tolerance`.
+pub fn compare(
+    platform: &Platform,
+    measured: &[ReferenceResult],
+    tolerance: f64,
+) -> Vec<Comparison> {
+    let mut comparisons = vec![];
+    for result in measured {
+        let Some(reference) = platform
+            .results
+            .iter()
+            .find(|reference| reference.srs_size_log2 == result.srs_size_log2)
+        else {
+            continue;
+        };
+        for (operation, measured, reference) in [
+            ("prove", result.prove, reference.prove),
+            ("verify", result.verify, reference.verify),
+        ] {
+            let ratio = measured.as_secs_f64() / reference.as_secs_f64();
+            let verdict = if ratio > 1.0 + tolerance {
+                Verdict::Slower
+            } else if ratio < 1.0 - tolerance {
+                Verdict::Faster
+            } else {
+                Verdict::Expected
+            };
+            comparisons.push(Comparison {
+                srs_size_log2: result.srs_size_log2,
+                operation,
+                measured,
+                reference,
+                ratio,
+                verdict,
+            });
+        }
+    }
+    comparisons
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    #[test]
+    fn test_bundled_references() {
+        // the bundle parses, and has a result for each reference size on each platform
+        let platforms = Platform::bundled();
+        for platform in &platforms {
+            let sizes: Vec<_> = platform.results.iter().map(|r| r.srs_size_log2).collect();
+            assert_eq!(sizes, REFERENCE_SIZES, "{} is incomplete", platform.name);
+        }
+        let names: BTreeSet<_> = platforms.iter().map(|platform| &platform.name).collect();
+        assert_eq!(names.len(), platforms.len(), "platforms have the same name");
+    }
+
+    #[test]
+    fn test_load_references() {
+        // the sources hold the same references as the bundle until a platform is recorded
+        assert_eq!(
+            Platform::load(Path::new(BUNDLE_PATH)).unwrap(),
+            Platform::bundled()
+        );
+        assert!(matches!(
+            Platform::load(Path::new("missing.json")),
+            Err(StoreError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_compare_to_reference() {
+        let result = |prove_ms, verify_ms| ReferenceResult {
+            srs_size_log2: 10,
+            prove: Duration::from_millis(prove_ms),
+            verify: Duration::from_millis(verify_ms),
+        };
+        let platform = Platform {
+            name: "reference".to_string(),
+            cpu: "unknown".to_string(),
+            threads: 1,
+            results: vec![result(100, 10)],
+        };
+        let comparisons = compare(&platform, &[result(150, 11)], 0.25);
+        assert_eq!(comparisons.len(), 2);
+        assert_eq!(comparisons[0].verdict, Verdict::Slower);
+        assert_eq!(comparisons[1].verdict, Verdict::Expected);
+
+        // sizes without a reference are skipped
+        let other = ReferenceResult {
+            srs_size_log2: 12,
+            ..result(1, 1)
+        };
+        assert!(compare(&platform, &[other], 0.25).is_empty());
+        assert_eq!(
+            Platform::closest(std::slice::from_ref(&platform)),
+            Some(&platform)
+        );
+
+        // recording a platform again replaces it
+        let mut platforms = vec![platform.clone()];
+        let faster = Platform {
+            results: vec![result(50, 5)],
+            ..platform.clone()
+        };
+        Platform::insert(&mut platforms, faster.clone());
+        Platform::insert(
+            &mut platforms,
+            Platform {
+                name: "another".to_string(),
+                ..platform
+            },
+        );
+        assert_eq!(platforms.len(), 2);
+        assert_eq!(platforms[1], faster);
+    }
+}
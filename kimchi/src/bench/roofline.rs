@@ -0,0 +1,219 @@
+//! Roofline analysis of the prover kernels (FFTs and MSMs) on the host.
+//!
+//! The peaks of the host are measured with microbenchmarks:
+//! the throughput of field multiplications, and the bandwidth of a large memory copy.
+//! Each kernel is then timed at the size used by a circuit,
+//! and its achieved throughput is compared to the roofline `min(peak_ops, intensity * peak_bandwidth)`,
+//! where the intensity is the number of operations per byte moved by the kernel.
+//!
+//! Operations are counted in field multiplications, as they dominate both kernels,
+//! and the numbers of operations and bytes of a kernel are estimated from its size
+//! (the kernels are not instrumented), so the fractions are indicative.
+
+use std::time::{Duration, Instant};
+
+use ark_ff::{One, PrimeField, UniformRand};
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
+use mina_curves::pasta::{Fp, Vesta};
+use poly_commitment::commitment::PolyComm;
+use serde::Serialize;
+
+/// The number of field multiplications of a mixed addition of points.
+const MULS_PER_ADDITION: f64 = 11.0;
+
+/// The size in bytes of a field element.
+const FIELD_BYTES: f64 = 32.0;
+
+/// The size in bytes of an affine point.
+const POINT_BYTES: f64 = 2.0 * FIELD_BYTES;
+
+/// The peaks of the host.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct HostPeaks {
+    /// Field multiplications per second, on all threads.
+    pub ops_per_sec: f64,
+    /// Bytes per second copied in memory.
+    pub bytes_per_sec: f64,
+}
+
+impl HostPeaks {
+    /// Measures the peaks of the host.
+    pub fn measure() -> Self {
+        use rayon::prelude::*;
+
+        // independent chains of multiplications, one per thread
+        let muls_per_thread = 1 << 22;
+        let threads = rayon::current_num_threads();
+        let start = Instant::now();
+        let results: Vec<Fp> = (0..threads)
+            .into_par_iter()
+            .map(|i| {
+                let x = Fp::from(i as u64 + 2);
+                let mut acc = Fp::one();
+                for _ in 0..muls_per_thread {
+                    acc *= x;
+                }
+                acc
+            })
+            .collect();
+        let ops_per_sec = (threads * muls_per_thread) as f64 / secs(start.elapsed());
+        assert!(!results.is_empty());
+
+        // a copy larger than the caches, which reads and writes every byte
+        let len = 1 << 26;
+        let src = vec![1u8; len];
+        let mut dst = vec![0u8; len];
+        let start = Instant::now();
+        dst.par_chunks_mut(1 << 20)
+            .zip(src.par_chunks(1 << 20))
+            .for_each(|(dst, src)| dst.copy_from_slice(src));
+        let bytes_per_sec = 2.0 * len as f64 / secs(start.elapsed());
+        assert_eq!(dst[len - 1], 1);
+
+        Self {
+            ops_per_sec,
+            bytes_per_sec,
+        }
+    }
+}
+
+/// Whether a kernel is limited by the compute or the memory bandwidth of the host.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Compute,
+    Memory,
+}
+
+/// The roofline analysis of a kernel.
+#[derive(Serialize, Clone, Debug)]
+pub struct KernelReport {
+    pub kernel: &'static str,
+    pub size: usize,
+    pub time: Duration,
+    /// Achieved field multiplications per second.
+    pub ops_per_sec: f64,
+    /// Achieved bytes per second.
+    pub bytes_per_sec: f64,
+    /// What limits the kernel, according to the roofline.
+    pub bound: Bound,
+    /// The achieved throughput, as a fraction of the roofline.
+    pub roofline_fraction: f64,
+}
+
+impl KernelReport {
+    fn new(
+        kernel: &'static str,
+        size: usize,
+        time: Duration,
+        ops: f64,
+        bytes: f64,
+        peaks: &HostPeaks,
+    ) -> Self {
+        let intensity = ops / bytes;
+        let memory_roof = intensity * peaks.bytes_per_sec;
+        let (bound, roof) = if memory_roof < peaks.ops_per_sec {
+            (Bound::Memory, memory_roof)
+        } else {
+            (Bound::Compute, peaks.ops_per_sec)
+        };
+
+        let ops_per_sec = ops / secs(time);
+        Self {
+            kernel,
+            size,
+            time,
+            ops_per_sec,
+            bytes_per_sec: bytes / secs(time),
+            bound,
+            roofline_fraction: ops_per_sec / roof,
+        }
+    }
+}
+
+/// The roofline section of a benchmark report.
+#[derive(Serialize, Clone, Debug)]
+pub struct RooflineReport {
+    pub peaks: HostPeaks,
+    pub kernels: Vec<KernelReport>,
+}
+
+fn secs(time: Duration) -> f64 {
+    time.as_secs_f64().max(f64::MIN_POSITIVE)
+}
+
+/// Times an FFT of `size` (a power of two) field elements.
+fn fft(size: usize, peaks: &HostPeaks) -> KernelReport {
+    let domain = Radix2EvaluationDomain::<Fp>::new(size).unwrap();
+    let mut rng = rand::thread_rng();
+    let mut coeffs: Vec<Fp> = (0..size).map(|_| Fp::rand(&mut rng)).collect();
+
+    let start = Instant::now();
+    domain.fft_in_place(&mut coeffs);
+    let time = start.elapsed();
+
+    // a butterfly per pair of elements and per level, each level reading and writing every element
+    let levels = domain.log_size_of_group as f64;
+    let ops = size as f64 / 2.0 * levels;
+    let bytes = 2.0 * size as f64 * FIELD_BYTES * levels;
+    KernelReport::new("fft", size, time, ops, bytes, peaks)
+}
+
+/// Times an MSM of `size` bases of the SRS.
+fn msm(bases: &[Vesta], peaks: &HostPeaks) -> KernelReport {
+    let size = bases.len();
+    let mut rng = rand::thread_rng();
+    let bases: Vec<_> = bases.iter().map(|g| PolyComm::new(vec![*g])).collect();
+    let bases: Vec<_> = bases.iter().collect();
+    let scalars: Vec<Fp> = (0..size).map(|_| Fp::rand(&mut rng)).collect();
+
+    let start = Instant::now();
+    let _ = PolyComm::<Vesta>::multi_scalar_mul(&bases, &scalars);
+    let time = start.elapsed();
+
+    // Pippenger with windows of ln(n) bits: each window adds every base to a bucket, then sums the buckets
+    let window = (size as f64).ln().max(1.0);
+    let windows = (Fp::size_in_bits() as f64 / window).ceil();
+    let additions = windows * (size as f64 + 2.0 * 2f64.powf(window));
+    let ops = additions * MULS_PER_ADDITION;
+    let bytes = windows * size as f64 * (POINT_BYTES + FIELD_BYTES);
+    KernelReport::new("msm", size, time, ops, bytes, peaks)
+}
+
+/// Runs the roofline analysis of the kernels of a circuit,
+/// whose domain has `domain_size` rows and whose SRS has the given bases.
+///
+/// The peaks of the host can be measured once with [HostPeaks::measure],
+/// and reused for every benchmark circuit.
+pub fn analyze(domain_size: usize, srs_bases: &[Vesta], peaks: HostPeaks) -> RooflineReport {
+    let msm_size = domain_size.min(srs_bases.len());
+    let kernels = vec![
+        fft(domain_size, &peaks),
+        // the quotient polynomial is computed on a domain 8 times larger
+        fft(8 * domain_size, &peaks),
+        msm(&srs_bases[..msm_size], &peaks),
+    ];
+    RooflineReport { peaks, kernels }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roofline_bound() {
+        let peaks = HostPeaks {
+            ops_per_sec: 1e9,
+            bytes_per_sec: 1e10,
+        };
+
+        // one operation per 100 bytes: the memory only allows 1e8 operations per second
+        let report = KernelReport::new("test", 1, Duration::from_secs(1), 5e7, 5e9, &peaks);
+        assert_eq!(report.bound, Bound::Memory);
+        assert!((report.roofline_fraction - 0.5).abs() < 1e-9);
+
+        // one operation per byte: the compute is the limit
+        let report = KernelReport::new("test", 1, Duration::from_secs(1), 5e8, 5e8, &peaks);
+        assert_eq!(report.bound, Bound::Compute);
+        assert!((report.roofline_fraction - 0.5).abs() < 1e-9);
+    }
+}
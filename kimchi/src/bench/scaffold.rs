@@ -0,0 +1,286 @@
+//! Scaffolding of new benchmark circuits (see the `new-circuit` binary).
+//!
+//! Every benchmark circuit follows the same structure, that [scaffold] lays out for a new one in `src/bench`:
+//! a module `<name>.rs` with the circuit built from gadgets, a loader of its inputs,
+//! and a test proving it on the golden vectors of `<name>_golden.json`,
+//! the expected outputs of the reference implementation of the model on fixed inputs.
+//! The module is registered in `src/bench/mod.rs`, in alphabetical order.
+//! The generated circuit only sums its inputs, and compiles and passes its test as is,
+//! so that contributors replace its gadgets and golden vectors with those of their model.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::error::ScaffoldError;
+
+/// The module of a new circuit, formatted as rustfmt would, where `__name__`, `__Name__` and `__title__` are replaced
+/// by its name in snake case, in camel case, and in words.
+const MODULE_TEMPLATE: &str = r##"//! A benchmark of the __title__ circuit.
+//!
+//! TODO: describe the model, the gadgets it is built from, and what the cost of the circuit grows with.
+//!
+//! The golden vectors of `__name___golden.json` are the expected outputs of the reference implementation
+//! of the model on fixed inputs; regenerate them whenever the model changes.
+
+use std::time::Duration;
+
+use mina_curves::pasta::{Fp, Vesta};
+use poly_commitment::evaluation_proof::OpeningProof;
+use serde::{Deserialize, Serialize};
+
+use super::BenchCircuit;
+use crate::{
+    loc,
+    snarky::{
+        api::SnarkyCircuit,
+        prelude::{FieldVar, RunState, SnarkyResult},
+    },
+};
+
+/// The golden vectors of the circuit, a JSON array of [GoldenVector]s.
+pub const GOLDEN_VECTORS: &str = include_str!("__name___golden.json");
+
+/// An input of the circuit, and its expected output.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GoldenVector {
+    pub input: Vec<u64>,
+    pub output: u64,
+}
+
+/// Loads inputs and their expected outputs from a JSON array of [GoldenVector]s.
+pub fn load_inputs(json: &str) -> serde_json::Result<Vec<GoldenVector>> {
+    serde_json::from_str(json)
+}
+
+/// The circuit computing the output of the model on a private input of `features` values.
+pub struct __Name__Circuit {
+    pub features: usize,
+}
+
+impl SnarkyCircuit for __Name__Circuit {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = Vec<u64>;
+    type PublicInput = ();
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        _: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let mut x = Vec::with_capacity(self.features);
+        for i in 0..self.features {
+            let value: FieldVar<Fp> = sys.compute(loc!(), |_| Fp::from(private.unwrap()[i]))?;
+            x.push(value);
+        }
+
+        // TODO: replace the sum of the inputs with the gadgets of the model
+        Ok(FieldVar::sum_many(&x))
+    }
+}
+
+/// The costs of proving the circuit on an input.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct __Name__Report {
+    pub gates: usize,
+    pub prove: Duration,
+    pub verify: Duration,
+}
+
+/// Proves and verifies the circuit on each input, checking its output.
+///
+/// # Panics
+///
+/// Will panic if the inputs have different sizes, or if an output differs from the expected one.
+pub fn run(vectors: &[GoldenVector]) -> Vec<__Name__Report> {
+    let features = vectors.first().map_or(0, |vector| vector.input.len());
+    let mut bench = BenchCircuit::compile(__Name__Circuit { features }).unwrap();
+
+    vectors
+        .iter()
+        .map(|vector| {
+            assert_eq!(
+                vector.input.len(),
+                features,
+                "the inputs have different sizes"
+            );
+
+            let run = bench.prove_and_verify((), vector.input.clone()).unwrap();
+            assert_eq!(
+                run.output,
+                Fp::from(vector.output),
+                "the output differs from the golden vector"
+            );
+
+            __Name__Report {
+                gates: run.gates,
+                prove: run.prove,
+                verify: run.verify,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test___name___golden_vectors() {
+        let vectors = load_inputs(GOLDEN_VECTORS).unwrap();
+        assert!(!vectors.is_empty(), "there are no golden vectors");
+        let reports = run(&vectors);
+        println!("__name__: {}", serde_json::to_string(&reports).unwrap());
+    }
+}
+"##;
+
+/// The golden vectors of a new circuit, which the circuit generated from [MODULE_TEMPLATE] passes.
+const GOLDEN_TEMPLATE: &str = r#"[
+  { "input": [1, 2, 3], "output": 6 },
+  { "input": [0, 0, 0], "output": 0 }
+]
+"#;
+
+/// Checks that a name is a snake case identifier: lowercase words of letters and digits separated by underscores.
+fn check_name(name: &str) -> Result<(), ScaffoldError> {
+    let valid = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.split('_').all(|word| {
+            !word.is_empty()
+                && word
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(ScaffoldError::InvalidName(name.to_string()))
+    }
+}
+
+/// Renders the module and the golden vectors of a new circuit.
+///
+/// # Errors
+///
+/// Will give error if the name is not a snake case identifier.
+pub fn render(name: &str) -> Result<(String, String), ScaffoldError> {
+    check_name(name)?;
+    let camel: String = name
+        .split('_')
+        .map(|word| word[..1].to_ascii_uppercase() + &word[1..])
+        .collect();
+    let title = name.replace('_', " ");
+    let module = MODULE_TEMPLATE
+        .replace("__Name__", &camel)
+        .replace("__name__", name)
+        .replace("__title__", &title);
+    Ok((module, GOLDEN_TEMPLATE.to_string()))
+}
+
+/// Declares the module `name` in the contents of a `mod.rs`, among its `pub mod` declarations in alphabetical order.
+///
+/// # Errors
+///
+/// Will give error if the module is already declared.
+pub fn register(mod_rs: &str, name: &str) -> Result<String, ScaffoldError> {
+    let declaration = format!("pub mod {name};");
+    let mut lines: Vec<&str> = mod_rs.lines().collect();
+    if lines.contains(&declaration.as_str()) {
+        return Err(ScaffoldError::AlreadyExists(name.to_string()));
+    }
+    let modules = || {
+        lines
+            .iter()
+            .enumerate()
+            .filter_map(|(i, line)| Some((i, line.strip_prefix("pub mod ")?.strip_suffix(';')?)))
+    };
+    let position = modules()
+        .find(|(_, module)| *module > name)
+        .map(|(i, _)| i)
+        .or_else(|| modules().last().map(|(i, _)| i + 1))
+        .unwrap_or(0);
+    lines.insert(position, &declaration);
+
+    let mut registered = lines.join("\n");
+    if mod_rs.ends_with('\n') {
+        registered.push('\n');
+    }
+    Ok(registered)
+}
+
+/// Scaffolds the circuit `name` in the directory of the benchmark modules (`src/bench`),
+/// and returns the paths of the files it wrote.
+///
+/// # Errors
+///
+/// Will give error if the name is not a snake case identifier, if the module already exists,
+/// or if the files cannot be written.
+pub fn scaffold(bench_dir: &Path, name: &str) -> Result<Vec<PathBuf>, ScaffoldError> {
+    let (module, golden) = render(name)?;
+    let module_path = bench_dir.join(format!("{name}.rs"));
+    let golden_path = bench_dir.join(format!("{name}_golden.json"));
+    let mod_path = bench_dir.join("mod.rs");
+    if module_path.exists() || golden_path.exists() {
+        return Err(ScaffoldError::AlreadyExists(name.to_string()));
+    }
+
+    let io = |e: std::io::Error| ScaffoldError::Io(e.to_string());
+    let registered = register(&fs::read_to_string(&mod_path).map_err(io)?, name)?;
+    fs::write(&module_path, module).map_err(io)?;
+    fs::write(&golden_path, golden).map_err(io)?;
+    fs::write(&mod_path, registered).map_err(io)?;
+    Ok(vec![module_path, golden_path, mod_path])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaffold() {
+        assert!(matches!(
+            render("NewCircuit"),
+            Err(ScaffoldError::InvalidName(_))
+        ));
+        assert!(matches!(
+            render("new__circuit"),
+            Err(ScaffoldError::InvalidName(_))
+        ));
+        let (module, _) = render("tiny_gpt2").unwrap();
+        assert!(module.contains("pub struct TinyGpt2Circuit"));
+        assert!(module.contains("include_str!(\"tiny_gpt2_golden.json\")"));
+        assert!(!module.contains("__"));
+
+        let mod_rs =
+            "pub mod ab;\npub mod forest;\npub mod session;\n\n#[cfg(test)]\nmod tests {}\n";
+        let registered = register(mod_rs, "knn").unwrap();
+        assert!(registered
+            .starts_with("pub mod ab;\npub mod forest;\npub mod knn;\npub mod session;\n"));
+        assert!(register(&registered, "zoo")
+            .unwrap()
+            .contains("pub mod session;\npub mod zoo;\n"));
+        assert!(matches!(
+            register(&registered, "knn"),
+            Err(ScaffoldError::AlreadyExists(_))
+        ));
+
+        let dir = std::env::temp_dir().join(format!("scaffold-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("mod.rs"), mod_rs).unwrap();
+        let written = scaffold(&dir, "knn").unwrap();
+        assert_eq!(written.len(), 3);
+        assert!(fs::read_to_string(dir.join("mod.rs"))
+            .unwrap()
+            .contains("pub mod knn;"));
+        assert!(matches!(
+            scaffold(&dir, "knn"),
+            Err(ScaffoldError::AlreadyExists(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+//! The security section of a benchmark report.
+//!
+//! Backends are only comparable at equal security: a proof system over a smaller field,
+//! with shorter challenges or over a larger domain is faster, but also easier to break.
+//! [SecurityLevel] estimates the effective security of a configuration, in bits, as the weakest of:
+//!
+//! - the discrete logarithm on the curve of the commitments, at half the bits of its group order (Pollard's rho),
+//! - the polynomial identities checked at random points, whose soundness error is the degree of the identities
+//!   over the size of the challenges (Schwartz-Zippel),
+//! - the lookup argument, whose soundness error is the size of the domain and of the table over the size of the challenges,
+//!
+//! the soundness terms losing the bits of a union bound over the Fiat-Shamir challenges.
+//! These are the standard estimates, not proofs of security; what matters is that all backends use the same ones.
+
+use serde::Serialize;
+
+use super::costs::CostReport;
+
+/// The parameters of a proof system that its security depends on.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SecurityParams {
+    pub backend: String,
+    pub curve: String,
+    /// The bits of the scalar field.
+    pub field_bits: u32,
+    /// The bits of the order of the group of the commitments.
+    pub group_order_bits: u32,
+    /// The bits of the shortest Fiat-Shamir challenges.
+    pub challenge_bits: u32,
+    /// The number of Fiat-Shamir challenges.
+    pub challenges: u32,
+    pub domain_size_log2: u32,
+    /// The degree of the polynomial identities, in multiples of the size of the domain.
+    pub max_degree: u32,
+    pub lookups: bool,
+}
+
+/// The term of a [SecurityLevel] that bounds it.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecurityBound {
+    DiscreteLog,
+    PolynomialIdentities,
+    Lookups,
+}
+
+/// The effective security of a configuration of a proof system, in bits.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SecurityLevel {
+    pub params: SecurityParams,
+    pub discrete_log_bits: u32,
+    pub identity_bits: u32,
+    /// [None] without lookups.
+    pub lookup_bits: Option<u32>,
+    /// The weakest of the terms.
+    pub bits: u32,
+    pub bound: SecurityBound,
+}
+
+/// The base 2 logarithm of `x`, rounded up.
+fn ceil_log2(x: u64) -> u32 {
+    x.next_power_of_two().trailing_zeros()
+}
+
+impl SecurityLevel {
+    /// Estimates the security of a configuration.
+    pub fn new(params: SecurityParams) -> Self {
+        let challenge_bits = params.challenge_bits.min(params.field_bits);
+        let union_bound = ceil_log2(params.challenges.into());
+        let soundness = |error_log2: u32| challenge_bits.saturating_sub(error_log2 + union_bound);
+
+        let discrete_log_bits = params.group_order_bits / 2;
+        let identity_bits =
+            soundness(ceil_log2(params.max_degree.into()) + params.domain_size_log2);
+        // the table is interpolated over the domain, so it has at most as many entries as the domain
+        let lookup_bits = params
+            .lookups
+            .then(|| soundness(params.domain_size_log2 + 1));
+
+        let (bits, bound) = [
+            (Some(discrete_log_bits), SecurityBound::DiscreteLog),
+            (Some(identity_bits), SecurityBound::PolynomialIdentities),
+            (lookup_bits, SecurityBound::Lookups),
+        ]
+        .into_iter()
+        .filter_map(|(bits, bound)| Some((bits?, bound)))
+        .min_by_key(|(bits, _)| *bits)
+        .unwrap();
+
+        Self {
+            params,
+            discrete_log_bits,
+            identity_bits,
+            lookup_bits,
+            bits,
+            bound,
+        }
+    }
+
+    /// The security of kimchi over the Pasta curves, for a circuit over a domain of `2^domain_size_log2` rows.
+    ///
+    /// Kimchi squeezes `beta`, `gamma`, `alpha`, `zeta`, `v`, `u`, and the joint combiner with lookups,
+    /// the shortest of which are the 128-bit endomorphism challenges,
+    /// and checks its identities over the extended domain of 8 times the size of the domain.
+    pub fn kimchi(domain_size_log2: u32, lookups: bool) -> Self {
+        Self::new(SecurityParams {
+            backend: super::plugin::KIMCHI.to_string(),
+            curve: "vesta".to_string(),
+            field_bits: 255,
+            group_order_bits: 255,
+            challenge_bits: 128,
+            challenges: if lookups { 7 } else { 6 },
+            domain_size_log2,
+            max_degree: 8,
+            lookups,
+        })
+    }
+}
+
+/// Checks that reports were measured at equal security,
+/// and otherwise returns a warning naming the security level of each of them.
+pub fn check_equal_security(reports: &[CostReport]) -> Option<String> {
+    let levels: Vec<Option<u32>> = reports
+        .iter()
+        .map(|report| report.security.as_ref().map(|level| level.bits))
+        .collect();
+    if levels.windows(2).all(|pair| pair[0] == pair[1]) {
+        return None;
+    }
+    let described: Vec<String> = reports
+        .iter()
+        .zip(levels)
+        .map(|(report, bits)| match bits {
+            Some(bits) => format!("{} at {bits} bits", report.model),
+            None => format!("{} at an unknown level", report.model),
+        })
+        .collect();
+    Some(format!(
+        "the reports are not at equal security: {}",
+        described.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::costs::{OneTimeCosts, RecurringCosts};
+
+    #[test]
+    fn test_security_level() {
+        let level = SecurityLevel::kimchi(16, false);
+        assert_eq!(level.discrete_log_bits, 127);
+        // 128 bits, less 3 for the degree, 16 for the domain and 3 for the 6 challenges
+        assert_eq!(level.identity_bits, 106);
+        assert_eq!(level.lookup_bits, None);
+        assert_eq!(
+            (level.bits, level.bound),
+            (106, SecurityBound::PolynomialIdentities)
+        );
+
+        let with_lookups = SecurityLevel::kimchi(16, true);
+        // 128 bits, less 17 for the domain and the table and 3 for the 7 challenges
+        assert_eq!(with_lookups.lookup_bits, Some(108));
+
+        let small_group = SecurityLevel::new(SecurityParams {
+            group_order_bits: 160,
+            ..level.params.clone()
+        });
+        assert_eq!(
+            (small_group.bits, small_group.bound),
+            (80, SecurityBound::DiscreteLog)
+        );
+
+        let report = |model: &str, level: Option<SecurityLevel>| CostReport {
+            security: level,
+            ..CostReport::new(
+                model.to_string(),
+                OneTimeCosts::default(),
+                RecurringCosts::default(),
+            )
+        };
+        let reports = [
+            report("a", Some(level.clone())),
+            report("b", Some(SecurityLevel::kimchi(16, false))),
+        ];
+        assert_eq!(check_equal_security(&reports), None);
+        let warning = check_equal_security(&[
+            report("a", Some(level)),
+            report("b", Some(small_group)),
+            report("c", None),
+        ])
+        .unwrap();
+        assert!(warning.contains("a at 106 bits, b at 80 bits, c at an unknown level"));
+    }
+}
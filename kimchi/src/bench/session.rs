@@ -0,0 +1,432 @@
+//! Sessions of chained proofs, for inferences that depend on the previous ones
+//! (for example autoregressive generation, where each proof produces the next token).
+//!
+//! The state passed from one proof to the next is never revealed.
+//! Instead, each proof outputs a commitment `poseidon(previous, digest(state))` to its new state,
+//! where `previous` is the commitment it received in its public input,
+//! and opens that commitment to the state it starts from.
+//! The commitments thus form a hash chain from the commitment to the initial state,
+//! which [SessionVerifier::verify_chain] checks proof by proof.
+//! Consumers receiving the proofs one at a time verify them as they arrive with a [ChainVerifier],
+//! which keeps nothing but the commitment reached so far.
+
+use std::{
+    borrow::Cow,
+    time::{Duration, Instant},
+};
+
+use ark_ff::Zero;
+use mina_curves::pasta::{Fp, Vesta};
+use poly_commitment::evaluation_proof::OpeningProof;
+use serde::Serialize;
+
+use super::{BaseSponge, ScalarSponge};
+use crate::{
+    curve::KimchiCurve,
+    error::SessionError,
+    loc,
+    proof::ProverProof,
+    snarky::{
+        api::{ProverIndexWrapper, SnarkyCircuit, VerifierIndexWrapper},
+        errors::{RealSnarkyError, SnarkyCompilationError, SnarkyError},
+        prelude::{FieldVar, RunState, SnarkyResult},
+        prf::poseidon_native,
+    },
+};
+
+/// A step of a session, proven by each proof of the chain.
+pub trait SessionStep {
+    /// The private input of a step, in addition to the state.
+    type PrivateInput;
+
+    /// The number of field elements of the state.
+    fn state_len(&self) -> usize;
+
+    /// Computes the next state in the circuit.
+    fn step(
+        &self,
+        sys: &mut RunState<Fp>,
+        loc: Cow<'static, str>,
+        state: &[FieldVar<Fp>],
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Vec<FieldVar<Fp>>>;
+
+    /// Computes the next state out of circuit, so that the prover can continue the session.
+    fn step_native(&self, state: &[Fp], private: &Self::PrivateInput) -> Vec<Fp>;
+}
+
+/// Commits to a state, following the commitment `previous`, out of circuit.
+pub fn commit_state(previous: Fp, state: &[Fp]) -> Fp {
+    let params = Vesta::sponge_params();
+    let digest = state.iter().fold(Fp::zero(), |digest, &value| {
+        poseidon_native(params, digest, value)
+    });
+    poseidon_native(params, previous, digest)
+}
+
+/// Commits to a state in the circuit (see [commit_state]).
+fn commit_state_var(
+    sys: &mut RunState<Fp>,
+    loc: Cow<'static, str>,
+    previous: FieldVar<Fp>,
+    state: &[FieldVar<Fp>],
+) -> FieldVar<Fp> {
+    let mut digest = FieldVar::zero();
+    for value in state {
+        digest = sys.poseidon(loc.clone(), (digest, value.clone())).0;
+    }
+    sys.poseidon(loc, (previous, digest)).0
+}
+
+/// The private input of a step circuit.
+struct StepWitness<P> {
+    /// The commitment that the commitment to the current state follows.
+    parent: Fp,
+    state: Vec<Fp>,
+    private: P,
+}
+
+/// The circuit proving a step of a session.
+struct StepCircuit<S> {
+    step: S,
+}
+
+impl<S: SessionStep> SnarkyCircuit for StepCircuit<S> {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = StepWitness<S::PrivateInput>;
+    /// The commitment to the current state.
+    type PublicInput = FieldVar<Fp>;
+    /// The commitment to the next state.
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        commitment: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let parent: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap().parent)?;
+        let mut state = Vec::with_capacity(self.step.state_len());
+        for i in 0..self.step.state_len() {
+            let value: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap().state[i])?;
+            state.push(value);
+        }
+
+        // open the commitment received from the previous proof
+        let opened = commit_state_var(sys, loc!(), parent, &state);
+        sys.assert_eq(
+            Some("session.open".into()),
+            loc!(),
+            opened,
+            commitment.clone(),
+        )?;
+
+        let next = self
+            .step
+            .step(sys, loc!(), &state, private.map(|witness| &witness.private))?;
+        Ok(commit_state_var(sys, loc!(), commitment, &next))
+    }
+}
+
+/// A proof of a session, with the commitments it goes from and to.
+pub struct ChainLink {
+    pub proof: ProverProof<Vesta, OpeningProof<Vesta>>,
+    pub input: Fp,
+    pub output: Fp,
+}
+
+/// The verifier side of a session.
+pub struct SessionVerifier<S: SessionStep> {
+    verifier_index: VerifierIndexWrapper<StepCircuit<S>>,
+}
+
+impl<S: SessionStep> SessionVerifier<S> {
+    /// Checks that the links form a chain starting from `genesis`,
+    /// and returns the commitment to the final state.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if a link does not start where the previous one ended,
+    /// or if its proof does not verify.
+    pub fn verify_chain(&self, genesis: Fp, links: &[ChainLink]) -> Result<Fp, SessionError> {
+        verify_chain(&self.verifier_index, genesis, links)
+    }
+
+    /// Starts verifying a chain from `genesis` incrementally, one link at a time (see [ChainVerifier::push]).
+    pub fn stream(&self, genesis: Fp) -> ChainVerifier<'_, S> {
+        ChainVerifier::new(&self.verifier_index, genesis)
+    }
+
+    /// Verifies the links of a stream as they arrive, dropping each once verified,
+    /// and returns the commitment to the final state and the throughput of the verification.
+    ///
+    /// # Errors
+    ///
+    /// Will give error as [SessionVerifier::verify_chain], at the first link that fails.
+    pub fn verify_stream(
+        &self,
+        genesis: Fp,
+        links: impl IntoIterator<Item = ChainLink>,
+    ) -> Result<(Fp, Throughput), SessionError> {
+        let mut verifier = self.stream(genesis);
+        for link in links {
+            verifier.push(&link)?;
+        }
+        Ok((verifier.commitment(), verifier.throughput()))
+    }
+}
+
+/// The rate at which a [ChainVerifier] verified its links.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Throughput {
+    pub links: usize,
+    /// The time spent verifying, excluding the time spent waiting for the links.
+    pub elapsed: Duration,
+    pub links_per_second: f64,
+}
+
+/// Verifies the links of a chain one at a time, in constant memory.
+pub struct ChainVerifier<'a, S: SessionStep> {
+    verifier_index: &'a VerifierIndexWrapper<StepCircuit<S>>,
+    commitment: Fp,
+    links: usize,
+    elapsed: Duration,
+}
+
+impl<'a, S: SessionStep> ChainVerifier<'a, S> {
+    fn new(verifier_index: &'a VerifierIndexWrapper<StepCircuit<S>>, genesis: Fp) -> Self {
+        Self {
+            verifier_index,
+            commitment: genesis,
+            links: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Verifies the next link of the chain, and returns the commitment it ends at.
+    /// A link that fails leaves the verifier where it was, expecting the same link.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the link does not start where the previous one ended,
+    /// or if its proof does not verify.
+    pub fn push(&mut self, link: &ChainLink) -> Result<Fp, SessionError> {
+        if link.input != self.commitment {
+            return Err(SessionError::BrokenChain(self.links));
+        }
+        let start = Instant::now();
+        let verified = self.verifier_index.try_verify::<BaseSponge, ScalarSponge>(
+            &link.proof,
+            &link.input,
+            &link.output,
+        );
+        self.elapsed += start.elapsed();
+        verified.map_err(|e| SessionError::InvalidProof(self.links, e))?;
+
+        self.links += 1;
+        self.commitment = link.output;
+        Ok(self.commitment)
+    }
+
+    /// The commitment reached so far: the genesis, then the output of the last verified link.
+    pub fn commitment(&self) -> Fp {
+        self.commitment
+    }
+
+    /// The number of links verified so far, and the rate at which they were.
+    pub fn throughput(&self) -> Throughput {
+        let seconds = self.elapsed.as_secs_f64();
+        Throughput {
+            links: self.links,
+            elapsed: self.elapsed,
+            links_per_second: if seconds > 0.0 {
+                self.links as f64 / seconds
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// See [SessionVerifier::verify_chain].
+fn verify_chain<S: SessionStep>(
+    verifier_index: &VerifierIndexWrapper<StepCircuit<S>>,
+    genesis: Fp,
+    links: &[ChainLink],
+) -> Result<Fp, SessionError> {
+    let mut verifier = ChainVerifier::new(verifier_index, genesis);
+    for link in links {
+        verifier.push(link)?;
+    }
+    Ok(verifier.commitment())
+}
+
+/// The prover side of a session.
+pub struct Session<S: SessionStep + Clone> {
+    step: S,
+    prover_index: ProverIndexWrapper<StepCircuit<S>>,
+    verifier_index: VerifierIndexWrapper<StepCircuit<S>>,
+    genesis: Fp,
+    parent: Fp,
+    commitment: Fp,
+    state: Vec<Fp>,
+    links: Vec<ChainLink>,
+}
+
+impl<S: SessionStep + Clone> Session<S> {
+    /// Compiles the step circuit and starts a session from an initial state.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the initial state is not of the length of the state of the step,
+    /// or if the step circuit does not compile.
+    pub fn new(step: S, initial: Vec<Fp>) -> SnarkyResult<Self> {
+        if initial.len() != step.state_len() {
+            return Err(Box::new(RealSnarkyError::new(
+                SnarkyError::CompilationError(SnarkyCompilationError::ShapeMismatch(
+                    "values",
+                    "session state".to_string(),
+                    initial.len(),
+                    step.state_len(),
+                )),
+            )));
+        }
+        let (prover_index, verifier_index) =
+            StepCircuit { step: step.clone() }.compile_to_indexes()?;
+
+        let genesis = commit_state(Fp::zero(), &initial);
+        Ok(Self {
+            step,
+            prover_index,
+            verifier_index,
+            genesis,
+            parent: Fp::zero(),
+            commitment: genesis,
+            state: initial,
+            links: vec![],
+        })
+    }
+
+    /// The commitment to the initial state, from which the chain starts.
+    pub fn genesis(&self) -> Fp {
+        self.genesis
+    }
+
+    /// The current state.
+    pub fn state(&self) -> &[Fp] {
+        &self.state
+    }
+
+    /// The proofs of the steps so far.
+    pub fn links(&self) -> &[ChainLink] {
+        &self.links
+    }
+
+    /// Proves the next step of the session, and returns the new state.
+    pub fn prove_step(&mut self, private: S::PrivateInput) -> SnarkyResult<&[Fp]> {
+        let next = self.step.step_native(&self.state, &private);
+        let witness = StepWitness {
+            parent: self.parent,
+            state: self.state.clone(),
+            private,
+        };
+
+        let debug = false;
+        let (proof, output) =
+            self.prover_index
+                .prove::<BaseSponge, ScalarSponge>(self.commitment, witness, debug)?;
+        self.links.push(ChainLink {
+            proof,
+            input: self.commitment,
+            output: *output,
+        });
+
+        self.parent = self.commitment;
+        self.commitment = *output;
+        self.state = next;
+        Ok(&self.state)
+    }
+
+    /// Checks the chain of the session (see [SessionVerifier::verify_chain]).
+    pub fn verify_chain(&self) -> Result<Fp, SessionError> {
+        verify_chain(&self.verifier_index, self.genesis, &self.links)
+    }
+
+    /// Ends the session, and returns the verifier, the commitment to the initial state, and the links.
+    pub fn finish(self) -> (SessionVerifier<S>, Fp, Vec<ChainLink>) {
+        let verifier = SessionVerifier {
+            verifier_index: self.verifier_index,
+        };
+        (verifier, self.genesis, self.links)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Produces the next Fibonacci number.
+    #[derive(Clone)]
+    struct Fibonacci;
+
+    impl SessionStep for Fibonacci {
+        type PrivateInput = ();
+
+        fn state_len(&self) -> usize {
+            2
+        }
+
+        fn step(
+            &self,
+            _: &mut RunState<Fp>,
+            _: Cow<'static, str>,
+            state: &[FieldVar<Fp>],
+            _: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Vec<FieldVar<Fp>>> {
+            Ok(vec![state[1].clone(), &state[0] + &state[1]])
+        }
+
+        fn step_native(&self, state: &[Fp], _: &Self::PrivateInput) -> Vec<Fp> {
+            vec![state[1], state[0] + state[1]]
+        }
+    }
+
+    #[test]
+    fn test_session() {
+        assert!(Session::new(Fibonacci, vec![Fp::from(1u64)]).is_err());
+
+        let initial = vec![Fp::from(0u64), Fp::from(1u64)];
+        let mut session = Session::new(Fibonacci, initial).unwrap();
+        for _ in 0..3 {
+            session.prove_step(()).unwrap();
+        }
+        assert_eq!(session.state(), &[Fp::from(2u64), Fp::from(3u64)]);
+
+        let last = session.verify_chain().unwrap();
+        let parent = session.links()[1].output;
+        assert_eq!(last, commit_state(parent, session.state()));
+
+        let (verifier, genesis, mut links) = session.finish();
+        assert_eq!(verifier.verify_chain(genesis, &links).unwrap(), last);
+
+        // the stream stays where it was on a link out of order
+        let mut stream = verifier.stream(genesis);
+        stream.push(&links[0]).unwrap();
+        assert!(matches!(
+            stream.push(&links[2]),
+            Err(SessionError::BrokenChain(1))
+        ));
+        stream.push(&links[1]).unwrap();
+        assert_eq!(stream.push(&links[2]).unwrap(), last);
+        assert_eq!(stream.throughput().links, 3);
+
+        // the links must follow each other
+        links.swap(0, 1);
+        assert!(matches!(
+            verifier.verify_chain(genesis, &links),
+            Err(SessionError::BrokenChain(0))
+        ));
+    }
+}
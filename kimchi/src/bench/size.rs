@@ -0,0 +1,154 @@
+//! The size section of a benchmark report, normalized across backends.
+//!
+//! Backends measure the size of a circuit in different units:
+//! kimchi counts rows of `COLUMNS` cells, each of which may check several constraints and lookups,
+//! while R1CS backends count constraints of a single multiplication.
+//! Comparing the rows of one backend with the constraints of another is meaningless,
+//! so the report gives every backend the same measures side by side:
+//! rows, wires (witness cells), lookups, and an estimate of the equivalent number of R1CS constraints.
+
+use ark_ff::PrimeField;
+use serde::Serialize;
+
+use crate::circuits::{
+    gate::{CircuitGate, CurrOrNext, GateType},
+    lookup::lookups::LookupPattern,
+    wires::COLUMNS,
+};
+
+/// The size of a circuit on a backend.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct BackendSize {
+    pub backend: String,
+    /// The number of rows, or of constraints for an R1CS backend.
+    pub rows: usize,
+    /// The number of witness values.
+    pub wires: usize,
+    /// The number of lookups.
+    pub lookups: usize,
+    /// The estimated number of R1CS constraints checking the same relation, lookups excluded.
+    pub r1cs: usize,
+    /// The rows of gates for which [Self::r1cs] has no estimate, and which it does not count.
+    pub unestimated_rows: usize,
+}
+
+/// The estimated number of R1CS constraints checked by a row of a kimchi gate, if known.
+///
+/// A multiplication is one constraint and a bit decomposition is one constraint per bit plus its recomposition;
+/// lookups are not constraints of R1CS, and are counted apart.
+fn r1cs_per_row(typ: GateType) -> Option<usize> {
+    use GateType::*;
+    match typ {
+        Zero | Lookup => Some(0),
+        // two generic constraints, of one multiplication each
+        Generic => Some(2),
+        // 5 rounds of 3 S-boxes x^7, of 4 multiplications each
+        Poseidon => Some(5 * 3 * 4),
+        CompleteAdd => Some(7),
+        // 5 bits, each a doubling and an addition
+        VarBaseMul => Some(5 * 7),
+        // 4 bits, each a doubling and an addition
+        EndoMul => Some(4 * 7),
+        // 8 crumbs of 2 bits, each checked and accumulated
+        EndoMulScalar => Some(8 * 2),
+        // an 88-bit limb
+        RangeCheck0 | RangeCheck1 => Some(88 + 1),
+        ForeignFieldAdd => Some(6),
+        ForeignFieldMul => Some(30),
+        // 16 bits of 3 values, and the 16 XORs
+        Xor16 => Some(16 * 3 + 16),
+        // a 64-bit word
+        Rot64 => Some(64 + 1),
+        CairoClaim | CairoInstruction | CairoFlags | CairoTransition | KeccakRound
+        | KeccakSponge => None,
+    }
+}
+
+impl BackendSize {
+    /// Measures a kimchi circuit.
+    pub fn kimchi<F: PrimeField>(gates: &[CircuitGate<F>]) -> Self {
+        let mut lookups = 0;
+        let mut r1cs = 0;
+        let mut unestimated_rows = 0;
+        for gate in gates {
+            lookups += [CurrOrNext::Curr, CurrOrNext::Next]
+                .into_iter()
+                .filter_map(|row| LookupPattern::from_gate(gate.typ, row))
+                .map(|pattern| pattern.max_lookups_per_row())
+                .sum::<usize>();
+            match r1cs_per_row(gate.typ) {
+                Some(constraints) => r1cs += constraints,
+                None => unestimated_rows += 1,
+            }
+        }
+
+        Self {
+            backend: "kimchi".to_string(),
+            rows: gates.len(),
+            wires: gates.len() * COLUMNS,
+            lookups,
+            r1cs,
+            unestimated_rows,
+        }
+    }
+
+    /// Measures a circuit on an R1CS backend, from its number of constraints and of variables.
+    pub fn r1cs(backend: String, constraints: usize, variables: usize) -> Self {
+        Self {
+            backend,
+            rows: constraints,
+            wires: variables,
+            lookups: 0,
+            r1cs: constraints,
+            unestimated_rows: 0,
+        }
+    }
+}
+
+/// The sizes of a model on each of the backends it was compiled for.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SizeReport {
+    pub model: String,
+    pub backends: Vec<BackendSize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuits::{polynomials::generic::GenericGateSpec, wires::Wire};
+    use mina_curves::pasta::Fp;
+
+    #[test]
+    fn test_kimchi_size() {
+        let generic = |row| {
+            CircuitGate::<Fp>::create_generic_gadget(
+                Wire::for_row(row),
+                GenericGateSpec::Const(1u32.into()),
+                None,
+            )
+        };
+        let gate = |typ, row| CircuitGate::<Fp>::new(typ, Wire::for_row(row), vec![]);
+        let gates = [
+            generic(0),
+            generic(1),
+            gate(GateType::Lookup, 2),
+            gate(GateType::RangeCheck1, 3),
+            gate(GateType::Zero, 4),
+            gate(GateType::KeccakRound, 5),
+        ];
+
+        let size = BackendSize::kimchi(&gates);
+        assert_eq!(size.rows, 6);
+        assert_eq!(size.wires, 6 * COLUMNS);
+        // 3 lookups of the lookup gate, 4 on each of the two rows of the range check
+        assert_eq!(size.lookups, 3 + 2 * 4);
+        assert_eq!(size.r1cs, 2 * 2 + 89);
+        assert_eq!(size.unestimated_rows, 1);
+
+        let report = SizeReport {
+            model: "test".to_string(),
+            backends: vec![size, BackendSize::r1cs("r1cs".to_string(), 93, 120)],
+        };
+        println!("size: {}", serde_json::to_string(&report).unwrap());
+    }
+}
@@ -0,0 +1,140 @@
+//! Per-job state of a benchmark suite run.
+//!
+//! The state file records which `(model, backend, config)` jobs have completed,
+//! so that a suite re-run after a crash skips the finished jobs instead of proving them again.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::StoreError;
+
+/// A single job of a benchmark suite.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JobId {
+    pub model: String,
+    pub backend: String,
+    pub config: String,
+}
+
+/// The state of a suite run, persisted after each completed job.
+#[derive(Debug)]
+pub struct SuiteState {
+    path: PathBuf,
+    completed: BTreeSet<JobId>,
+}
+
+impl SuiteState {
+    /// Loads the state at `path`, or starts from an empty state if there is none.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, StoreError> {
+        let path = path.into();
+        let completed = if path.exists() {
+            let json = fs::read_to_string(&path).map_err(|e| StoreError::Io(e.to_string()))?;
+            serde_json::from_str(&json)
+                .map_err(|e| StoreError::Malformed(path.display().to_string(), e.to_string()))?
+        } else {
+            BTreeSet::new()
+        };
+        Ok(Self { path, completed })
+    }
+
+    /// The path of the state file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns whether the job already completed in a previous run.
+    pub fn is_completed(&self, job: &JobId) -> bool {
+        self.completed.contains(job)
+    }
+
+    /// Records that a job completed, and persists the state.
+    /// The state file is replaced atomically, so that a crash never leaves it half-written.
+    pub fn mark_completed(&mut self, job: JobId) -> Result<(), StoreError> {
+        self.completed.insert(job);
+
+        let json = serde_json::to_string_pretty(&self.completed)
+            .map_err(|e| StoreError::Io(e.to_string()))?;
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, json).map_err(|e| StoreError::Io(e.to_string()))?;
+        fs::rename(&tmp, &self.path).map_err(|e| StoreError::Io(e.to_string()))
+    }
+
+    /// Runs every job that has not completed yet, recording each one as soon as it succeeds.
+    /// Returns the jobs that were skipped.
+    ///
+    /// # Errors
+    ///
+    /// Stops at the first job that fails, leaving it (and the jobs after it) to the next run.
+    pub fn run<E, FUNC>(&mut self, jobs: Vec<JobId>, mut run_job: FUNC) -> Result<Vec<JobId>, E>
+    where
+        E: From<StoreError>,
+        FUNC: FnMut(&JobId) -> Result<(), E>,
+    {
+        let mut skipped = vec![];
+        for job in jobs {
+            if self.is_completed(&job) {
+                skipped.push(job);
+                continue;
+            }
+            run_job(&job)?;
+            self.mark_completed(job)?;
+        }
+        Ok(skipped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(model: &str) -> JobId {
+        JobId {
+            model: model.to_string(),
+            backend: "kimchi".to_string(),
+            config: "default".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_resume_skips_completed_jobs() {
+        let path = std::env::temp_dir().join(format!("kimchi-state-{}.json", std::process::id()));
+        let jobs = vec![job("linreg"), job("mlp"), job("lenet")];
+
+        // the first run crashes on the second job
+        let mut state = SuiteState::open(&path).unwrap();
+        let res: Result<_, StoreError> = state.run(jobs.clone(), |job| {
+            if job.model == "mlp" {
+                Err(StoreError::Io("crash".to_string()))
+            } else {
+                Ok(())
+            }
+        });
+        assert!(res.is_err());
+
+        // the second run only repeats the jobs that did not complete
+        let mut state = SuiteState::open(&path).unwrap();
+        let mut ran = vec![];
+        let skipped = state
+            .run::<StoreError, _>(jobs, |job| {
+                ran.push(job.model.clone());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(skipped, vec![job("linreg")]);
+        assert_eq!(ran, vec!["mlp".to_string(), "lenet".to_string()]);
+
+        // a corrupted state file is malformed, not an I/O error
+        fs::write(&path, "{").unwrap();
+        assert!(matches!(
+            SuiteState::open(&path),
+            Err(StoreError::Malformed(..))
+        ));
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,253 @@
+//! Storage backends for benchmark artifacts (proofs, keys, reports).
+//!
+//! [LocalStore] writes artifacts to a directory,
+//! [S3Store] uploads them to an S3 bucket (through the `aws` command line tool, which must be installed and configured).
+
+use std::{
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use crate::error::StoreError;
+
+/// The different kinds of artifacts that a benchmark run produces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArtifactKind {
+    Proof,
+    Key,
+    Report,
+}
+
+impl ArtifactKind {
+    /// The directory (or key prefix) artifacts of this kind are stored under.
+    fn dir(self) -> &'static str {
+        match self {
+            ArtifactKind::Proof => "proofs",
+            ArtifactKind::Key => "keys",
+            ArtifactKind::Report => "reports",
+        }
+    }
+}
+
+/// A place where benchmark artifacts can be stored and retrieved.
+pub trait ArtifactStore {
+    /// Stores an artifact, overwriting any artifact with the same kind and name.
+    fn put(&self, kind: ArtifactKind, name: &str, bytes: &[u8]) -> Result<(), StoreError>;
+
+    /// Retrieves an artifact.
+    fn get(&self, kind: ArtifactKind, name: &str) -> Result<Vec<u8>, StoreError>;
+
+    /// Lists the names of the stored artifacts of a given kind.
+    fn list(&self, kind: ArtifactKind) -> Result<Vec<String>, StoreError>;
+}
+
+/// Stores artifacts in a local directory.
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    /// Creates a store rooted at `root`. The directory is created lazily.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, kind: ArtifactKind, name: &str) -> PathBuf {
+        self.root.join(kind.dir()).join(name)
+    }
+}
+
+impl ArtifactStore for LocalStore {
+    fn put(&self, kind: ArtifactKind, name: &str, bytes: &[u8]) -> Result<(), StoreError> {
+        let path = self.path(kind, name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| StoreError::Io(e.to_string()))?;
+        }
+        fs::write(path, bytes).map_err(|e| StoreError::Io(e.to_string()))
+    }
+
+    fn get(&self, kind: ArtifactKind, name: &str) -> Result<Vec<u8>, StoreError> {
+        let path = self.path(kind, name);
+        if !path.exists() {
+            return Err(StoreError::NotFound(name.to_string()));
+        }
+        fs::read(path).map_err(|e| StoreError::Io(e.to_string()))
+    }
+
+    fn list(&self, kind: ArtifactKind) -> Result<Vec<String>, StoreError> {
+        let dir = self.root.join(kind.dir());
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut names = vec![];
+        for entry in fs::read_dir(dir).map_err(|e| StoreError::Io(e.to_string()))? {
+            let entry = entry.map_err(|e| StoreError::Io(e.to_string()))?;
+            names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+/// Stores artifacts in an S3 bucket, under an optional prefix.
+pub struct S3Store {
+    bucket: String,
+    prefix: String,
+}
+
+impl S3Store {
+    /// Creates a store for `s3://<bucket>/<prefix>`.
+    pub fn new(bucket: impl Into<String>, prefix: impl Into<String>) -> Self {
+        Self {
+            bucket: bucket.into(),
+            prefix: prefix.into(),
+        }
+    }
+
+    fn url(&self, kind: ArtifactKind, name: &str) -> String {
+        let prefix = self.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            format!("s3://{}/{}/{name}", self.bucket, kind.dir())
+        } else {
+            format!("s3://{}/{prefix}/{}/{name}", self.bucket, kind.dir())
+        }
+    }
+
+    /// Runs an `aws s3` command, feeding it `stdin` and returning its output.
+    fn aws(&self, args: &[&str], stdin: Option<&[u8]>) -> Result<Vec<u8>, StoreError> {
+        let mut child = Command::new("aws")
+            .arg("s3")
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| StoreError::Io(format!("could not run the aws cli: {e}")))?;
+
+        if let Some(bytes) = stdin {
+            child
+                .stdin
+                .take()
+                .expect("stdin is piped")
+                .write_all(bytes)
+                .map_err(|e| StoreError::Io(e.to_string()))?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| StoreError::Io(e.to_string()))?;
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(StoreError::Remote(
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            ))
+        }
+    }
+}
+
+/// Returns whether the error output of the `aws` cli reports a missing key,
+/// which it does with a `NoSuchKey` error, or a 404 when it checks the object before downloading it.
+fn is_missing_key(stderr: &str) -> bool {
+    stderr.contains("NoSuchKey") || stderr.contains("(404)")
+}
+
+/// Returns the name of an object in a line of `aws s3 ls`, which looks like `2024-01-01 00:00:00       1234 name`.
+/// The name is everything after the third column, as it may contain spaces;
+/// the lines of the prefixes (`PRE name/`) have no name.
+fn listed_name(line: &str) -> Option<&str> {
+    let mut rest = line;
+    for _ in 0..3 {
+        rest = rest.trim_start();
+        rest = &rest[rest.find(char::is_whitespace)?..];
+    }
+    rest.strip_prefix(' ').filter(|name| !name.is_empty())
+}
+
+impl ArtifactStore for S3Store {
+    fn put(&self, kind: ArtifactKind, name: &str, bytes: &[u8]) -> Result<(), StoreError> {
+        self.aws(&["cp", "-", &self.url(kind, name)], Some(bytes))
+            .map(|_| ())
+    }
+
+    fn get(&self, kind: ArtifactKind, name: &str) -> Result<Vec<u8>, StoreError> {
+        match self.aws(&["cp", &self.url(kind, name), "-"], None) {
+            Err(StoreError::Remote(stderr)) if is_missing_key(&stderr) => {
+                Err(StoreError::NotFound(name.to_string()))
+            }
+            result => result,
+        }
+    }
+
+    fn list(&self, kind: ArtifactKind) -> Result<Vec<String>, StoreError> {
+        let url = self.url(kind, "");
+        let listing = self.aws(&["ls", &url], None)?;
+
+        let mut names: Vec<_> = String::from_utf8_lossy(&listing)
+            .lines()
+            .filter_map(listed_name)
+            .map(str::to_string)
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_store() {
+        let root = std::env::temp_dir().join(format!("kimchi-store-{}", std::process::id()));
+        let store = LocalStore::new(&root);
+
+        assert!(store.list(ArtifactKind::Proof).unwrap().is_empty());
+        assert!(matches!(
+            store.get(ArtifactKind::Proof, "missing"),
+            Err(StoreError::NotFound(_))
+        ));
+
+        store.put(ArtifactKind::Proof, "b.bin", b"proof b").unwrap();
+        store.put(ArtifactKind::Proof, "a.bin", b"proof a").unwrap();
+        store.put(ArtifactKind::Report, "a.json", b"{}").unwrap();
+
+        assert_eq!(store.get(ArtifactKind::Proof, "a.bin").unwrap(), b"proof a");
+        assert_eq!(
+            store.list(ArtifactKind::Proof).unwrap(),
+            vec!["a.bin".to_string(), "b.bin".to_string()]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_listed_name() {
+        assert_eq!(
+            listed_name("2024-01-01 00:00:00       1234 report.json"),
+            Some("report.json")
+        );
+        assert_eq!(
+            listed_name("2024-01-01 00:00:00       1234 mlp  run 2.json"),
+            Some("mlp  run 2.json")
+        );
+        assert_eq!(listed_name("                           PRE old/"), None);
+        assert_eq!(listed_name(""), None);
+    }
+
+    #[test]
+    fn test_s3_missing_key() {
+        assert!(is_missing_key(
+            "fatal error: An error occurred (404) when calling the HeadObject operation: Key \"proofs/a.bin\" does not exist"
+        ));
+        assert!(is_missing_key(
+            "download failed: An error occurred (NoSuchKey) when calling the GetObject operation"
+        ));
+        assert!(!is_missing_key(
+            "fatal error: An error occurred (403) when calling the HeadObject operation: Forbidden"
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+//! The optional trusted-execution comparison of a benchmark.
+//!
+//! A trusted execution environment (SGX, SEV) runs the same quantized inference inside an enclave,
+//! and attests that the outputs were computed by the measured code from the inputs.
+//! This trusts the hardware vendor instead of a proof, so comparing it with a proof of the same model
+//! tells what the trustlessness of a proof costs.
+//! Enclaves are plugged in through the [Enclave] trait.
+//! Real enclaves are platform-specific and implemented outside of this crate;
+//! [SimulatedEnclave] runs the inference on the host and adds the overhead of an enclave, for hosts without one.
+
+use std::time::{Duration, Instant};
+
+use mina_curves::pasta::Fp;
+use serde::Serialize;
+
+use super::baseline::digest;
+use crate::error::TeeError;
+
+/// An enclave in which a model was loaded.
+pub trait Enclave {
+    /// The attestation of an inference.
+    type Attestation: Serialize;
+
+    /// The name of the enclave, used in the reports.
+    fn name(&self) -> &str;
+
+    /// Whether the enclave is simulated, in which case its latencies are modeled.
+    fn simulated(&self) -> bool {
+        false
+    }
+
+    /// Runs the model on the inputs in the enclave, and returns the outputs with their attestation.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the inference fails in the enclave.
+    fn run(&self, inputs: &[Fp]) -> Result<(Vec<Fp>, Self::Attestation), TeeError>;
+
+    /// Verifies the attestation of an inference.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the attestation is not for the loaded model and the given inputs and outputs.
+    fn verify(
+        &self,
+        attestation: &Self::Attestation,
+        inputs: &[Fp],
+        outputs: &[Fp],
+    ) -> Result<(), TeeError>;
+}
+
+/// The attestation of a [SimulatedEnclave].
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SimulatedAttestation {
+    /// The hex-encoded digest of the weights of the model, standing for the measurement of the enclave.
+    pub measurement: String,
+    /// The hex-encoded digest of the inputs and the outputs, standing for the report data of the quote.
+    pub report: String,
+}
+
+/// An enclave simulated on the host.
+///
+/// The inference runs on the host, and is then delayed to account for the enclave:
+/// the inference takes `slowdown` times longer (for example because of memory encryption and paging),
+/// and creating an attestation takes `attestation`.
+pub struct SimulatedEnclave<M> {
+    pub model: M,
+    /// The weights of the model, which are measured.
+    pub weights: Vec<Fp>,
+    pub slowdown: f64,
+    pub attestation: Duration,
+}
+
+impl<M> SimulatedEnclave<M> {
+    fn measurement(&self) -> String {
+        hex::encode(digest(&self.weights, &[], &[]))
+    }
+}
+
+impl<M: Fn(&[Fp]) -> Vec<Fp>> Enclave for SimulatedEnclave<M> {
+    type Attestation = SimulatedAttestation;
+
+    fn name(&self) -> &str {
+        "simulated"
+    }
+
+    fn simulated(&self) -> bool {
+        true
+    }
+
+    fn run(&self, inputs: &[Fp]) -> Result<(Vec<Fp>, Self::Attestation), TeeError> {
+        let start = Instant::now();
+        let outputs = (self.model)(inputs);
+        let overhead = start.elapsed().mul_f64((self.slowdown - 1.0).max(0.0));
+        std::thread::sleep(overhead + self.attestation);
+
+        let attestation = SimulatedAttestation {
+            measurement: self.measurement(),
+            report: hex::encode(digest(&[], inputs, &outputs)),
+        };
+        Ok((outputs, attestation))
+    }
+
+    fn verify(
+        &self,
+        attestation: &Self::Attestation,
+        inputs: &[Fp],
+        outputs: &[Fp],
+    ) -> Result<(), TeeError> {
+        if attestation.measurement != self.measurement() {
+            return Err(TeeError::Attestation("unexpected measurement".to_string()));
+        }
+        if attestation.report != hex::encode(digest(&[], inputs, outputs)) {
+            return Err(TeeError::Attestation(
+                "the report does not match the inputs and outputs".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The trusted-execution section of a benchmark report.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct TeeReport {
+    pub model: String,
+    pub enclave: String,
+    /// Whether the latencies are modeled by a simulated enclave rather than measured.
+    pub simulated: bool,
+    /// The time it takes to run the inference in the enclave and attest to it.
+    pub latency: Duration,
+    /// The time it takes to verify the attestation.
+    pub verify: Duration,
+}
+
+/// Runs an inference of a model in an enclave, checks its attestation, and reports the latencies.
+///
+/// # Errors
+///
+/// Will give error if the inference fails, or if its attestation does not verify.
+pub fn compare<E: Enclave>(
+    model: String,
+    enclave: &E,
+    inputs: &[Fp],
+) -> Result<(Vec<Fp>, TeeReport), TeeError> {
+    let start = Instant::now();
+    let (outputs, attestation) = enclave.run(inputs)?;
+    let latency = start.elapsed();
+
+    let start = Instant::now();
+    enclave.verify(&attestation, inputs, &outputs)?;
+    let verify = start.elapsed();
+
+    let report = TeeReport {
+        model,
+        enclave: enclave.name().to_string(),
+        simulated: enclave.simulated(),
+        latency,
+        verify,
+    };
+    Ok((outputs, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulated_enclave() {
+        let weights = vec![Fp::from(2u64), Fp::from(3u64)];
+        let model_weights = weights.clone();
+        let enclave = SimulatedEnclave {
+            model: move |inputs: &[Fp]| {
+                vec![inputs.iter().zip(&model_weights).map(|(x, w)| *x * w).sum()]
+            },
+            weights,
+            slowdown: 1.5,
+            attestation: Duration::from_millis(1),
+        };
+
+        let inputs = [Fp::from(4u64), Fp::from(5u64)];
+        let (outputs, report) = compare("test".to_string(), &enclave, &inputs).unwrap();
+        assert_eq!(outputs, vec![Fp::from(23u64)]);
+        assert!(report.simulated);
+        assert!(report.latency >= Duration::from_millis(1));
+        println!("tee: {}", serde_json::to_string(&report).unwrap());
+
+        // an attestation only verifies for its inputs and outputs
+        let (_, attestation) = enclave.run(&inputs).unwrap();
+        assert!(enclave
+            .verify(&attestation, &inputs, &[Fp::from(24u64)])
+            .is_err());
+    }
+}
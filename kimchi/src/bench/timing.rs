@@ -0,0 +1,131 @@
+//! Constant-time audit of verifier paths, in the style of [dudect](https://eprint.iacr.org/2016/1123.pdf).
+//!
+//! The same operation is timed on two classes of inputs (typically a fixed input and varying inputs),
+//! with the classes interleaved at random to avoid drifts in the machine's state.
+//! Welch's t-test is then used to decide whether the timing distributions differ:
+//! a statistic above [LEAK_THRESHOLD] in absolute value indicates a timing variance that depends on the input.
+
+use std::time::Instant;
+
+use rand::Rng;
+use serde::Serialize;
+
+use crate::error::TimingAuditError;
+
+/// The absolute value of the t-statistic above which a leak is reported (the one used by dudect).
+pub const LEAK_THRESHOLD: f64 = 4.5;
+
+/// Running mean and variance (Welford's algorithm).
+#[derive(Default, Clone, Copy, Debug)]
+struct Moments {
+    n: f64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    fn push(&mut self, x: f64) {
+        self.n += 1.0;
+        let delta = x - self.mean;
+        self.mean += delta / self.n;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn variance(&self) -> f64 {
+        if self.n < 2.0 {
+            0.0
+        } else {
+            self.m2 / (self.n - 1.0)
+        }
+    }
+}
+
+/// The result of a timing audit.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct TimingAudit {
+    /// The number of measurements of each class.
+    pub samples: (usize, usize),
+    /// The mean duration of each class, in nanoseconds.
+    pub mean_ns: (f64, f64),
+    /// Welch's t-statistic between the two classes.
+    pub t_statistic: f64,
+}
+
+impl TimingAudit {
+    /// Returns whether the timings of the two classes significantly differ.
+    pub fn leak_suspected(&self) -> bool {
+        self.t_statistic.abs() > LEAK_THRESHOLD
+    }
+}
+
+/// Times `op` on `measurements` inputs drawn at random from the two classes,
+/// `input(false)` providing inputs of the first class and `input(true)` of the second one.
+///
+/// # Errors
+///
+/// Will give error if a class got fewer than 2 measurements, too few for Welch's t-test.
+pub fn audit<I, INPUT, OP>(
+    measurements: usize,
+    mut input: INPUT,
+    mut op: OP,
+) -> Result<TimingAudit, TimingAuditError>
+where
+    INPUT: FnMut(bool) -> I,
+    OP: FnMut(I),
+{
+    let mut rng = rand::thread_rng();
+    let mut classes = [Moments::default(); 2];
+
+    for _ in 0..measurements {
+        let class: bool = rng.gen();
+        let input = input(class);
+
+        let start = Instant::now();
+        op(input);
+        let elapsed = start.elapsed().as_nanos() as f64;
+
+        classes[usize::from(class)].push(elapsed);
+    }
+
+    let [a, b] = classes;
+    if a.n < 2.0 || b.n < 2.0 {
+        return Err(TimingAuditError::TooFewSamples(a.n as usize, b.n as usize));
+    }
+    let denominator = (a.variance() / a.n + b.variance() / b.n).sqrt();
+    let t_statistic = if denominator == 0.0 {
+        0.0
+    } else {
+        (a.mean - b.mean) / denominator
+    };
+
+    Ok(TimingAudit {
+        samples: (a.n as usize, b.n as usize),
+        mean_ns: (a.mean, b.mean),
+        t_statistic,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_detects_input_dependent_timing() {
+        // the second class does a lot more work
+        let audit = audit(
+            200,
+            |class| if class { 20_000u64 } else { 10u64 },
+            |n| {
+                std::hint::black_box((0..n).fold(0u64, |acc, x| acc.wrapping_add(x * x)));
+            },
+        )
+        .unwrap();
+        assert!(audit.leak_suspected(), "{audit:?}");
+
+        // a single measurement cannot be audited
+        assert!(matches!(
+            super::audit(1, |_| (), |_| ()),
+            Err(TimingAuditError::TooFewSamples(_, _))
+        ));
+    }
+}
@@ -0,0 +1,51 @@
+//! Compares the proving time of two configurations of the benchmark circuit, and tells whether they differ significantly
+//! (see [kimchi::bench::ab]).
+//!
+//! ```console
+//! $ cargo run --release --bin bench-ab -- <srs-size>[@<threads>] <srs-size>[@<threads>] [--rounds <n>] [--seed <seed>]
+//! ```
+//!
+//! Sizes are given as powers of two, and threads default to those of rayon.
+//! Prints the report as JSON, followed by the verdict.
+
+use std::env;
+
+use kimchi::bench::ab::{ab_test, AbConfig, SIGNIFICANCE};
+
+const USAGE: &str =
+    "usage: bench-ab <srs-size>[@<threads>] <srs-size>[@<threads>] [--rounds <n>] [--seed <seed>]";
+
+fn parse_config(arg: &str) -> AbConfig {
+    let (size, threads) = arg.split_once('@').unwrap_or((arg, "0"));
+    AbConfig {
+        srs_size_log2: size.parse().expect(USAGE),
+        threads: threads.parse().expect(USAGE),
+    }
+}
+
+fn main() {
+    let mut configs = vec![];
+    let mut rounds = 20;
+    let mut seed = 0;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rounds" => rounds = args.next().and_then(|n| n.parse().ok()).expect(USAGE),
+            "--seed" => seed = args.next().and_then(|n| n.parse().ok()).expect(USAGE),
+            _ => configs.push(parse_config(&arg)),
+        }
+    }
+    let [a, b]: [AbConfig; 2] = configs.try_into().expect(USAGE);
+
+    let report = ab_test(a, b, rounds, seed);
+    println!("{}", serde_json::to_string(&report).unwrap());
+    let (median_a, median_b) = report.median_ns;
+    if report.significant() {
+        println!(
+            "significant: the second configuration takes {:.1}% of the time of the first one",
+            100.0 * median_b / median_a
+        );
+    } else {
+        println!("not significant at the {SIGNIFICANCE} level: the difference may be noise");
+    }
+}
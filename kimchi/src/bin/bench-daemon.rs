@@ -0,0 +1,85 @@
+//! Runs the benchmark suite whenever a directory or a git ref changes (see [kimchi::bench::daemon]).
+//!
+//! ```console
+//! $ cargo run --bin bench-daemon -- (--dir <models> | --git <repo> --ref <ref>) --store <dir> [--state <state.json>] [--interval <secs>] [--srs-size <log2>] [--timeout <secs>]
+//! ```
+//!
+//! The reports are appended to a local artifact store, and the jobs that ran are recorded in the state file,
+//! `daemon-state.json` by default, so that a restarted daemon does not run them again.
+//! A job whose setup or proof panics, or takes longer than the timeout, is recorded as a failure.
+//! Every job benchmarks the built-in circuit for an SRS of `2^srs-size` points:
+//! the model files and configs only trigger the jobs and name their reports, they are not loaded.
+
+use std::{env, path::PathBuf, time::Duration};
+
+use kimchi::bench::{
+    costs::CostReport,
+    daemon::{Daemon, WatchSource},
+    failure::{run_stage, Failure, Stage},
+    state::JobId,
+    store::LocalStore,
+    BenchmarkCtx,
+};
+
+const USAGE: &str = "usage: bench-daemon (--dir <models> | --git <repo> --ref <ref>) --store <dir> [--state <state.json>] [--interval <secs>] [--srs-size <log2>] [--timeout <secs>]";
+
+fn main() {
+    let mut dir = None;
+    let mut repo = None;
+    let mut reference = None;
+    let mut store = None;
+    let mut state = PathBuf::from("daemon-state.json");
+    let mut interval = 60;
+    let mut srs_size = 10;
+    let mut timeout = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| panic!("missing value for {arg}"));
+        match arg.as_str() {
+            "--dir" => dir = Some(PathBuf::from(value)),
+            "--git" => repo = Some(PathBuf::from(value)),
+            "--ref" => reference = Some(value),
+            "--store" => store = Some(PathBuf::from(value)),
+            "--state" => state = PathBuf::from(value),
+            "--interval" => interval = value.parse().expect("the interval must be a number"),
+            "--srs-size" => srs_size = value.parse().expect("the SRS size must be a number"),
+            "--timeout" => {
+                timeout = Some(Duration::from_secs(
+                    value.parse().expect("the timeout must be a number"),
+                ))
+            }
+            _ => panic!("{USAGE}"),
+        }
+    }
+
+    let source = match (dir, repo) {
+        (Some(dir), None) => WatchSource::Directory(dir),
+        (None, Some(repo)) => WatchSource::GitRef {
+            repo,
+            reference: reference.expect(USAGE),
+        },
+        _ => panic!("{USAGE}"),
+    };
+    let mut daemon = Daemon::new(
+        source,
+        LocalStore::new(store.expect(USAGE)),
+        state,
+        Duration::from_secs(interval),
+    )
+    .expect("failed to open the state file");
+
+    let run = |job: &JobId| -> Result<CostReport, Failure> {
+        // the model is not loaded: the built-in circuit is benchmarked under its name
+        println!("running {} ({})", job.model, job.config);
+        let (ctx, one_time) = run_stage(Stage::Setup, timeout, || {
+            Ok(BenchmarkCtx::with_costs(srs_size))
+        })?;
+        let recurring = run_stage(Stage::Prove, timeout, || Ok(ctx.recurring_costs()))?;
+        Ok(CostReport::new(job.model.clone(), one_time, recurring).with_security(ctx.security()))
+    };
+    let e = daemon.watch(run).unwrap_err();
+    panic!("the daemon stopped: {e}");
+}
@@ -0,0 +1,254 @@
+//! Checks that a model is imported faithfully, without proving anything.
+//!
+//! ```console
+//! $ cargo run --bin check -- <model.json> [--onnx <model.onnx>] [--tolerance <eps>]
+//! ```
+//!
+//! The model is a stack of dense layers exported with sample inputs.
+//! Its weights and inputs are quantized to fixed-point values with [to_fixed], as in the circuits,
+//! and the layers are evaluated on the quantized values by the gadgets of the circuits ([DynDenseLayer]),
+//! run in witness mode without a constraint system.
+//! Between layers, the outputs are requantized to the scale of the inputs with [shift_right_signed].
+//! The dequantized outputs of the last layer are compared with the outputs of the framework.
+//! The reference outputs are computed with ONNX Runtime when an ONNX model is given and the
+//! `onnxruntime` Python package is installed, and read from the samples of the model otherwise.
+//!
+//! The check fails if the maximum deviation exceeds the tolerance, when one is given.
+
+use std::{
+    env, fs,
+    io::Write,
+    process::{exit, Command, Stdio},
+};
+
+use kimchi::{
+    loc,
+    snarky::{
+        dense::{DenseShape, DynDenseLayer},
+        prelude::{FieldVar, RunState, SnarkyResult},
+        shift::{shift_right_signed, to_fixed, to_signed, Rounding},
+    },
+};
+use mina_curves::pasta::{Fp, Vesta};
+use serde::Deserialize;
+
+/// The number of bits of the accumulators of the layers, sign included.
+const ACCUMULATOR_BITS: usize = 64;
+
+/// A sample input, with the output of the framework if it was exported.
+#[derive(Deserialize)]
+struct Sample {
+    input: Vec<f64>,
+    #[serde(default)]
+    expected: Option<Vec<f64>>,
+}
+
+/// A dense layer `y = W x + b`.
+#[derive(Deserialize)]
+struct Layer {
+    /// The weights, one row per output.
+    weights: Vec<Vec<f64>>,
+    bias: Vec<f64>,
+}
+
+/// A stack of dense layers.
+#[derive(Deserialize)]
+struct Model {
+    /// The number of fractional bits of the fixed-point values.
+    scale_bits: usize,
+    layers: Vec<Layer>,
+    samples: Vec<Sample>,
+}
+
+impl Model {
+    /// Quantizes the layers: the weights at the scale of the inputs, and the bias at the scale of the products.
+    fn quantize(&self) -> Vec<DynDenseLayer<Fp>> {
+        let bits = self.scale_bits;
+        self.layers
+            .iter()
+            .enumerate()
+            .map(|(l, layer)| {
+                let shape = DenseShape {
+                    name: format!("layer {l}"),
+                    inputs: layer.weights.first().map_or(0, Vec::len),
+                    outputs: layer.weights.len(),
+                };
+                let weights: Vec<Fp> = layer
+                    .weights
+                    .iter()
+                    .flatten()
+                    .map(|&w| to_fixed(w, bits))
+                    .collect();
+                let bias: Vec<Fp> = layer.bias.iter().map(|&b| to_fixed(b, 2 * bits)).collect();
+                DynDenseLayer::constant(shape, &weights, &bias)
+            })
+            .collect()
+    }
+}
+
+/// Evaluates the layers on quantized values, as the circuit does, and dequantizes the outputs.
+///
+/// # Errors
+///
+/// Will give error if the shapes of the layers do not match,
+/// or if an accumulator of a hidden layer does not fit in [ACCUMULATOR_BITS] bits.
+fn evaluate_quantized(
+    model: &Model,
+    layers: &[DynDenseLayer<Fp>],
+    input: &[f64],
+) -> SnarkyResult<Vec<f64>> {
+    let bits = model.scale_bits;
+    let mut sys = RunState::new::<Vesta>(0, 0, false);
+    sys.generate_witness_init(vec![])?;
+
+    let mut values = Vec::with_capacity(input.len());
+    for &x in input {
+        let value: FieldVar<Fp> = sys.compute(loc!(), |_| to_fixed(x, bits))?;
+        values.push(value);
+    }
+    for (l, layer) in layers.iter().enumerate() {
+        let outputs = layer.forward(&mut sys, loc!(), &values)?;
+        if l + 1 == layers.len() {
+            values = outputs;
+            break;
+        }
+        values = outputs
+            .iter()
+            .map(|y| {
+                shift_right_signed(
+                    &mut sys,
+                    loc!(),
+                    y,
+                    ACCUMULATOR_BITS,
+                    bits,
+                    Rounding::Nearest,
+                )
+            })
+            .collect::<SnarkyResult<_>>()?;
+    }
+
+    let scale = 2f64.powi(2 * bits as i32);
+    Ok(values
+        .iter()
+        .map(|y| to_signed(y.eval(&sys), ACCUMULATOR_BITS) as f64 / scale)
+        .collect())
+}
+
+/// Runs the ONNX model on the sample inputs with ONNX Runtime,
+/// or returns [None] if ONNX Runtime is not available.
+fn run_onnx(path: &str, inputs: &[&[f64]]) -> Option<Vec<Vec<f64>>> {
+    const SCRIPT: &str = "\
+import json, sys
+import numpy as np
+import onnxruntime as ort
+session = ort.InferenceSession(sys.argv[1])
+name = session.get_inputs()[0].name
+inputs = np.array(json.load(sys.stdin), dtype=np.float32)
+outputs = session.run(None, {name: inputs})[0]
+json.dump(outputs.reshape(len(inputs), -1).tolist(), sys.stdout)
+";
+    let mut child = Command::new("python3")
+        .args(["-c", SCRIPT, path])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let inputs = serde_json::to_vec(inputs).expect("failed to serialize the inputs");
+    child.stdin.take()?.write_all(&inputs).ok()?;
+
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut model_path = None;
+    let mut onnx_path = None;
+    let mut tolerance: Option<f64> = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--onnx" => onnx_path = Some(args.next().expect("--onnx needs a path")),
+            "--tolerance" => {
+                let eps = args.next().expect("--tolerance needs a value");
+                tolerance = Some(eps.parse().expect("the tolerance must be a number"));
+            }
+            _ => model_path = Some(arg),
+        }
+    }
+    let model_path =
+        model_path.expect("usage: check <model.json> [--onnx <model.onnx>] [--tolerance <eps>]");
+
+    let model: Model =
+        serde_json::from_str(&fs::read_to_string(&model_path).expect("failed to read the model"))
+            .expect("failed to parse the model");
+    assert!(!model.layers.is_empty(), "the model has no layers");
+    for (l, layer) in model.layers.iter().enumerate() {
+        assert_eq!(
+            layer.weights.len(),
+            layer.bias.len(),
+            "the weights and the bias of layer {l} have different numbers of outputs"
+        );
+    }
+    let layers = model.quantize();
+
+    let inputs: Vec<_> = model.samples.iter().map(|s| s.input.as_slice()).collect();
+    let onnx = onnx_path
+        .as_deref()
+        .and_then(|path| run_onnx(path, &inputs));
+    let references: Vec<Vec<f64>> = match onnx {
+        Some(outputs) => {
+            println!("reference outputs computed with ONNX Runtime");
+            outputs
+        }
+        None => {
+            if onnx_path.is_some() {
+                println!("ONNX Runtime is not available, using the exported outputs");
+            }
+            model
+                .samples
+                .iter()
+                .enumerate()
+                .map(|(i, s)| {
+                    s.expected
+                        .clone()
+                        .unwrap_or_else(|| panic!("sample {i} has no expected output"))
+                })
+                .collect()
+        }
+    };
+
+    let mut max_deviation = 0f64;
+    for (i, (sample, reference)) in model.samples.iter().zip(&references).enumerate() {
+        let outputs = evaluate_quantized(&model, &layers, &sample.input)
+            .unwrap_or_else(|e| panic!("sample {i} cannot be evaluated: {e}"));
+        assert_eq!(
+            outputs.len(),
+            reference.len(),
+            "sample {i} has {} reference outputs instead of {}",
+            reference.len(),
+            outputs.len()
+        );
+        let deviation = outputs
+            .iter()
+            .zip(reference)
+            .map(|(y, r)| (y - r).abs())
+            .fold(0f64, f64::max);
+        println!("sample {i}: max deviation {deviation:e}");
+        max_deviation = max_deviation.max(deviation);
+    }
+    println!(
+        "{} samples: max deviation {max_deviation:e}",
+        model.samples.len()
+    );
+
+    if let Some(tolerance) = tolerance {
+        if max_deviation > tolerance {
+            println!("the deviation exceeds the tolerance {tolerance:e}");
+            exit(1);
+        }
+    }
+}
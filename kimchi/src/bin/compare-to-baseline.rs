@@ -0,0 +1,90 @@
+//! Compares the proving and verification times of the host with the reference results bundled with the crate
+//! (see [kimchi::bench::reference]).
+//!
+//! ```console
+//! $ cargo run --release --bin compare-to-baseline -- [--platform <name>] [--tolerance <fraction>]
+//! $ cargo run --release --bin compare-to-baseline -- --record <name>
+//! ```
+//!
+//! Without `--platform`, the times are compared with those of the platform closest to the host.
+//! Exits with an error if a time is not within the tolerance (0.25 by default) of the reference.
+//! `--record` measures the reference results of the host instead, and adds them to the bundle under the given name,
+//! replacing any previous results of that name.
+//! The references are read from the sources of the crate when they are there, so that a recorded platform is used
+//! without rebuilding, and otherwise from the references compiled into the binary.
+
+use std::{env, fs, path::Path, process::exit};
+
+use kimchi::bench::reference::{compare, measure, Platform, Verdict, BUNDLE_PATH, REFERENCE_SIZES};
+
+/// The references in the sources of the crate if they are there, and otherwise the bundled ones.
+fn references() -> Vec<Platform> {
+    let path = Path::new(BUNDLE_PATH);
+    if path.exists() {
+        Platform::load(path).expect("failed to read the reference results")
+    } else {
+        Platform::bundled()
+    }
+}
+
+fn main() {
+    let mut platform_name = None;
+    let mut tolerance = 0.25;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--platform" => platform_name = Some(args.next().expect("--platform needs a name")),
+            "--tolerance" => {
+                tolerance = args
+                    .next()
+                    .and_then(|tolerance| tolerance.parse().ok())
+                    .expect("--tolerance needs a fraction")
+            }
+            "--record" => {
+                let platform = Platform::record(args.next().expect("--record needs a name"));
+                println!("{}", serde_json::to_string_pretty(&platform).unwrap());
+
+                let mut platforms = references();
+                Platform::insert(&mut platforms, platform);
+                let json = serde_json::to_string_pretty(&platforms).unwrap();
+                fs::write(BUNDLE_PATH, json + "\n").expect("failed to write the reference results");
+                println!("recorded in {BUNDLE_PATH}");
+                return;
+            }
+            _ => panic!("usage: compare-to-baseline [--platform <name>] [--tolerance <fraction>] | --record <name>"),
+        }
+    }
+
+    let platforms = references();
+    if platforms.is_empty() {
+        println!("no reference results are recorded yet; record some with --record <name>");
+        exit(1);
+    }
+    let platform = match &platform_name {
+        Some(name) => platforms.iter().find(|platform| &platform.name == name),
+        None => Platform::closest(&platforms),
+    };
+    let Some(platform) = platform else {
+        println!("no reference results for this host; record some with --record <name>");
+        exit(1);
+    };
+    if cfg!(debug_assertions) {
+        println!("warning: this is a debug build, which is much slower than the references");
+    }
+
+    println!(
+        "comparing with {} ({}, {} threads)",
+        platform.name, platform.cpu, platform.threads
+    );
+    let comparisons = compare(platform, &measure(&REFERENCE_SIZES), tolerance);
+    for c in &comparisons {
+        println!(
+            "2^{} {}: {:?} against {:?} ({:.2}x): {:?}",
+            c.srs_size_log2, c.operation, c.measured, c.reference, c.ratio, c.verdict
+        );
+    }
+    if comparisons.iter().any(|c| c.verdict != Verdict::Expected) {
+        println!("the environment does not perform as the reference");
+        exit(1);
+    }
+}
@@ -0,0 +1,57 @@
+//! Runs a benchmark and exports the manifest needed to reproduce it (see [kimchi::bench::manifest]).
+//!
+//! ```console
+//! $ cargo run --bin export-manifest -- --model <file> --dataset <file> --quantization <config.json> --srs-size <log2> [--lock <Cargo.lock>] [--out <manifest.json>]
+//! ```
+//!
+//! The versions of the dependencies are read from the `Cargo.lock`, `Cargo.lock` by default if it exists.
+//! The model and the dataset are hashed into the manifest, but the benchmarked circuit is the built-in one,
+//! sized by `--srs-size`, whatever the model.
+
+use std::{env, fs, path::PathBuf};
+
+use kimchi::bench::manifest::Manifest;
+
+const USAGE: &str = "usage: export-manifest --model <file> --dataset <file> --quantization <config.json> --srs-size <log2> [--lock <Cargo.lock>] [--out <manifest.json>]";
+
+fn main() {
+    let mut model = None;
+    let mut dataset = None;
+    let mut quantization = None;
+    let mut srs_size = None;
+    let mut lock = Some(PathBuf::from("Cargo.lock")).filter(|lock| lock.exists());
+    let mut out = PathBuf::from("manifest.json");
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| panic!("missing value for {arg}"));
+        match arg.as_str() {
+            "--model" => model = Some(PathBuf::from(value)),
+            "--dataset" => dataset = Some(PathBuf::from(value)),
+            "--quantization" => quantization = Some(PathBuf::from(value)),
+            "--srs-size" => srs_size = Some(value.parse().expect("the SRS size must be a number")),
+            "--lock" => lock = Some(PathBuf::from(value)),
+            "--out" => out = PathBuf::from(value),
+            _ => panic!("{USAGE}"),
+        }
+    }
+
+    let quantization = fs::read_to_string(quantization.expect(USAGE))
+        .expect("failed to read the quantization config");
+    let quantization =
+        serde_json::from_str(&quantization).expect("failed to parse the quantization config");
+    let manifest = Manifest::run(
+        model.expect(USAGE),
+        dataset.expect(USAGE),
+        quantization,
+        srs_size.expect(USAGE),
+        lock,
+    )
+    .expect("failed to run the benchmark");
+
+    let json = serde_json::to_string_pretty(&manifest).unwrap();
+    fs::write(&out, json).expect("failed to write the manifest");
+    println!("manifest written to {out:?}");
+}
@@ -0,0 +1,39 @@
+//! Scaffolds a new benchmark circuit (see [kimchi::bench::scaffold]).
+//!
+//! ```console
+//! $ cargo run --bin new-circuit -- <name> [--dir <src/bench>]
+//! ```
+//!
+//! Writes the module of the circuit and its golden vectors in the directory of the benchmark modules,
+//! `src/bench` of the crate by default, and registers the module there.
+
+use std::{env, path::PathBuf, process::exit};
+
+use kimchi::bench::scaffold::scaffold;
+
+fn main() {
+    let mut name = None;
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/bench");
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dir" => dir = PathBuf::from(args.next().expect("--dir needs a path")),
+            _ => name = Some(arg),
+        }
+    }
+    let name = name.expect("usage: new-circuit <name> [--dir <src/bench>]");
+
+    match scaffold(&dir, &name) {
+        Ok(written) => {
+            for path in written {
+                println!("wrote {}", path.display());
+            }
+            println!("replace the gadgets and the golden vectors of {name}, then run its test");
+        }
+        Err(e) => {
+            println!("failed to scaffold {name}: {e}");
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Builds self-contained benchmark binaries for other platforms, and packages them per target.
+//!
+//! ```console
+//! $ cargo run --bin package -- --target <target>... [--bin <name>]... [--out <dir>]
+//! ```
+//!
+//! A target is either one of the aliases below or a Rust target triple.
+//! Linux binaries are linked statically against musl, so that they run on any distribution.
+//! The Rust targets must be installed (`rustup target add <triple>`),
+//! and cross-compiling to macOS or Windows needs the corresponding linker.
+//!
+//! Each target gets a directory `<out>/<triple>` with the binaries and a `manifest.json`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The targets of benchmark campaigns, by alias.
+const TARGETS: [(&str, &str); 5] = [
+    ("linux", "x86_64-unknown-linux-musl"),
+    ("linux-arm", "aarch64-unknown-linux-musl"),
+    ("macos", "x86_64-apple-darwin"),
+    ("macos-arm", "aarch64-apple-darwin"),
+    ("windows", "x86_64-pc-windows-gnu"),
+];
+
+/// The binaries packaged when none is given.
+const DEFAULT_BINS: [&str; 1] = ["srs"];
+
+fn triple(target: &str) -> &str {
+    TARGETS
+        .iter()
+        .find(|(alias, _)| *alias == target)
+        .map_or(target, |(_, triple)| *triple)
+}
+
+fn package(triple: &str, bins: &[String], out: &Path) {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let mut command = Command::new(cargo);
+    command.args(["build", "--release", "--target", triple]);
+    for bin in bins {
+        command.args(["--bin", bin]);
+    }
+    if triple.contains("musl") {
+        let mut rustflags = env::var("RUSTFLAGS").unwrap_or_default();
+        rustflags.push_str(" -C target-feature=+crt-static");
+        command.env("RUSTFLAGS", rustflags.trim());
+    }
+
+    let status = command.status().expect("failed to run cargo");
+    assert!(status.success(), "the build for {triple} failed");
+
+    let target_dir = env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".to_string());
+    let build_dir = PathBuf::from(target_dir).join(triple).join("release");
+    let dir = out.join(triple);
+    fs::create_dir_all(&dir).expect("failed to create the package directory");
+
+    let extension = if triple.contains("windows") {
+        ".exe"
+    } else {
+        ""
+    };
+    for bin in bins {
+        let name = format!("{bin}{extension}");
+        fs::copy(build_dir.join(&name), dir.join(&name))
+            .unwrap_or_else(|e| panic!("failed to copy {name}: {e}"));
+    }
+
+    let manifest = serde_json::json!({
+        "target": triple,
+        "version": env!("CARGO_PKG_VERSION"),
+        "static": triple.contains("musl"),
+        "bins": bins,
+    });
+    let manifest = serde_json::to_string_pretty(&manifest).unwrap();
+    fs::write(dir.join("manifest.json"), manifest).expect("failed to write the manifest");
+
+    println!("packaged {} binaries for {triple} in {dir:?}", bins.len());
+}
+
+fn main() {
+    let mut targets = vec![];
+    let mut bins = vec![];
+    let mut out = PathBuf::from("dist");
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| panic!("missing value for {arg}"));
+        match arg.as_str() {
+            "--target" => targets.push(value),
+            "--bin" => bins.push(value),
+            "--out" => out = PathBuf::from(value),
+            _ => panic!("usage: package --target <target>... [--bin <name>]... [--out <dir>]"),
+        }
+    }
+
+    assert!(
+        !targets.is_empty(),
+        "no target given (aliases: {})",
+        TARGETS.map(|(alias, _)| alias).join(", ")
+    );
+    if bins.is_empty() {
+        bins = DEFAULT_BINS.map(String::from).to_vec();
+    }
+
+    for target in &targets {
+        package(triple(target), &bins, &out);
+    }
+}
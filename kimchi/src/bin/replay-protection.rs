@@ -0,0 +1,284 @@
+//! An end-to-end example of replay-protected inferences, from the commitment to a model to the verification of a proof.
+//!
+//! ```console
+//! $ cargo run --release --bin replay-protection -- [--store <nullifiers.json>]
+//! ```
+//!
+//! 1. The model owner commits to the weights of a linear model, the hash of its parameters.
+//! 2. The user commits to an input, the hash of its features and of a secret salt.
+//! 3. The prover proves the prediction of the committed model on the committed input,
+//!    and outputs the nullifier of the inference, the hash of the salt and of both commitments.
+//! 4. The verifier checks the proof, then that the nullifier was never seen before, in a local store.
+//!
+//! A proof can thus be accepted once: replaying it, or proving the same committed input again,
+//! gives the same nullifier, and only the user knowing the salt can create a nullifier at all.
+//! The nullifiers are kept in a JSON file, a fresh temporary one by default.
+//! The salts are drawn at random for every run, so a store shared with previous runs
+//! accepts the new requests, and still rejects the replays of the requests of this run.
+//! Exits with an error if any step of the flow does not behave as expected,
+//! which makes the binary an integration test of the whole pipeline (run twice on the same store by its test).
+
+use std::{collections::BTreeSet, env, fs, path::PathBuf, process::exit};
+
+use ark_ff::UniformRand;
+use kimchi::{
+    bench::BenchCircuit,
+    curve::KimchiCurve,
+    loc,
+    proof::ProverProof,
+    snarky::{
+        api::{SnarkyCircuit, VerifierIndexWrapper},
+        poseidon::{hash_slice, hash_slice_native},
+        prelude::{FieldVar, RunState, SnarkyResult},
+    },
+};
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::evaluation_proof::OpeningProof;
+
+type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+/// The number of features of the model.
+const FEATURES: usize = 4;
+
+/// Proves the prediction of a committed linear model on a committed input, and outputs its nullifier.
+struct InferenceCircuit;
+
+impl SnarkyCircuit for InferenceCircuit {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    /// The weights and the bias of the model, and the input and its salt.
+    type PrivateInput = ([Fp; FEATURES], Fp, [Fp; FEATURES], Fp);
+    /// The commitments to the model and to the input.
+    type PublicInput = [FieldVar<Fp>; 2];
+    /// The prediction and the nullifier.
+    type PublicOutput = [FieldVar<Fp>; 2];
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        [model, input]: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let weights: [FieldVar<Fp>; FEATURES] = sys.compute(loc!(), |_| private.unwrap().0)?;
+        let bias: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap().1)?;
+        let x: [FieldVar<Fp>; FEATURES] = sys.compute(loc!(), |_| private.unwrap().2)?;
+        let salt: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap().3)?;
+
+        let parameters: Vec<_> = weights.iter().chain([&bias]).cloned().collect();
+        let model_commitment = hash_slice(sys, loc!(), &parameters);
+        sys.assert_eq(
+            Some("model.commitment".into()),
+            loc!(),
+            model_commitment,
+            model.clone(),
+        )?;
+        let features: Vec<_> = x.iter().chain([&salt]).cloned().collect();
+        let input_commitment = hash_slice(sys, loc!(), &features);
+        sys.assert_eq(
+            Some("input.commitment".into()),
+            loc!(),
+            input_commitment,
+            input.clone(),
+        )?;
+
+        let prediction = FieldVar::dot_product(&weights, &x, None, loc!(), sys)? + &bias;
+        let nullifier = hash_slice(sys, loc!(), &[salt, model, input]);
+        Ok([prediction, nullifier])
+    }
+}
+
+/// The nullifiers of the accepted proofs, kept in a JSON file.
+struct NullifierStore {
+    path: PathBuf,
+    seen: BTreeSet<String>,
+}
+
+impl NullifierStore {
+    fn open(path: PathBuf) -> Self {
+        let seen = fs::read_to_string(&path)
+            .map(|json| serde_json::from_str(&json).expect("failed to parse the nullifier store"))
+            .unwrap_or_default();
+        NullifierStore { path, seen }
+    }
+
+    /// Records a nullifier, and returns whether it was new.
+    fn insert(&mut self, nullifier: Fp) -> bool {
+        if !self.seen.insert(nullifier.to_string()) {
+            return false;
+        }
+        let json = serde_json::to_string(&self.seen).unwrap();
+        fs::write(&self.path, json).expect("failed to write the nullifier store");
+        true
+    }
+}
+
+/// An inference request of the user: its input, its salt, and the commitment to both.
+struct Request {
+    x: [Fp; FEATURES],
+    salt: Fp,
+    commitment: Fp,
+}
+
+impl Request {
+    /// Commits to an input with a fresh random salt.
+    fn new(x: [u64; FEATURES]) -> Self {
+        let x = x.map(Fp::from);
+        let salt = Fp::rand(&mut rand::thread_rng());
+        let features: Vec<_> = x.iter().chain([&salt]).copied().collect();
+        let commitment = hash_slice_native(Vesta::sponge_params(), &features);
+        Request {
+            x,
+            salt,
+            commitment,
+        }
+    }
+}
+
+/// The verifier: accepts a proof if it verifies and its nullifier is new.
+fn verify(
+    verifier_index: &VerifierIndexWrapper<InferenceCircuit>,
+    store: &mut NullifierStore,
+    proof: &ProverProof<Vesta, OpeningProof<Vesta>>,
+    public: [Fp; 2],
+    output: [Fp; 2],
+) -> Result<(), String> {
+    verifier_index
+        .try_verify::<BaseSponge, ScalarSponge>(proof, &public, &output)
+        .map_err(|e| format!("invalid proof: {e}"))?;
+    if !store.insert(output[1]) {
+        return Err("replayed: the nullifier was already used".to_string());
+    }
+    Ok(())
+}
+
+/// Checks that a step of the flow behaves as expected.
+fn expect(step: &str, result: Result<(), String>, accepted: bool) -> Result<(), String> {
+    match (result, accepted) {
+        (Ok(()), true) => println!("{step}: accepted"),
+        (Err(e), false) => println!("{step}: rejected ({e})"),
+        (Ok(()), false) => return Err(format!("{step}: accepted, but should have been rejected")),
+        (Err(e), true) => {
+            return Err(format!(
+                "{step}: rejected ({e}), but should have been accepted"
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Runs the whole flow against a store, failing at the first step that does not behave as expected.
+fn run(store: &mut NullifierStore) -> Result<(), String> {
+    // 1. The model owner commits to the model
+    let weights = [3u64, 1, 4, 1].map(Fp::from);
+    let bias = Fp::from(5u64);
+    let parameters: Vec<_> = weights.iter().chain([&bias]).copied().collect();
+    let model = hash_slice_native(Vesta::sponge_params(), &parameters);
+
+    let mut bench = BenchCircuit::compile(InferenceCircuit).unwrap();
+    let prove = |bench: &mut BenchCircuit<InferenceCircuit>, request: &Request| {
+        let private = (weights, bias, request.x, request.salt);
+        let run = bench
+            .prove_and_verify([model, request.commitment], private)
+            .expect("failed to prove the inference");
+        (run.proof, run.output)
+    };
+
+    // 2. The user commits to an input, and 3. the prover proves the inference
+    let request = Request::new([1, 2, 3, 4]);
+    let (proof, output) = prove(&mut bench, &request);
+    println!("prediction of the committed model: {}", output[0]);
+    let public = [model, request.commitment];
+
+    // 4. The verifier accepts the proof once
+    expect(
+        "first submission",
+        verify(&bench.verifier_index, store, &proof, public, output),
+        true,
+    )?;
+    expect(
+        "replayed proof",
+        verify(&bench.verifier_index, store, &proof, public, output),
+        false,
+    )?;
+    let (again, output_again) = prove(&mut bench, &request);
+    expect(
+        "new proof of the same input",
+        verify(&bench.verifier_index, store, &again, public, output_again),
+        false,
+    )?;
+    expect(
+        "forged nullifier",
+        verify(
+            &bench.verifier_index,
+            store,
+            &proof,
+            public,
+            [output[0], output[1] + Fp::from(1u64)],
+        ),
+        false,
+    )?;
+
+    // a new request, with a new salt, is a new inference
+    let request = Request::new([1, 2, 3, 4]);
+    let (proof, output) = prove(&mut bench, &request);
+    expect(
+        "new request",
+        verify(
+            &bench.verifier_index,
+            store,
+            &proof,
+            [model, request.commitment],
+            output,
+        ),
+        true,
+    )
+}
+
+fn main() {
+    let mut store_path = env::temp_dir().join(format!("nullifiers-{}.json", std::process::id()));
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--store" => store_path = PathBuf::from(args.next().expect("--store needs a path")),
+            _ => {
+                println!("usage: replay-protection [--store <nullifiers.json>]");
+                exit(1);
+            }
+        }
+    }
+    let mut store = NullifierStore::open(store_path);
+
+    if let Err(e) = run(&mut store) {
+        println!("{e}");
+        exit(1);
+    }
+    println!(
+        "{} nullifiers in {}",
+        store.seen.len(),
+        store.path.display()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_protection() {
+        let path = env::temp_dir().join(format!("nullifiers-test-{}.json", std::process::id()));
+
+        // the second run shares the store of the first one, whose nullifiers it must not collide with
+        for runs in 1..=2 {
+            let mut store = NullifierStore::open(path.clone());
+            run(&mut store).unwrap();
+            assert_eq!(store.seen.len(), 2 * runs);
+        }
+        fs::remove_file(path).unwrap();
+    }
+}
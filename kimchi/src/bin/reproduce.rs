@@ -0,0 +1,45 @@
+//! Re-runs a benchmark from its manifest and diffs the results (see [kimchi::bench::manifest]).
+//!
+//! ```console
+//! $ cargo run --bin reproduce -- <manifest.json> [--lock <Cargo.lock>]
+//! ```
+//!
+//! Exits with an error if anything differs from the manifest.
+
+use std::{env, fs, path::PathBuf, process::exit};
+
+use kimchi::bench::manifest::Manifest;
+
+fn main() {
+    let mut manifest_path = None;
+    let mut lock = Some(PathBuf::from("Cargo.lock")).filter(|lock| lock.exists());
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lock" => lock = Some(PathBuf::from(args.next().expect("--lock needs a path"))),
+            _ => manifest_path = Some(arg),
+        }
+    }
+    let manifest_path =
+        manifest_path.expect("usage: reproduce <manifest.json> [--lock <Cargo.lock>]");
+
+    let manifest: Manifest = serde_json::from_str(
+        &fs::read_to_string(&manifest_path).expect("failed to read the manifest"),
+    )
+    .expect("failed to parse the manifest");
+    let reproduced = manifest
+        .reproduce(lock)
+        .expect("failed to run the benchmark");
+
+    let diffs = manifest.diff(&reproduced);
+    if diffs.is_empty() {
+        println!("reproduced {manifest_path}");
+        return;
+    }
+    for diff in &diffs {
+        println!("{diff}");
+    }
+    println!("{} differences with {manifest_path}", diffs.len());
+    exit(1);
+}
@@ -0,0 +1,55 @@
+//! Signs benchmark artifacts (proof cards, reports) with a local secp256k1 key, and verifies signed artifacts.
+//!
+//! ```console
+//! $ cargo run --features signing --bin sign -- sign <secret_key_hex> <artifact.json> <signed.json>
+//! $ cargo run --features signing --bin sign -- verify <signed.json> [<public_key_hex>]
+//! ```
+
+use std::{env, fs};
+
+use kimchi::signing::Signed;
+use secp256k1::{PublicKey, SecretKey};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["sign", secret_key, input, output] => {
+            let secret_key = hex::decode(secret_key).expect("the secret key must be hex-encoded");
+            let secret_key = SecretKey::from_slice(&secret_key).expect("invalid secret key");
+
+            let artifact = fs::read_to_string(input).expect("failed to read the artifact");
+            let artifact: serde_json::Value =
+                serde_json::from_str(&artifact).expect("the artifact must be JSON");
+
+            let signed = Signed::sign(artifact, &secret_key).unwrap();
+            let signed = serde_json::to_string_pretty(&signed).unwrap();
+            fs::write(output, signed).expect("failed to write the signed artifact");
+        }
+        ["verify", input, rest @ ..] => {
+            let signed = fs::read_to_string(input).expect("failed to read the signed artifact");
+            let signed: Signed<serde_json::Value> =
+                serde_json::from_str(&signed).expect("malformed signed artifact");
+
+            match rest {
+                [] => signed.verify(),
+                [public_key] => {
+                    let public_key =
+                        hex::decode(public_key).expect("the public key must be hex-encoded");
+                    let public_key =
+                        PublicKey::from_slice(&public_key).expect("invalid public key");
+                    signed.verify_signer(&public_key)
+                }
+                _ => panic!("usage: sign verify <signed.json> [<public_key_hex>]"),
+            }
+            .unwrap();
+
+            println!("valid signature by {}", signed.public_key);
+        }
+        _ => panic!("you must provide a command (sign or verify)"),
+    }
+}
@@ -0,0 +1,53 @@
+//! Generates the SRS needed by a circuit of a given size, and writes it to disk.
+//!
+//! ```console
+//! $ cargo run --release --bin srs -- <vesta|pallas> <log2_size> <output_path>
+//! ```
+//!
+//! Only production SRS are written: test SRS are cheap to recreate and must not end up being published.
+
+use std::{
+    env,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use ark_ff::PrimeField;
+use kimchi::{
+    curve::KimchiCurve,
+    mina_curves::pasta::{Pallas, Vesta},
+    precomputed_srs::get_srs_for_size,
+};
+
+fn write_srs<G>(log2_size: u32, path: &Path)
+where
+    G: KimchiCurve,
+    G::BaseField: PrimeField,
+{
+    let srs = get_srs_for_size::<G>(log2_size);
+    let srs_bytes = rmp_serde::to_vec(&srs).expect("failed to serialize SRS");
+
+    let mut file = File::create(path).expect("failed to create SRS file");
+    file.write_all(&srs_bytes).expect("failed to write file");
+    file.flush().expect("failed to flush file");
+
+    println!("wrote {} SRS of size 2^{log2_size} to {path:?}", G::NAME);
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let (curve, log2_size, path) = match (args.next(), args.next(), args.next()) {
+        (Some(curve), Some(log2_size), Some(path)) => (curve, log2_size, PathBuf::from(path)),
+        _ => panic!("usage: srs <vesta|pallas> <log2_size> <output_path>"),
+    };
+    let log2_size: u32 = log2_size
+        .parse()
+        .expect("the SRS size must be given as a power of two exponent");
+
+    match curve.as_str() {
+        "vesta" => write_srs::<Vesta>(log2_size, &path),
+        "pallas" => write_srs::<Pallas>(log2_size, &path),
+        _ => panic!("unknown curve {curve} (expected vesta or pallas)"),
+    };
+}
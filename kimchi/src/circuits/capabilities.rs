@@ -0,0 +1,140 @@
+//! This module implements [Capabilities], a description of what a proving backend supports.
+//!
+//! Circuits are checked against the capabilities of their backend when they are compiled,
+//! rather than failing when they are proven.
+//! Gadgets that have several implementations (see [crate::snarky::runner::RunState::range_check])
+//! use the capabilities to pick one that the backend supports.
+
+use std::collections::BTreeSet;
+
+use ark_ff::PrimeField;
+use strum::IntoEnumIterator;
+
+use crate::{
+    circuits::{
+        gate::{CircuitGate, GateType},
+        lookup::lookups::LookupPatterns,
+    },
+    error::CapabilityError,
+    precomputed_srs::SERIALIZED_SRS_SIZE,
+};
+
+/// The gates, lookups and circuit sizes supported by a proving backend.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The gates that the backend can prove.
+    pub gates: BTreeSet<GateType>,
+    /// The maximum number of values in a single (joint) lookup, `0` if lookups are not supported.
+    pub max_lookup_width: usize,
+    /// The maximum number of rows of a circuit.
+    pub max_rows: usize,
+}
+
+impl Capabilities {
+    /// The capabilities of kimchi, which proves every [GateType].
+    /// The number of rows is not limited, as an SRS can be created for any circuit size.
+    pub fn kimchi() -> Self {
+        Self {
+            gates: GateType::iter().collect(),
+            max_lookup_width: 3,
+            max_rows: usize::MAX,
+        }
+    }
+
+    /// The capabilities of kimchi, for circuits that fit in the serialized SRS
+    /// (see [crate::precomputed_srs::get_srs_for_size]).
+    pub fn kimchi_with_serialized_srs() -> Self {
+        Self {
+            max_rows: 1 << SERIALIZED_SRS_SIZE,
+            ..Self::kimchi()
+        }
+    }
+
+    /// The capabilities of a backend that only supports generic gates and no lookups.
+    pub fn generic_only(max_rows: usize) -> Self {
+        Self {
+            gates: [GateType::Zero, GateType::Generic].into_iter().collect(),
+            max_lookup_width: 0,
+            max_rows,
+        }
+    }
+
+    /// Returns true if the backend supports all the given gates.
+    pub fn supports(&self, gates: &[GateType]) -> bool {
+        gates.iter().all(|typ| self.gates.contains(typ))
+    }
+
+    /// Checks that a circuit can be proven by the backend.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the circuit uses an unsupported gate, lookups that are too wide,
+    /// or more rows than the backend supports.
+    pub fn check<F: PrimeField>(&self, gates: &[CircuitGate<F>]) -> Result<(), CapabilityError> {
+        if let Some(gate) = gates.iter().find(|gate| !self.gates.contains(&gate.typ)) {
+            return Err(CapabilityError::UnsupportedGate(gate.typ));
+        }
+
+        let lookup_width = LookupPatterns::from_gates(gates)
+            .into_iter()
+            .map(|pattern| pattern.max_joint_size() as usize)
+            .max()
+            .unwrap_or(0);
+        if lookup_width > self.max_lookup_width {
+            return Err(CapabilityError::LookupTooWide(
+                lookup_width,
+                self.max_lookup_width,
+            ));
+        }
+
+        if gates.len() > self.max_rows {
+            return Err(CapabilityError::TooManyRows(gates.len(), self.max_rows));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuits::{polynomials::generic::GenericGateSpec, wires::Wire};
+    use mina_curves::pasta::Fp;
+
+    #[test]
+    fn test_capabilities_check() {
+        let mut gates = vec![CircuitGate::<Fp>::create_generic_gadget(
+            Wire::for_row(0),
+            GenericGateSpec::Pub,
+            None,
+        )];
+        assert!(Capabilities::kimchi().check(&gates).is_ok());
+        assert!(Capabilities::generic_only(1).check(&gates).is_ok());
+
+        gates.push(CircuitGate::new(GateType::Xor16, Wire::for_row(1), vec![]));
+        assert!(Capabilities::kimchi().check(&gates).is_ok());
+        assert!(Capabilities::kimchi().supports(&[
+            GateType::CairoClaim,
+            GateType::KeccakRound,
+            GateType::KeccakSponge
+        ]));
+        assert!(matches!(
+            Capabilities::generic_only(2).check(&gates),
+            Err(CapabilityError::UnsupportedGate(GateType::Xor16))
+        ));
+
+        let mut no_lookups = Capabilities::kimchi();
+        no_lookups.max_lookup_width = 0;
+        assert!(matches!(
+            no_lookups.check(&gates),
+            Err(CapabilityError::LookupTooWide(3, 0))
+        ));
+
+        let mut small = Capabilities::kimchi();
+        small.max_rows = 1;
+        assert!(matches!(
+            small.check(&gates),
+            Err(CapabilityError::TooManyRows(2, 1))
+        ));
+    }
+}
@@ -1,5 +1,7 @@
 //! This module implements Plonk circuit constraint primitive.
 use super::lookup::runtime_tables::RuntimeTableCfg;
+#[cfg(feature = "prover")]
+use crate::prover_index::ProverIndex;
 use crate::{
     circuits::{
         domain_constant_evaluation::DomainConstantEvaluations,
@@ -15,8 +17,7 @@ use crate::{
         wires::*,
     },
     curve::KimchiCurve,
-    error::{DomainCreationError, SetupError},
-    prover_index::ProverIndex,
+    error::{DomainCreationError, DomainSizeContext, SetupError},
 };
 use ark_ff::{PrimeField, SquareRootField, Zero};
 use ark_poly::{
@@ -277,6 +278,7 @@ impl<F: PrimeField> ConstraintSystem<F> {
     }
 }
 
+#[cfg(feature = "prover")]
 impl<
         F: PrimeField + SquareRootField,
         G: KimchiCurve<ScalarField = F>,
@@ -825,9 +827,9 @@ impl<F: PrimeField + SquareRootField> Builder<F> {
                 // more than once.
                 while {
                     let domain_size = D::<F>::compute_size_of_domain(domain_size_lower_bound)
-                        .ok_or(SetupError::DomainCreation(
-                            DomainCreationError::DomainSizeFailed(domain_size_lower_bound),
-                        ))?;
+                        .ok_or(SetupError::DomainCreation(DomainCreationError::TooLarge(
+                            DomainSizeContext::new::<F>(domain_size_lower_bound),
+                        )))?;
                     let num_chunks = if domain_size < max_poly_size {
                         1
                     } else {
@@ -0,0 +1,318 @@
+//! This module implements an API to register user-defined gates,
+//! so that new gates can be prototyped without modifying kimchi.
+//!
+//! A [CustomGate] is described by its constraint polynomials
+//! (written against the same [ArgumentEnv] interface as the built-in gates)
+//! and a witness filler.
+//! Registering it in a [CustomGateRegistry] records its constraints as [CircuitExpr]s,
+//! which can be checked on a witness, or lowered to generic gates by snarky
+//! (see [crate::snarky::runner::RunState::custom_gate]).
+//!
+//! Custom gates do not get their own selector polynomial:
+//! lowering them to generic gates keeps the proof system unchanged,
+//! at the price of more rows than a dedicated gate would need.
+//! Custom gates can only use cells, coefficients and literals,
+//! not the constants and challenges that are only known to the prover and verifier.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub},
+};
+
+use ark_ff::{One, PrimeField, Zero};
+
+use crate::{
+    circuits::{
+        argument::{ArgumentData, ArgumentEnv, ArgumentWitness},
+        expr::{constraints::ExprOps, Cache, ConstantExpr},
+        gate::CurrOrNext,
+    },
+    error::CustomGateError,
+};
+
+/// A user-defined gate.
+pub trait CustomGate<F: PrimeField> {
+    /// The name under which the gate is registered.
+    const NAME: &'static str;
+
+    /// The number of constraints of the gate.
+    const CONSTRAINTS: u32;
+
+    /// Constraints of the gate, over the cells of its row and of the next row.
+    fn constraint_checks<T: ExprOps<F>>(env: &ArgumentEnv<F, T>, cache: &mut Cache) -> Vec<T>;
+
+    /// Computes the two rows of the gate from its inputs,
+    /// which must be placed in the first cells of the current row,
+    /// the inputs past the [crate::circuits::wires::COLUMNS] cells of the row spilling into the first cells of the next row.
+    /// As the constraints only read these two rows, a gate takes at most twice as many inputs as a row has cells.
+    fn fill_witness(inputs: &[F], coeffs: &[F]) -> ArgumentWitness<F>;
+}
+
+/// A symbolic constraint of a [CustomGate].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CircuitExpr<F> {
+    /// A field element.
+    Literal(F),
+    /// A witness cell of the current or next row.
+    Cell(CurrOrNext, usize),
+    /// A coefficient of the gate.
+    Coeff(usize),
+    /// The sum of two expressions.
+    Add(Box<Self>, Box<Self>),
+    /// The difference of two expressions.
+    Sub(Box<Self>, Box<Self>),
+    /// The product of two expressions.
+    Mul(Box<Self>, Box<Self>),
+    /// The opposite of an expression.
+    Neg(Box<Self>),
+}
+
+impl<F: PrimeField> CircuitExpr<F> {
+    /// Evaluates the expression on the rows of a gate.
+    /// Missing coefficients are zero, as in the coefficient columns of kimchi.
+    pub fn evaluate(&self, witness: &ArgumentWitness<F>, coeffs: &[F]) -> F {
+        match self {
+            CircuitExpr::Literal(x) => *x,
+            CircuitExpr::Cell(row, col) => witness[(*row, *col)],
+            CircuitExpr::Coeff(i) => coeffs.get(*i).copied().unwrap_or_else(F::zero),
+            CircuitExpr::Add(x, y) => x.evaluate(witness, coeffs) + y.evaluate(witness, coeffs),
+            CircuitExpr::Sub(x, y) => x.evaluate(witness, coeffs) - y.evaluate(witness, coeffs),
+            CircuitExpr::Mul(x, y) => x.evaluate(witness, coeffs) * y.evaluate(witness, coeffs),
+            CircuitExpr::Neg(x) => -x.evaluate(witness, coeffs),
+        }
+    }
+}
+
+impl<F> Add for CircuitExpr<F> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        CircuitExpr::Add(Box::new(self), Box::new(other))
+    }
+}
+
+impl<F> Sub for CircuitExpr<F> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        CircuitExpr::Sub(Box::new(self), Box::new(other))
+    }
+}
+
+impl<F> Mul for CircuitExpr<F> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        CircuitExpr::Mul(Box::new(self), Box::new(other))
+    }
+}
+
+impl<F> Neg for CircuitExpr<F> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        CircuitExpr::Neg(Box::new(self))
+    }
+}
+
+impl<F: Clone> AddAssign for CircuitExpr<F> {
+    fn add_assign(&mut self, other: Self) {
+        *self = self.clone() + other;
+    }
+}
+
+impl<F: Clone> MulAssign for CircuitExpr<F> {
+    fn mul_assign(&mut self, other: Self) {
+        *self = self.clone() * other;
+    }
+}
+
+impl<F: Zero> Zero for CircuitExpr<F> {
+    fn zero() -> Self {
+        CircuitExpr::Literal(F::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        matches!(self, CircuitExpr::Literal(x) if x.is_zero())
+    }
+}
+
+impl<F: One> One for CircuitExpr<F> {
+    fn one() -> Self {
+        CircuitExpr::Literal(F::one())
+    }
+}
+
+impl<F: From<u64>> From<u64> for CircuitExpr<F> {
+    fn from(x: u64) -> Self {
+        CircuitExpr::Literal(F::from(x))
+    }
+}
+
+impl<F: fmt::Display> fmt::Display for CircuitExpr<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitExpr::Literal(x) => write!(f, "{x}"),
+            CircuitExpr::Cell(CurrOrNext::Curr, col) => write!(f, "w[{col}]"),
+            CircuitExpr::Cell(CurrOrNext::Next, col) => write!(f, "w_next[{col}]"),
+            CircuitExpr::Coeff(i) => write!(f, "c[{i}]"),
+            CircuitExpr::Add(x, y) => write!(f, "({x} + {y})"),
+            CircuitExpr::Sub(x, y) => write!(f, "({x} - {y})"),
+            CircuitExpr::Mul(x, y) => write!(f, "({x} * {y})"),
+            CircuitExpr::Neg(x) => write!(f, "-{x}"),
+        }
+    }
+}
+
+impl<F: PrimeField> ExprOps<F> for CircuitExpr<F> {
+    fn two_pow(pow: u64) -> Self {
+        CircuitExpr::Literal(<F as ExprOps<F>>::two_pow(pow))
+    }
+
+    fn two_to_limb() -> Self {
+        CircuitExpr::Literal(<F as ExprOps<F>>::two_to_limb())
+    }
+
+    fn two_to_2limb() -> Self {
+        CircuitExpr::Literal(<F as ExprOps<F>>::two_to_2limb())
+    }
+
+    fn two_to_3limb() -> Self {
+        CircuitExpr::Literal(<F as ExprOps<F>>::two_to_3limb())
+    }
+
+    fn double(&self) -> Self {
+        self.clone() + self.clone()
+    }
+
+    fn square(&self) -> Self {
+        self.clone() * self.clone()
+    }
+
+    fn pow(&self, p: u64) -> Self {
+        (0..p).fold(Self::one(), |acc, _| acc * self.clone())
+    }
+
+    fn boolean(&self) -> Self {
+        self.square() - self.clone()
+    }
+
+    fn crumb(&self) -> Self {
+        self.clone()
+            * (self.clone() - 1u64.into())
+            * (self.clone() - 2u64.into())
+            * (self.clone() - 3u64.into())
+    }
+
+    fn literal(x: F) -> Self {
+        CircuitExpr::Literal(x)
+    }
+
+    fn witness(row: CurrOrNext, col: usize, _: Option<&ArgumentData<F>>) -> Self {
+        CircuitExpr::Cell(row, col)
+    }
+
+    fn coeff(col: usize, _: Option<&ArgumentData<F>>) -> Self {
+        CircuitExpr::Coeff(col)
+    }
+
+    fn constant(expr: ConstantExpr<F>, env: Option<&ArgumentData<F>>) -> Self {
+        CircuitExpr::Literal(<F as ExprOps<F>>::constant(expr, env))
+    }
+
+    fn cache(&self, _: &mut Cache) -> Self {
+        self.clone()
+    }
+}
+
+/// A [CustomGate] recorded in a [CustomGateRegistry].
+pub struct RegisteredGate<F> {
+    /// The name of the gate.
+    pub name: &'static str,
+    /// The constraints of the gate.
+    pub constraints: Vec<CircuitExpr<F>>,
+    /// The witness filler of the gate (see [CustomGate::fill_witness]).
+    pub fill_witness: fn(&[F], &[F]) -> ArgumentWitness<F>,
+}
+
+impl<F: PrimeField> RegisteredGate<F> {
+    /// Checks that the rows of a gate satisfy its constraints.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if a constraint does not evaluate to zero.
+    pub fn check(&self, witness: &ArgumentWitness<F>, coeffs: &[F]) -> Result<(), CustomGateError> {
+        match self
+            .constraints
+            .iter()
+            .position(|constraint| !constraint.evaluate(witness, coeffs).is_zero())
+        {
+            Some(i) => Err(CustomGateError::Unsatisfied(self.name.to_string(), i)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The custom gates available to the circuits of a benchmark.
+pub struct CustomGateRegistry<F> {
+    gates: BTreeMap<&'static str, RegisteredGate<F>>,
+}
+
+impl<F> Default for CustomGateRegistry<F> {
+    fn default() -> Self {
+        Self {
+            gates: BTreeMap::new(),
+        }
+    }
+}
+
+impl<F: PrimeField> CustomGateRegistry<F> {
+    /// Registers a custom gate under its [CustomGate::NAME].
+    ///
+    /// # Errors
+    ///
+    /// Will give error if a gate with the same name is already registered,
+    /// or if the gate does not produce [CustomGate::CONSTRAINTS] constraints.
+    pub fn register<G: CustomGate<F>>(&mut self) -> Result<(), CustomGateError> {
+        if self.gates.contains_key(G::NAME) {
+            return Err(CustomGateError::AlreadyRegistered(G::NAME.to_string()));
+        }
+
+        let constraints =
+            G::constraint_checks::<CircuitExpr<F>>(&ArgumentEnv::default(), &mut Cache::default());
+        if constraints.len() != G::CONSTRAINTS as usize {
+            return Err(CustomGateError::ConstraintCount(
+                G::NAME.to_string(),
+                G::CONSTRAINTS as usize,
+                constraints.len(),
+            ));
+        }
+
+        self.gates.insert(
+            G::NAME,
+            RegisteredGate {
+                name: G::NAME,
+                constraints,
+                fill_witness: G::fill_witness,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns the gate registered under `name`.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if no gate is registered under `name`.
+    pub fn get(&self, name: &str) -> Result<&RegisteredGate<F>, CustomGateError> {
+        self.gates
+            .get(name)
+            .ok_or_else(|| CustomGateError::UnknownGate(name.to_string()))
+    }
+
+    /// The names of the registered gates, in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.gates.keys().copied()
+    }
+}
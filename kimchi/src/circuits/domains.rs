@@ -1,9 +1,9 @@
-use ark_ff::FftField;
+use ark_ff::{FftField, FftParameters};
 use ark_poly::{EvaluationDomain, Radix2EvaluationDomain as Domain};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 
-use crate::error::DomainCreationError;
+use crate::error::{DomainCreationError, DomainSizeContext};
 
 #[serde_as]
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -23,6 +23,11 @@ impl<F: FftField> EvaluationDomains<F> {
     /// and `d8` (of size `8n`). If generator of `d8` is `g`, the generator
     /// of `d4` is `g^2`, the generator of `d2` is `g^4`, and the generator of `d1` is `g^8`.
     pub fn create(n: usize) -> Result<Self, DomainCreationError> {
+        let context = DomainSizeContext::new::<F>(n);
+        if context.chunks.is_some() {
+            return Err(DomainCreationError::TooLarge(context));
+        }
+
         let n = Domain::<F>::compute_size_of_domain(n)
             .ok_or(DomainCreationError::DomainSizeFailed(n))?;
 
@@ -56,12 +61,51 @@ impl<F: FftField> EvaluationDomains<F> {
     }
 }
 
+impl DomainSizeContext {
+    /// The context of a domain of `requested` rows for the field `F`.
+    pub fn new<F: FftField>(requested: usize) -> Self {
+        // the evaluation domains are up to 8 times larger than the domain
+        let max_log2 = (F::FftParams::TWO_ADICITY - 3).min(usize::BITS - 1);
+        let max_size = 1 << max_log2;
+
+        let below = (requested > 0).then(|| (1 << requested.ilog2()).min(max_size));
+        let above = requested
+            .checked_next_power_of_two()
+            .filter(|&size| size <= max_size);
+        let chunks = (requested > max_size).then(|| (requested + max_size - 1) / max_size);
+        DomainSizeContext {
+            requested,
+            max_size,
+            nearest: (below, above),
+            chunks,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use ark_ff::Field;
     use mina_curves::pasta::Fp;
 
+    #[test]
+    fn test_domain_size_context() {
+        let context = DomainSizeContext::new::<Fp>(1000);
+        assert_eq!(context.nearest, (Some(512), Some(1024)));
+        assert_eq!(context.chunks, None);
+
+        // the two-adicity of Fp is 32, so its evaluation domains fit domains of up to 2^29 rows
+        match EvaluationDomains::<Fp>::create((1 << 29) + 1) {
+            Err(DomainCreationError::TooLarge(context)) => {
+                assert_eq!(context.max_size, 1 << 29);
+                assert_eq!(context.nearest, (Some(1 << 29), None));
+                assert_eq!(context.chunks, Some(2));
+                println!("{context}");
+            }
+            _ => panic!("the domain should be too large"),
+        }
+    }
+
     #[test]
     #[ignore] // TODO(mimoo): wait for fix upstream (https://github.com/arkworks-rs/algebra/pull/307)
     fn test_create_domain() {
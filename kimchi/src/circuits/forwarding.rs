@@ -0,0 +1,220 @@
+//! This module implements a value forwarding pass,
+//! which removes the generic rows that only copy values from one cell to another.
+//!
+//! Circuits generated layer by layer are copy-heavy:
+//! the output of a layer is often copied to the input of the next one with a generic gate
+//! enforcing `left - right = 0`.
+//! Such a copy is already expressible with the permutation argument,
+//! so the pass merges the permutation cycles of the copied cells and drops the row.
+//! Chains of copies are forwarded in a single pass.
+//!
+//! Like [crate::circuits::layout::optimize_layout],
+//! only [GateType::Generic] rows that follow another generic row are removed,
+//! as other gates (and the rows right after them) can depend on their neighbouring rows.
+
+use ark_ff::PrimeField;
+
+use crate::circuits::{
+    gate::{CircuitGate, Connect, GateType},
+    polynomials::generic::{GENERIC_COEFFS, GENERIC_REGISTERS},
+    wires::{Wire, COLUMNS, PERMUTS},
+};
+
+/// The rows kept by [forward_copies].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForwardedRows {
+    /// `kept[i]` is the original row that now sits at row `i`.
+    pub kept: Vec<usize>,
+}
+
+impl ForwardedRows {
+    /// The number of rows that were removed.
+    pub fn removed(&self, original_rows: usize) -> usize {
+        original_rows - self.kept.len()
+    }
+
+    /// Removes from a witness the rows that were removed from the circuit.
+    pub fn apply_to_witness<F: Copy>(&self, witness: &mut [Vec<F>; COLUMNS]) {
+        for column in witness.iter_mut() {
+            *column = self.kept.iter().map(|&row| column[row]).collect();
+        }
+    }
+}
+
+/// Returns the pair of columns that one half of a generic gate copies,
+/// if its coefficients are of the form `c * a - c * b = 0`.
+fn copied_cells<F: PrimeField>(coeffs: &[F]) -> Option<(usize, usize)> {
+    let (registers, rest) = coeffs.split_at(GENERIC_REGISTERS);
+    if rest.iter().any(|c| !c.is_zero()) {
+        return None;
+    }
+    let used: Vec<_> = (0..GENERIC_REGISTERS)
+        .filter(|&col| !registers[col].is_zero())
+        .collect();
+    match used[..] {
+        [a, b] if registers[a] == -registers[b] => Some((a, b)),
+        _ => None,
+    }
+}
+
+/// Returns the copies performed by a generic row,
+/// or [None] if the row enforces anything else than copies.
+fn copy_row<F: PrimeField>(gate: &CircuitGate<F>) -> Option<Vec<(usize, usize)>> {
+    if gate.typ != GateType::Generic {
+        return None;
+    }
+    let mut copies = vec![];
+    for (half, coeffs) in gate.coeffs.chunks(GENERIC_COEFFS).enumerate() {
+        if coeffs.iter().all(|c| c.is_zero()) {
+            continue;
+        }
+        let (a, b) = copied_cells(coeffs)?;
+        let offset = half * GENERIC_REGISTERS;
+        copies.push((offset + a, offset + b));
+    }
+    Some(copies)
+}
+
+/// Returns true if the two cells are in the same permutation cycle.
+fn same_cycle<F: PrimeField>(gates: &[CircuitGate<F>], from: Wire, to: Wire) -> bool {
+    let mut cell = gates[from.row].wires[from.col];
+    while cell != from {
+        if cell == to {
+            return true;
+        }
+        cell = gates[cell.row].wires[cell.col];
+    }
+    false
+}
+
+/// Removes the movable generic rows that only copy values,
+/// after merging the permutation cycles of the copied cells.
+/// The first `public` rows (holding the public input) are never removed.
+///
+/// The returned [ForwardedRows] must be applied to the witness of the circuit.
+pub fn forward_copies<F: PrimeField>(
+    mut gates: Vec<CircuitGate<F>>,
+    public: usize,
+) -> (Vec<CircuitGate<F>>, ForwardedRows) {
+    let removable: Vec<bool> = (0..gates.len())
+        .map(|row| {
+            row > public
+                && gates[row - 1].typ == GateType::Generic
+                && copy_row(&gates[row]).is_some()
+        })
+        .collect();
+
+    for row in (0..gates.len()).filter(|&row| removable[row]) {
+        // the copy is now enforced by the permutation argument
+        for (a, b) in copy_row(&gates[row]).unwrap_or_default() {
+            if !same_cycle(&gates, Wire::new(row, a), Wire::new(row, b)) {
+                gates.connect_cell_pair((row, a), (row, b));
+            }
+        }
+
+        // splice the cells of the row out of their cycles
+        for col in 0..PERMUTS {
+            let cell = Wire::new(row, col);
+            let next = gates[row].wires[col];
+            if next == cell {
+                continue;
+            }
+            let mut prev = next;
+            while gates[prev.row].wires[prev.col] != cell {
+                prev = gates[prev.row].wires[prev.col];
+            }
+            gates[prev.row].wires[prev.col] = next;
+            gates[row].wires[col] = cell;
+        }
+    }
+
+    let kept: Vec<usize> = (0..gates.len()).filter(|&row| !removable[row]).collect();
+    let mut new_row = vec![0; gates.len()];
+    for (new, &old) in kept.iter().enumerate() {
+        new_row[old] = new;
+    }
+
+    let forwarded = kept
+        .iter()
+        .map(|&old| {
+            let mut gate = gates[old].clone();
+            for wire in gate.wires.iter_mut() {
+                wire.row = new_row[wire.row];
+            }
+            gate
+        })
+        .collect();
+
+    (forwarded, ForwardedRows { kept })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuits::{
+        layout::wiring_stats,
+        polynomials::generic::{GenericGateSpec, DOUBLE_GENERIC_COEFFS},
+    };
+    use ark_ff::{One, Zero};
+    use mina_curves::pasta::Fp;
+
+    fn constant_row(row: usize) -> CircuitGate<Fp> {
+        CircuitGate::create_generic_gadget(
+            Wire::for_row(row),
+            GenericGateSpec::Const(Fp::from(7u32)),
+            None,
+        )
+    }
+
+    fn copy_gate(row: usize) -> CircuitGate<Fp> {
+        let mut coeffs = [Fp::zero(); DOUBLE_GENERIC_COEFFS];
+        coeffs[0] = Fp::one();
+        coeffs[1] = -Fp::one();
+        CircuitGate::create_generic(Wire::for_row(row), coeffs)
+    }
+
+    #[test]
+    fn test_forward_copy_chain() {
+        // row 0 produces a value, rows 1 and 2 copy it, row 3 consumes it
+        let mut gates = vec![constant_row(0), copy_gate(1), copy_gate(2), constant_row(3)];
+        gates.connect_cell_pair((0, 0), (1, 0));
+        gates.connect_cell_pair((1, 1), (2, 0));
+        gates.connect_cell_pair((2, 1), (3, 0));
+
+        let (gates, forwarded) = forward_copies(gates, 0);
+        assert_eq!(forwarded.kept, vec![0, 3]);
+        assert_eq!(forwarded.removed(4), 2);
+
+        // the producer is now wired directly to the consumer
+        assert_eq!(gates[0].wires[0], Wire::new(1, 0));
+        assert_eq!(gates[1].wires[0], Wire::new(0, 0));
+        assert_eq!(wiring_stats(&gates).cycles, 1);
+
+        let mut witness: [Vec<Fp>; COLUMNS] =
+            std::array::from_fn(|_| (0..4u64).map(Fp::from).collect());
+        forwarded.apply_to_witness(&mut witness);
+        assert_eq!(witness[0], vec![Fp::from(0u64), Fp::from(3u64)]);
+    }
+
+    #[test]
+    fn test_forward_copies_keeps_other_rows() {
+        let gates = vec![
+            constant_row(0),
+            CircuitGate::create_generic_gadget(
+                Wire::for_row(1),
+                GenericGateSpec::Add {
+                    left_coeff: None,
+                    right_coeff: None,
+                    output_coeff: None,
+                },
+                None,
+            ),
+            copy_gate(2),
+        ];
+
+        // the public row is kept, the addition is kept, the copy is removed
+        let (gates, forwarded) = forward_copies(gates, 1);
+        assert_eq!(forwarded.kept, vec![0, 1]);
+        assert_eq!(gates.len(), 2);
+    }
+}
@@ -1,5 +1,7 @@
 //! This module implements Plonk constraint gate primitive.
 
+#[cfg(feature = "prover")]
+use crate::prover_index::ProverIndex;
 use crate::{
     circuits::{
         argument::{Argument, ArgumentEnv},
@@ -11,7 +13,6 @@ use crate::{
         wires::*,
     },
     curve::KimchiCurve,
-    prover_index::ProverIndex,
 };
 use ark_ff::{bytes::ToBytes, PrimeField, SquareRootField};
 use num_traits::cast::ToPrimitive;
@@ -20,6 +21,7 @@ use poly_commitment::OpenProof;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use std::io::{Result as IoResult, Write};
+use strum_macros::EnumIter;
 use thiserror::Error;
 
 use super::{argument::ArgumentWitness, expr};
@@ -71,6 +73,7 @@ impl CurrOrNext {
     Hash,
     PartialOrd,
     Ord,
+    EnumIter,
 )]
 #[cfg_attr(
     feature = "ocaml_types",
@@ -192,6 +195,7 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
     /// # Errors
     ///
     /// Will give error if verify process returns error.
+    #[cfg(feature = "prover")]
     pub fn verify<G: KimchiCurve<ScalarField = F>, OpeningProof: OpenProof<G>>(
         &self,
         row: usize,
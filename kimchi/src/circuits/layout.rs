@@ -0,0 +1,380 @@
+//! This module implements an analysis of the copy constraints (wiring) of a circuit,
+//! a layout pass that reorders rows to keep wired cells close to each other,
+//! and a packing pass that co-locates narrow generic operations in shared rows.
+//!
+//! Note that the cost of kimchi's permutation argument only depends on the domain size:
+//! every row takes part in the permutation, whether its cells are wired or not.
+//! Reordering rows therefore does not reduce proving time by itself,
+//! but the [WiringStats] before and after the pass quantify how local the layout of a circuit is,
+//! which matters for passes that pack several operations in a single row.
+//! Packing reduces proving time only when it brings the circuit under a smaller power of two,
+//! which halves the domain and roughly halves the time of the prover;
+//! the circuit tests measure it on a circuit of constants.
+//!
+//! Only [GateType::Generic] rows that follow another generic row are moved,
+//! as other gates (and the rows right after them) can depend on their neighbouring rows.
+
+use ark_ff::PrimeField;
+
+use crate::circuits::{
+    gate::{CircuitGate, GateType},
+    polynomials::generic::{GENERIC_COEFFS, GENERIC_REGISTERS},
+    wires::{Wire, COLUMNS, PERMUTS},
+};
+
+/// Statistics about the copy constraints of a circuit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WiringStats {
+    /// The number of rows of the circuit.
+    pub rows: usize,
+    /// The number of cells that are wired to another cell.
+    pub wired_cells: usize,
+    /// The number of non-trivial permutation cycles.
+    pub cycles: usize,
+    /// The sum, over all wired cells, of the distance (in rows) to the cell they are wired to.
+    pub total_row_distance: usize,
+}
+
+/// Computes the [WiringStats] of a circuit.
+pub fn wiring_stats<F: PrimeField>(gates: &[CircuitGate<F>]) -> WiringStats {
+    let mut stats = WiringStats {
+        rows: gates.len(),
+        ..Default::default()
+    };
+
+    let mut visited = vec![[false; PERMUTS]; gates.len()];
+    for row in 0..gates.len() {
+        for col in 0..PERMUTS {
+            let target = gates[row].wires[col];
+            if target == Wire::new(row, col) {
+                continue;
+            }
+            stats.wired_cells += 1;
+            stats.total_row_distance += row.abs_diff(target.row);
+
+            // walk the cycle once, from its first cell
+            if !visited[row][col] {
+                stats.cycles += 1;
+                let mut cell = Wire::new(row, col);
+                while !visited[cell.row][cell.col] {
+                    visited[cell.row][cell.col] = true;
+                    cell = gates[cell.row].wires[cell.col];
+                }
+            }
+        }
+    }
+
+    stats
+}
+
+/// A permutation of the rows of a circuit, produced by [optimize_layout].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RowPermutation {
+    /// `new_row[i]` is the row that the original row `i` was moved to.
+    pub new_row: Vec<usize>,
+}
+
+impl RowPermutation {
+    /// Moves the rows of a witness the same way the rows of the circuit were moved.
+    pub fn apply_to_witness<F: Copy>(&self, witness: &mut [Vec<F>; COLUMNS]) {
+        for column in witness.iter_mut() {
+            let original = column.clone();
+            for (old_row, &new_row) in self.new_row.iter().enumerate() {
+                column[new_row] = original[old_row];
+            }
+        }
+    }
+}
+
+/// Reorders the movable rows of a circuit so that each one sits close to the rows it is wired to.
+/// The first `public` rows (holding the public input) are never moved.
+///
+/// The new layout is only kept if it reduces [WiringStats::total_row_distance].
+/// The returned [RowPermutation] must be applied to the witness of the circuit.
+pub fn optimize_layout<F: PrimeField>(
+    gates: Vec<CircuitGate<F>>,
+    public: usize,
+) -> (Vec<CircuitGate<F>>, RowPermutation) {
+    let movable = |row: usize| {
+        row > public
+            && gates[row].typ == GateType::Generic
+            && gates[row - 1].typ == GateType::Generic
+    };
+
+    // a row would like to sit right after the earliest row it is wired to
+    let anchor = |row: usize| {
+        gates[row]
+            .wires
+            .iter()
+            .map(|wire| wire.row)
+            .min()
+            .unwrap_or(row)
+            .min(row)
+    };
+
+    // sort each run of movable rows by anchor
+    let mut order: Vec<usize> = (0..gates.len()).collect();
+    let mut start = 0;
+    while start < gates.len() {
+        if !movable(start) {
+            start += 1;
+            continue;
+        }
+        let mut end = start;
+        while end < gates.len() && movable(end) {
+            end += 1;
+        }
+        order[start..end].sort_by_key(|&row| anchor(row));
+        start = end;
+    }
+
+    let mut new_row = vec![0; gates.len()];
+    for (new, &old) in order.iter().enumerate() {
+        new_row[old] = new;
+    }
+
+    let reordered: Vec<_> = order
+        .iter()
+        .map(|&old| {
+            let mut gate = gates[old].clone();
+            for wire in gate.wires.iter_mut() {
+                wire.row = new_row[wire.row];
+            }
+            gate
+        })
+        .collect();
+
+    if wiring_stats(&reordered).total_row_distance < wiring_stats(&gates).total_row_distance {
+        (reordered, RowPermutation { new_row })
+    } else {
+        let identity = (0..gates.len()).collect();
+        (gates, RowPermutation { new_row: identity })
+    }
+}
+
+/// The utilization of the generic rows of a circuit.
+/// Each generic row holds two generic operations, one per half.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GenericUtilization {
+    /// The number of generic rows.
+    pub rows: usize,
+    /// The number of halves of these rows that hold an operation.
+    pub used_halves: usize,
+}
+
+impl GenericUtilization {
+    /// The fraction of the generic halves that hold an operation.
+    pub fn ratio(&self) -> f64 {
+        if self.rows == 0 {
+            return 1.;
+        }
+        self.used_halves as f64 / (2 * self.rows) as f64
+    }
+}
+
+/// Returns true if the second half of a generic row holds no operation and no wired cell.
+fn second_half_free<F: PrimeField>(row: usize, gate: &CircuitGate<F>) -> bool {
+    gate.coeffs[GENERIC_COEFFS..].iter().all(|c| c.is_zero())
+        && (GENERIC_REGISTERS..PERMUTS).all(|col| gate.wires[col] == Wire::new(row, col))
+}
+
+/// Computes the [GenericUtilization] of a circuit.
+pub fn generic_utilization<F: PrimeField>(gates: &[CircuitGate<F>]) -> GenericUtilization {
+    let mut utilization = GenericUtilization::default();
+    for gate in gates.iter().filter(|gate| gate.typ == GateType::Generic) {
+        utilization.rows += 1;
+        utilization.used_halves += gate
+            .coeffs
+            .chunks(GENERIC_COEFFS)
+            .filter(|half| half.iter().any(|c| !c.is_zero()))
+            .count();
+    }
+    utilization
+}
+
+/// The rows of a circuit after [pack_generic_rows].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PackedRows {
+    /// `kept[i]` is the original row that now sits at row `i`.
+    pub kept: Vec<usize>,
+    /// `packed[i]` is the original row whose operation was moved to the second half of row `i`.
+    pub packed: Vec<Option<usize>>,
+}
+
+impl PackedRows {
+    /// Moves the cells of a witness the same way the operations of the circuit were moved.
+    pub fn apply_to_witness<F: Copy>(&self, witness: &mut [Vec<F>; COLUMNS]) {
+        let original = witness.clone();
+        for (col, column) in witness.iter_mut().enumerate() {
+            *column = self
+                .kept
+                .iter()
+                .zip(&self.packed)
+                .map(|(&row, &packed)| match packed {
+                    Some(packed) if (GENERIC_REGISTERS..2 * GENERIC_REGISTERS).contains(&col) => {
+                        original[col - GENERIC_REGISTERS][packed]
+                    }
+                    _ => original[col][row],
+                })
+                .collect();
+        }
+    }
+}
+
+/// Packs movable generic rows that only use their first half
+/// (bias additions, boolean checks, constants...) into the free second half of an earlier generic row.
+/// The first `public` rows (holding the public input) are never used or removed,
+/// and neither are the rows after another gate, which can read them as its next row.
+///
+/// The returned [PackedRows] must be applied to the witness of the circuit.
+pub fn pack_generic_rows<F: PrimeField>(
+    gates: Vec<CircuitGate<F>>,
+    public: usize,
+) -> (Vec<CircuitGate<F>>, PackedRows) {
+    let half_free = |row: usize| {
+        row >= public
+            && (row == 0 || gates[row - 1].typ == GateType::Generic)
+            && gates[row].typ == GateType::Generic
+            && second_half_free(row, &gates[row])
+    };
+    let movable = |row: usize| row > public && gates[row - 1].typ == GateType::Generic;
+
+    // greedily pair each narrow row with the last row that still has a free half
+    let mut host = vec![None; gates.len()];
+    let mut guest = vec![None; gates.len()];
+    let mut open = None;
+    for row in (0..gates.len()).filter(|&row| half_free(row)) {
+        match open {
+            Some(open_row) if movable(row) => {
+                host[row] = Some(open_row);
+                guest[open_row] = Some(row);
+                open = None;
+            }
+            _ => open = Some(row),
+        }
+    }
+
+    let kept: Vec<usize> = (0..gates.len())
+        .filter(|&row| host[row].is_none())
+        .collect();
+    let mut new_row = vec![0; gates.len()];
+    for (new, &old) in kept.iter().enumerate() {
+        new_row[old] = new;
+    }
+    let relocate = |wire: Wire| match host[wire.row] {
+        Some(host_row) => Wire::new(new_row[host_row], wire.col + GENERIC_REGISTERS),
+        None => Wire::new(new_row[wire.row], wire.col),
+    };
+
+    let packed_gates = kept
+        .iter()
+        .map(|&old| {
+            let mut gate = gates[old].clone();
+            if let Some(guest_row) = guest[old] {
+                let guest_gate = &gates[guest_row];
+                gate.coeffs[GENERIC_COEFFS..2 * GENERIC_COEFFS]
+                    .clone_from_slice(&guest_gate.coeffs[..GENERIC_COEFFS]);
+                gate.wires[GENERIC_REGISTERS..2 * GENERIC_REGISTERS]
+                    .copy_from_slice(&guest_gate.wires[..GENERIC_REGISTERS]);
+            }
+            for wire in gate.wires.iter_mut() {
+                *wire = relocate(*wire);
+            }
+            gate
+        })
+        .collect();
+
+    let packed = kept.iter().map(|&old| guest[old]).collect();
+    (packed_gates, PackedRows { kept, packed })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuits::{gate::Connect, polynomials::generic::GenericGateSpec, wires::Wirable};
+    use mina_curves::pasta::Fp;
+
+    fn generic_rows(n: usize) -> Vec<CircuitGate<Fp>> {
+        (0..n)
+            .map(|row| {
+                CircuitGate::create_generic_gadget(
+                    Wire::for_row(row),
+                    GenericGateSpec::Const(1u32.into()),
+                    None,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_wiring_stats() {
+        let mut gates = generic_rows(4);
+        gates.connect_cell_pair((0, 0), (3, 1));
+
+        let stats = wiring_stats(&gates);
+        assert_eq!(stats.rows, 4);
+        assert_eq!(stats.wired_cells, 2);
+        assert_eq!(stats.cycles, 1);
+        assert_eq!(stats.total_row_distance, 6);
+    }
+
+    #[test]
+    fn test_optimize_layout_brings_wired_rows_together() {
+        // row 1 produces a value consumed by row 5
+        let mut gates = generic_rows(6);
+        gates.connect_cell_pair((1, 2), (5, 0));
+        let before = wiring_stats(&gates);
+
+        let (gates, permutation) = optimize_layout(gates, 0);
+        let after = wiring_stats(&gates);
+
+        assert!(after.total_row_distance < before.total_row_distance);
+        assert_eq!(after.cycles, before.cycles);
+        assert_eq!(after.wired_cells, before.wired_cells);
+
+        // the witness follows the rows
+        let mut witness: [Vec<Fp>; COLUMNS] =
+            std::array::from_fn(|_| (0..6u64).map(Fp::from).collect());
+        permutation.apply_to_witness(&mut witness);
+        for (old_row, &new_row) in permutation.new_row.iter().enumerate() {
+            assert_eq!(witness[0][new_row], Fp::from(old_row as u64));
+        }
+    }
+
+    #[test]
+    fn test_optimize_layout_keeps_public_rows() {
+        let mut gates = generic_rows(4);
+        gates[0].wires = Wire::for_row(0).wire(0, Wire::new(3, 0));
+        gates[3].wires = Wire::for_row(3).wire(0, Wire::new(0, 0));
+
+        let (_, permutation) = optimize_layout(gates, 1);
+        assert_eq!(permutation.new_row[0], 0);
+    }
+
+    #[test]
+    fn test_pack_generic_rows() {
+        // rows 1 and 2 each hold a single operation, and row 2 consumes the output of row 1
+        let mut gates = generic_rows(3);
+        gates.connect_cell_pair((1, 2), (2, 0));
+        let before = generic_utilization(&gates);
+
+        let (gates, packed) = pack_generic_rows(gates, 1);
+        assert_eq!(packed.kept, vec![0, 1]);
+        assert_eq!(packed.packed, vec![None, Some(2)]);
+        assert!(generic_utilization(&gates).ratio() > before.ratio());
+
+        // the operation of row 2 now sits in the second half of row 1
+        assert_eq!(gates[1].wires[2], Wire::new(1, 3));
+        assert_eq!(gates[1].wires[3], Wire::new(1, 2));
+        assert_eq!(wiring_stats(&gates).cycles, 1);
+
+        let mut witness: [Vec<Fp>; COLUMNS] = std::array::from_fn(|col| {
+            (0..3u64)
+                .map(|row| Fp::from(10 * row + col as u64))
+                .collect()
+        });
+        packed.apply_to_witness(&mut witness);
+        assert_eq!(witness[0][1], Fp::from(10u64));
+        assert_eq!(witness[3][1], Fp::from(20u64));
+    }
+}
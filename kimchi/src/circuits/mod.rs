@@ -3,12 +3,17 @@ pub mod macros;
 
 pub mod argument;
 pub mod berkeley_columns;
+pub mod capabilities;
 pub mod constraints;
+pub mod custom_gate;
 pub mod domain_constant_evaluation;
 pub mod domains;
 pub mod expr;
+pub mod forwarding;
 pub mod gate;
+pub mod layout;
 pub mod lookup;
+pub mod parallel;
 pub mod polynomial;
 pub mod polynomials;
 pub mod scalars;
@@ -0,0 +1,95 @@
+//! This module implements the parallel synthesis of circuits made of independent chunks
+//! (for example the neurons of a layer, or the samples of a batch).
+//!
+//! Each chunk is synthesized on its own, with its rows numbered from zero,
+//! and the chunks are then concatenated in their original order.
+//! The resulting circuit, and thus its digest and verifier index,
+//! does not depend on the number of threads nor on the order in which the chunks were synthesized.
+//! Wires between chunks are added afterwards, using the offsets of the chunks.
+
+use ark_ff::PrimeField;
+use rayon::prelude::*;
+
+use crate::circuits::gate::CircuitGate;
+
+/// Synthesizes the chunks in parallel, and returns the concatenated gates
+/// along with the row at which each chunk starts.
+///
+/// The gates of a chunk must only be wired to the rows of the same chunk.
+pub fn synthesize_parallel<T, F, S>(
+    chunks: &[T],
+    synthesize: S,
+) -> (Vec<CircuitGate<F>>, Vec<usize>)
+where
+    T: Sync,
+    F: PrimeField,
+    S: Fn(&T) -> Vec<CircuitGate<F>> + Sync + Send,
+{
+    // `collect` keeps the order of the chunks, whatever the scheduling
+    let synthesized: Vec<Vec<CircuitGate<F>>> = chunks.par_iter().map(synthesize).collect();
+
+    let mut gates = Vec::with_capacity(synthesized.iter().map(Vec::len).sum());
+    let mut offsets = Vec::with_capacity(synthesized.len());
+    for chunk in synthesized {
+        let offset = gates.len();
+        offsets.push(offset);
+        gates.extend(chunk.into_iter().map(|mut gate| {
+            for wire in gate.wires.iter_mut() {
+                wire.row += offset;
+            }
+            gate
+        }));
+    }
+
+    (gates, offsets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuits::{
+        gate::{Circuit, Connect},
+        polynomials::generic::GenericGateSpec,
+        wires::Wire,
+    };
+    use mina_curves::pasta::Fp;
+    use o1_utils::hasher::CryptoDigest;
+
+    /// A chunk of `n` constant gates, each wired to the next one.
+    fn chunk(n: &usize) -> Vec<CircuitGate<Fp>> {
+        let mut gates: Vec<_> = (0..*n)
+            .map(|row| {
+                CircuitGate::create_generic_gadget(
+                    Wire::for_row(row),
+                    GenericGateSpec::Const(Fp::from(row as u64)),
+                    None,
+                )
+            })
+            .collect();
+        for row in 1..*n {
+            gates.connect_cell_pair((row - 1, 0), (row, 0));
+        }
+        gates
+    }
+
+    #[test]
+    fn test_synthesize_parallel_is_deterministic() {
+        let chunks: Vec<usize> = (1..20).collect();
+
+        let digests: Vec<_> = [1, 2, 8]
+            .into_iter()
+            .map(|threads| {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .unwrap();
+                let (gates, offsets) = pool.install(|| synthesize_parallel(&chunks, chunk));
+                assert_eq!(offsets[1], 1);
+                assert_eq!(gates[offsets[2]].wires[0], Wire::new(offsets[2] + 1, 0));
+                Circuit::new(0, &gates).digest()
+            })
+            .collect();
+
+        assert!(digests.windows(2).all(|pair| pair[0] == pair[1]));
+    }
+}
@@ -33,6 +33,8 @@
 //~ and c1 (resp. c2) the constant selector for the first (resp. second) gate.
 //~
 
+#[cfg(feature = "prover")]
+use crate::prover_index::ProverIndex;
 use crate::{
     circuits::{
         argument::{Argument, ArgumentEnv, ArgumentType},
@@ -42,7 +44,6 @@ use crate::{
         wires::GateWires,
     },
     curve::KimchiCurve,
-    prover_index::ProverIndex,
 };
 use ark_ff::{FftField, PrimeField, Zero};
 use ark_poly::univariate::DensePolynomial;
@@ -310,6 +311,7 @@ pub mod testing {
         }
     }
 
+    #[cfg(feature = "prover")]
     impl<F: PrimeField, G: KimchiCurve<ScalarField = F>, OpeningProof: OpenProof<G>>
         ProverIndex<G, OpeningProof>
     {
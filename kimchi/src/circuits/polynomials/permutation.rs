@@ -38,6 +38,8 @@
 //~
 //~ You can read more about why it looks like that in [this post](https://minaprotocol.com/blog/a-more-efficient-approach-to-zero-knowledge-for-plonk).
 //~
+#[cfg(feature = "prover")]
+use crate::prover_index::ProverIndex;
 use crate::{
     circuits::{
         constraints::ConstraintSystem,
@@ -47,7 +49,6 @@ use crate::{
     curve::KimchiCurve,
     error::ProverError,
     proof::{PointEvaluations, ProofEvaluations},
-    prover_index::ProverIndex,
 };
 use ark_ff::{FftField, PrimeField, SquareRootField, Zero};
 use ark_poly::{
@@ -193,6 +194,7 @@ where
     }
 }
 
+#[cfg(feature = "prover")]
 impl<F: PrimeField, G: KimchiCurve<ScalarField = F>, OpeningProof: OpenProof<G>>
     ProverIndex<G, OpeningProof>
 {
@@ -401,6 +403,7 @@ impl<F: PrimeField> ConstraintSystem<F> {
     }
 }
 
+#[cfg(feature = "prover")]
 impl<F: PrimeField, G: KimchiCurve<ScalarField = F>, OpeningProof: OpenProof<G>>
     ProverIndex<G, OpeningProof>
 {
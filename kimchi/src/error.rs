@@ -1,7 +1,8 @@
 //! This module implements the [`ProverError`] type.
 
-use crate::circuits::lookup::index::LookupError; // not sure about hierarchy
+use crate::circuits::{gate::GateType, lookup::index::LookupError}; // not sure about hierarchy
 use poly_commitment::error::CommitmentError;
+use std::fmt;
 use thiserror::Error;
 
 /// Errors that can arise when creating a proof
@@ -91,6 +92,50 @@ pub enum DomainCreationError {
 
     #[error("construction of domain {0} for size {1} failed")]
     DomainConstructionFailed(String, usize),
+
+    #[error("the circuit is too large for the field: {0}")]
+    TooLarge(DomainSizeContext),
+}
+
+/// The size of a domain that could not be created, with the sizes the field supports,
+/// to tell how to make the circuit fit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DomainSizeContext {
+    /// The number of rows requested, zero-knowledge rows included.
+    pub requested: usize,
+    /// The largest domain the field supports: its two-adicity bounds the largest evaluation domain, 8 times larger.
+    pub max_size: usize,
+    /// The supported sizes closest to the requested size, below and above it.
+    pub nearest: (Option<usize>, Option<usize>),
+    /// The number of circuits of at most `max_size` rows splitting the circuit would take, if it does not fit.
+    pub chunks: Option<usize>,
+}
+
+impl fmt::Display for DomainSizeContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} rows were requested, and the field supports domains of at most {} rows",
+            self.requested, self.max_size
+        )?;
+        match self.nearest {
+            (Some(below), Some(above)) if below != above => {
+                write!(f, " (the nearest sizes are {below} and {above} rows)")?
+            }
+            (Some(size), _) | (None, Some(size)) => {
+                write!(f, " (the nearest size is {size} rows)")?
+            }
+            (None, None) => (),
+        }
+        if let Some(chunks) = self.chunks {
+            write!(
+                f,
+                "; splitting the circuit into {chunks} circuits of at most {} rows would fit",
+                self.max_size
+            )?;
+        }
+        Ok(())
+    }
 }
 
 /// Errors that can arise when preparing the setup
@@ -112,3 +157,213 @@ pub enum VerifierIndexError {
     #[error("srs has already been set")]
     SRSHasBeenSet,
 }
+
+/// Errors that can arise when creating or checking a proof card
+#[derive(Error, Debug, Clone)]
+pub enum ProofCardError {
+    #[error("the proof card could not be (de)serialized: {0}")]
+    Serialization(String),
+
+    #[error("the proof card was created for a different verifier index")]
+    VerifierIndexMismatch,
+
+    #[error("the proof of the proof card does not verify: {0}")]
+    Proof(VerifyError),
+}
+
+/// Errors that can arise when signing or checking a signed artifact
+#[derive(Error, Debug, Clone)]
+pub enum SigningError {
+    #[error("the artifact could not be serialized: {0}")]
+    Serialization(String),
+
+    #[error("the public key is malformed")]
+    InvalidPublicKey,
+
+    #[error("the signature is malformed or does not verify")]
+    InvalidSignature,
+
+    #[error("the artifact was not signed by the expected signer")]
+    UnexpectedSigner,
+}
+
+/// Errors that can arise when storing or retrieving benchmark artifacts
+#[derive(Error, Debug, Clone)]
+pub enum StoreError {
+    #[error("the artifact {0} does not exist")]
+    NotFound(String),
+
+    #[error("an I/O error occurred: {0}")]
+    Io(String),
+
+    #[error("the remote store returned an error: {0}")]
+    Remote(String),
+
+    #[error("the artifact {0} is malformed: {1}")]
+    Malformed(String, String),
+
+    #[error("shards must hold at least one sample")]
+    EmptyShards,
+}
+
+/// Errors that can arise when registering or checking a custom gate
+#[derive(Error, Debug, Clone)]
+pub enum CustomGateError {
+    #[error("a custom gate named {0} is already registered")]
+    AlreadyRegistered(String),
+
+    #[error("no custom gate named {0} is registered")]
+    UnknownGate(String),
+
+    #[error("the custom gate {0} declares {1} constraints but produces {2}")]
+    ConstraintCount(String, usize, usize),
+
+    #[error("constraint {1} of the custom gate {0} is not satisfied")]
+    Unsatisfied(String, usize),
+}
+
+/// Errors that can arise when checking a circuit against the capabilities of a backend
+#[derive(Error, Debug, Clone, Copy)]
+pub enum CapabilityError {
+    #[error("the backend does not support the {0:?} gate")]
+    UnsupportedGate(GateType),
+
+    #[error("the circuit uses lookups of width {0}, but the backend supports at most {1}")]
+    LookupTooWide(usize, usize),
+
+    #[error("the circuit has {0} rows, but the backend supports at most {1}")]
+    TooManyRows(usize, usize),
+}
+
+/// Errors that can arise when checking an accuracy claim
+#[derive(Error, Debug, Clone, Copy)]
+pub enum AccuracyClaimError {
+    #[error("the proofs cover {0} samples but the claim is about {1} samples")]
+    SampleCount(usize, u64),
+
+    #[error("the batch {0} does not continue the previous batch")]
+    BrokenChain(usize),
+
+    #[error("the batch {0} does not verify: {1}")]
+    InvalidProof(usize, VerifyError),
+
+    #[error("the proofs do not conclude with the claimed test set and accuracy")]
+    ClaimMismatch,
+}
+
+/// Errors that can arise when proving an accuracy claim
+#[derive(Error, Debug, Clone)]
+pub enum AccuracyProofError {
+    #[error("the {0} samples are not a whole number of batches of {1}")]
+    PartialBatch(usize, usize),
+
+    #[error("the batch {0} could not be proven: {1}")]
+    Prove(usize, String),
+}
+
+/// Errors that can arise when checking a chain of proofs
+#[derive(Error, Debug, Clone, Copy)]
+pub enum SessionError {
+    #[error("the proof {0} does not continue the previous proof")]
+    BrokenChain(usize),
+
+    #[error("the proof {0} does not verify: {1}")]
+    InvalidProof(usize, VerifyError),
+}
+
+/// Errors that can arise when compressing a proof
+#[derive(Error, Debug, Clone)]
+pub enum CompressionError {
+    #[error("the proof could not be compressed: {0}")]
+    Compress(String),
+
+    #[error("the compressed proof does not verify: {0}")]
+    Verify(String),
+}
+
+/// Errors that can arise when running an inference in a trusted execution environment
+#[derive(Error, Debug, Clone)]
+pub enum TeeError {
+    #[error("the inference could not run in the enclave: {0}")]
+    Run(String),
+
+    #[error("the attestation does not verify: {0}")]
+    Attestation(String),
+}
+
+/// Errors that can arise when streaming witnesses to the prover
+#[derive(Error, Debug, Clone, Copy)]
+pub enum WitnessStreamError {
+    #[error("a chunk starts at row {1}, but row {0} was expected")]
+    OutOfOrder(usize, usize),
+
+    #[error("a chunk of {1} rows at row {0} overflows the {2} rows of the circuit")]
+    Overflow(usize, usize, usize),
+
+    #[error("the stream ended after {0} of the {1} rows of a witness")]
+    Incomplete(usize, usize),
+}
+
+/// Errors that can arise when validating Poseidon parameters
+#[derive(Error, Debug, Clone)]
+pub enum PoseidonParamsError {
+    #[error("the parameters do not have the shape of the permutation: {0}")]
+    Shape(String),
+
+    #[error("x^{0} is not a permutation of the field")]
+    SboxNotPermutation(u64),
+
+    #[error("the matrix is not MDS: the submatrix of rows {0:?} and columns {1:?} is singular")]
+    NotMds(Vec<usize>, Vec<usize>),
+
+    #[error("{0} full and {1} partial rounds are fewer than the {2} full and {3} partial rounds required")]
+    TooFewRounds(usize, usize, usize, usize),
+}
+
+/// Errors that can arise when loading gradient-boosted trees from an XGBoost dump
+#[derive(Error, Debug, Clone)]
+pub enum XgboostError {
+    #[error("the dump is malformed: {0}")]
+    Json(String),
+
+    #[error("the feature {0} is not named f<index>")]
+    UnknownFeature(String),
+
+    #[error("the node {1} of tree {0} has no child {2}")]
+    MissingChild(usize, usize, usize),
+
+    #[error("the quantization is not supported: {0}")]
+    Quantization(String),
+}
+
+/// Errors that can arise when scaffolding a benchmark circuit
+#[derive(Error, Debug, Clone)]
+pub enum ScaffoldError {
+    #[error("the circuit name {0} is not a snake_case identifier")]
+    InvalidName(String),
+
+    #[error("the module {0} already exists")]
+    AlreadyExists(String),
+
+    #[error("an I/O error occurred: {0}")]
+    Io(String),
+}
+
+/// Errors that can arise when registering a plugin
+#[derive(Error, Debug, Clone)]
+pub enum PluginError {
+    #[error("a {0} named {1} is already registered")]
+    Duplicate(&'static str, String),
+}
+
+/// Errors that can arise when auditing the timing of an operation
+#[derive(Error, Debug, Clone, Copy)]
+pub enum TimingAuditError {
+    #[error("there are no varying inputs to time")]
+    NoVaryingInputs,
+
+    #[error(
+        "the classes have {0} and {1} measurements, fewer than the 2 needed for their variance"
+    )]
+    TooFewSamples(usize, usize),
+}
@@ -11,6 +11,7 @@ pub use poly_commitment;
 pub use turshi;
 
 pub mod alphas;
+#[cfg(feature = "prover")]
 pub mod bench;
 pub mod circuits;
 pub mod curve;
@@ -19,13 +20,24 @@ pub mod lagrange_basis_evaluations;
 pub mod linearization;
 pub mod oracles;
 pub mod plonk_sponge;
+pub mod poseidon_params;
 pub mod precomputed_srs;
 pub mod proof;
+pub mod proof_card;
+#[cfg(feature = "prover")]
 pub mod prover;
+#[cfg(feature = "prover")]
 pub mod prover_index;
+#[cfg(feature = "signing")]
+pub mod signing;
+#[cfg(feature = "prover")]
 pub mod snarky;
+#[cfg(feature = "prover")]
+pub mod test_only;
 pub mod verifier;
 pub mod verifier_index;
+#[cfg(feature = "prover")]
+pub mod witness_stream;
 
 #[cfg(test)]
 mod tests;
@@ -39,4 +51,5 @@ macro_rules! loc {
 }
 
 /// Export what is commonly used.
+#[cfg(feature = "prover")]
 pub use snarky::prelude::*;
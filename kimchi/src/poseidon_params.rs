@@ -0,0 +1,270 @@
+//! Generating and validating Poseidon parameters for custom sponge widths.
+//!
+//! The parameters of the kimchi sponge (see [mina_poseidon::pasta]) are only given for a state of 3 elements.
+//! Experimenting with a wider sponge needs round constants and an MDS matrix of another size:
+//! [generate] derives them deterministically from a seed,
+//! and [validate] checks any set of parameters, generated or copied, against a [PoseidonConfig]:
+//! the shape of the constants, the S-box being a permutation of the field, the matrix being MDS,
+//! and the number of rounds meeting the bounds of the Poseidon paper with its security margin.
+
+use ark_ff::{Field, PrimeField};
+use blake2::{Blake2b512, Digest};
+use itertools::Itertools;
+use mina_poseidon::poseidon::ArithmeticSpongeParams;
+
+use crate::error::PoseidonParamsError;
+
+/// The shape of a Poseidon permutation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoseidonConfig {
+    /// The number of field elements of the state.
+    pub width: usize,
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+    /// The power of the S-box.
+    pub alpha: u64,
+}
+
+impl PoseidonConfig {
+    /// The shape of the kimchi permutation: 55 full rounds of `x^7` over 3 elements.
+    pub const KIMCHI: Self = Self {
+        width: 3,
+        full_rounds: 55,
+        partial_rounds: 0,
+        alpha: 7,
+    };
+
+    /// The number of rounds, each of which has its round constants.
+    pub fn rounds(&self) -> usize {
+        self.full_rounds + self.partial_rounds
+    }
+}
+
+/// Derives the parameters of a permutation from a seed.
+///
+/// Each round constant is a Blake2b-512 digest of the seed, the shape and the position of the constant,
+/// reduced modulo the field (which makes its bias negligible).
+/// The MDS matrix is the Cauchy matrix `1 / (i + width + j)`, which is MDS as its `x_i = i` and `y_j = width + j` are distinct.
+pub fn generate<F: PrimeField>(config: &PoseidonConfig, seed: &[u8]) -> ArithmeticSpongeParams<F> {
+    let round_constants = (0..config.rounds())
+        .map(|round| {
+            (0..config.width)
+                .map(|i| {
+                    let mut h = Blake2b512::new();
+                    h.update(seed);
+                    for value in [
+                        config.width,
+                        config.full_rounds,
+                        config.partial_rounds,
+                        round,
+                        i,
+                    ] {
+                        h.update((value as u64).to_le_bytes());
+                    }
+                    h.update(config.alpha.to_le_bytes());
+                    F::from_le_bytes_mod_order(&h.finalize())
+                })
+                .collect()
+        })
+        .collect();
+
+    let mds = (0..config.width)
+        .map(|i| {
+            (0..config.width)
+                .map(|j| {
+                    F::from((i + config.width + j) as u64)
+                        .inverse()
+                        .expect("the entries of the Cauchy matrix are small and non-zero")
+                })
+                .collect()
+        })
+        .collect();
+
+    ArithmeticSpongeParams {
+        round_constants,
+        mds,
+    }
+}
+
+/// The numbers of full and partial rounds required for `security_bits` bits of security,
+/// given the number of full rounds of the permutation.
+///
+/// The bounds are those of the Poseidon paper against statistical attacks (6 full rounds),
+/// interpolation and Gröbner basis attacks (on the total number of rounds),
+/// increased by its security margin: 2 more full rounds, and 7.5% more partial rounds.
+pub fn required_rounds(
+    config: &PoseidonConfig,
+    field_bits: usize,
+    security_bits: usize,
+) -> (usize, usize) {
+    let log_alpha = |x: f64| x.log2() / (config.alpha as f64).log2();
+    let (m, n) = (security_bits as f64, field_bits as f64);
+    let width = log_alpha(config.width as f64).ceil();
+
+    let interpolation = (log_alpha(2.0) * m.min(n)).ceil() + width;
+    let groebner = (log_alpha(2.0) * (m / 3.0).min(n / 2.0)).ceil() + width;
+    let total = interpolation.max(groebner) as usize;
+
+    let full = 6 + 2;
+    let partial = total.saturating_sub(config.full_rounds);
+    (full, (partial as f64 * 1.075).ceil() as usize)
+}
+
+/// The remainder of the characteristic of a field modulo a small number.
+fn characteristic_mod<F: PrimeField>(modulus: u64) -> u64 {
+    F::characteristic().iter().rev().fold(0u128, |r, limb| {
+        ((r << 64) + u128::from(*limb)) % u128::from(modulus)
+    }) as u64
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// The determinant of a square matrix, by Gaussian elimination.
+fn determinant<F: Field>(mut matrix: Vec<Vec<F>>) -> F {
+    let size = matrix.len();
+    let mut det = F::one();
+    for col in 0..size {
+        let pivot = match (col..size).find(|&row| !matrix[row][col].is_zero()) {
+            Some(pivot) => pivot,
+            None => return F::zero(),
+        };
+        if pivot != col {
+            matrix.swap(pivot, col);
+            det = -det;
+        }
+        det *= matrix[col][col];
+        let inverse = matrix[col][col].inverse().unwrap();
+        for row in col + 1..size {
+            let factor = matrix[row][col] * inverse;
+            for k in col..size {
+                let value = matrix[col][k];
+                matrix[row][k] -= factor * value;
+            }
+        }
+    }
+    det
+}
+
+/// Checks parameters against the shape of a permutation, for `security_bits` bits of security.
+///
+/// Checking that the matrix is MDS computes the determinants of all its square submatrices,
+/// whose number grows exponentially with the width.
+///
+/// # Errors
+///
+/// Will give error if the parameters do not have the shape of the permutation,
+/// if the S-box is not a permutation of the field, if the matrix is not MDS,
+/// or if there are fewer rounds than required (see [required_rounds]).
+pub fn validate<F: PrimeField>(
+    config: &PoseidonConfig,
+    params: &ArithmeticSpongeParams<F>,
+    security_bits: usize,
+) -> Result<(), PoseidonParamsError> {
+    let width = config.width;
+    if params.round_constants.len() != config.rounds()
+        || params
+            .round_constants
+            .iter()
+            .any(|round| round.len() != width)
+    {
+        return Err(PoseidonParamsError::Shape(format!(
+            "expected {} rounds of {width} round constants",
+            config.rounds()
+        )));
+    }
+    if params.mds.len() != width || params.mds.iter().any(|row| row.len() != width) {
+        return Err(PoseidonParamsError::Shape(format!(
+            "expected a {width}x{width} MDS matrix"
+        )));
+    }
+
+    // x^alpha is a permutation iff alpha is coprime with the order p - 1 of the multiplicative group
+    let alpha = config.alpha;
+    if alpha < 3 || gcd(alpha, (characteristic_mod::<F>(alpha) + alpha - 1) % alpha) != 1 {
+        return Err(PoseidonParamsError::SboxNotPermutation(alpha));
+    }
+
+    for size in 1..=width {
+        for rows in (0..width).combinations(size) {
+            for cols in (0..width).combinations(size) {
+                let submatrix = rows
+                    .iter()
+                    .map(|&row| cols.iter().map(|&col| params.mds[row][col]).collect())
+                    .collect();
+                if determinant(submatrix).is_zero() {
+                    return Err(PoseidonParamsError::NotMds(rows, cols));
+                }
+            }
+        }
+    }
+
+    let field_bits = F::size_in_bits();
+    let (full, partial) = required_rounds(config, field_bits, security_bits);
+    if config.full_rounds < full || config.partial_rounds < partial {
+        return Err(PoseidonParamsError::TooFewRounds(
+            config.full_rounds,
+            config.partial_rounds,
+            full,
+            partial,
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mina_curves::pasta::Fp;
+
+    #[test]
+    fn test_poseidon_params() {
+        // the kimchi parameters are valid
+        let kimchi = mina_poseidon::pasta::fp_kimchi::static_params();
+        validate(&PoseidonConfig::KIMCHI, kimchi, 128).unwrap();
+
+        // a wider sponge, with generated parameters
+        let config = PoseidonConfig {
+            width: 5,
+            full_rounds: 8,
+            partial_rounds: 60,
+            alpha: 7,
+        };
+        let params = generate::<Fp>(&config, b"widened sponge");
+        validate(&config, &params, 128).unwrap();
+        assert_eq!(
+            generate::<Fp>(&config, b"widened sponge").round_constants,
+            params.round_constants
+        );
+
+        // too few partial rounds
+        let weak = PoseidonConfig {
+            partial_rounds: 20,
+            ..config
+        };
+        assert!(matches!(
+            validate(&weak, &generate::<Fp>(&weak, b"weak"), 128),
+            Err(PoseidonParamsError::TooFewRounds(..))
+        ));
+
+        // x^2 is not a permutation
+        let square = PoseidonConfig { alpha: 2, ..config };
+        assert!(matches!(
+            validate(&square, &params, 128),
+            Err(PoseidonParamsError::SboxNotPermutation(2))
+        ));
+
+        // a matrix with a zero entry is not MDS
+        let mut singular = params.clone();
+        singular.mds[1][2] = Fp::from(0u64);
+        assert!(matches!(
+            validate(&config, &singular, 128),
+            Err(PoseidonParamsError::NotMds(..))
+        ));
+    }
+}
@@ -4,8 +4,12 @@
 //!
 //! We generate the SRS within the test in this module.
 //! If you modify the SRS, you will need to regenerate the SRS by passing the `SRS_OVERWRITE` env var.
+//!
+//! For SRS larger than the serialized ones, use [get_srs_for_size] (or the `srs` binary to generate it once and store it).
+//! For CI-scale correctness runs, [InsecureTestSRS] provides a deterministic SRS that must never be used in production.
 
 use crate::curve::KimchiCurve;
+use ark_ff::PrimeField;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use poly_commitment::{srs::SRS, PolyComm};
 use serde::{Deserialize, Serialize};
@@ -114,12 +118,60 @@ where
     get_srs_generic(StoredSRSType::Test)
 }
 
+/// Obtains an SRS of `1 << log2_size` elements for a specific curve.
+/// If the size fits in the serialized SRS, it is read from disk and trimmed,
+/// otherwise it is generated from scratch (which is slow for large sizes).
+pub fn get_srs_for_size<G>(log2_size: u32) -> SRS<G>
+where
+    G: KimchiCurve,
+    G::BaseField: PrimeField,
+{
+    let size = 1 << log2_size;
+    if log2_size <= SERIALIZED_SRS_SIZE {
+        let mut srs = get_srs::<G>();
+        srs.g.truncate(size);
+        srs.lagrange_bases
+            .retain(|&domain_size, _| domain_size <= size);
+        srs
+    } else {
+        SRS::<G>::create(size)
+    }
+}
+
+/// The (publicly known!) trapdoor used to create an [InsecureTestSRS].
+const INSECURE_TEST_SRS_TRAPDOOR: u64 = 42;
+
+/// An SRS created from a publicly known trapdoor.
+///
+/// It is deterministic and does not require reading anything from disk,
+/// which makes it convenient for CI-scale correctness runs,
+/// but anyone can forge proofs against it.
+/// For this reason it is kept as a distinct type that cannot be serialized,
+/// and that can only be turned into [crate::test_only::TestOnly] artifacts.
+pub struct InsecureTestSRS<G>(SRS<G>);
+
+impl<G> InsecureTestSRS<G>
+where
+    G: KimchiCurve,
+{
+    /// Creates an insecure SRS of `1 << log2_size` elements.
+    pub fn create(log2_size: u32) -> Self {
+        let x = G::ScalarField::from(INSECURE_TEST_SRS_TRAPDOOR);
+        Self(SRS::<G>::create_trusted_setup(x, 1 << log2_size))
+    }
+
+    /// Returns the underlying [SRS].
+    /// Outside of this crate, use [crate::test_only::TestOnly] to build artifacts from it.
+    pub(crate) fn into_inner_insecure(self) -> SRS<G> {
+        self.0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use ark_ec::AffineCurve;
-    use ark_ff::PrimeField;
     use ark_serialize::Write;
     use hex;
     use mina_curves::pasta::{Pallas, Vesta};
@@ -226,6 +278,23 @@ mod tests {
         get_srs_test::<Vesta>();
     }
 
+    /// Checks that trimming the serialized SRS gives the requested size.
+    #[test]
+    pub fn check_get_srs_for_size() {
+        let srs = get_srs_for_size::<Vesta>(10);
+        assert_eq!(srs.g.len(), 1 << 10);
+        assert!(srs.lagrange_bases.keys().all(|&size| size <= 1 << 10));
+    }
+
+    /// Checks that the insecure test SRS is deterministic.
+    #[test]
+    pub fn check_insecure_test_srs_deterministic() {
+        let srs1 = InsecureTestSRS::<Vesta>::create(4).into_inner_insecure();
+        let srs2 = InsecureTestSRS::<Vesta>::create(4).into_inner_insecure();
+        assert_eq!(srs1.g.len(), 1 << 4);
+        assert_eq!(srs1, srs2);
+    }
+
     /// This test checks that the two serialized SRS on disk are correct.
     #[test]
     pub fn heavy_test_srs_serialization() {
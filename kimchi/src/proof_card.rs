@@ -0,0 +1,125 @@
+//! This module implements the [`ProofCard`] type,
+//! a self-describing bundle of a proof and of what it is a proof of.
+//!
+//! A proof card contains the proof and its public input,
+//! the digest of the verifier index it was produced for,
+//! a commitment to the model, the quantization configuration,
+//! and the accuracy metrics measured for the model.
+//! It is serialized to JSON so that published benchmark artifacts can be checked by anyone holding the verifier index.
+
+use std::collections::BTreeMap;
+
+use ark_ff::PrimeField;
+use mina_poseidon::FqSponge;
+use poly_commitment::OpenProof;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_with::serde_as;
+
+use crate::{
+    curve::KimchiCurve, error::ProofCardError, plonk_sponge::FrSponge, proof::ProverProof,
+    verifier::verify, verifier_index::VerifierIndex,
+};
+
+/// The fixed-point quantization used to encode the model and its inputs in the field.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuantizationConfig {
+    /// Values are scaled by `2^scale_bits` before being converted to field elements.
+    pub scale_bits: u32,
+
+    /// The number of bits of the quantized values (including the fractional part).
+    pub value_bits: u32,
+}
+
+/// A proof bundled with everything needed to interpret it.
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "ProverProof<G, OpeningProof>: Serialize + DeserializeOwned")]
+pub struct ProofCard<G: KimchiCurve, OpeningProof: OpenProof<G>> {
+    /// The name of the model.
+    pub model: String,
+
+    /// The digest of the verifier index the proof was created for.
+    #[serde_as(as = "o1_utils::serialization::SerdeAs")]
+    pub verifier_index_digest: G::BaseField,
+
+    /// A commitment to the model weights.
+    #[serde_as(as = "o1_utils::serialization::SerdeAs")]
+    pub model_commitment: G::ScalarField,
+
+    /// The quantization used by the circuit.
+    pub quantization: QuantizationConfig,
+
+    /// Accuracy metrics of the quantized model (e.g. `"accuracy"`, `"max_abs_error"`).
+    pub metrics: BTreeMap<String, f64>,
+
+    /// The public input of the proof.
+    #[serde_as(as = "Vec<o1_utils::serialization::SerdeAs>")]
+    pub public_input: Vec<G::ScalarField>,
+
+    /// The proof.
+    pub proof: ProverProof<G, OpeningProof>,
+}
+
+impl<G: KimchiCurve, OpeningProof: OpenProof<G>> ProofCard<G, OpeningProof>
+where
+    G::BaseField: PrimeField,
+    ProverProof<G, OpeningProof>: Serialize + DeserializeOwned,
+{
+    /// Creates a proof card for a proof produced against `verifier_index`.
+    pub fn new<EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>>(
+        model: String,
+        verifier_index: &VerifierIndex<G, OpeningProof>,
+        model_commitment: G::ScalarField,
+        quantization: QuantizationConfig,
+        metrics: BTreeMap<String, f64>,
+        public_input: Vec<G::ScalarField>,
+        proof: ProverProof<G, OpeningProof>,
+    ) -> Self {
+        Self {
+            model,
+            verifier_index_digest: verifier_index.digest::<EFqSponge>(),
+            model_commitment,
+            quantization,
+            metrics,
+            public_input,
+            proof,
+        }
+    }
+
+    /// Serializes the proof card to JSON.
+    pub fn to_json(&self) -> Result<String, ProofCardError> {
+        serde_json::to_string_pretty(self).map_err(|e| ProofCardError::Serialization(e.to_string()))
+    }
+
+    /// Deserializes a proof card from JSON.
+    pub fn from_json(json: &str) -> Result<Self, ProofCardError> {
+        serde_json::from_str(json).map_err(|e| ProofCardError::Serialization(e.to_string()))
+    }
+
+    /// Checks that the proof card was created for `verifier_index`,
+    /// and that its proof verifies.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the verifier index digest does not match, or if the proof does not verify.
+    pub fn verify<
+        EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+        EFrSponge: FrSponge<G::ScalarField>,
+    >(
+        &self,
+        group_map: &G::Map,
+        verifier_index: &VerifierIndex<G, OpeningProof>,
+    ) -> Result<(), ProofCardError> {
+        if verifier_index.digest::<EFqSponge>() != self.verifier_index_digest {
+            return Err(ProofCardError::VerifierIndexMismatch);
+        }
+
+        verify::<G, EFqSponge, EFrSponge, OpeningProof>(
+            group_map,
+            verifier_index,
+            &self.proof,
+            &self.public_input,
+        )
+        .map_err(ProofCardError::Proof)
+    }
+}
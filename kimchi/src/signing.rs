@@ -0,0 +1,166 @@
+//! This module implements the [`Signed`] type,
+//! which attaches a secp256k1 signature to a benchmark artifact (a proof card, a report),
+//! so that third parties can attribute the published numbers to whoever produced them.
+//!
+//! The signature is an ECDSA signature over the Blake2b hash of the canonical JSON encoding of the artifact
+//! (that is, compact, with the keys of every object sorted).
+//! The keys are sorted explicitly rather than by the map of `serde_json`,
+//! whose order depends on its `preserve_order` feature.
+//! This makes it possible to verify a signed artifact without knowing its type.
+
+use blake2::{Blake2b512, Digest};
+use secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1, SecretKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::SigningError;
+
+/// An artifact signed with a local key.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Signed<T> {
+    /// The signed artifact.
+    pub payload: T,
+
+    /// The hex-encoded compressed public key of the signer.
+    pub public_key: String,
+
+    /// The hex-encoded compact signature.
+    pub signature: String,
+}
+
+/// Sorts the keys of every object of a JSON value.
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let mut fields: Vec<_> = fields.into_iter().collect();
+            fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
+
+/// Encodes `payload` in canonical JSON.
+fn canonical_json<T: Serialize>(payload: &T) -> Result<Vec<u8>, SigningError> {
+    serde_json::to_value(payload)
+        .and_then(|value| serde_json::to_vec(&sort_keys(value)))
+        .map_err(|e| SigningError::Serialization(e.to_string()))
+}
+
+/// Hashes the canonical JSON encoding of `payload` into a message to sign.
+fn message<T: Serialize>(payload: &T) -> Result<Message, SigningError> {
+    let canonical = canonical_json(payload)?;
+
+    let mut h = Blake2b512::new();
+    h.update(canonical);
+    Message::from_slice(&h.finalize()[..32]).map_err(|_| SigningError::InvalidSignature)
+}
+
+impl<T: Serialize> Signed<T> {
+    /// Signs `payload` with `secret_key`.
+    pub fn sign(payload: T, secret_key: &SecretKey) -> Result<Self, SigningError> {
+        let secp = Secp256k1::new();
+        let msg = message(&payload)?;
+        let signature = secp.sign_ecdsa(&msg, secret_key);
+        let public_key = PublicKey::from_secret_key(&secp, secret_key);
+
+        Ok(Self {
+            payload,
+            public_key: hex::encode(public_key.serialize()),
+            signature: hex::encode(signature.serialize_compact()),
+        })
+    }
+
+    /// Returns the public key of the signer.
+    pub fn signer(&self) -> Result<PublicKey, SigningError> {
+        let bytes = hex::decode(&self.public_key).map_err(|_| SigningError::InvalidPublicKey)?;
+        PublicKey::from_slice(&bytes).map_err(|_| SigningError::InvalidPublicKey)
+    }
+
+    /// Checks the signature against the embedded public key.
+    /// Use [Self::verify_signer] to also check who the signer is.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the signature does not verify.
+    pub fn verify(&self) -> Result<(), SigningError> {
+        let bytes = hex::decode(&self.signature).map_err(|_| SigningError::InvalidSignature)?;
+        let signature =
+            Signature::from_compact(&bytes).map_err(|_| SigningError::InvalidSignature)?;
+        let msg = message(&self.payload)?;
+
+        Secp256k1::verification_only()
+            .verify_ecdsa(&msg, &signature, &self.signer()?)
+            .map_err(|_| SigningError::InvalidSignature)
+    }
+
+    /// Checks the signature, and that it was produced by `expected_signer`.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the signature does not verify, or if the signer is not the expected one.
+    pub fn verify_signer(&self, expected_signer: &PublicKey) -> Result<(), SigningError> {
+        if &self.signer()? != expected_signer {
+            return Err(SigningError::UnexpectedSigner);
+        }
+        self.verify()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let secret_key = SecretKey::from_slice(&[7u8; 32]).unwrap();
+        let signed = Signed::sign(("proof size", 4242u64), &secret_key).unwrap();
+        signed.verify().unwrap();
+
+        // the signature also verifies for an untyped payload
+        let json = serde_json::to_string(&signed).unwrap();
+        let untyped: Signed<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        untyped.verify().unwrap();
+
+        // a modified payload is rejected
+        let mut tampered = signed.clone();
+        tampered.payload.1 = 4241;
+        assert!(tampered.verify().is_err());
+
+        // a different signer is rejected
+        let other = SecretKey::from_slice(&[8u8; 32]).unwrap();
+        let other = PublicKey::from_secret_key(&Secp256k1::new(), &other);
+        assert!(matches!(
+            signed.verify_signer(&other),
+            Err(SigningError::UnexpectedSigner)
+        ));
+    }
+
+    #[test]
+    fn test_canonical_json() {
+        #[derive(Serialize)]
+        struct Report {
+            model: &'static str,
+            costs: Value,
+        }
+
+        // the fields of structs and the keys of maps are sorted, whatever their order
+        let report = Report {
+            model: "mlp",
+            costs: serde_json::json!({"verify": 2, "prove": {"secs": 1, "nanos": 0}}),
+        };
+        let expected = br#"{"costs":{"prove":{"nanos":0,"secs":1},"verify":2},"model":"mlp"}"#;
+        assert_eq!(canonical_json(&report).unwrap(), expected);
+
+        let mut reversed = serde_json::Map::new();
+        reversed.insert("model".to_string(), "mlp".into());
+        reversed.insert("costs".to_string(), report.costs.clone());
+        assert_eq!(canonical_json(&reversed).unwrap(), expected);
+    }
+}
@@ -3,10 +3,16 @@
 //! To use Snarky, simply implements the [SnarkyCircuit] trait.
 
 use std::marker::PhantomData;
+#[cfg(feature = "mmap_witness")]
+use std::path::PathBuf;
 
 use crate::{
-    circuits::{constraints::ConstraintSystem, gate::CircuitGate, polynomial::COLUMNS},
+    circuits::{
+        capabilities::Capabilities, constraints::ConstraintSystem, gate::CircuitGate,
+        polynomial::COLUMNS,
+    },
     curve::KimchiCurve,
+    error::VerifyError,
     groupmap::GroupMap,
     mina_poseidon::FqSponge,
     plonk_sponge::FrSponge,
@@ -21,7 +27,16 @@ use ark_ff::PrimeField;
 use log::debug;
 use poly_commitment::{commitment::CommitmentCurve, OpenProof, SRS};
 
-use super::{errors::SnarkyResult, runner::RunState, snarky_type::SnarkyType};
+#[cfg(feature = "mmap_witness")]
+use super::{
+    errors::SnarkyRuntimeError,
+    witness_store::{MappedWitness, WitnessLayout, WitnessMatrix},
+};
+use super::{
+    errors::{RealSnarkyError, SnarkyCompilationError, SnarkyError, SnarkyResult},
+    runner::RunState,
+    snarky_type::SnarkyType,
+};
 
 /// A witness represents the execution trace of a circuit.
 #[derive(Debug)]
@@ -41,6 +56,9 @@ where
 {
     compiled_circuit: CompiledCircuit<Circuit>,
     index: ProverIndex<Circuit::Curve, Circuit::Proof>,
+    /// Where to store the witness, if not in memory (see [Self::store_witness_in]).
+    #[cfg(feature = "mmap_witness")]
+    witness_storage: Option<(PathBuf, WitnessLayout)>,
 }
 
 type Proof<C> = ProverProof<<C as SnarkyCircuit>::Curve, <C as SnarkyCircuit>::Proof>;
@@ -61,6 +79,54 @@ where
         .generate_asm()
     }
 
+    /// The number of gates of the circuit, before padding to the domain size.
+    pub fn num_gates(&self) -> usize {
+        self.compiled_circuit.gates.len()
+    }
+
+    /// Generates the witness of the next proofs into a memory-mapped file at `path`, overwritten by each proof,
+    /// which the prover then reads back into memory (see [crate::snarky::witness_store]).
+    #[cfg(feature = "mmap_witness")]
+    pub fn store_witness_in(&mut self, path: PathBuf, layout: WitnessLayout) {
+        self.witness_storage = Some((path, layout));
+    }
+
+    /// Generates the witness, with the given values for the public output.
+    fn generate_witness(
+        &mut self,
+        public_output_values: &[ScalarField<Circuit::Curve>],
+    ) -> SnarkyResult<Witness<ScalarField<Circuit::Curve>>> {
+        let start = Circuit::PublicInput::SIZE_IN_FIELD_ELEMENTS;
+        let sys = &mut self.compiled_circuit.sys;
+
+        #[cfg(feature = "mmap_witness")]
+        if let Some((path, layout)) = &self.witness_storage {
+            let mut witness = sys
+                .generate_witness_into(|rows| MappedWitness::create(path, rows, *layout))
+                .map_err(|e| {
+                    sys.runtime_error(SnarkyRuntimeError::WitnessStorage(e.to_string()))
+                })?;
+
+            // replace public output part of witness
+            for (row, val) in (start..).zip(public_output_values) {
+                witness.set(0, row, *val);
+            }
+            return Ok(Witness(witness.into_columns()));
+        }
+
+        let mut witness = sys.generate_witness();
+
+        // replace public output part of witness
+        let end = start + public_output_values.len();
+        for (cell, val) in &mut witness.0[0][start..end]
+            .iter_mut()
+            .zip(public_output_values)
+        {
+            *cell = *val;
+        }
+        Ok(witness)
+    }
+
     /// Produces a proof for the given public input.
     pub fn prove<EFqSponge, EFrSponge>(
         // TODO: this should not be mutable ideally
@@ -113,17 +179,7 @@ where
         }
 
         // finalize
-        let mut witness = self.compiled_circuit.sys.generate_witness();
-
-        // replace public output part of witness
-        let start = Circuit::PublicInput::SIZE_IN_FIELD_ELEMENTS;
-        let end = start + Circuit::PublicOutput::SIZE_IN_FIELD_ELEMENTS;
-        for (cell, val) in &mut witness.0[0][start..end]
-            .iter_mut()
-            .zip(&public_output_values)
-        {
-            *cell = *val;
-        }
+        let witness = self.generate_witness(&public_output_values)?;
 
         // same but with the full public input
         let mut public_input_and_output = public_input_without_output;
@@ -179,8 +235,29 @@ where
             + FqSponge<BaseField<Circuit::Curve>, Circuit::Curve, ScalarField<Circuit::Curve>>,
         EFrSponge: FrSponge<ScalarField<Circuit::Curve>>,
     {
-        let mut public_input = Circuit::PublicInput::value_to_field_elements(&public_input).0;
-        public_input.extend(Circuit::PublicOutput::value_to_field_elements(&public_output).0);
+        self.try_verify::<EFqSponge, EFrSponge>(&proof, &public_input, &public_output)
+            .unwrap()
+    }
+
+    /// Same as [Self::verify], but returns an error instead of panicking if the proof is invalid.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the proof does not verify (see [crate::verifier::verify]).
+    pub fn try_verify<EFqSponge, EFrSponge>(
+        &self,
+        proof: &ProverProof<Circuit::Curve, Circuit::Proof>,
+        public_input: &<Circuit::PublicInput as SnarkyType<ScalarField<Circuit::Curve>>>::OutOfCircuit,
+        public_output: &<Circuit::PublicOutput as SnarkyType<ScalarField<Circuit::Curve>>>::OutOfCircuit,
+    ) -> Result<(), VerifyError>
+    where
+        <Circuit::Curve as AffineCurve>::BaseField: PrimeField,
+        EFqSponge: Clone
+            + FqSponge<BaseField<Circuit::Curve>, Circuit::Curve, ScalarField<Circuit::Curve>>,
+        EFrSponge: FrSponge<ScalarField<Circuit::Curve>>,
+    {
+        let mut public_input = Circuit::PublicInput::value_to_field_elements(public_input).0;
+        public_input.extend(Circuit::PublicOutput::value_to_field_elements(public_output).0);
 
         // verify the proof
         let group_map = <Circuit::Curve as CommitmentCurve>::Map::setup();
@@ -188,10 +265,9 @@ where
         verify::<Circuit::Curve, EFqSponge, EFrSponge, Circuit::Proof>(
             &group_map,
             &self.index,
-            &proof,
+            proof,
             &public_input,
         )
-        .unwrap()
     }
 }
 
@@ -232,6 +308,8 @@ fn compile<Circuit: SnarkyCircuit>(circuit: Circuit) -> SnarkyResult<CompiledCir
         Circuit::PublicOutput::SIZE_IN_FIELD_ELEMENTS,
         true,
     );
+    sys.capabilities = circuit.capabilities();
+    sys.max_domain_log2 = circuit.max_domain_log2();
 
     // run circuit and get return var
     let public_input: Circuit::PublicInput = sys.public_input();
@@ -244,6 +322,11 @@ fn compile<Circuit: SnarkyCircuit>(circuit: Circuit) -> SnarkyResult<CompiledCir
     let gates = sys.wire_output_and_compile(return_var).unwrap();
     let gates = gates.to_vec();
 
+    // make sure the backend can prove the circuit
+    sys.capabilities
+        .check(&gates)
+        .map_err(|err| sys.compilation_error(SnarkyCompilationError::UnsupportedByBackend(err)))?;
+
     // return compiled circuit
     let compiled_circuit = CompiledCircuit {
         circuit,
@@ -295,6 +378,26 @@ pub trait SnarkyCircuit: Sized {
         private_input: Option<&Self::PrivateInput>,
     ) -> SnarkyResult<Self::PublicOutput>;
 
+    /// The capabilities of the backend the circuit is compiled for.
+    /// Gadgets pick implementations that the backend supports,
+    /// and compilation fails if the circuit still cannot be proven by the backend.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::kimchi()
+    }
+
+    /// The largest domain the circuit may use, as a power of two (for example, that of the largest SRS available).
+    /// Compilation fails as soon as a layer (see [RunState::with_layer]) goes past it.
+    fn max_domain_log2(&self) -> Option<usize> {
+        None
+    }
+
+    /// The size of the largest SRS available, rounded down to a power of two.
+    /// A circuit whose domain is larger is not rejected: its polynomials are committed to in chunks of this size.
+    /// Compilation fails if it is zero.
+    fn max_srs_size(&self) -> Option<usize> {
+        None
+    }
+
     /// Compiles the circuit to a prover index ([ProverIndexWrapper]) and a verifier index ([VerifierIndexWrapper]).
     fn compile_to_indexes(
         self,
@@ -302,23 +405,38 @@ pub trait SnarkyCircuit: Sized {
     where
         <Self::Curve as AffineCurve>::BaseField: PrimeField,
     {
+        let max_srs_size = match self.max_srs_size() {
+            Some(0) => {
+                return Err(Box::new(RealSnarkyError::new(
+                    SnarkyError::CompilationError(SnarkyCompilationError::EmptySrs(0)),
+                )))
+            }
+            size => size.map(|size| 1 << size.ilog2()),
+        };
         let compiled_circuit = compile(self)?;
 
         // create constraint system
+        // (the number of chunks sets the number of zero-knowledge rows)
         let cs = ConstraintSystem::create(compiled_circuit.gates.clone())
             .public(compiled_circuit.public_input_size)
+            .max_poly_size(max_srs_size)
             .build()
             .unwrap();
 
         // create SRS (for vesta, as the circuit is in Fp)
         // let mut srs = SRS::<Self::Curve>::create(cs.domain.d1.size as usize);
-        let mut srs = <<Self::Proof as OpenProof<Self::Curve>>::SRS as SRS<Self::Curve>>::create(
-            cs.domain.d1.size as usize,
-        );
+        let domain_size = cs.domain.d1.size as usize;
+        let srs_size = max_srs_size.map_or(domain_size, |max| domain_size.min(max));
+        let mut srs =
+            <<Self::Proof as OpenProof<Self::Curve>>::SRS as SRS<Self::Curve>>::create(srs_size);
         srs.add_lagrange_basis(cs.domain.d1);
         let srs = std::sync::Arc::new(srs);
 
-        debug!("using an SRS of size {}", srs.size());
+        debug!(
+            "using an SRS of size {}, in {} chunks",
+            srs.size(),
+            domain_size / srs_size
+        );
 
         // create indexes
         let endo_q = <<Self as SnarkyCircuit>::Curve as KimchiCurve>::other_curve_endo();
@@ -330,6 +448,8 @@ pub trait SnarkyCircuit: Sized {
         let prover_index = ProverIndexWrapper {
             compiled_circuit,
             index: prover_index,
+            #[cfg(feature = "mmap_witness")]
+            witness_storage: None,
         };
 
         let verifier_index = VerifierIndexWrapper {
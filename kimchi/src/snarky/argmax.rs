@@ -0,0 +1,157 @@
+//! A gadget proving the predicted class of a classifier: the index of the largest of its logits,
+//! so that a classification circuit can output only the label instead of the logits.
+//!
+//! The index is selected by a one-hot vector of private booleans, and the gadget proves with comparisons
+//! that the selected logit is at least every logit after it, and strictly greater than every logit before it,
+//! so that the index is the first of the largest logits and nothing else.
+//! It costs a multiplication and two bit decompositions per logit.
+//! Logits are unsigned and must fit in the width given to the gadget, which constrains them to do so.
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+
+use crate::snarky::{
+    bits::{to_bits, Endianness},
+    boolean::Boolean,
+    errors::SnarkyCompilationError,
+    prelude::{FieldVar, RunState, SnarkyResult},
+};
+
+/// Returns the index of the largest of the logits, the first of them on ties.
+pub fn argmax_native<V: Ord>(logits: &[V]) -> Option<usize> {
+    logits
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_, logit)| *logit)
+        .map(|(class, _)| class)
+}
+
+/// Returns the index of the largest of the logits, the first of them on ties,
+/// constraining it to dominate all the other logits.
+///
+/// # Errors
+///
+/// Will give error if there are no logits.
+pub fn argmax<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    logits: &[FieldVar<F>],
+    width: usize,
+) -> SnarkyResult<FieldVar<F>> {
+    if logits.is_empty() {
+        return Err(sys.compilation_error(SnarkyCompilationError::ShapeMismatch(
+            "classes",
+            "argmax".to_string(),
+            0,
+            1,
+        )));
+    }
+
+    // a one-hot vector selecting the predicted class
+    let mut selectors = Vec::with_capacity(logits.len());
+    for class in 0..logits.len() {
+        let advice = logits.to_vec();
+        let selected: Boolean<F> = sys.compute(loc.clone(), move |env| {
+            let values: Vec<_> = advice
+                .iter()
+                .map(|logit| env.read_var(logit).into_repr())
+                .collect();
+            argmax_native(&values) == Some(class)
+        })?;
+        selectors.push(selected.to_field_var());
+    }
+    sys.assert_eq(
+        Some("argmax.one_hot".into()),
+        loc.clone(),
+        FieldVar::sum_many(&selectors),
+        FieldVar::constant(F::one()),
+    )?;
+
+    let selected = selectors
+        .iter()
+        .zip(logits)
+        .map(|(selector, logit)| selector.mul(logit, None, loc.clone(), sys))
+        .collect::<SnarkyResult<Vec<_>>>()?;
+    let max = FieldVar::sum_many(&selected);
+
+    // `after` is 1 for the logits before the selected one, which it must exceed strictly
+    let mut after = FieldVar::zero();
+    for (selector, logit) in selectors.iter().zip(logits).rev() {
+        to_bits(sys, loc.clone(), logit, width, Endianness::Little)?;
+        let difference = &max - logit - &after;
+        to_bits(sys, loc.clone(), &difference, width, Endianness::Little)?;
+        after = after + selector;
+    }
+
+    let classes: Vec<_> = selectors
+        .iter()
+        .enumerate()
+        .map(|(class, selector)| selector.scale(F::from(class as u64)))
+        .collect();
+    Ok(FieldVar::sum_many(&classes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Outputs the predicted class of 4 private logits of 8 bits.
+    struct TestCircuit;
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = [Fp; 4];
+        type PublicInput = ();
+        type PublicOutput = FieldVar<Fp>;
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let logits: [FieldVar<Fp>; 4] = sys.compute(loc!(), |_| *private.unwrap())?;
+            argmax(sys, loc!(), &logits, 8)
+        }
+    }
+
+    #[test]
+    fn snarky_argmax() {
+        assert_eq!(argmax_native(&[3, 9, 2, 9]), Some(1));
+        assert_eq!(argmax_native::<u64>(&[]), None);
+
+        let (mut prover_index, verifier_index) = TestCircuit.compile_to_indexes().unwrap();
+        let debug = true;
+        for (logits, class) in [
+            ([3u64, 9, 2, 7], 1u64),
+            ([3, 9, 2, 9], 1),
+            ([5, 5, 5, 5], 0),
+            ([0, 0, 0, 255], 3),
+        ] {
+            let (proof, output) = prover_index
+                .prove::<BaseSponge, ScalarSponge>((), logits.map(Fp::from), debug)
+                .unwrap();
+            assert_eq!(*output, Fp::from(class));
+            verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+        }
+
+        // the logits must fit in the width
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>((), [1u64, 256, 0, 0].map(Fp::from), debug)
+            .is_err());
+    }
+}
@@ -0,0 +1,157 @@
+//! Gadgets to decompose values into bits, and to pack bits back into values.
+//!
+//! Only the decomposition creates constraints (see [Boolean::unpack]):
+//! packing bits, or splitting them into limbs, is a linear combination of the bits,
+//! so comparisons, shifts and hash gadgets can decompose a value once and repack it freely.
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+
+use crate::snarky::{
+    boolean::Boolean,
+    prelude::{FieldVar, RunState, SnarkyResult},
+};
+
+/// The order of a sequence of bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    /// The least significant bit first.
+    Little,
+    /// The most significant bit first.
+    Big,
+}
+
+/// Decomposes a value into `width` bits in the given order,
+/// and constrains the value to fit in `width` bits.
+///
+/// # Panics
+///
+/// Will panic if `width` is not smaller than the size of the field,
+/// as the decomposition would not be unique.
+pub fn to_bits<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    value: &FieldVar<F>,
+    width: usize,
+    endianness: Endianness,
+) -> SnarkyResult<Vec<Boolean<F>>> {
+    assert!(
+        width < F::size_in_bits(),
+        "a decomposition in {width} bits is not unique"
+    );
+
+    let mut bits = Boolean::unpack(sys, loc, value, width)?;
+    if endianness == Endianness::Big {
+        bits.reverse();
+    }
+    Ok(bits)
+}
+
+/// Packs bits given in the given order into a value.
+/// This does not create any constraint.
+pub fn from_bits<F: PrimeField>(bits: &[Boolean<F>], endianness: Endianness) -> FieldVar<F> {
+    let mut power = F::one();
+    let mut terms = Vec::with_capacity(bits.len());
+    let mut push = |bit: &Boolean<F>| {
+        terms.push((power, bit.to_field_var()));
+        power.double_in_place();
+    };
+    match endianness {
+        Endianness::Little => bits.iter().for_each(&mut push),
+        Endianness::Big => bits.iter().rev().for_each(&mut push),
+    }
+    FieldVar::linear_combination(&terms)
+}
+
+/// Packs bits given in the given order into limbs of `limb_width` bits,
+/// returned in the same order as the bits.
+/// If the number of bits is not a multiple of `limb_width`, the most significant limb is shorter.
+/// This does not create any constraint.
+///
+/// # Panics
+///
+/// Will panic if `limb_width` is zero.
+pub fn pack<F: PrimeField>(
+    bits: &[Boolean<F>],
+    limb_width: usize,
+    endianness: Endianness,
+) -> Vec<FieldVar<F>> {
+    assert!(limb_width > 0, "limbs must have at least one bit");
+
+    let mut little = bits.to_vec();
+    if endianness == Endianness::Big {
+        little.reverse();
+    }
+
+    let mut limbs: Vec<_> = little
+        .chunks(limb_width)
+        .map(|limb| from_bits(limb, Endianness::Little))
+        .collect();
+    if endianness == Endianness::Big {
+        limbs.reverse();
+    }
+    limbs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Decomposes the private input in 5 big-endian bits,
+    /// and outputs its most significant bit, its recomposition, and its limbs of 2 bits.
+    struct TestCircuit;
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = Fp;
+        type PublicInput = ();
+        type PublicOutput = ((Boolean<Fp>, FieldVar<Fp>), [FieldVar<Fp>; 3]);
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let x: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+
+            let bits = to_bits(sys, loc!(), &x, 5, Endianness::Big)?;
+            let limbs = pack(&bits, 2, Endianness::Big).try_into().unwrap();
+            Ok(((bits[0].clone(), from_bits(&bits, Endianness::Big)), limbs))
+        }
+    }
+
+    #[test]
+    fn snarky_bits() {
+        let (mut prover_index, verifier_index) = TestCircuit.compile_to_indexes().unwrap();
+
+        // 22 = 0b1_01_10
+        let debug = true;
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), Fp::from(22u64), debug)
+            .unwrap();
+        let ((msb, value), limbs) = *output;
+        assert!(msb);
+        assert_eq!(value, Fp::from(22u64));
+        assert_eq!(limbs, [Fp::from(1u64), Fp::from(1u64), Fp::from(2u64)]);
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+
+        // the value must fit in the width
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>((), Fp::from(32u64), debug)
+            .is_err());
+    }
+}
@@ -6,7 +6,7 @@ use crate::snarky::{
     constraint_system::BasicSnarkyConstraint, cvar::FieldVar, runner::RunState,
     snarky_type::SnarkyType,
 };
-use ark_ff::PrimeField;
+use ark_ff::{BigInteger, PrimeField};
 
 use super::{errors::SnarkyResult, runner::Constraint};
 
@@ -90,6 +90,39 @@ where
         self.0.clone()
     }
 
+    /// Decomposes a value into `bits` booleans, least significant first,
+    /// and constrains the value to be equal to their recomposition.
+    /// The decomposition is unique as long as `bits` is smaller than the size of the field.
+    pub fn unpack(
+        cs: &mut RunState<F>,
+        loc: Cow<'static, str>,
+        value: &FieldVar<F>,
+        bits: usize,
+    ) -> SnarkyResult<Vec<Self>> {
+        let mut res = Vec::with_capacity(bits);
+        for i in 0..bits {
+            let value = value.clone();
+            let bit: Self = cs.compute(loc.clone(), move |env| {
+                env.read_var(&value).into_repr().to_bits_le()[i]
+            })?;
+            res.push(bit);
+        }
+
+        let terms: Vec<_> = res
+            .iter()
+            .enumerate()
+            .map(|(i, bit)| (F::from(2u64).pow([i as u64]), bit.0.clone()))
+            .collect();
+        cs.assert_eq(
+            Some("bool.unpack".into()),
+            loc,
+            FieldVar::linear_combination(&terms),
+            value.clone(),
+        )?;
+
+        Ok(res)
+    }
+
     pub fn not(&self) -> Self {
         Self(Self::true_().0 - &self.0)
     }
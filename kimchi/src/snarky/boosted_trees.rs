@@ -0,0 +1,446 @@
+//! A gadget proving the inference of gradient-boosted trees, loaded from an XGBoost JSON dump.
+//!
+//! The prediction of a boosted model is the sum of the leaves its trees predict (the margin),
+//! plus a base margin, followed by a sigmoid for binary classification.
+//! The trees are private, and bound to a public commitment as a [RandomForest] aggregated by [Aggregation::Average],
+//! whose sum of leaves is the margin: see [crate::snarky::decision_tree].
+//!
+//! XGBoost trees are not complete, and go left if a feature is below the threshold:
+//! [BoostedTrees::from_xgboost_dump] pads each tree to the depth of the deepest one,
+//! a leaf above that depth becoming a subtree whose leaves all repeat it.
+//! Features, thresholds and leaves are quantized to fixed point, at `2^scale_bits`:
+//! thresholds are rounded up, so inputs on the fixed-point grid take the same path as in XGBoost.
+//! Features are signed, and shifted by `2^(width - 1)` to be compared as unsigned values of `width` bits.
+//! Missing values are not supported.
+//!
+//! With no lookups in snarky, the sigmoid selects its output among the [SIGMOID_TABLE_BITS]-bit table
+//! of its values over `[-SIGMOID_RANGE, SIGMOID_RANGE)`, the margins beyond being clamped.
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+use mina_poseidon::poseidon::ArithmeticSpongeParams;
+use serde::Deserialize;
+
+use crate::{
+    error::XgboostError,
+    snarky::{
+        bits::{to_bits, Endianness},
+        decision_tree::{
+            predict_forest, select, Aggregation, DecisionTree, DecisionTreeVar, RandomForest,
+        },
+        prelude::{FieldVar, RunState, SnarkyResult},
+        shift::{to_fixed, to_signed},
+        statistics::at_least,
+    },
+};
+
+/// The number of bits of the integer part of margins: margins are signed values of `scale_bits + MARGIN_INTEGER_BITS` bits.
+pub const MARGIN_INTEGER_BITS: usize = 16;
+
+/// The margins beyond `±SIGMOID_RANGE` are clamped by the sigmoid.
+pub const SIGMOID_RANGE: u64 = 8;
+
+/// The number of bits of the index of the sigmoid table, whose entries are the sigmoid of the middle of their interval.
+pub const SIGMOID_TABLE_BITS: usize = 8;
+
+/// How the margin of a model is turned into its prediction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Objective {
+    /// The margin, as for `reg:squarederror`.
+    Regression,
+    /// The sigmoid of the margin, as for `binary:logistic`.
+    BinaryLogistic,
+}
+
+/// A node of a tree dumped by XGBoost with `dump_model(path, dump_format="json")`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DumpNode {
+    Split {
+        nodeid: usize,
+        split: String,
+        split_condition: f64,
+        yes: usize,
+        no: usize,
+        children: Vec<DumpNode>,
+    },
+    Leaf {
+        nodeid: usize,
+        leaf: f64,
+    },
+}
+
+impl DumpNode {
+    fn id(&self) -> usize {
+        match self {
+            DumpNode::Split { nodeid, .. } | DumpNode::Leaf { nodeid, .. } => *nodeid,
+        }
+    }
+
+    fn depth(&self) -> usize {
+        match self {
+            DumpNode::Split { children, .. } => {
+                1 + children.iter().map(DumpNode::depth).max().unwrap_or(0)
+            }
+            DumpNode::Leaf { .. } => 0,
+        }
+    }
+}
+
+/// Gradient-boosted trees, out of circuit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoostedTrees<F> {
+    /// The trees, padded to the same depth, with fixed-point leaves.
+    pub trees: Vec<DecisionTree<F>>,
+    /// The base margin, in fixed point.
+    pub base_margin: F,
+    pub objective: Objective,
+    /// The fixed-point scale of features, thresholds, leaves and predictions is `2^scale_bits`.
+    pub scale_bits: usize,
+    /// The number of bits of the shifted features.
+    pub width: usize,
+}
+
+/// The entries of the sigmoid table, at `2^scale_bits`.
+fn sigmoid_table<F: PrimeField>(scale_bits: usize) -> Vec<F> {
+    let step = 2.0 * SIGMOID_RANGE as f64 / (1u64 << SIGMOID_TABLE_BITS) as f64;
+    (0..1u64 << SIGMOID_TABLE_BITS)
+        .map(|k| {
+            let x = -(SIGMOID_RANGE as f64) + (k as f64 + 0.5) * step;
+            let y = (1u64 << scale_bits) as f64 / (1.0 + (-x).exp());
+            F::from(y.round() as u64)
+        })
+        .collect()
+}
+
+impl<F: PrimeField> BoostedTrees<F> {
+    /// Loads the trees of an XGBoost JSON dump (an array of trees), whose features are named `f0`, `f1`, ...
+    /// The base margin is the `base_score` of the model in margin space (0 for a `binary:logistic` model of base score 0.5).
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the dump is malformed, if it names a feature otherwise,
+    /// or if the scale does not leave room for the margins or the sigmoid table.
+    pub fn from_xgboost_dump(
+        json: &str,
+        base_margin: f64,
+        objective: Objective,
+        scale_bits: usize,
+        width: usize,
+    ) -> Result<Self, XgboostError> {
+        if scale_bits < SIGMOID_TABLE_BITS - 4 || scale_bits + MARGIN_INTEGER_BITS >= 64 {
+            return Err(XgboostError::Quantization(format!(
+                "a scale of 2^{scale_bits} does not fit the sigmoid table and 64-bit margins"
+            )));
+        }
+        if width == 0 || width >= 64 {
+            return Err(XgboostError::Quantization(format!(
+                "the features cannot have {width} bits"
+            )));
+        }
+
+        let dump: Vec<DumpNode> =
+            serde_json::from_str(json).map_err(|e| XgboostError::Json(e.to_string()))?;
+        let depth = dump.iter().map(DumpNode::depth).max().unwrap_or(0);
+
+        let mut trees = Vec::with_capacity(dump.len());
+        for (t, root) in dump.iter().enumerate() {
+            let mut tree = DecisionTree {
+                features: vec![0; (1 << depth) - 1],
+                thresholds: vec![0; (1 << depth) - 1],
+                leaves: vec![F::zero(); 1 << depth],
+            };
+            Self::pad(&mut tree, t, root, 0, scale_bits, width)?;
+            trees.push(tree);
+        }
+
+        Ok(Self {
+            trees,
+            base_margin: to_fixed(base_margin, scale_bits),
+            objective,
+            scale_bits,
+            width,
+        })
+    }
+
+    /// Lays out the subtree of `node` at the breadth-first `index` of a complete tree.
+    fn pad(
+        tree: &mut DecisionTree<F>,
+        t: usize,
+        node: &DumpNode,
+        index: usize,
+        scale_bits: usize,
+        width: usize,
+    ) -> Result<(), XgboostError> {
+        let internal = tree.features.len();
+        match node {
+            DumpNode::Leaf { leaf, .. } if index >= internal => {
+                tree.leaves[index - internal] = to_fixed(*leaf, scale_bits);
+            }
+            // a leaf above the depth compares the first feature with 0, and repeats itself on both sides
+            DumpNode::Leaf { .. } => {
+                Self::pad(tree, t, node, 2 * index + 1, scale_bits, width)?;
+                Self::pad(tree, t, node, 2 * index + 2, scale_bits, width)?;
+            }
+            DumpNode::Split {
+                nodeid,
+                split,
+                split_condition,
+                yes,
+                no,
+                children,
+            } => {
+                let feature = split
+                    .strip_prefix('f')
+                    .and_then(|index| index.parse().ok())
+                    .ok_or_else(|| XgboostError::UnknownFeature(split.clone()))?;
+                // x < t iff x >= ceil(t), on the grid
+                let half = 1i128 << (width - 1);
+                let threshold = (split_condition * (1u64 << scale_bits) as f64).ceil() as i128;
+                tree.features[index] = feature;
+                tree.thresholds[index] = (threshold + half).clamp(0, 2 * half) as u64;
+
+                let child = |id: usize| {
+                    children
+                        .iter()
+                        .find(|child| child.id() == id)
+                        .ok_or(XgboostError::MissingChild(t, *nodeid, id))
+                };
+                // XGBoost goes left (yes) below the threshold, as the tree
+                Self::pad(tree, t, child(*yes)?, 2 * index + 1, scale_bits, width)?;
+                Self::pad(tree, t, child(*no)?, 2 * index + 2, scale_bits, width)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of bits of the signed margins.
+    pub fn margin_bits(&self) -> usize {
+        self.scale_bits + MARGIN_INTEGER_BITS
+    }
+
+    /// Quantizes an input, rounding each feature to the fixed-point grid and shifting it by `2^(width - 1)`.
+    pub fn quantize(&self, x: &[f64]) -> Vec<u64> {
+        let half = 1i128 << (self.width - 1);
+        x.iter()
+            .map(|value| {
+                let value = (value * (1u64 << self.scale_bits) as f64).round() as i128;
+                (value + half).clamp(0, 2 * half - 1) as u64
+            })
+            .collect()
+    }
+
+    /// The trees, as a forest whose aggregation is the sum of their leaves.
+    pub fn forest(&self) -> RandomForest<F> {
+        RandomForest {
+            trees: self.trees.clone(),
+            aggregation: Aggregation::Average,
+        }
+    }
+
+    /// The commitment to the trees (see [RandomForest::commitment]).
+    pub fn commitment(&self, params: &ArithmeticSpongeParams<F>) -> F {
+        self.forest().commitment(params)
+    }
+
+    /// The margin of a quantized input.
+    pub fn margin(&self, x: &[u64]) -> F {
+        self.forest().predict(x) + self.base_margin
+    }
+
+    /// Predicts the output of a quantized input, at `2^scale_bits`.
+    pub fn predict(&self, x: &[u64]) -> F {
+        let margin = self.margin(x);
+        match self.objective {
+            Objective::Regression => margin,
+            Objective::BinaryLogistic => {
+                let range = i128::from(SIGMOID_RANGE) << self.scale_bits;
+                let shifted = to_signed(margin, self.margin_bits()) + range;
+                let clamped = shifted.clamp(0, 2 * range - 1);
+                let index = clamped >> (self.scale_bits + 4 - SIGMOID_TABLE_BITS);
+                sigmoid_table(self.scale_bits)[index as usize]
+            }
+        }
+    }
+}
+
+/// Approximates the sigmoid of a signed fixed-point margin at `2^scale_bits`, of `bits` bits sign included,
+/// with the table of [sigmoid_table].
+fn sigmoid<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: &Cow<'static, str>,
+    margin: &FieldVar<F>,
+    scale_bits: usize,
+    bits: usize,
+) -> SnarkyResult<FieldVar<F>> {
+    let half = 1u64 << (bits - 1);
+    let range = SIGMOID_RANGE << scale_bits;
+    let range_bits = scale_bits + 4;
+
+    // the margin, shifted to an unsigned value of bits bits
+    let shifted = margin + FieldVar::constant(F::from(half));
+    to_bits(sys, loc.clone(), &shifted, bits, Endianness::Little)?;
+
+    // clamped to [-range, range)
+    let below = at_least(sys, loc.clone(), &shifted, bits, half - range)?.not();
+    let above = at_least(sys, loc.clone(), &shifted, bits, half + range)?;
+    let offset = shifted - FieldVar::constant(F::from(half - range));
+    let clamped = sys.if_(loc.clone(), below, FieldVar::zero(), offset)?;
+    let last = FieldVar::constant(F::from(2 * range - 1));
+    let clamped = sys.if_(loc.clone(), above, last, clamped)?;
+
+    let index = to_bits(sys, loc.clone(), &clamped, range_bits, Endianness::Big)?;
+    let table: Vec<_> = sigmoid_table(scale_bits)
+        .into_iter()
+        .map(FieldVar::constant)
+        .collect();
+    select(sys, loc, &table, &index[..SIGMOID_TABLE_BITS])
+}
+
+/// Predicts the output of a boosted model on the quantized input `x`, at `2^scale_bits`,
+/// its private trees being constrained to match `commitment` (see [BoostedTrees::commitment]).
+///
+/// The base margin, the objective and the quantization are those of `model`, whose trees are not used.
+pub fn predict_boosted<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    commitment: &FieldVar<F>,
+    trees: &[DecisionTreeVar<F>],
+    x: &[FieldVar<F>],
+    model: &BoostedTrees<F>,
+) -> SnarkyResult<FieldVar<F>> {
+    let sum = predict_forest(
+        sys,
+        loc.clone(),
+        commitment,
+        trees,
+        x,
+        model.width,
+        Aggregation::Average,
+    )?;
+    let margin = sum + FieldVar::constant(model.base_margin);
+    match model.objective {
+        Objective::Regression => Ok(margin),
+        Objective::BinaryLogistic => {
+            sigmoid(sys, &loc, &margin, model.scale_bits, model.margin_bits())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{curve::KimchiCurve, loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Two trees of depths 2 and 1, over 2 features.
+    const DUMP: &str = r#"[
+        { "nodeid": 0, "depth": 0, "split": "f0", "split_condition": 0.5, "yes": 1, "no": 2, "missing": 1, "children": [
+            { "nodeid": 1, "depth": 1, "split": "f1", "split_condition": -1.25, "yes": 3, "no": 4, "missing": 3, "children": [
+                { "nodeid": 3, "leaf": -0.4 },
+                { "nodeid": 4, "leaf": 0.1 }
+            ]},
+            { "nodeid": 2, "leaf": 0.6 }
+        ]},
+        { "nodeid": 0, "depth": 0, "split": "f1", "split_condition": 2, "yes": 1, "no": 2, "missing": 1, "children": [
+            { "nodeid": 1, "leaf": 0.3 },
+            { "nodeid": 2, "leaf": -0.2 }
+        ]}
+    ]"#;
+
+    /// Predicts the output of a private input with the boosted trees committed to by the public input.
+    struct TestCircuit {
+        model: BoostedTrees<Fp>,
+    }
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = (Vec<DecisionTree<Fp>>, Vec<u64>);
+        type PublicInput = FieldVar<Fp>;
+        type PublicOutput = FieldVar<Fp>;
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            commitment: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let mut trees = vec![];
+            for (t, shape) in self.model.trees.iter().enumerate() {
+                let tree = move || &private.unwrap().0[t];
+                let mut var = DecisionTreeVar {
+                    features: vec![],
+                    thresholds: vec![],
+                    leaves: vec![],
+                };
+                for i in 0..shape.features.len() {
+                    var.features
+                        .push(sys.compute(loc!(), |_| Fp::from(tree().features[i] as u64))?);
+                    var.thresholds
+                        .push(sys.compute(loc!(), |_| Fp::from(tree().thresholds[i]))?);
+                }
+                for i in 0..shape.leaves.len() {
+                    var.leaves.push(sys.compute(loc!(), |_| tree().leaves[i])?);
+                }
+                trees.push(var);
+            }
+            let x: [FieldVar<Fp>; 2] = sys.compute(loc!(), |_| {
+                let x = &private.unwrap().1;
+                [Fp::from(x[0]), Fp::from(x[1])]
+            })?;
+
+            predict_boosted(sys, loc!(), &commitment, &trees, &x, &self.model)
+        }
+    }
+
+    #[test]
+    fn snarky_boosted_trees() {
+        let model =
+            BoostedTrees::<Fp>::from_xgboost_dump(DUMP, 0.0, Objective::BinaryLogistic, 8, 16)
+                .unwrap();
+        assert_eq!(model.trees[1].leaves.len(), 4);
+
+        // x[0] < 0.5 and x[1] >= -1.25 gives 0.1, then x[1] < 2 gives 0.3
+        let x = model.quantize(&[0.25, 1.0]);
+        assert_eq!(model.margin(&x), Fp::from(26u64 + 77));
+        // on the grid, a feature equal to the threshold goes right
+        let on_threshold = model.quantize(&[0.5, 2.0]);
+        assert_eq!(
+            model.margin(&on_threshold),
+            to_fixed::<Fp>(0.6, 8) + to_fixed::<Fp>(-0.2, 8)
+        );
+
+        let commitment = model.commitment(Vesta::sponge_params());
+        let circuit = TestCircuit {
+            model: model.clone(),
+        };
+        let (mut prover_index, verifier_index) = circuit.compile_to_indexes().unwrap();
+        let debug = true;
+        for x in [x, on_threshold, model.quantize(&[-3.0, 40.0])] {
+            let (proof, output) = prover_index
+                .prove::<BaseSponge, ScalarSponge>(
+                    commitment,
+                    (model.trees.clone(), x.clone()),
+                    debug,
+                )
+                .unwrap();
+            assert_eq!(*output, model.predict(&x));
+            verifier_index.verify::<BaseSponge, ScalarSponge>(proof, commitment, *output);
+        }
+
+        // the sigmoid of a margin of 0.4 is about 0.6
+        let p = to_signed(model.predict(&model.quantize(&[0.5, 2.0])), 24);
+        assert!((p - 154).abs() <= 2);
+    }
+}
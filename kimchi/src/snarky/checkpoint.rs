@@ -0,0 +1,262 @@
+//! Commitments to the activations of each layer, exposed by a debug variant of a circuit.
+//!
+//! When the output of a proof does not match the output expected from the model,
+//! the checkpoints tell which layer diverged first:
+//! the commitments of two runs (or of a run and of the model evaluated out of circuit)
+//! are equal up to the first diverging layer (see [first_divergence]).
+//! A circuit records its checkpoints once, and is compiled either [WithCheckpoints] for debugging,
+//! or [WithoutCheckpoints], in which case recording a checkpoint costs nothing.
+//!
+//! The commitments are not hiding, so the debug variant reveals whether the activations take given values.
+
+use std::borrow::Cow;
+
+use ark_ec::AffineCurve;
+use ark_ff::PrimeField;
+use mina_poseidon::poseidon::ArithmeticSpongeParams;
+use poly_commitment::OpenProof;
+
+use crate::{
+    circuits::capabilities::Capabilities,
+    curve::KimchiCurve,
+    snarky::{
+        api::SnarkyCircuit,
+        poseidon::{hash_slice, hash_slice_native},
+        prelude::{FieldVar, RunState, SnarkyResult},
+        snarky_type::SnarkyType,
+    },
+};
+
+type ScalarField<C> = <C as AffineCurve>::ScalarField;
+
+/// Computes the commitment to the activations of a layer, out of circuit.
+/// The commitment is the [hash_slice] of the activations, whose length prefix tells apart layers of different widths.
+pub fn commit_activations<F: PrimeField>(
+    params: &ArithmeticSpongeParams<F>,
+    activations: &[F],
+) -> F {
+    hash_slice_native(params, activations)
+}
+
+/// Returns the first layer whose checkpoints differ, if any.
+pub fn first_divergence<F: PrimeField>(checkpoints: &[F], expected: &[F]) -> Option<usize> {
+    checkpoints
+        .iter()
+        .zip(expected)
+        .position(|(checkpoint, expected)| checkpoint != expected)
+}
+
+/// The checkpoints recorded by a circuit, if they are enabled.
+pub struct Checkpoints<F>
+where
+    F: PrimeField,
+{
+    commitments: Option<Vec<FieldVar<F>>>,
+}
+
+impl<F> Checkpoints<F>
+where
+    F: PrimeField,
+{
+    /// Records the activations of the next layer.
+    pub fn record(
+        &mut self,
+        sys: &mut RunState<F>,
+        loc: Cow<'static, str>,
+        activations: &[FieldVar<F>],
+    ) {
+        if let Some(commitments) = &mut self.commitments {
+            commitments.push(hash_slice(sys, loc, activations));
+        }
+    }
+}
+
+/// A circuit whose layers can be checkpointed.
+/// It is turned into a [SnarkyCircuit] by [WithCheckpoints] or [WithoutCheckpoints].
+pub trait CheckpointedCircuit: Sized {
+    type Curve: KimchiCurve;
+    type Proof: OpenProof<Self::Curve>;
+
+    type PrivateInput;
+    type PublicInput: SnarkyType<ScalarField<Self::Curve>>;
+    type PublicOutput: SnarkyType<ScalarField<Self::Curve>>;
+
+    /// The circuit, calling [Checkpoints::record] with the activations of each layer.
+    fn circuit(
+        &self,
+        sys: &mut RunState<ScalarField<Self::Curve>>,
+        public_input: Self::PublicInput,
+        private_input: Option<&Self::PrivateInput>,
+        checkpoints: &mut Checkpoints<ScalarField<Self::Curve>>,
+    ) -> SnarkyResult<Self::PublicOutput>;
+
+    /// See [SnarkyCircuit::capabilities].
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::kimchi()
+    }
+}
+
+/// The debug variant of a circuit of `N` layers,
+/// outputting the commitments to the activations of each layer after the output of the circuit.
+///
+/// # Panics
+///
+/// The circuit panics if it does not record exactly `N` checkpoints.
+pub struct WithCheckpoints<C, const N: usize>(pub C);
+
+impl<C: CheckpointedCircuit, const N: usize> SnarkyCircuit for WithCheckpoints<C, N> {
+    type Curve = C::Curve;
+    type Proof = C::Proof;
+
+    type PrivateInput = C::PrivateInput;
+    type PublicInput = C::PublicInput;
+    type PublicOutput = (C::PublicOutput, [FieldVar<ScalarField<C::Curve>>; N]);
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<ScalarField<Self::Curve>>,
+        public_input: Self::PublicInput,
+        private_input: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let mut checkpoints = Checkpoints {
+            commitments: Some(Vec::with_capacity(N)),
+        };
+        let output = self
+            .0
+            .circuit(sys, public_input, private_input, &mut checkpoints)?;
+
+        let commitments = checkpoints.commitments.unwrap();
+        let recorded = commitments.len();
+        let commitments = commitments.try_into().unwrap_or_else(|_| {
+            panic!("the circuit recorded {recorded} checkpoints instead of {N}")
+        });
+        Ok((output, commitments))
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.0.capabilities()
+    }
+}
+
+/// The release variant of a circuit, without checkpoints.
+pub struct WithoutCheckpoints<C>(pub C);
+
+impl<C: CheckpointedCircuit> SnarkyCircuit for WithoutCheckpoints<C> {
+    type Curve = C::Curve;
+    type Proof = C::Proof;
+
+    type PrivateInput = C::PrivateInput;
+    type PublicInput = C::PublicInput;
+    type PublicOutput = C::PublicOutput;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<ScalarField<Self::Curve>>,
+        public_input: Self::PublicInput,
+        private_input: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let mut checkpoints = Checkpoints { commitments: None };
+        self.0
+            .circuit(sys, public_input, private_input, &mut checkpoints)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.0.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::loc;
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Squares both private inputs, then adds `offset` to the squares.
+    struct TestCircuit {
+        offset: u64,
+    }
+
+    impl CheckpointedCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = [Fp; 2];
+        type PublicInput = ();
+        type PublicOutput = [FieldVar<Fp>; 2];
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+            checkpoints: &mut Checkpoints<Fp>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let x: [FieldVar<Fp>; 2] = sys.compute(loc!(), |_| *private.unwrap())?;
+
+            let squares = [
+                x[0].mul(&x[0], None, loc!(), sys)?,
+                x[1].mul(&x[1], None, loc!(), sys)?,
+            ];
+            checkpoints.record(sys, loc!(), &squares);
+
+            let offset = FieldVar::constant(Fp::from(self.offset));
+            let outputs = squares.map(|square| square + &offset);
+            checkpoints.record(sys, loc!(), &outputs);
+
+            Ok(outputs)
+        }
+    }
+
+    #[test]
+    fn snarky_checkpoints() {
+        let params = Vesta::sponge_params();
+        let x = [Fp::from(3u64), Fp::from(4u64)];
+        let expected = [
+            commit_activations(params, &[Fp::from(9u64), Fp::from(16u64)]),
+            commit_activations(params, &[Fp::from(10u64), Fp::from(17u64)]),
+        ];
+
+        let (mut prover_index, verifier_index) = WithCheckpoints::<_, 2>(TestCircuit { offset: 1 })
+            .compile_to_indexes()
+            .unwrap();
+        let debug = true;
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), x, debug)
+            .unwrap();
+        assert_eq!(output.1, expected);
+        assert_eq!(first_divergence(&output.1, &expected), None);
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+
+        // a bug in the second layer only changes the second checkpoint
+        let (mut prover_index, _) = WithCheckpoints::<_, 2>(TestCircuit { offset: 2 })
+            .compile_to_indexes()
+            .unwrap();
+        let (_, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), x, debug)
+            .unwrap();
+        assert_eq!(first_divergence(&output.1, &expected), Some(1));
+
+        // the release variant outputs the same values
+        let (mut prover_index, _) = WithoutCheckpoints(TestCircuit { offset: 1 })
+            .compile_to_indexes()
+            .unwrap();
+        let (_, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), x, debug)
+            .unwrap();
+        assert_eq!(*output, [Fp::from(10u64), Fp::from(17u64)]);
+
+        // a layer with a trailing zero activation has another commitment
+        assert_ne!(
+            commit_activations(params, &[Fp::from(9u64)]),
+            commit_activations(params, &[Fp::from(9u64), Fp::from(0u64)])
+        );
+    }
+}
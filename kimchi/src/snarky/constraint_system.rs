@@ -7,7 +7,8 @@
 
 use crate::{
     circuits::{
-        gate::{CircuitGate, GateType},
+        gate::{CircuitGate, CurrOrNext, GateType},
+        lookup::lookups::LookupPattern,
         polynomials::{
             generic::GENERIC_COEFFS,
             poseidon::{ROUNDS_PER_HASH, ROUNDS_PER_ROW, SPONGE_WIDTH},
@@ -23,7 +24,7 @@ use std::{
     collections::{HashMap, HashSet},
 };
 
-use super::{errors::SnarkyRuntimeError, union_find::DisjointSet};
+use super::{errors::SnarkyRuntimeError, union_find::DisjointSet, witness_store::WitnessMatrix};
 
 /** A row indexing in a constraint system.
     Either a public input row, or a non-public input row that starts at index 0.
@@ -416,6 +417,32 @@ impl<Field: PrimeField> SnarkyConstraintSystem<Field> {
     pub fn compute_witness<FUNC>(&mut self, external_values: FUNC) -> [Vec<Field>; COLUMNS]
     where
         FUNC: Fn(usize) -> Field,
+    {
+        let num_rows = self.num_witness_rows();
+        let mut res: [_; COLUMNS] = std::array::from_fn(|_| vec![Field::zero(); num_rows]);
+        self.compute_witness_into(external_values, &mut res);
+        res
+    }
+
+    /// The number of rows of the witness (see [Self::compute_witness]).
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `public_input_size` is unknown.
+    pub fn num_witness_rows(&mut self) -> usize {
+        // make sure it's finalized
+        self.finalize();
+
+        self.public_input_size.unwrap() + self.next_row
+    }
+
+    /// Same as [Self::compute_witness], but writes the witness in a zeroed matrix of [Self::num_witness_rows] rows.
+    pub fn compute_witness_into<FUNC>(
+        &mut self,
+        external_values: FUNC,
+        res: &mut impl WitnessMatrix<Field>,
+    ) where
+        FUNC: Fn(usize) -> Field,
     {
         // make sure it's finalized
         self.finalize();
@@ -423,12 +450,10 @@ impl<Field: PrimeField> SnarkyConstraintSystem<Field> {
         // init execution trace table
         let mut internal_values = HashMap::new();
         let public_input_size = self.public_input_size.unwrap();
-        let num_rows = public_input_size + self.next_row;
-        let mut res: [_; COLUMNS] = std::array::from_fn(|_| vec![Field::zero(); num_rows]);
 
         // obtain public input from closure
         for i in 0..public_input_size {
-            res[0][i] = external_values(i);
+            res.set(0, i, external_values(i));
         }
 
         // compute rest of execution trace table
@@ -440,7 +465,7 @@ impl<Field: PrimeField> SnarkyConstraintSystem<Field> {
                     None => (),
 
                     // use closure for external values
-                    Some(V::External(var)) => res[col_idx][row_idx] = external_values(*var),
+                    Some(V::External(var)) => res.set(col_idx, row_idx, external_values(*var)),
 
                     // for internal values, compute the linear combination
                     Some(V::Internal(var)) => {
@@ -462,14 +487,12 @@ impl<Field: PrimeField> SnarkyConstraintSystem<Field> {
                                 acc + (*s * x)
                             })
                         };
-                        res[col_idx][row_idx] = value;
+                        res.set(col_idx, row_idx, value);
                         internal_values.insert(var, value);
                     }
                 }
             }
         }
-
-        res
     }
 
     fn union_find(&mut self, value: V) {
@@ -585,6 +608,24 @@ impl<Field: PrimeField> SnarkyConstraintSystem<Field> {
         self.rows.len()
     }
 
+    /// Returns the number of lookups made by the gates created so far,
+    /// each gate making the lookups of its [LookupPattern] on its row and the next.
+    pub fn num_lookups(&self) -> usize {
+        let kinds: Vec<GateType> = match &self.gates {
+            Circuit::Unfinalized(gates) => gates.iter().map(|gate| gate.kind).collect(),
+            Circuit::Compiled(_, gates) => gates.iter().map(|gate| gate.typ).collect(),
+        };
+        kinds
+            .into_iter()
+            .flat_map(|kind| {
+                [CurrOrNext::Curr, CurrOrNext::Next]
+                    .into_iter()
+                    .filter_map(move |row| LookupPattern::from_gate(kind, row))
+            })
+            .map(|pattern| pattern.lookups::<Field>().len())
+            .sum()
+    }
+
     /// Fill the `gate` values(input and output), and finalize the `circuit`.
     ///
     /// # Panics
@@ -1688,6 +1729,10 @@ impl<Field: PrimeField> SnarkyConstraintSystem<Field> {
     pub(crate) fn sponge_params(&self) -> mina_poseidon::poseidon::ArithmeticSpongeParams<Field> {
         self.constants.poseidon.clone()
     }
+
+    pub(crate) fn constants(&self) -> &Constants<Field> {
+        &self.constants
+    }
 }
 
 enum ConstantOrVar {
@@ -0,0 +1,316 @@
+//! Lowering of [RegisteredGate]s to snarky constraints.
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+
+use crate::{
+    circuits::{
+        argument::ArgumentWitness,
+        custom_gate::{CircuitExpr, RegisteredGate},
+        polynomial::COLUMNS,
+    },
+    snarky::errors::SnarkyCompilationError,
+    FieldVar, RunState, SnarkyResult,
+};
+
+/// Converts a constraint of a custom gate into a circuit variable.
+fn lower<F: PrimeField>(
+    runner: &mut RunState<F>,
+    loc: &Cow<'static, str>,
+    expr: &CircuitExpr<F>,
+    witness: &ArgumentWitness<FieldVar<F>>,
+    coeffs: &[F],
+) -> SnarkyResult<FieldVar<F>> {
+    let var = match expr {
+        CircuitExpr::Literal(x) => FieldVar::constant(*x),
+        CircuitExpr::Cell(row, col) => witness[(*row, *col)].clone(),
+        CircuitExpr::Coeff(i) => {
+            FieldVar::constant(coeffs.get(*i).copied().unwrap_or_else(F::zero))
+        }
+        CircuitExpr::Add(x, y) => {
+            lower(runner, loc, x, witness, coeffs)? + lower(runner, loc, y, witness, coeffs)?
+        }
+        CircuitExpr::Sub(x, y) => {
+            lower(runner, loc, x, witness, coeffs)? - lower(runner, loc, y, witness, coeffs)?
+        }
+        CircuitExpr::Mul(x, y) => {
+            let x = lower(runner, loc, x, witness, coeffs)?;
+            let y = lower(runner, loc, y, witness, coeffs)?;
+            x.mul(&y, None, loc.clone(), runner)?
+        }
+        CircuitExpr::Neg(x) => -lower(runner, loc, x, witness, coeffs)?,
+    };
+    Ok(var)
+}
+
+/// Adds a custom gate to the circuit, and returns the cells of its two rows.
+/// The `inputs` are constrained to be equal to the first cells of the current row,
+/// and those past the [COLUMNS] cells of the row spill into the first cells of the next row.
+/// The constraints of a gate only read its two rows, so inputs cannot spill any further:
+/// a gate with more than `2 * COLUMNS` inputs must be split into several gates by hand.
+///
+/// # Errors
+///
+/// Will give error [SnarkyCompilationError::GateTooWide] if there are more inputs than the cells of the two rows.
+pub fn custom_gate<F: PrimeField>(
+    runner: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    gate: &RegisteredGate<F>,
+    inputs: &[FieldVar<F>],
+    coeffs: &[F],
+) -> SnarkyResult<ArgumentWitness<FieldVar<F>>> {
+    if inputs.len() > 2 * COLUMNS {
+        return Err(
+            runner.compilation_error(SnarkyCompilationError::GateTooWide(
+                gate.name.to_string(),
+                inputs.len(),
+                2 * COLUMNS,
+            )),
+        );
+    }
+    let label: Option<Cow<'static, str>> = Some(gate.name.into());
+    let fill_witness = gate.fill_witness;
+
+    let (curr, next): ([FieldVar<F>; COLUMNS], [FieldVar<F>; COLUMNS]) =
+        runner.compute(loc.clone(), |env| {
+            let inputs: Vec<F> = inputs.iter().map(|input| env.read_var(input)).collect();
+            let witness = fill_witness(&inputs, coeffs);
+            (witness.curr, witness.next)
+        })?;
+
+    for (input, cell) in inputs.iter().zip(curr.iter().chain(&next)) {
+        runner.assert_eq(label.clone(), loc.clone(), input.clone(), cell.clone())?;
+    }
+
+    let witness = ArgumentWitness { curr, next };
+    for constraint in &gate.constraints {
+        let res = lower(runner, &loc, constraint, &witness, coeffs)?;
+        runner.assert_eq(label.clone(), loc.clone(), res, FieldVar::zero())?;
+    }
+
+    Ok(witness)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        circuits::{
+            argument::{ArgumentEnv, ArgumentWitness},
+            custom_gate::{CustomGate, CustomGateRegistry},
+            expr::{constraints::ExprOps, Cache},
+            polynomial::COLUMNS,
+        },
+        error::CustomGateError,
+        loc,
+        snarky::{
+            api::SnarkyCircuit,
+            errors::{SnarkyCompilationError, SnarkyError},
+        },
+        FieldVar, RunState, SnarkyResult,
+    };
+    use ark_ff::PrimeField;
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Computes `w[2] = w[0]^2 + c[0] * w[1]`.
+    struct SquareAdd;
+
+    impl<F: PrimeField> CustomGate<F> for SquareAdd {
+        const NAME: &'static str = "square_add";
+        const CONSTRAINTS: u32 = 1;
+
+        fn constraint_checks<T: ExprOps<F>>(env: &ArgumentEnv<F, T>, _: &mut Cache) -> Vec<T> {
+            let x = env.witness_curr(0);
+            let y = env.witness_curr(1);
+            let out = env.witness_curr(2);
+            vec![out - (x.square() + env.coeff(0) * y)]
+        }
+
+        fn fill_witness(inputs: &[F], coeffs: &[F]) -> ArgumentWitness<F> {
+            let mut curr = [F::zero(); COLUMNS];
+            curr[0] = inputs[0];
+            curr[1] = inputs[1];
+            curr[2] = inputs[0] * inputs[0] + coeffs[0] * inputs[1];
+            ArgumentWitness {
+                curr,
+                next: [F::zero(); COLUMNS],
+            }
+        }
+    }
+
+    /// Sums 20 inputs, spilling into the next row, into the last cell of the next row.
+    struct WideSum;
+
+    impl<F: PrimeField> CustomGate<F> for WideSum {
+        const NAME: &'static str = "wide_sum";
+        const CONSTRAINTS: u32 = 1;
+
+        fn constraint_checks<T: ExprOps<F>>(env: &ArgumentEnv<F, T>, _: &mut Cache) -> Vec<T> {
+            let mut sum = env.witness_next(0);
+            for col in 1..5 {
+                sum = sum + env.witness_next(col);
+            }
+            for col in 0..COLUMNS {
+                sum = sum + env.witness_curr(col);
+            }
+            vec![env.witness_next(COLUMNS - 1) - sum]
+        }
+
+        fn fill_witness(inputs: &[F], _: &[F]) -> ArgumentWitness<F> {
+            let mut curr = [F::zero(); COLUMNS];
+            let mut next = [F::zero(); COLUMNS];
+            curr.copy_from_slice(&inputs[..COLUMNS]);
+            next[..5].copy_from_slice(&inputs[COLUMNS..]);
+            next[COLUMNS - 1] = inputs.iter().copied().sum();
+            ArgumentWitness { curr, next }
+        }
+    }
+
+    struct TestCircuit {
+        registry: CustomGateRegistry<Fp>,
+    }
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = (Fp, Fp);
+        type PublicInput = ();
+        type PublicOutput = FieldVar<Fp>;
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _public: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let x: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap().0)?;
+            let y: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap().1)?;
+
+            let gate = self.registry.get("square_add").unwrap();
+            let cells = sys.custom_gate(loc!(), gate, &[x, y], &[Fp::from(3u64)])?;
+
+            Ok(cells.curr[2].clone())
+        }
+    }
+
+    /// Spills the inputs of [WideSum], each input being the sum of its index and a private value.
+    struct WideCircuit {
+        registry: CustomGateRegistry<Fp>,
+        inputs: usize,
+    }
+
+    impl SnarkyCircuit for WideCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = Fp;
+        type PublicInput = ();
+        type PublicOutput = FieldVar<Fp>;
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _public: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let x: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+            let inputs: Vec<_> = (0..self.inputs as u64)
+                .map(|i| &x + FieldVar::constant(Fp::from(i)))
+                .collect();
+
+            let gate = self.registry.get("wide_sum").unwrap();
+            let cells = sys.custom_gate(loc!(), gate, &inputs, &[])?;
+
+            Ok(cells.next[COLUMNS - 1].clone())
+        }
+    }
+
+    fn registry() -> CustomGateRegistry<Fp> {
+        let mut registry = CustomGateRegistry::default();
+        registry.register::<SquareAdd>().unwrap();
+        registry.register::<WideSum>().unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_custom_gate_registry() {
+        let mut registry = registry();
+        assert!(matches!(
+            registry.register::<SquareAdd>(),
+            Err(CustomGateError::AlreadyRegistered(_))
+        ));
+        assert!(registry.get("unknown").is_err());
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            vec!["square_add", "wide_sum"]
+        );
+
+        let gate = registry.get("square_add").unwrap();
+        let coeffs = [Fp::from(3u64)];
+        let mut witness = (gate.fill_witness)(&[Fp::from(2u64), Fp::from(5u64)], &coeffs);
+        assert!(gate.check(&witness, &coeffs).is_ok());
+
+        witness.curr[2] += Fp::from(1u64);
+        assert!(matches!(
+            gate.check(&witness, &coeffs),
+            Err(CustomGateError::Unsatisfied(_, 0))
+        ));
+    }
+
+    #[test]
+    fn snarky_custom_gate() {
+        let test_circuit = TestCircuit {
+            registry: registry(),
+        };
+
+        let (mut prover_index, verifier_index) = test_circuit.compile_to_indexes().unwrap();
+
+        let private_input = (Fp::from(2u64), Fp::from(5u64));
+        let debug = true;
+        let (proof, public_output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), private_input, debug)
+            .unwrap();
+        assert_eq!(*public_output, Fp::from(19u64));
+
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *public_output);
+    }
+
+    #[test]
+    fn snarky_custom_gate_spill() {
+        let circuit = WideCircuit {
+            registry: registry(),
+            inputs: 20,
+        };
+        let (mut prover_index, verifier_index) = circuit.compile_to_indexes().unwrap();
+
+        // 20 x + 0 + 1 + ... + 19
+        let debug = true;
+        let (proof, public_output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), Fp::from(1u64), debug)
+            .unwrap();
+        assert_eq!(*public_output, Fp::from(20u64 + 190));
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *public_output);
+
+        // past the two rows of the gate
+        let res = WideCircuit {
+            registry: registry(),
+            inputs: 2 * COLUMNS + 1,
+        }
+        .compile_to_indexes();
+        assert!(matches!(
+            res.map(|_| ()).map_err(|err| err.source),
+            Err(SnarkyError::CompilationError(
+                SnarkyCompilationError::GateTooWide(_, 31, 30)
+            ))
+        ));
+    }
+}
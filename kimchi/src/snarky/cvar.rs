@@ -147,11 +147,8 @@ where
     }
 
     pub fn linear_combination(terms: &[ScaledCVar<F>]) -> Self {
-        let mut res = FieldVar::zero();
-        for (cst, term) in terms {
-            res = res.add(&term.scale(*cst));
-        }
-        res
+        let terms: Vec<_> = terms.iter().map(|(cst, term)| term.scale(*cst)).collect();
+        Self::sum_many(&terms)
     }
 
     pub fn sum(vs: &[&Self]) -> Self {
@@ -159,6 +156,21 @@ where
         Self::linear_combination(&terms)
     }
 
+    /// Sums values with a balanced tree of additions.
+    /// A chain of additions is as deep as the number of values, and is copied by each addition,
+    /// while the tree is only logarithmically deep, which keeps its evaluation cheap for wide layers.
+    /// The terms keep their order, so the constraints created from the sum are the same as with a chain.
+    pub fn sum_many(vs: &[Self]) -> Self {
+        match vs {
+            [] => Self::zero(),
+            [v] => v.clone(),
+            _ => {
+                let (left, right) = vs.split_at(vs.len() / 2);
+                Self::sum_many(left) + Self::sum_many(right)
+            }
+        }
+    }
+
     pub fn mul(
         &self,
         other: &Self,
@@ -191,6 +203,35 @@ where
         Ok(res)
     }
 
+    /// Computes the inner product of `xs` and `ys`.
+    ///
+    /// Products by a constant (for example the weights of a model fixed in the circuit) are scaled for free,
+    /// and a product of two variables takes a single generic gate.
+    /// The products are then summed as one linear combination (see [Self::sum_many]),
+    /// reduced to constraints once when the result is used,
+    /// instead of constraining an accumulator variable for every term.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `xs` and `ys` do not have the same length.
+    pub fn dot_product(
+        xs: &[Self],
+        ys: &[Self],
+        label: Option<Cow<'static, str>>,
+        loc: Cow<'static, str>,
+        cs: &mut RunState<F>,
+    ) -> SnarkyResult<Self> {
+        assert_eq!(xs.len(), ys.len(), "the vectors have different lengths");
+
+        let label = label.or(Some("dot_product".into()));
+        let products = xs
+            .iter()
+            .zip(ys)
+            .map(|(x, y)| x.mul(y, label.clone(), loc.clone(), cs))
+            .collect::<SnarkyResult<Vec<_>>>()?;
+        Ok(Self::sum_many(&products))
+    }
+
     /** [equal_constraints z z_inv r] asserts that
        if z = 0 then r = 1, or
        if z <> 0 then r = 0 and z * z_inv = 1
@@ -0,0 +1,426 @@
+//! A gadget proving the inference of a committed decision tree.
+//!
+//! The tree is complete: its internal nodes, numbered breadth-first from the root,
+//! each compare a feature of the input with a threshold and go right if the feature is at least the threshold,
+//! and its `2^depth` leaves hold the predictions.
+//! The traversal is oblivious: each level selects its node among all the nodes of the level
+//! with a tree of multiplexers driven by the comparisons made so far,
+//! so the circuit only depends on the depth of the tree and the number of features, not on the path taken.
+//! Unlike a dense layer, the cost is in comparisons and selections rather than multiplications.
+//!
+//! The tree is private, and bound to a public commitment (see [DecisionTree::commitment]).
+//! Features and thresholds are unsigned and must fit in the width given to the gadget;
+//! the gadget constrains their differences, and the caller the features of the input.
+//!
+//! A random forest (see [predict_forest]) runs the traversal of each of its trees,
+//! bound together by a single commitment, and aggregates their leaves by averaging or by a majority vote.
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+use mina_poseidon::poseidon::ArithmeticSpongeParams;
+
+use crate::snarky::{
+    bits::{to_bits, Endianness},
+    boolean::Boolean,
+    errors::SnarkyCompilationError,
+    poseidon::{hash_slice, hash_slice_native},
+    prelude::{FieldVar, RunState, SnarkyResult},
+};
+
+/// A complete decision tree, out of circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecisionTree<F> {
+    /// The feature compared by each internal node, breadth-first.
+    pub features: Vec<usize>,
+    /// The threshold of each internal node, breadth-first.
+    pub thresholds: Vec<u64>,
+    pub leaves: Vec<F>,
+}
+
+impl<F: PrimeField> DecisionTree<F> {
+    pub fn depth(&self) -> usize {
+        self.leaves.len().trailing_zeros() as usize
+    }
+
+    /// The nodes of the tree, as the feature and the threshold of each, followed by the leaves.
+    fn flatten(&self) -> Vec<F> {
+        self.features
+            .iter()
+            .zip(&self.thresholds)
+            .flat_map(|(&feature, &threshold)| [F::from(feature as u64), F::from(threshold)])
+            .chain(self.leaves.iter().copied())
+            .collect()
+    }
+
+    /// The commitment to the tree: the [hash_slice_native] of its flattened nodes and leaves.
+    pub fn commitment(&self, params: &ArithmeticSpongeParams<F>) -> F {
+        hash_slice_native(params, &self.flatten())
+    }
+
+    /// Predicts the leaf of an input.
+    /// A node comparing a feature beyond the input reads zero, as in the circuit.
+    pub fn predict(&self, x: &[u64]) -> F {
+        let mut node = 0;
+        for _ in 0..self.depth() {
+            let value = x.get(self.features[node]).copied().unwrap_or(0);
+            let right = value >= self.thresholds[node];
+            node = 2 * node + 1 + usize::from(right);
+        }
+        self.leaves[node - self.features.len()]
+    }
+}
+
+/// A complete decision tree in the circuit, laid out as a [DecisionTree].
+#[derive(Clone, Debug)]
+pub struct DecisionTreeVar<F: PrimeField> {
+    pub features: Vec<FieldVar<F>>,
+    pub thresholds: Vec<FieldVar<F>>,
+    pub leaves: Vec<FieldVar<F>>,
+}
+
+/// Selects the value at the index given by `bits` (most significant first) among `2^bits.len()` values.
+pub(crate) fn select<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: &Cow<'static, str>,
+    values: &[FieldVar<F>],
+    bits: &[Boolean<F>],
+) -> SnarkyResult<FieldVar<F>> {
+    match bits.split_first() {
+        None => Ok(values[0].clone()),
+        Some((bit, bits)) => {
+            let (left, right) = values.split_at(values.len() / 2);
+            let left = select(sys, loc, left, bits)?;
+            let right = select(sys, loc, right, bits)?;
+            sys.if_(loc.clone(), bit.clone(), right, left)
+        }
+    }
+}
+
+impl<F: PrimeField> DecisionTreeVar<F> {
+    /// Checks that the tree is complete.
+    fn check_shape(&self, sys: &mut RunState<F>) -> SnarkyResult<()> {
+        let DecisionTreeVar {
+            features,
+            thresholds,
+            leaves,
+        } = self;
+        if !leaves.len().is_power_of_two() || features.len() != leaves.len() - 1 {
+            return Err(sys.compilation_error(SnarkyCompilationError::ShapeMismatch(
+                "internal nodes",
+                "decision tree".to_string(),
+                features.len(),
+                leaves.len().next_power_of_two() - 1,
+            )));
+        }
+        if thresholds.len() != features.len() {
+            return Err(sys.compilation_error(SnarkyCompilationError::ShapeMismatch(
+                "thresholds",
+                "decision tree".to_string(),
+                thresholds.len(),
+                features.len(),
+            )));
+        }
+        Ok(())
+    }
+
+    /// The commitment to the tree, as [DecisionTree::commitment].
+    fn commitment(&self, sys: &mut RunState<F>, loc: Cow<'static, str>) -> FieldVar<F> {
+        let flattened: Vec<_> = self
+            .features
+            .iter()
+            .zip(&self.thresholds)
+            .flat_map(|(feature, threshold)| [feature.clone(), threshold.clone()])
+            .chain(self.leaves.iter().cloned())
+            .collect();
+        hash_slice(sys, loc, &flattened)
+    }
+
+    /// Predicts the leaf of the input `x`, the tree being complete.
+    fn traverse(
+        &self,
+        sys: &mut RunState<F>,
+        loc: &Cow<'static, str>,
+        x: &[FieldVar<F>],
+        width: usize,
+    ) -> SnarkyResult<FieldVar<F>> {
+        // the input, padded to a power of two
+        let feature_bits = x.len().next_power_of_two().trailing_zeros() as usize;
+        let mut padded = x.to_vec();
+        padded.resize(1 << feature_bits, FieldVar::zero());
+
+        // the traversal, one decision per level
+        let offset = FieldVar::constant(F::from(2u64).pow([width as u64]));
+        let depth = self.leaves.len().trailing_zeros() as usize;
+        let mut path = Vec::with_capacity(depth);
+        for level in 0..depth {
+            let nodes = (1 << level) - 1..(1 << (level + 1)) - 1;
+            let feature = select(sys, loc, &self.features[nodes.clone()], &path)?;
+            let threshold = select(sys, loc, &self.thresholds[nodes], &path)?;
+
+            let index = to_bits(sys, loc.clone(), &feature, feature_bits, Endianness::Big)?;
+            let value = select(sys, loc, &padded, &index)?;
+
+            // value + 2^width - threshold has its most significant bit set iff value >= threshold
+            let shifted = value + &offset - threshold;
+            let bits = to_bits(sys, loc.clone(), &shifted, width + 1, Endianness::Little)?;
+            path.push(bits[width].clone());
+        }
+
+        select(sys, loc, &self.leaves, &path)
+    }
+}
+
+/// Predicts the leaf of the input `x` in a private tree, constrained to match `commitment`.
+///
+/// A node comparing a feature beyond the input reads zero.
+pub fn predict<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    commitment: &FieldVar<F>,
+    tree: &DecisionTreeVar<F>,
+    x: &[FieldVar<F>],
+    width: usize,
+) -> SnarkyResult<FieldVar<F>> {
+    tree.check_shape(sys)?;
+    let digest = tree.commitment(sys, loc.clone());
+    sys.assert_eq(
+        Some("decision_tree.commitment".into()),
+        loc.clone(),
+        digest,
+        commitment.clone(),
+    )?;
+    tree.traverse(sys, &loc, x, width)
+}
+
+//
+// Random forests
+//
+
+/// How a forest aggregates the predictions of its trees.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aggregation {
+    /// The sum of the leaves: their average scaled by the number of trees, which keeps it an integer.
+    Average,
+    /// The class predicted by the most trees, among the given number of classes numbered from 0.
+    /// The lowest class wins ties.
+    MajorityVote(usize),
+}
+
+impl Aggregation {
+    /// Aggregates the predictions of the trees, out of circuit.
+    pub fn aggregate<F: PrimeField>(&self, predictions: &[F]) -> F {
+        match *self {
+            Aggregation::Average => predictions.iter().sum(),
+            Aggregation::MajorityVote(classes) => {
+                let votes = |class: &usize| {
+                    let class = F::from(*class as u64);
+                    predictions.iter().filter(|&&p| p == class).count()
+                };
+                // the maximum of the reversed classes is the lowest of the tied ones
+                let winner = (0..classes).rev().max_by_key(votes).unwrap_or(0);
+                F::from(winner as u64)
+            }
+        }
+    }
+}
+
+/// A forest of decision trees, out of circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RandomForest<F> {
+    pub trees: Vec<DecisionTree<F>>,
+    pub aggregation: Aggregation,
+}
+
+impl<F: PrimeField> RandomForest<F> {
+    /// The commitment to the forest: the [hash_slice_native] of the commitments to its trees.
+    pub fn commitment(&self, params: &ArithmeticSpongeParams<F>) -> F {
+        let trees: Vec<F> = self
+            .trees
+            .iter()
+            .map(|tree| tree.commitment(params))
+            .collect();
+        hash_slice_native(params, &trees)
+    }
+
+    /// Predicts the aggregated prediction of the trees on an input.
+    pub fn predict(&self, x: &[u64]) -> F {
+        let predictions: Vec<F> = self.trees.iter().map(|tree| tree.predict(x)).collect();
+        self.aggregation.aggregate(&predictions)
+    }
+}
+
+/// Counts the votes for each class, and returns the class with the most votes, the lowest one on ties.
+pub(crate) fn majority_vote<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: &Cow<'static, str>,
+    predictions: &[FieldVar<F>],
+    classes: usize,
+) -> SnarkyResult<FieldVar<F>> {
+    let mut votes = Vec::with_capacity(classes);
+    for class in 0..classes {
+        let class = FieldVar::constant(F::from(class as u64));
+        let mut ballots = Vec::with_capacity(predictions.len());
+        for prediction in predictions {
+            ballots.push(prediction.equal(sys, loc.clone(), &class)?.to_field_var());
+        }
+        votes.push(FieldVar::sum_many(&ballots));
+    }
+    let Some((first, votes)) = votes.split_first() else {
+        return Ok(FieldVar::zero());
+    };
+
+    // the counts are at most the number of trees, below 2^bits
+    let bits = (usize::BITS - predictions.len().leading_zeros()) as usize;
+    let offset = FieldVar::constant(F::from(2u64).pow([bits as u64]) - F::one());
+    let mut best = first.clone();
+    let mut winner = FieldVar::zero();
+    for (class, count) in votes.iter().enumerate() {
+        // count + 2^bits - 1 - best has its most significant bit set iff count > best
+        let shifted = count.clone() + &offset - &best;
+        let more = to_bits(sys, loc.clone(), &shifted, bits + 1, Endianness::Little)?[bits].clone();
+        best = sys.if_(loc.clone(), more.clone(), count.clone(), best)?;
+        let class = FieldVar::constant(F::from(class as u64 + 1));
+        winner = sys.if_(loc.clone(), more, class, winner)?;
+    }
+    Ok(winner)
+}
+
+/// Predicts the aggregated prediction of a private forest on the input `x`,
+/// the forest being constrained to match `commitment` (see [RandomForest::commitment]).
+///
+/// The trees may have different depths; a node comparing a feature beyond the input reads zero.
+/// With [Aggregation::MajorityVote], the vote of a tree whose leaf is not a class is lost.
+pub fn predict_forest<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    commitment: &FieldVar<F>,
+    trees: &[DecisionTreeVar<F>],
+    x: &[FieldVar<F>],
+    width: usize,
+    aggregation: Aggregation,
+) -> SnarkyResult<FieldVar<F>> {
+    let mut digests = Vec::with_capacity(trees.len());
+    for tree in trees {
+        tree.check_shape(sys)?;
+        digests.push(tree.commitment(sys, loc.clone()));
+    }
+    let digest = hash_slice(sys, loc.clone(), &digests);
+    sys.assert_eq(
+        Some("random_forest.commitment".into()),
+        loc.clone(),
+        digest,
+        commitment.clone(),
+    )?;
+
+    let mut predictions = Vec::with_capacity(trees.len());
+    for tree in trees {
+        predictions.push(tree.traverse(sys, &loc, x, width)?);
+    }
+    match aggregation {
+        Aggregation::Average => Ok(FieldVar::sum_many(&predictions)),
+        Aggregation::MajorityVote(classes) => majority_vote(sys, &loc, &predictions, classes),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{curve::KimchiCurve, loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Predicts the leaf of a private input in a private tree of depth 2 committed to by the public input.
+    struct TestCircuit;
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = ([Fp; 3], [Fp; 3], [Fp; 4], [Fp; 3]);
+        type PublicInput = FieldVar<Fp>;
+        type PublicOutput = FieldVar<Fp>;
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            commitment: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let features: [FieldVar<Fp>; 3] = sys.compute(loc!(), |_| private.unwrap().0)?;
+            let thresholds: [FieldVar<Fp>; 3] = sys.compute(loc!(), |_| private.unwrap().1)?;
+            let leaves: [FieldVar<Fp>; 4] = sys.compute(loc!(), |_| private.unwrap().2)?;
+            let x: [FieldVar<Fp>; 3] = sys.compute(loc!(), |_| private.unwrap().3)?;
+
+            let tree = DecisionTreeVar {
+                features: features.to_vec(),
+                thresholds: thresholds.to_vec(),
+                leaves: leaves.to_vec(),
+            };
+            predict(sys, loc!(), &commitment, &tree, &x, 16)
+        }
+    }
+
+    #[test]
+    fn snarky_decision_tree() {
+        let tree = DecisionTree {
+            features: vec![0, 2, 1],
+            thresholds: vec![10, 5, 7],
+            leaves: (1..=4u64).map(Fp::from).collect(),
+        };
+        let commitment = tree.commitment(Vesta::sponge_params());
+        let x = [12u64, 3, 9];
+        // x[0] >= 10 goes right, then x[1] < 7 goes left
+        assert_eq!(tree.predict(&x), Fp::from(3u64));
+
+        let private = |tree: &DecisionTree<Fp>| {
+            let features = tree.features.iter().map(|&f| Fp::from(f as u64));
+            let thresholds = tree.thresholds.iter().map(|&t| Fp::from(t));
+            (
+                features.collect::<Vec<_>>().try_into().unwrap(),
+                thresholds.collect::<Vec<_>>().try_into().unwrap(),
+                tree.leaves.clone().try_into().unwrap(),
+                x.map(Fp::from),
+            )
+        };
+
+        let (mut prover_index, verifier_index) = TestCircuit.compile_to_indexes().unwrap();
+        let debug = true;
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>(commitment, private(&tree), debug)
+            .unwrap();
+        assert_eq!(*output, tree.predict(&x));
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, commitment, *output);
+
+        // another tree does not match the commitment
+        let other = DecisionTree {
+            thresholds: vec![20, 5, 7],
+            ..tree
+        };
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>(commitment, private(&other), debug)
+            .is_err());
+
+        // a feature beyond the input reads zero, in and out of the circuit
+        let beyond = DecisionTree {
+            features: vec![3, 2, 1],
+            thresholds: vec![1, 5, 7],
+            leaves: (1..=4u64).map(Fp::from).collect(),
+        };
+        let commitment = beyond.commitment(Vesta::sponge_params());
+        // x[3] = 0 < 1 goes left, then x[2] >= 5 goes right
+        assert_eq!(beyond.predict(&x), Fp::from(2u64));
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>(commitment, private(&beyond), debug)
+            .unwrap();
+        assert_eq!(*output, beyond.predict(&x));
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, commitment, *output);
+    }
+}
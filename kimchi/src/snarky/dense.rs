@@ -0,0 +1,272 @@
+//! Dense (fully connected) layers, with their shapes checked at compile time or at runtime.
+//!
+//! The dimensions of a [DenseLayer] are const generics,
+//! so feeding a layer with an input of the wrong size, or chaining layers of mismatched sizes,
+//! is a type error in a hand-written circuit instead of a panic when it is compiled.
+//! The shape of a [DynDenseLayer] is only known at runtime, from the metadata of an imported graph,
+//! and is checked when the circuit is compiled.
+//! Both apply the same kernel, so they create the same constraints for the same layer.
+//!
+//! The weights and the bias are circuit variables:
+//! constants for a model fixed in the circuit, whose products are free (see [FieldVar::dot_product]),
+//! or private inputs for a private model.
+//! Outputs are at the scale of the weights times the scale of the inputs,
+//! so fixed-point models requantize them (see [crate::snarky::shift]) before the next layer.
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+use crate::snarky::{
+    errors::SnarkyCompilationError,
+    prelude::{FieldVar, RunState, SnarkyResult},
+};
+
+/// Applies a dense layer given by the rows of its weights and its bias.
+/// The shapes must have been checked by the caller.
+fn dense<'a, F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    rows: impl Iterator<Item = &'a [FieldVar<F>]>,
+    bias: &[FieldVar<F>],
+    input: &[FieldVar<F>],
+) -> SnarkyResult<Vec<FieldVar<F>>> {
+    let mut output = Vec::with_capacity(bias.len());
+    for (row, bias) in rows.zip(bias) {
+        let product = FieldVar::dot_product(row, input, Some("dense".into()), loc.clone(), sys)?;
+        output.push(product + bias);
+    }
+    Ok(output)
+}
+
+/// A dense layer `y = W x + b`, from `IN` inputs to `OUT` outputs.
+pub struct DenseLayer<F, const IN: usize, const OUT: usize>
+where
+    F: PrimeField,
+{
+    /// The weights, one row per output.
+    pub weights: [[FieldVar<F>; IN]; OUT],
+    pub bias: [FieldVar<F>; OUT],
+}
+
+impl<F, const IN: usize, const OUT: usize> DenseLayer<F, IN, OUT>
+where
+    F: PrimeField,
+{
+    /// Creates a layer whose weights and bias are fixed in the circuit.
+    pub fn constant(weights: [[F; IN]; OUT], bias: [F; OUT]) -> Self {
+        Self {
+            weights: weights.map(|row| row.map(FieldVar::constant)),
+            bias: bias.map(FieldVar::constant),
+        }
+    }
+
+    /// Applies the layer to an input.
+    pub fn forward(
+        &self,
+        sys: &mut RunState<F>,
+        loc: Cow<'static, str>,
+        input: &[FieldVar<F>; IN],
+    ) -> SnarkyResult<[FieldVar<F>; OUT]> {
+        let rows = self.weights.iter().map(|row| row.as_slice());
+        let output = dense(sys, loc, rows, &self.bias, input)?;
+        Ok(output.try_into().unwrap())
+    }
+}
+
+/// The shape of a dense layer, as given by the metadata of an imported graph.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DenseShape {
+    /// The name of the layer in the graph, for errors.
+    pub name: String,
+    pub inputs: usize,
+    pub outputs: usize,
+}
+
+/// A dense layer whose shape is only known at runtime.
+pub struct DynDenseLayer<F>
+where
+    F: PrimeField,
+{
+    pub shape: DenseShape,
+    /// The weights in row-major order, one row of `inputs` weights per output.
+    pub weights: Vec<FieldVar<F>>,
+    pub bias: Vec<FieldVar<F>>,
+}
+
+impl<F> DynDenseLayer<F>
+where
+    F: PrimeField,
+{
+    /// Creates a layer whose weights and bias are fixed in the circuit.
+    pub fn constant(shape: DenseShape, weights: &[F], bias: &[F]) -> Self {
+        Self {
+            shape,
+            weights: weights.iter().copied().map(FieldVar::constant).collect(),
+            bias: bias.iter().copied().map(FieldVar::constant).collect(),
+        }
+    }
+
+    /// Applies the layer to an input,
+    /// failing the compilation if the weights, the bias or the input do not match the shape of the layer.
+    pub fn forward(
+        &self,
+        sys: &mut RunState<F>,
+        loc: Cow<'static, str>,
+        input: &[FieldVar<F>],
+    ) -> SnarkyResult<Vec<FieldVar<F>>> {
+        let DenseShape {
+            name,
+            inputs,
+            outputs,
+        } = &self.shape;
+        let checks = [
+            ("weights", self.weights.len(), inputs * outputs),
+            ("bias", self.bias.len(), *outputs),
+            ("inputs", input.len(), *inputs),
+        ];
+        for (what, len, expected) in checks {
+            if len != expected {
+                return Err(sys.compilation_error(SnarkyCompilationError::ShapeMismatch(
+                    what,
+                    name.clone(),
+                    len,
+                    expected,
+                )));
+            }
+        }
+
+        // a layer without inputs has no weights, but still has a bias
+        let rows = self
+            .weights
+            .chunks((*inputs).max(1))
+            .chain(std::iter::repeat(&[][..]));
+        dense(sys, loc, rows, &self.bias, input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Applies a constant layer from 3 to 2 values, then a private layer from 2 to 1 value.
+    struct TestCircuit;
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        /// The input, and the weights and bias of the second layer.
+        type PrivateInput = ([Fp; 3], ([Fp; 2], Fp));
+        type PublicInput = ();
+        type PublicOutput = [FieldVar<Fp>; 1];
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let (x, (weights, bias)): ([FieldVar<Fp>; 3], ([FieldVar<Fp>; 2], FieldVar<Fp>)) =
+                sys.compute(loc!(), |_| *private.unwrap())?;
+
+            let first = DenseLayer::constant(
+                [[1u64, 2, 3], [0, 1, 0]].map(|row| row.map(Fp::from)),
+                [Fp::from(1u64), Fp::from(0u64)],
+            );
+            let second = DenseLayer {
+                weights: [weights],
+                bias: [bias],
+            };
+
+            let hidden = first.forward(sys, loc!(), &x)?;
+            second.forward(sys, loc!(), &hidden)
+        }
+    }
+
+    #[test]
+    fn snarky_dense() {
+        let (mut prover_index, verifier_index) = TestCircuit.compile_to_indexes().unwrap();
+
+        // hidden = [1 + 4 + 9 + 1, 2] = [15, 2], output = 15 * 3 + 2 * 5 + 7 = 62
+        let x = [1u64, 2, 3].map(Fp::from);
+        let second = ([Fp::from(3u64), Fp::from(5u64)], Fp::from(7u64));
+        let debug = true;
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), (x, second), debug)
+            .unwrap();
+        assert_eq!(*output, [Fp::from(62u64)]);
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+    }
+
+    /// Applies the constant layer of [TestCircuit] with a shape read at runtime,
+    /// and checks that it matches the const-generic layer.
+    struct DynTestCircuit {
+        shape: DenseShape,
+    }
+
+    impl SnarkyCircuit for DynTestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = [Fp; 3];
+        type PublicInput = ();
+        type PublicOutput = [FieldVar<Fp>; 2];
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let x: [FieldVar<Fp>; 3] = sys.compute(loc!(), |_| *private.unwrap())?;
+
+            let weights = [1u64, 2, 3, 0, 1, 0].map(Fp::from);
+            let bias = [1u64, 0].map(Fp::from);
+            let layer = DynDenseLayer::constant(self.shape.clone(), &weights, &bias);
+            let output = layer.forward(sys, loc!(), &x)?;
+
+            let expected =
+                DenseLayer::constant([[1u64, 2, 3], [0, 1, 0]].map(|row| row.map(Fp::from)), bias)
+                    .forward(sys, loc!(), &x)?;
+            for (y, expected) in output.iter().zip(expected) {
+                sys.assert_eq(None, loc!(), y.clone(), expected)?;
+            }
+            Ok(output.try_into().unwrap())
+        }
+    }
+
+    #[test]
+    fn snarky_dense_dyn() {
+        let shape: DenseShape =
+            serde_json::from_str(r#"{"name": "fc1", "inputs": 3, "outputs": 2}"#).unwrap();
+        let (mut prover_index, verifier_index) = DynTestCircuit {
+            shape: shape.clone(),
+        }
+        .compile_to_indexes()
+        .unwrap();
+
+        let x = [1u64, 2, 3].map(Fp::from);
+        let debug = true;
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), x, debug)
+            .unwrap();
+        assert_eq!(*output, [Fp::from(15u64), Fp::from(2u64)]);
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+
+        // a shape that does not match the weights fails the compilation
+        let shape = DenseShape { inputs: 2, ..shape };
+        assert!(DynTestCircuit { shape }.compile_to_indexes().is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! Gadgets for early-exit networks, where each stage of the network has an exit head
+//! that can stop the inference when it is confident enough in its prediction.
+//!
+//! The circuit proves which exit fired and that its criterion held,
+//! i.e. that its criterion is the first one to hold (the last exit always fires).
+//! If the exit is public, it is fixed when the circuit is compiled
+//! and the stages after it are not synthesized at all.
+//! Otherwise, every stage is synthesized and the exit is selected in the circuit.
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+
+use crate::snarky::{
+    boolean::Boolean,
+    prelude::{FieldVar, RunState, SnarkyResult},
+};
+
+/// A stage of an early-exit network.
+pub trait Stage<F: PrimeField> {
+    /// Computes the hidden state of the stage from the hidden state of the previous stage
+    /// (or from the input of the network, for the first stage).
+    fn forward(
+        &self,
+        sys: &mut RunState<F>,
+        loc: Cow<'static, str>,
+        input: &[FieldVar<F>],
+    ) -> SnarkyResult<Vec<FieldVar<F>>>;
+
+    /// The exit head of the stage,
+    /// returning its prediction and whether its exit criterion holds.
+    fn exit(
+        &self,
+        sys: &mut RunState<F>,
+        loc: Cow<'static, str>,
+        hidden: &[FieldVar<F>],
+    ) -> SnarkyResult<(FieldVar<F>, Boolean<F>)>;
+}
+
+/// Whether the exit that fires is known when the circuit is compiled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitPolicy {
+    /// The exit is selected in the circuit.
+    Private,
+    /// The exit is the one of the given stage.
+    Public(usize),
+}
+
+/// The exit that fired, and its prediction.
+#[derive(Clone, Debug)]
+pub struct ExitOutcome<F>
+where
+    F: PrimeField,
+{
+    pub exit: FieldVar<F>,
+    pub prediction: FieldVar<F>,
+}
+
+/// Runs an early-exit network on an input, and constrains the outcome to be the one of the first exit that fires.
+///
+/// # Panics
+///
+/// Will panic if there are no stages, or if a public exit is not one of the stages.
+pub fn early_exit<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    stages: &[&dyn Stage<F>],
+    input: Vec<FieldVar<F>>,
+    policy: ExitPolicy,
+) -> SnarkyResult<ExitOutcome<F>> {
+    assert!(!stages.is_empty(), "the network has no stages");
+    let last = stages.len() - 1;
+
+    match policy {
+        ExitPolicy::Public(exit) => {
+            assert!(exit <= last, "the exit {exit} is not one of the stages");
+
+            let mut hidden = input;
+            for (i, stage) in stages[..=exit].iter().enumerate() {
+                hidden = stage.forward(sys, loc.clone(), &hidden)?;
+                let (prediction, fires) = stage.exit(sys, loc.clone(), &hidden)?;
+
+                // the earlier exits must not fire, and the last exit always fires
+                let expected = if i < exit { F::zero() } else { F::one() };
+                if i < last {
+                    sys.assert_eq(
+                        Some("early_exit.criterion".into()),
+                        loc.clone(),
+                        fires.to_field_var(),
+                        FieldVar::constant(expected),
+                    )?;
+                }
+
+                if i == exit {
+                    return Ok(ExitOutcome {
+                        exit: FieldVar::constant(F::from(exit as u64)),
+                        prediction,
+                    });
+                }
+            }
+            unreachable!("the public exit is one of the stages")
+        }
+
+        ExitPolicy::Private => {
+            let mut hidden = input;
+            let mut done = Boolean::false_();
+            let mut exit = FieldVar::zero();
+            let mut outcome = FieldVar::zero();
+            for (i, stage) in stages.iter().enumerate() {
+                hidden = stage.forward(sys, loc.clone(), &hidden)?;
+                let (prediction, fires) = stage.exit(sys, loc.clone(), &hidden)?;
+                let fires = if i == last { Boolean::true_() } else { fires };
+
+                let take = done.not().and(&fires, sys, loc.clone());
+                exit = sys.if_(
+                    loc.clone(),
+                    take.clone(),
+                    FieldVar::constant(F::from(i as u64)),
+                    exit,
+                )?;
+                outcome = sys.if_(loc.clone(), take, prediction, outcome)?;
+                done = done.or(&fires, loc.clone(), sys);
+            }
+
+            Ok(ExitOutcome {
+                exit,
+                prediction: outcome,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Increments the hidden state, and exits if it reaches a target.
+    struct Increment {
+        target: u64,
+    }
+
+    impl Stage<Fp> for Increment {
+        fn forward(
+            &self,
+            _: &mut RunState<Fp>,
+            _: Cow<'static, str>,
+            input: &[FieldVar<Fp>],
+        ) -> SnarkyResult<Vec<FieldVar<Fp>>> {
+            Ok(vec![&input[0] + FieldVar::constant(Fp::from(1u64))])
+        }
+
+        fn exit(
+            &self,
+            sys: &mut RunState<Fp>,
+            loc: Cow<'static, str>,
+            hidden: &[FieldVar<Fp>],
+        ) -> SnarkyResult<(FieldVar<Fp>, Boolean<Fp>)> {
+            let fires = hidden[0].equal(sys, loc, &FieldVar::constant(Fp::from(self.target)))?;
+            Ok((hidden[0].clone(), fires))
+        }
+    }
+
+    /// Runs a network of three stages on the private input, and outputs the exit and the prediction.
+    struct TestCircuit {
+        policy: ExitPolicy,
+    }
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = Fp;
+        type PublicInput = ();
+        type PublicOutput = (FieldVar<Fp>, FieldVar<Fp>);
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let x: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+            let stages = [
+                Increment { target: 2 },
+                Increment { target: 4 },
+                Increment { target: 0 },
+            ];
+            let stages: Vec<&dyn Stage<Fp>> = stages.iter().map(|s| s as &dyn Stage<Fp>).collect();
+
+            let outcome = early_exit(sys, loc!(), &stages, vec![x], self.policy)?;
+            Ok((outcome.exit, outcome.prediction))
+        }
+    }
+
+    #[test]
+    fn snarky_early_exit() {
+        let (mut private_index, verifier_index) = TestCircuit {
+            policy: ExitPolicy::Private,
+        }
+        .compile_to_indexes()
+        .unwrap();
+
+        let debug = true;
+        for (x, exit, prediction) in [(1u64, 0u64, 2u64), (2, 1, 4), (5, 2, 8)] {
+            let (proof, output) = private_index
+                .prove::<BaseSponge, ScalarSponge>((), Fp::from(x), debug)
+                .unwrap();
+            assert_eq!(*output, (Fp::from(exit), Fp::from(prediction)));
+            verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+        }
+
+        // a public exit skips the later stages
+        let (mut public_index, _) = TestCircuit {
+            policy: ExitPolicy::Public(0),
+        }
+        .compile_to_indexes()
+        .unwrap();
+        assert!(public_index.asm().lines().count() < private_index.asm().lines().count());
+
+        let (_, output) = public_index
+            .prove::<BaseSponge, ScalarSponge>((), Fp::from(1u64), debug)
+            .unwrap();
+        assert_eq!(*output, (Fp::from(0u64), Fp::from(2u64)));
+
+        // the public exit must be the one that fires
+        assert!(public_index
+            .prove::<BaseSponge, ScalarSponge>((), Fp::from(2u64), debug)
+            .is_err());
+    }
+}
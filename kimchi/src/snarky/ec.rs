@@ -0,0 +1,133 @@
+//! Elliptic curve gadgets, on a curve whose base field is the field of the circuit
+//! (for example Pallas points in a circuit over the scalar field of Vesta).
+
+use std::borrow::Cow;
+
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{Field, PrimeField};
+use poly_commitment::commitment::CommitmentCurve;
+
+use crate::snarky::{
+    boolean::Boolean,
+    constraint_system::{EcAddCompleteInput, KimchiConstraint},
+    prelude::{FieldVar, RunState, SnarkyResult},
+    runner::Constraint,
+};
+
+/// An affine point in the circuit.
+pub type PointVar<F> = (FieldVar<F>, FieldVar<F>);
+
+/// Computes the cells of a [crate::circuits::gate::GateType::CompleteAdd] row:
+/// `[x3, y3, inf, same_x, s, inf_z, x21_inv]`.
+fn complete_add_witness<F: PrimeField>((x1, y1): (F, F), (x2, y2): (F, F)) -> [F; 7] {
+    let x21 = x2 - x1;
+    let y21 = y2 - y1;
+    let same_x = x21.is_zero();
+
+    let s = if same_x {
+        // 2 * s * y1 = 3 * x1^2
+        let x1_squared = x1.square();
+        (x1_squared.double() + x1_squared) / y1.double()
+    } else {
+        // (x2 - x1) * s = y2 - y1
+        y21 / x21
+    };
+    let x3 = s.square() - x1 - x2;
+    let y3 = s * (x1 - x3) - y1;
+
+    let inf = same_x && !y21.is_zero();
+    let inf_z = if inf {
+        y21.inverse().unwrap()
+    } else {
+        F::zero()
+    };
+    let x21_inv = x21.inverse().unwrap_or_else(F::zero);
+
+    [
+        x3,
+        y3,
+        F::from(inf as u64),
+        F::from(same_x as u64),
+        s,
+        inf_z,
+        x21_inv,
+    ]
+}
+
+/// Adds two points with a [crate::circuits::gate::GateType::CompleteAdd] gate.
+/// The result is meaningless if it is the point at infinity.
+pub fn add_complete<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    p1: &PointVar<F>,
+    p2: &PointVar<F>,
+) -> SnarkyResult<PointVar<F>> {
+    let (x1, y1) = p1.clone();
+    let (x2, y2) = p2.clone();
+    let [x3, y3, inf, same_x, slope, inf_z, x21_inv]: [FieldVar<F>; 7] =
+        sys.compute(loc.clone(), |env| {
+            complete_add_witness(
+                (env.read_var(&x1), env.read_var(&y1)),
+                (env.read_var(&x2), env.read_var(&y2)),
+            )
+        })?;
+
+    let constraint = KimchiConstraint::EcAddComplete(EcAddCompleteInput {
+        p1: p1.clone(),
+        p2: p2.clone(),
+        p3: (x3.clone(), y3.clone()),
+        inf,
+        same_x,
+        slope,
+        inf_z,
+        x21_inv,
+    });
+    sys.add_constraint(
+        Constraint::KimchiConstraint(constraint),
+        Some("EC addition".into()),
+        loc,
+    )?;
+
+    Ok((x3, y3))
+}
+
+/// Converts a point of the curve to a constant point of the circuit.
+pub fn constant_point<C: CommitmentCurve>(point: &C) -> PointVar<C::BaseField>
+where
+    C::BaseField: PrimeField,
+{
+    let (x, y) = point.to_coordinates().expect("the point at infinity");
+    (FieldVar::constant(x), FieldVar::constant(y))
+}
+
+/// Returns `acc + k * base`, where `k` is the integer whose bits (least significant first) are `bits`.
+///
+/// `acc` must be a point whose discrete logarithm in `base` is unknown,
+/// so that none of the intermediate additions hits an exceptional case.
+pub fn add_fixed_base_multiple<C: CommitmentCurve>(
+    sys: &mut RunState<C::BaseField>,
+    loc: Cow<'static, str>,
+    acc: PointVar<C::BaseField>,
+    base: C,
+    bits: &[Boolean<C::BaseField>],
+) -> SnarkyResult<PointVar<C::BaseField>>
+where
+    C::BaseField: PrimeField,
+{
+    let mut acc = acc;
+    let mut power = base.into_projective();
+    for bit in bits {
+        let sum = add_complete(
+            sys,
+            loc.clone(),
+            &acc,
+            &constant_point(&power.into_affine()),
+        )?;
+        acc = (
+            sys.if_(loc.clone(), bit.clone(), sum.0, acc.0)?,
+            sys.if_(loc.clone(), bit.clone(), sum.1, acc.1)?,
+        );
+        power.double_in_place();
+    }
+    Ok(acc)
+}
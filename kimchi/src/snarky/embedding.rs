@@ -0,0 +1,190 @@
+//! A gadget proving an embedding lookup: the row of a committed embedding matrix selected by a private token.
+//!
+//! The matrix is committed to by the root of a Merkle tree (see [crate::snarky::merkle])
+//! whose leaves are the [hash_slice_native] of its rows, padded with zero leaves to a power of two.
+//! A lookup opens the leaf at the index of the token, so it costs the hash of a row
+//! and a hash per level of the tree, whatever the size of the vocabulary.
+//! No row hashes to zero, so the padding cannot be opened, and the token is below the number of rows.
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+use mina_poseidon::poseidon::ArithmeticSpongeParams;
+
+use crate::snarky::{
+    errors::SnarkyCompilationError,
+    merkle::{check_membership, node},
+    poseidon::{hash_slice, hash_slice_native},
+    prelude::{FieldVar, RunState, SnarkyResult},
+};
+
+/// An embedding matrix, a row per token, out of circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmbeddingTable<F> {
+    pub rows: Vec<Vec<F>>,
+}
+
+/// A row of an [EmbeddingTable] and the path of its leaf, to be checked by [lookup_embedding].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmbeddingOpening<F> {
+    pub token: usize,
+    pub row: Vec<F>,
+    pub siblings: Vec<F>,
+}
+
+impl<F: PrimeField> EmbeddingTable<F> {
+    /// The depth of the Merkle tree of the rows.
+    pub fn depth(&self) -> usize {
+        self.rows.len().next_power_of_two().trailing_zeros() as usize
+    }
+
+    /// The levels of the Merkle tree, from the leaves to the root.
+    fn levels(&self, params: &ArithmeticSpongeParams<F>) -> Vec<Vec<F>> {
+        let mut leaves: Vec<F> = self
+            .rows
+            .iter()
+            .map(|row| hash_slice_native(params, row))
+            .collect();
+        leaves.resize(1 << self.depth(), F::zero());
+
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| node(params, pair[0], pair[1]))
+                .collect();
+            levels.push(level);
+        }
+        levels
+    }
+
+    /// The commitment to the matrix: the root of the Merkle tree of its rows.
+    pub fn root(&self, params: &ArithmeticSpongeParams<F>) -> F {
+        self.levels(params).last().unwrap()[0]
+    }
+
+    /// The row of a token, with the path of its leaf.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the token has no row.
+    pub fn open(&self, params: &ArithmeticSpongeParams<F>, token: usize) -> EmbeddingOpening<F> {
+        let levels = self.levels(params);
+        let siblings = levels[..levels.len() - 1]
+            .iter()
+            .enumerate()
+            .map(|(level, nodes)| nodes[(token >> level) ^ 1])
+            .collect();
+        EmbeddingOpening {
+            token,
+            row: self.rows[token].clone(),
+            siblings,
+        }
+    }
+}
+
+/// Returns the row of the private `token` in the embedding matrix committed to by `root` (see [EmbeddingTable::root]),
+/// constraining `row` to be its row through the path `siblings` of its leaf.
+///
+/// # Errors
+///
+/// Will give error if the row is empty, or if the row is not the one of the token in the committed matrix.
+pub fn lookup_embedding<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    root: &FieldVar<F>,
+    token: &FieldVar<F>,
+    row: Vec<FieldVar<F>>,
+    siblings: &[FieldVar<F>],
+) -> SnarkyResult<Vec<FieldVar<F>>> {
+    if row.is_empty() {
+        return Err(sys.compilation_error(SnarkyCompilationError::ShapeMismatch(
+            "columns",
+            "embedding".to_string(),
+            0,
+            1,
+        )));
+    }
+    let leaf = hash_slice(sys, loc.clone(), &row);
+    check_membership(sys, loc, root, leaf, token, siblings)?;
+    Ok(row)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{curve::KimchiCurve, loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Outputs the row of 2 columns of a private token, in a matrix of at most 4 rows committed to by the public input.
+    struct TestCircuit;
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = (Fp, [Fp; 2], [Fp; 2]);
+        type PublicInput = FieldVar<Fp>;
+        type PublicOutput = [FieldVar<Fp>; 2];
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            root: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let token: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap().0)?;
+            let row: [FieldVar<Fp>; 2] = sys.compute(loc!(), |_| private.unwrap().1)?;
+            let siblings: [FieldVar<Fp>; 2] = sys.compute(loc!(), |_| private.unwrap().2)?;
+
+            let row = lookup_embedding(sys, loc!(), &root, &token, row.to_vec(), &siblings)?;
+            Ok([row[0].clone(), row[1].clone()])
+        }
+    }
+
+    #[test]
+    fn snarky_embedding_lookup() {
+        let params = Vesta::sponge_params();
+        let table = EmbeddingTable {
+            rows: (0..3u64)
+                .map(|i| vec![Fp::from(10 * i), Fp::from(10 * i + 1)])
+                .collect(),
+        };
+        assert_eq!(table.depth(), 2);
+        let root = table.root(params);
+
+        let private = |opening: &EmbeddingOpening<Fp>| {
+            (
+                Fp::from(opening.token as u64),
+                opening.row.clone().try_into().unwrap(),
+                opening.siblings.clone().try_into().unwrap(),
+            )
+        };
+
+        let (mut prover_index, verifier_index) = TestCircuit.compile_to_indexes().unwrap();
+        let debug = true;
+        let opening = table.open(params, 2);
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>(root, private(&opening), debug)
+            .unwrap();
+        assert_eq!(output.to_vec(), table.rows[2]);
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, root, *output);
+
+        // the row must be the one of the token
+        let mut other = opening;
+        other.token = 1;
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>(root, private(&other), debug)
+            .is_err());
+    }
+}
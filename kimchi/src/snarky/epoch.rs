@@ -0,0 +1,109 @@
+//! Proofs bound to an epoch, for inferences that must be shown to be fresh.
+//!
+//! [EpochBound] adds an external nonce (for example the hash of a recent block) to the public input of a circuit.
+//! Kimchi absorbs the commitment to the public input in the transcript of the proof,
+//! so a proof created for one nonce does not verify for any other:
+//! a verifier that checks a proof against the nonce of the current epoch knows it was created during that epoch.
+
+use ark_ec::AffineCurve;
+use ark_ff::PrimeField;
+
+use crate::{
+    circuits::capabilities::Capabilities,
+    snarky::{
+        api::SnarkyCircuit,
+        prelude::{FieldVar, RunState, SnarkyResult},
+    },
+};
+
+type ScalarField<C> = <C as AffineCurve>::ScalarField;
+
+/// A circuit whose proofs are bound to a nonce, given before its public input.
+pub struct EpochBound<C>(pub C);
+
+impl<C: SnarkyCircuit> SnarkyCircuit for EpochBound<C> {
+    type Curve = C::Curve;
+    type Proof = C::Proof;
+
+    type PrivateInput = C::PrivateInput;
+    type PublicInput = (FieldVar<ScalarField<C::Curve>>, C::PublicInput);
+    type PublicOutput = C::PublicOutput;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<ScalarField<Self::Curve>>,
+        (_nonce, public_input): Self::PublicInput,
+        private_input: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        // the nonce does not need to be used: being public is what binds the proof to it
+        self.0.circuit(sys, public_input, private_input)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.0.capabilities()
+    }
+}
+
+/// Converts an external nonce (for example a block hash) to a field element.
+/// Nonces longer than the field are reduced modulo its order.
+pub fn nonce_from_bytes<F: PrimeField>(bytes: &[u8]) -> F {
+    F::from_le_bytes_mod_order(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::loc;
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Outputs the square of the private input.
+    struct Square;
+
+    impl SnarkyCircuit for Square {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = Fp;
+        type PublicInput = ();
+        type PublicOutput = FieldVar<Fp>;
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let x: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+            x.mul(&x, None, loc!(), sys)
+        }
+    }
+
+    #[test]
+    fn snarky_epoch_bound() {
+        let (mut prover_index, verifier_index) = EpochBound(Square).compile_to_indexes().unwrap();
+
+        let epoch: Fp = nonce_from_bytes(&[0xab; 32]);
+        let next_epoch: Fp = nonce_from_bytes(&[0xcd; 32]);
+
+        let debug = true;
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((epoch, ()), Fp::from(3u64), debug)
+            .unwrap();
+        assert_eq!(*output, Fp::from(9u64));
+
+        verifier_index
+            .try_verify::<BaseSponge, ScalarSponge>(&proof, &(epoch, ()), &output)
+            .unwrap();
+        assert!(verifier_index
+            .try_verify::<BaseSponge, ScalarSponge>(&proof, &(next_epoch, ()), &output)
+            .is_err());
+    }
+}
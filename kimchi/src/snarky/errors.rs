@@ -2,6 +2,8 @@ use std::{backtrace::Backtrace, borrow::Cow};
 
 use thiserror::Error;
 
+use crate::error::CapabilityError;
+
 /// A result type for Snarky errors.
 pub type SnarkyResult<T> = std::result::Result<T, Box<RealSnarkyError>>;
 
@@ -80,6 +82,33 @@ pub enum SnarkyError {
 pub enum SnarkyCompilationError {
     #[error("the two values were not equal: {0} != {1}")]
     ConstantAssertEquals(String, String),
+
+    #[error("the backend cannot prove the circuit: {0}")]
+    UnsupportedByBackend(CapabilityError),
+
+    #[error("the gadget {0} uses {1} rows, more than its budget of {2} rows")]
+    BudgetExceeded(String, usize, usize),
+
+    #[error("the hint {0} is not justified by any constraint")]
+    UnjustifiedHint(String),
+
+    #[error("the {0} of the layer {1} have {2} values instead of {3}")]
+    ShapeMismatch(&'static str, String, usize, usize),
+
+    #[error("the constraint {0} cannot be created by a gadget running on a shard")]
+    UnsupportedInShard(String),
+
+    #[error("layer {0} pushed rows past 2^{1}; consider splitting at layer {2}")]
+    DomainTooSmall(String, usize, String),
+
+    #[error("the custom gate {0} has {1} inputs, more than the {2} cells of its rows")]
+    GateTooWide(String, usize, usize),
+
+    #[error("the value {1} of {0} does not fit in {2} bits")]
+    ValueTooWide(String, u64, usize),
+
+    #[error("an SRS of size {0} cannot commit to any polynomial")]
+    EmptySrs(usize),
 }
 
 /// Errors that can occur during runtime (proving).
@@ -117,4 +146,7 @@ pub enum SnarkyRuntimeError {
 
     #[error("the value returned by the circuit has an incorrect number of field variables. It hardcoded {1} field variables, but returned {0}")]
     CircuitReturnVar(usize, usize),
+
+    #[error("the witness could not be stored: {0}")]
+    WitnessStorage(String),
 }
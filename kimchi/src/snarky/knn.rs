@@ -0,0 +1,317 @@
+//! A gadget proving the prediction of a k-nearest-neighbors classifier over a committed reference set.
+//!
+//! The circuit computes the squared Euclidean distance from the input to every point of the set,
+//! selects the `k` smallest with a selection network, and takes a majority vote of their labels.
+//! The network runs `k` passes of compare-exchanges from the last point to the first,
+//! each pass moving the nearest remaining point to the front,
+//! so it costs `k (2n - k - 1) / 2` comparisons for `n` points and does not depend on the input.
+//! Points at the same distance keep their order, so the first of them is the nearest.
+//!
+//! The reference set is private, and bound to a public commitment (see [ReferenceSet::commitment]).
+//! Features are unsigned and must fit in the width given to the gadget:
+//! the caller constrains those of the input, and the commitment binds those of the points.
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+use mina_poseidon::poseidon::ArithmeticSpongeParams;
+
+use crate::snarky::{
+    bits::{to_bits, Endianness},
+    decision_tree::{majority_vote, Aggregation},
+    errors::SnarkyCompilationError,
+    poseidon::{hash_slice, hash_slice_native},
+    prelude::{FieldVar, RunState, SnarkyResult},
+};
+
+/// The parameters of a classifier: the number of neighbors voting, and the number of classes numbered from 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Knn {
+    pub k: usize,
+    pub classes: usize,
+}
+
+impl Knn {
+    /// Predicts the class of an input, out of circuit: the class of the most of its `k` nearest points,
+    /// the lowest one on ties.
+    pub fn predict<F: PrimeField>(&self, reference: &ReferenceSet<F>, x: &[u64]) -> F {
+        let labels: Vec<F> = reference
+            .nearest(x, self.k)
+            .into_iter()
+            .map(|i| reference.labels[i])
+            .collect();
+        Aggregation::MajorityVote(self.classes).aggregate(&labels)
+    }
+}
+
+/// A set of labelled points, out of circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReferenceSet<F> {
+    pub points: Vec<Vec<u64>>,
+    pub labels: Vec<F>,
+}
+
+impl<F: PrimeField> ReferenceSet<F> {
+    /// The features of the points, followed by the labels.
+    fn flatten(&self) -> Vec<F> {
+        self.points
+            .iter()
+            .flatten()
+            .map(|&feature| F::from(feature))
+            .chain(self.labels.iter().copied())
+            .collect()
+    }
+
+    /// The commitment to the set: the [hash_slice_native] of its flattened points and labels.
+    pub fn commitment(&self, params: &ArithmeticSpongeParams<F>) -> F {
+        hash_slice_native(params, &self.flatten())
+    }
+
+    /// The indices of the `k` points nearest to an input, nearest first, the first point winning ties.
+    pub fn nearest(&self, x: &[u64], k: usize) -> Vec<usize> {
+        let distance = |point: &[u64]| -> u128 {
+            point
+                .iter()
+                .zip(x)
+                .map(|(&p, &x)| (p.abs_diff(x) as u128).pow(2))
+                .sum()
+        };
+        let mut indices: Vec<usize> = (0..self.points.len()).collect();
+        indices.sort_by_key(|&i| distance(&self.points[i]));
+        indices.truncate(k);
+        indices
+    }
+}
+
+/// A set of labelled points in the circuit, laid out as a [ReferenceSet].
+#[derive(Clone, Debug)]
+pub struct ReferenceSetVar<F: PrimeField> {
+    pub points: Vec<Vec<FieldVar<F>>>,
+    pub labels: Vec<FieldVar<F>>,
+}
+
+impl<F: PrimeField> ReferenceSetVar<F> {
+    /// Checks that there is a label per point, at least `k` points, and as many features in each point as in the input.
+    fn check_shape(&self, sys: &mut RunState<F>, features: usize, k: usize) -> SnarkyResult<()> {
+        if self.labels.len() != self.points.len() {
+            return Err(sys.compilation_error(SnarkyCompilationError::ShapeMismatch(
+                "labels",
+                "k-nearest neighbors".to_string(),
+                self.labels.len(),
+                self.points.len(),
+            )));
+        }
+        if self.points.len() < k {
+            return Err(sys.compilation_error(SnarkyCompilationError::ShapeMismatch(
+                "points",
+                "k-nearest neighbors".to_string(),
+                self.points.len(),
+                k,
+            )));
+        }
+        if let Some(point) = self.points.iter().find(|point| point.len() != features) {
+            return Err(sys.compilation_error(SnarkyCompilationError::ShapeMismatch(
+                "features",
+                "k-nearest neighbors".to_string(),
+                point.len(),
+                features,
+            )));
+        }
+        Ok(())
+    }
+
+    /// The commitment to the set, as [ReferenceSet::commitment].
+    fn commitment(&self, sys: &mut RunState<F>, loc: Cow<'static, str>) -> FieldVar<F> {
+        let flattened: Vec<_> = self
+            .points
+            .iter()
+            .flatten()
+            .chain(&self.labels)
+            .cloned()
+            .collect();
+        hash_slice(sys, loc, &flattened)
+    }
+}
+
+/// Predicts the class of the input `x` among the classes of its `k` nearest points in a private reference set,
+/// the set being constrained to match `commitment` (see [ReferenceSet::commitment]).
+/// The class of the most neighbors wins, the lowest one on ties, as [Knn::predict].
+///
+/// # Panics
+///
+/// Will panic if the squared distances may not fit in the field.
+pub fn predict_knn<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    commitment: &FieldVar<F>,
+    reference: &ReferenceSetVar<F>,
+    x: &[FieldVar<F>],
+    width: usize,
+    knn: Knn,
+) -> SnarkyResult<FieldVar<F>> {
+    reference.check_shape(sys, x.len(), knn.k)?;
+    let digest = reference.commitment(sys, loc.clone());
+    sys.assert_eq(
+        Some("knn.commitment".into()),
+        loc.clone(),
+        digest,
+        commitment.clone(),
+    )?;
+
+    // each of the squared differences is below 2^(2 width)
+    let feature_bits = (usize::BITS - x.len().saturating_sub(1).leading_zeros()) as usize;
+    let distance_bits = 2 * width + feature_bits;
+    assert!(
+        distance_bits + 1 < F::size_in_bits(),
+        "the distances may not fit in the field"
+    );
+
+    let mut neighbors = Vec::with_capacity(reference.points.len());
+    for (point, label) in reference.points.iter().zip(&reference.labels) {
+        let mut squares = Vec::with_capacity(x.len());
+        for (p, x) in point.iter().zip(x) {
+            let difference = x - p;
+            squares.push(difference.mul(&difference, None, loc.clone(), sys)?);
+        }
+        let distance = FieldVar::sum_many(&squares);
+        to_bits(
+            sys,
+            loc.clone(),
+            &distance,
+            distance_bits,
+            Endianness::Little,
+        )?;
+        neighbors.push((distance, label.clone()));
+    }
+
+    // the selection network: pass i moves the nearest of the points from i on to i
+    let offset = FieldVar::constant(F::from(2u64).pow([distance_bits as u64]) - F::one());
+    for i in 0..knn.k {
+        for j in (i + 1..neighbors.len()).rev() {
+            let (near, near_label) = neighbors[j - 1].clone();
+            let (far, far_label) = neighbors[j].clone();
+            // near + 2^bits - 1 - far has its most significant bit set iff near > far
+            let shifted = near.clone() + &offset - &far;
+            let swap = to_bits(
+                sys,
+                loc.clone(),
+                &shifted,
+                distance_bits + 1,
+                Endianness::Little,
+            )?[distance_bits]
+                .clone();
+
+            let nearest = sys.if_(loc.clone(), swap.clone(), far.clone(), near.clone())?;
+            let nearest_label =
+                sys.if_(loc.clone(), swap, far_label.clone(), near_label.clone())?;
+            neighbors[j] = (
+                near + far - &nearest,
+                near_label + far_label - &nearest_label,
+            );
+            neighbors[j - 1] = (nearest, nearest_label);
+        }
+    }
+
+    let labels: Vec<_> = neighbors[..knn.k]
+        .iter()
+        .map(|(_, label)| label.clone())
+        .collect();
+    majority_vote(sys, &loc, &labels, knn.classes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{curve::KimchiCurve, loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Predicts the class of a private input of 2 features among 6 private points committed to by the public input.
+    struct TestCircuit(Knn);
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = ([[Fp; 2]; 6], [Fp; 6], [Fp; 2]);
+        type PublicInput = FieldVar<Fp>;
+        type PublicOutput = FieldVar<Fp>;
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            commitment: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let mut points = Vec::with_capacity(6);
+            for i in 0..6 {
+                let point: [FieldVar<Fp>; 2] = sys.compute(loc!(), |_| private.unwrap().0[i])?;
+                points.push(point.to_vec());
+            }
+            let labels: [FieldVar<Fp>; 6] = sys.compute(loc!(), |_| private.unwrap().1)?;
+            let x: [FieldVar<Fp>; 2] = sys.compute(loc!(), |_| private.unwrap().2)?;
+
+            let reference = ReferenceSetVar {
+                points,
+                labels: labels.to_vec(),
+            };
+            predict_knn(sys, loc!(), &commitment, &reference, &x, 8, self.0)
+        }
+    }
+
+    #[test]
+    fn snarky_knn() {
+        let reference = ReferenceSet {
+            points: vec![
+                vec![1, 1],
+                vec![2, 2],
+                vec![10, 10],
+                vec![11, 9],
+                vec![9, 11],
+                vec![3, 1],
+            ],
+            labels: [0u64, 0, 1, 1, 1, 0].map(Fp::from).to_vec(),
+        };
+        let commitment = reference.commitment(Vesta::sponge_params());
+        let x = [8u64, 8];
+        // the points 3 and 4 are at the same distance, and the first of them is the nearest
+        assert_eq!(reference.nearest(&x, 2), vec![2, 3]);
+        let knn = Knn { k: 3, classes: 2 };
+        assert_eq!(knn.predict(&reference, &x), Fp::from(1u64));
+
+        let private = |reference: &ReferenceSet<Fp>| {
+            let points: Vec<[Fp; 2]> = reference
+                .points
+                .iter()
+                .map(|point| [Fp::from(point[0]), Fp::from(point[1])])
+                .collect();
+            (
+                points.try_into().unwrap(),
+                reference.labels.clone().try_into().unwrap(),
+                x.map(Fp::from),
+            )
+        };
+
+        let (mut prover_index, verifier_index) = TestCircuit(knn).compile_to_indexes().unwrap();
+        let debug = true;
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>(commitment, private(&reference), debug)
+            .unwrap();
+        assert_eq!(*output, knn.predict(&reference, &x));
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, commitment, *output);
+
+        // another set does not match the commitment
+        let mut other = reference.clone();
+        other.labels[2] = Fp::from(0u64);
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>(commitment, private(&other), debug)
+            .is_err());
+    }
+}
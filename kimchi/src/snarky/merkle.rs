@@ -0,0 +1,128 @@
+//! A gadget checking the membership of a leaf in a Merkle tree,
+//! whose nodes are hashed with the Poseidon permutation used by [crate::snarky::poseidon::poseidon].
+//!
+//! A node is `poseidon(left, right).0`,
+//! and the path of a leaf lists its siblings from the bottom of the tree to the top.
+//! The bits of the index of the leaf (least significant first) tell whether the sibling is on the left.
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+use mina_poseidon::poseidon::ArithmeticSpongeParams;
+
+use crate::snarky::{
+    boolean::Boolean,
+    prelude::{FieldVar, RunState, SnarkyResult},
+    prf::poseidon_native,
+};
+
+/// Computes a node of the tree from its children, out of circuit.
+pub fn node<F: PrimeField>(params: &ArithmeticSpongeParams<F>, left: F, right: F) -> F {
+    poseidon_native(params, left, right)
+}
+
+/// Computes the root of the tree from a leaf and its path, out of circuit.
+pub fn root_from_path<F: PrimeField>(
+    params: &ArithmeticSpongeParams<F>,
+    leaf: F,
+    index: usize,
+    siblings: &[F],
+) -> F {
+    siblings
+        .iter()
+        .enumerate()
+        .fold(leaf, |current, (level, &sibling)| {
+            if (index >> level) & 1 == 1 {
+                node(params, sibling, current)
+            } else {
+                node(params, current, sibling)
+            }
+        })
+}
+
+/// Constrains `leaf` to be the leaf at position `index` of the tree of root `root`.
+/// The depth of the tree is the length of the path, and the index is constrained to fit in it.
+pub fn check_membership<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    root: &FieldVar<F>,
+    leaf: FieldVar<F>,
+    index: &FieldVar<F>,
+    siblings: &[FieldVar<F>],
+) -> SnarkyResult<()> {
+    let bits = Boolean::unpack(sys, loc.clone(), index, siblings.len())?;
+
+    let mut current = leaf;
+    for (bit, sibling) in bits.into_iter().zip(siblings) {
+        let left = sys.if_(loc.clone(), bit, sibling.clone(), current.clone())?;
+        let right = &current + sibling - &left;
+        current = sys.poseidon(loc.clone(), (left, right)).0;
+    }
+
+    sys.assert_eq(Some("merkle.root".into()), loc, current, root.clone())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{curve::KimchiCurve, loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Checks that a leaf belongs to a tree of depth 2 whose root is the public input.
+    struct TestCircuit {}
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = (Fp, Fp, [Fp; 2]);
+        type PublicInput = FieldVar<Fp>;
+        type PublicOutput = ();
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            root: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let leaf: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap().0)?;
+            let index: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap().1)?;
+            let siblings: [FieldVar<Fp>; 2] = sys.compute(loc!(), |_| private.unwrap().2)?;
+
+            check_membership(sys, loc!(), &root, leaf, &index, &siblings)
+        }
+    }
+
+    #[test]
+    fn snarky_merkle_membership() {
+        let params = Vesta::sponge_params();
+        let leaves: Vec<_> = (0..4u64).map(Fp::from).collect();
+        let left = node(params, leaves[0], leaves[1]);
+        let right = node(params, leaves[2], leaves[3]);
+        let root = node(params, left, right);
+
+        let siblings = [leaves[3], left];
+        assert_eq!(root_from_path(params, leaves[2], 2, &siblings), root);
+
+        let (mut prover_index, verifier_index) = TestCircuit {}.compile_to_indexes().unwrap();
+
+        let debug = true;
+        let (proof, _) = prover_index
+            .prove::<BaseSponge, ScalarSponge>(root, (leaves[2], Fp::from(2u64), siblings), debug)
+            .unwrap();
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, root, ());
+
+        // the leaf must be at the given index
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>(root, (leaves[2], Fp::from(3u64), siblings), debug)
+            .is_err());
+    }
+}
@@ -8,18 +8,40 @@
 //! See the `tests.rs` file for examples of how to use snarky.
 
 pub mod api;
+pub mod argmax;
 pub mod asm;
+pub mod bits;
 pub mod boolean;
+pub mod boosted_trees;
+pub mod checkpoint;
 pub mod constants;
 pub mod constraint_system;
+pub(crate) mod custom_gate;
 pub mod cvar;
+pub mod decision_tree;
+pub mod dense;
+pub mod early_exit;
+pub mod ec;
+pub mod embedding;
+pub mod epoch;
 pub mod errors;
 pub mod folding;
+pub mod knn;
+pub mod merkle;
+pub mod noise;
+pub mod pedersen;
 pub mod poseidon;
+pub mod prf;
 pub(crate) mod range_checks;
+pub mod rescue;
 pub mod runner;
+pub mod shard;
+pub mod shift;
 pub mod snarky_type;
+pub mod statistics;
+pub mod tolerance;
 pub mod union_find;
+pub mod witness_store;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,173 @@
+//! A gadget proving that calibrated noise was added to values before they are disclosed,
+//! for benchmarks combining differential privacy with zero-knowledge.
+//!
+//! The noise follows the binomial mechanism:
+//! each value receives `scale * (c - coins / 2)`, where `c` is the number of heads among `coins` fair coins,
+//! which approximates a Gaussian of standard deviation `scale * sqrt(coins) / 2`.
+//! The coins are the bits of values derived with a [Prf] from a committed seed,
+//! so the verifier knows that the noise was not chosen by the prover.
+
+use std::borrow::Cow;
+
+use ark_ff::{BigInteger, PrimeField};
+use mina_poseidon::poseidon::ArithmeticSpongeParams;
+
+use crate::snarky::{
+    boolean::Boolean,
+    prelude::{FieldVar, RunState, SnarkyResult},
+    prf::{prf, Prf},
+};
+
+/// The parameters of the binomial mechanism.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BinomialNoise {
+    /// The number of coins flipped for each value, must be even.
+    pub coins: usize,
+    /// The scale of the noise (for example the scale factor of fixed-point values).
+    pub scale: u64,
+}
+
+/// The number of coins taken from each pseudo-random value.
+/// As the modulus is larger than `2^coin_bits`, a value has at most one decomposition in that many bits,
+/// so the prover cannot pick the coins
+/// (values above `2^coin_bits`, which occur with negligible probability, cannot be decomposed).
+fn coin_bits<F: PrimeField>() -> usize {
+    F::size_in_bits() - 1
+}
+
+impl BinomialNoise {
+    /// Computes the noise added to `count` values, out of circuit.
+    pub fn sample<F: PrimeField>(
+        &self,
+        params: &ArithmeticSpongeParams<F>,
+        seed: F,
+        first_counter: u64,
+        count: usize,
+    ) -> Vec<F> {
+        let coins: Vec<bool> = (first_counter..)
+            .flat_map(|counter| {
+                let mut bits = prf(params, seed, counter).into_repr().to_bits_le();
+                bits.truncate(coin_bits::<F>());
+                bits
+            })
+            .take(count * self.coins)
+            .collect();
+
+        coins
+            .chunks(self.coins)
+            .map(|coins| {
+                let heads = coins.iter().filter(|&&coin| coin).count() as u64;
+                (F::from(heads) - F::from(self.coins as u64 / 2)) * F::from(self.scale)
+            })
+            .collect()
+    }
+
+    /// Adds noise derived from `prf` to the `values`, and returns the noised values.
+    /// The pseudo-random values with counters starting at `first_counter` are used,
+    /// in order, `coins` bits per value.
+    pub fn add<F: PrimeField>(
+        &self,
+        sys: &mut RunState<F>,
+        loc: Cow<'static, str>,
+        prf: &Prf<F>,
+        first_counter: u64,
+        values: &[FieldVar<F>],
+    ) -> SnarkyResult<Vec<FieldVar<F>>> {
+        assert!(self.coins % 2 == 0, "the number of coins must be even");
+
+        let mut coins: Vec<Boolean<F>> = vec![];
+        let mut counter = first_counter;
+        while coins.len() < values.len() * self.coins {
+            let value = prf.eval(sys, loc.clone(), counter);
+            coins.extend(Boolean::unpack(sys, loc.clone(), &value, coin_bits::<F>())?);
+            counter += 1;
+        }
+
+        let half = F::from(self.coins as u64 / 2);
+        let scale = F::from(self.scale);
+        let noised = values
+            .iter()
+            .zip(coins.chunks(self.coins))
+            .map(|(value, coins)| {
+                let heads: Vec<_> = coins
+                    .iter()
+                    .map(|coin| (scale, coin.to_field_var()))
+                    .collect();
+                value + FieldVar::linear_combination(&heads) - FieldVar::constant(half * scale)
+            })
+            .collect();
+        Ok(noised)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        curve::KimchiCurve,
+        loc,
+        snarky::{api::SnarkyCircuit, prf::commit_seed},
+    };
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    const NOISE: BinomialNoise = BinomialNoise {
+        coins: 200,
+        scale: 1 << 16,
+    };
+
+    /// Discloses two private values with noise derived from a committed seed.
+    struct TestCircuit {}
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = (Fp, [Fp; 2]);
+        type PublicInput = FieldVar<Fp>;
+        type PublicOutput = [FieldVar<Fp>; 2];
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            commitment: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let seed: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap().0)?;
+            let values: [FieldVar<Fp>; 2] = sys.compute(loc!(), |_| private.unwrap().1)?;
+
+            let prf = Prf::new(sys, loc!(), seed);
+            sys.assert_eq(None, loc!(), prf.commitment().clone(), commitment)?;
+
+            let noised = NOISE.add(sys, loc!(), &prf, 0, &values)?;
+            Ok(noised.try_into().unwrap())
+        }
+    }
+
+    #[test]
+    fn snarky_binomial_noise() {
+        let params = Vesta::sponge_params();
+        let seed = Fp::from(7u64);
+        let values = [Fp::from(1000u64), Fp::from(2000u64)];
+        let commitment = commit_seed(params, seed);
+
+        let (mut prover_index, verifier_index) = TestCircuit {}.compile_to_indexes().unwrap();
+
+        let debug = true;
+        let (proof, public_output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>(commitment, (seed, values), debug)
+            .unwrap();
+
+        let noise = NOISE.sample(params, seed, 0, 2);
+        assert_eq!(*public_output, [values[0] + noise[0], values[1] + noise[1]]);
+
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, commitment, *public_output);
+    }
+}
@@ -0,0 +1,195 @@
+//! A bridge for inputs provided as Pedersen commitments,
+//! for hybrid pipelines where the inputs of a model are produced by an MPC protocol
+//! (which can compute on homomorphic commitments) and the inference is proven in zero-knowledge.
+//!
+//! The inputs `x_i` are committed as `C = sum x_i * g_i + r * h` with a blinding factor `r`,
+//! where the bases are taken from an [SRS], so that nobody knows their discrete logarithms.
+//! The circuit proves that the commitment (given in the public input) opens to its witness inputs.
+//!
+//! The commitment lives on a curve whose base field is the field of the circuit
+//! (for example a Pallas commitment for a circuit over the scalar field of Vesta).
+//! Inputs must fit in [PedersenBridge::bits] bits, which the opening enforces,
+//! and the blinding factor must fit in one bit less than the field.
+
+use std::borrow::Cow;
+
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::PrimeField;
+use poly_commitment::{commitment::CommitmentCurve, srs::SRS};
+
+use crate::snarky::{
+    boolean::Boolean,
+    ec::{add_complete, add_fixed_base_multiple, constant_point, PointVar},
+    prelude::{FieldVar, RunState, SnarkyResult},
+};
+
+/// The bases of a Pedersen commitment to a fixed number of inputs.
+#[derive(Clone, Debug)]
+pub struct PedersenBridge<C> {
+    /// The base of each input.
+    pub bases: Vec<C>,
+    /// The base of the blinding factor.
+    pub blinding_base: C,
+    /// The point the in-circuit accumulation starts from,
+    /// so that it never hits the exceptional cases of the addition.
+    pub offset: C,
+    /// The number of bits of each input.
+    pub bits: usize,
+}
+
+impl<C> PedersenBridge<C>
+where
+    C: CommitmentCurve,
+    C::BaseField: PrimeField,
+{
+    /// Takes the bases of a commitment to `inputs` values from an SRS.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the SRS has less than `inputs + 1` bases,
+    /// or if inputs of `bits` bits do not fit in both fields of the curve.
+    pub fn new(srs: &SRS<C>, inputs: usize, bits: usize) -> Self {
+        assert!(srs.g.len() > inputs, "the SRS is too small");
+        assert!(bits < Self::blinding_bits(), "the inputs are too large");
+        Self {
+            bases: srs.g[..inputs].to_vec(),
+            blinding_base: srs.h,
+            offset: srs.g[inputs],
+            bits,
+        }
+    }
+
+    /// The number of bits of the blinding factor.
+    pub fn blinding_bits() -> usize {
+        let bits = C::BaseField::size_in_bits().min(C::ScalarField::size_in_bits());
+        bits - 1
+    }
+
+    /// Commits to the inputs, out of circuit.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the number of inputs does not match the bases.
+    pub fn commit(&self, inputs: &[C::BaseField], blinding: C::BaseField) -> C {
+        assert_eq!(inputs.len(), self.bases.len());
+        let mut commitment = self
+            .blinding_base
+            .into_projective()
+            .mul(blinding.into_repr());
+        for (base, input) in self.bases.iter().zip(inputs) {
+            commitment += base.into_projective().mul(input.into_repr());
+        }
+        commitment.into_affine()
+    }
+
+    /// Constrains a commitment to open to the given inputs.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the number of inputs does not match the bases.
+    pub fn open(
+        &self,
+        sys: &mut RunState<C::BaseField>,
+        loc: Cow<'static, str>,
+        commitment: &PointVar<C::BaseField>,
+        inputs: &[FieldVar<C::BaseField>],
+        blinding: &FieldVar<C::BaseField>,
+    ) -> SnarkyResult<()> {
+        assert_eq!(inputs.len(), self.bases.len());
+
+        let mut acc = constant_point(&self.offset);
+        for (base, input) in self.bases.iter().zip(inputs) {
+            let bits = Boolean::unpack(sys, loc.clone(), input, self.bits)?;
+            acc = add_fixed_base_multiple(sys, loc.clone(), acc, *base, &bits)?;
+        }
+        let bits = Boolean::unpack(sys, loc.clone(), blinding, Self::blinding_bits())?;
+        acc = add_fixed_base_multiple(sys, loc.clone(), acc, self.blinding_base, &bits)?;
+
+        let (x, y) = add_complete(sys, loc.clone(), &acc, &constant_point(&-self.offset))?;
+        sys.assert_eq(
+            Some("pedersen.x".into()),
+            loc.clone(),
+            x,
+            commitment.0.clone(),
+        )?;
+        sys.assert_eq(Some("pedersen.y".into()), loc, y, commitment.1.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Pallas, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Opens a commitment to two inputs given in the public input, and outputs their sum.
+    struct TestCircuit {
+        bridge: PedersenBridge<Pallas>,
+    }
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = ([Fp; 2], Fp);
+        type PublicInput = (FieldVar<Fp>, FieldVar<Fp>);
+        type PublicOutput = FieldVar<Fp>;
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            commitment: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let inputs: [FieldVar<Fp>; 2] = sys.compute(loc!(), |_| private.unwrap().0)?;
+            let blinding: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap().1)?;
+
+            self.bridge
+                .open(sys, loc!(), &commitment, &inputs, &blinding)?;
+
+            Ok(&inputs[0] + &inputs[1])
+        }
+    }
+
+    #[test]
+    fn snarky_pedersen_bridge() {
+        let srs = SRS::<Pallas>::create(4);
+        let bridge = PedersenBridge::new(&srs, 2, 16);
+
+        let inputs = [Fp::from(1234u64), Fp::from(u16::MAX)];
+        let blinding = Fp::from(987654321u64);
+        let commitment = bridge.commit(&inputs, blinding).to_coordinates().unwrap();
+
+        let (mut prover_index, verifier_index) =
+            TestCircuit { bridge }.compile_to_indexes().unwrap();
+
+        let debug = true;
+        let (proof, public_output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>(commitment, (inputs, blinding), debug)
+            .unwrap();
+        assert_eq!(*public_output, inputs[0] + inputs[1]);
+
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, commitment, *public_output);
+
+        // the commitment must open to the witness inputs
+        let other_inputs = [inputs[0] + Fp::from(1u64), inputs[1]];
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>(commitment, (other_inputs, blinding), debug)
+            .is_err());
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>(
+                commitment,
+                (inputs, blinding + Fp::from(1u64)),
+                debug
+            )
+            .is_err());
+    }
+}
@@ -4,7 +4,8 @@ use crate::{
     circuits::polynomials::poseidon::{ROUNDS_PER_HASH, ROUNDS_PER_ROW, SPONGE_WIDTH},
     snarky::{
         constraint_system::KimchiConstraint,
-        prelude::{FieldVar, RunState},
+        errors::SnarkyCompilationError,
+        prelude::{FieldVar, RunState, SnarkyResult},
         runner::Constraint,
     },
 };
@@ -80,6 +81,60 @@ fn round<F: PrimeField>(
         .expect("compiler bug")
 }
 
+//
+// Linear layer
+//
+
+/// Applies a matrix to a vector, out of circuit.
+fn apply_matrix<F: PrimeField>(matrix: &[Vec<F>], inputs: &[F]) -> Vec<F> {
+    matrix
+        .iter()
+        .map(|row| row.iter().zip(inputs).map(|(m, x)| *m * x).sum())
+        .collect()
+}
+
+/// Applies the MDS matrix of the Poseidon permutation to a state, out of circuit.
+pub fn mds_native<F: PrimeField>(params: &ArithmeticSpongeParams<F>, state: &[F]) -> Vec<F> {
+    apply_matrix(&params.mds, state)
+}
+
+/// Applies a matrix fixed in the circuit to a vector of variables.
+/// Each output is a linear combination of the inputs, sealed into a variable:
+/// sealing reduces the combination two terms at a time, so an output of `n` inputs (with non-zero coefficients)
+/// costs `n - 1` generic constraints, two of which share a row, and no multiplication.
+pub fn linear_transform<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    matrix: &[Vec<F>],
+    inputs: &[FieldVar<F>],
+) -> SnarkyResult<Vec<FieldVar<F>>> {
+    let mut outputs = Vec::with_capacity(matrix.len());
+    for row in matrix {
+        if row.len() != inputs.len() {
+            return Err(sys.compilation_error(SnarkyCompilationError::ShapeMismatch(
+                "inputs",
+                "linear transform".to_string(),
+                inputs.len(),
+                row.len(),
+            )));
+        }
+        let terms: Vec<_> = row.iter().zip(inputs).map(|(m, x)| x.scale(*m)).collect();
+        outputs.push(FieldVar::sum_many(&terms).seal(sys, loc.clone())?);
+    }
+    Ok(outputs)
+}
+
+/// Applies the MDS matrix of the Poseidon permutation of the circuit (the linear layer of its rounds) to a state,
+/// as a standalone [linear_transform].
+pub fn mds<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    state: &[FieldVar<F>],
+) -> SnarkyResult<Vec<FieldVar<F>>> {
+    let params = sys.poseidon_params();
+    linear_transform(sys, loc, &params.mds, state)
+}
+
 //
 // Duplex API
 //
@@ -156,6 +211,9 @@ where
 
     /// Permute. You should most likely not use this function directly,
     /// and use [Self::absorb] and [Self::squeeze] instead.
+    ///
+    /// The rate is replaced by the output of the permutation,
+    /// so that the next permutation (to absorb or squeeze more) starts from it.
     fn permute(
         &mut self,
         sys: &mut RunState<F>,
@@ -163,7 +221,10 @@ where
     ) -> (FieldVar<F>, FieldVar<F>) {
         let left = self.state[0].clone();
         let right = self.state[1].clone();
-        sys.poseidon(loc, (left, right))
+        let (left, right) = sys.poseidon(loc, (left, right));
+        self.state[0] = left.clone();
+        self.state[1] = right.clone();
+        (left, right)
     }
 
     /// Squeeze.
@@ -192,6 +253,16 @@ where
         self.squeezed = Some(right);
         left
     }
+
+    /// Squeezes `n` elements, with `n` calls to [Self::squeeze].
+    pub fn squeeze_n(
+        &mut self,
+        sys: &mut RunState<F>,
+        loc: Cow<'static, str>,
+        n: usize,
+    ) -> Vec<FieldVar<F>> {
+        (0..n).map(|_| self.squeeze(sys, loc.clone())).collect()
+    }
 }
 
 // TODO: create a macro to derive this function automatically
@@ -201,3 +272,241 @@ where
 {
     fn absorb(&self, duplex: &mut DuplexState<F>, sys: &mut RunState<F>);
 }
+
+//
+// Hashing slices
+//
+
+/// Applies the permutation of the duplex to its rate, out of circuit.
+fn permute_native<F: PrimeField>(params: &ArithmeticSpongeParams<F>, rate: &mut [F; RATE_SIZE]) {
+    let mut state = vec![rate[0], rate[1], F::zero()];
+    for round in 0..ROUNDS_PER_HASH {
+        full_round::<F, PlonkSpongeConstantsKimchi>(params, &mut state, round);
+    }
+    rate.copy_from_slice(&state[..RATE_SIZE]);
+}
+
+/// Hashes a slice of any length into a single digest, with a fresh [DuplexState].
+///
+/// The padding rule encodes the length: the sponge absorbs the number of inputs, then the inputs, and squeezes once.
+/// Without it, a slice and the same slice followed by a zero would collide,
+/// as the last rate is filled with zeros. See [hash_slice_native] for the same hash out of circuit.
+pub fn hash_slice<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    inputs: &[FieldVar<F>],
+) -> FieldVar<F> {
+    let mut sponge = DuplexState::new();
+    let length = FieldVar::constant(F::from(inputs.len() as u64));
+    sponge.absorb(sys, loc.clone(), &[length]);
+    sponge.absorb(sys, loc.clone(), inputs);
+    sponge.squeeze(sys, loc)
+}
+
+/// Computes [hash_slice] out of circuit.
+pub fn hash_slice_native<F: PrimeField>(params: &ArithmeticSpongeParams<F>, inputs: &[F]) -> F {
+    let padded: Vec<F> = std::iter::once(F::from(inputs.len() as u64))
+        .chain(inputs.iter().copied())
+        .collect();
+    let mut rate = [F::zero(); RATE_SIZE];
+    for chunk in padded.chunks(RATE_SIZE) {
+        for (x, input) in rate.iter_mut().zip(chunk) {
+            *x += input;
+        }
+        permute_native(params, &mut rate);
+    }
+    rate[0]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Applies the MDS matrix to the private input.
+    struct TestCircuit;
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = [Fp; SPONGE_WIDTH];
+        type PublicInput = ();
+        type PublicOutput = [FieldVar<Fp>; SPONGE_WIDTH];
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let state: [FieldVar<Fp>; SPONGE_WIDTH] = sys.compute(loc!(), |_| *private.unwrap())?;
+            let output = mds(sys, loc!(), &state)?;
+            Ok(output.try_into().unwrap())
+        }
+    }
+
+    /// Applies a fixed matrix to the private input, or returns the input as is without a matrix.
+    struct LinearCircuit(Option<Vec<Vec<Fp>>>);
+
+    impl SnarkyCircuit for LinearCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = [Fp; 4];
+        type PublicInput = ();
+        type PublicOutput = [FieldVar<Fp>; 4];
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let inputs: [FieldVar<Fp>; 4] = sys.compute(loc!(), |_| *private.unwrap())?;
+            match &self.0 {
+                Some(matrix) => Ok(linear_transform(sys, loc!(), matrix, &inputs)?
+                    .try_into()
+                    .unwrap()),
+                None => Ok(inputs),
+            }
+        }
+    }
+
+    /// Squeezes the same sponge with [DuplexState::squeeze_n] and with [DuplexState::squeeze].
+    struct SqueezeCircuit;
+
+    impl SnarkyCircuit for SqueezeCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = [Fp; 3];
+        type PublicInput = ();
+        type PublicOutput = [FieldVar<Fp>; 5];
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let inputs: [FieldVar<Fp>; 3] = sys.compute(loc!(), |_| *private.unwrap())?;
+
+            let mut sponge = DuplexState::new();
+            sponge.absorb(sys, loc!(), &inputs);
+            let mut squeezed = vec![sponge.squeeze(sys, loc!())];
+            squeezed.extend(sponge.squeeze_n(sys, loc!(), 4));
+
+            let mut other = DuplexState::new();
+            other.absorb(sys, loc!(), &inputs);
+            for expected in &squeezed {
+                other
+                    .squeeze(sys, loc!())
+                    .assert_equals(sys, loc!(), expected)?;
+            }
+            Ok(squeezed.try_into().unwrap())
+        }
+    }
+
+    #[test]
+    fn snarky_squeeze_n() {
+        let (mut prover_index, verifier_index) = SqueezeCircuit.compile_to_indexes().unwrap();
+
+        let debug = true;
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), [1u64, 2, 3].map(Fp::from), debug)
+            .unwrap();
+        // every permutation gives new elements
+        assert_ne!(output[0], output[2]);
+        assert_ne!(output[2], output[4]);
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+    }
+
+    /// Hashes the private inputs with [hash_slice].
+    struct HashSliceCircuit;
+
+    impl SnarkyCircuit for HashSliceCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = [Fp; 5];
+        type PublicInput = ();
+        type PublicOutput = FieldVar<Fp>;
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let inputs: [FieldVar<Fp>; 5] = sys.compute(loc!(), |_| *private.unwrap())?;
+            Ok(hash_slice(sys, loc!(), &inputs))
+        }
+    }
+
+    #[test]
+    fn snarky_hash_slice() {
+        let params = mina_poseidon::pasta::fp_kimchi::static_params();
+        let inputs = [1u64, 2, 3, 4, 5].map(Fp::from);
+
+        let (mut prover_index, verifier_index) = HashSliceCircuit.compile_to_indexes().unwrap();
+        let debug = true;
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), inputs, debug)
+            .unwrap();
+        assert_eq!(*output, hash_slice_native(params, &inputs));
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+
+        // the length tells apart a trailing zero
+        let one = [Fp::from(1u64)];
+        let padded = [Fp::from(1u64), Fp::from(0u64)];
+        assert_ne!(
+            hash_slice_native(params, &one),
+            hash_slice_native(params, &padded)
+        );
+    }
+
+    #[test]
+    fn snarky_linear_transform_rows() {
+        let matrix: Vec<Vec<Fp>> = (0..4u64)
+            .map(|i| (1..=4u64).map(|j| Fp::from(4 * i + j)).collect())
+            .collect();
+        let (identity, _) = LinearCircuit(None).compile_to_indexes().unwrap();
+        let (mut prover_index, verifier_index) = LinearCircuit(Some(matrix.clone()))
+            .compile_to_indexes()
+            .unwrap();
+        // each of the 4 outputs takes 3 generic constraints, two to a row
+        assert_eq!(prover_index.num_gates() - identity.num_gates(), 4 * 3 / 2);
+
+        let inputs = [1u64, 2, 3, 4].map(Fp::from);
+        let debug = true;
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), inputs, debug)
+            .unwrap();
+        assert_eq!(output.to_vec(), apply_matrix(&matrix, &inputs));
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+    }
+
+    #[test]
+    fn snarky_mds() {
+        let (mut prover_index, verifier_index) = TestCircuit.compile_to_indexes().unwrap();
+
+        let state = [1u64, 2, 3].map(Fp::from);
+        let expected = mds_native(mina_poseidon::pasta::fp_kimchi::static_params(), &state);
+        let debug = true;
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), state, debug)
+            .unwrap();
+        assert_eq!(output.to_vec(), expected);
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+    }
+}
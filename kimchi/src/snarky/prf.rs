@@ -0,0 +1,139 @@
+//! A pseudo-random function (PRF) based on the Poseidon permutation,
+//! to derive verifiable pseudo-random values (for example for stochastic layers) from a committed seed.
+//!
+//! The seed is committed as `poseidon(seed, 0).0`,
+//! and the `i`-th pseudo-random value is `poseidon(seed, i + 1).0`.
+//! Counters are constants, so that the values used by a circuit are fixed at compile time.
+//! The commitment is only hiding if the seed is sampled uniformly.
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi, permutation::full_round,
+    poseidon::ArithmeticSpongeParams,
+};
+
+use crate::{
+    circuits::polynomials::poseidon::ROUNDS_PER_HASH,
+    snarky::prelude::{FieldVar, RunState},
+};
+
+/// Computes the first output of the permutation used by [crate::snarky::poseidon::poseidon].
+pub(crate) fn poseidon_native<F: PrimeField>(
+    params: &ArithmeticSpongeParams<F>,
+    left: F,
+    right: F,
+) -> F {
+    let mut state = vec![left, right, F::zero()];
+    for round in 0..ROUNDS_PER_HASH {
+        full_round::<F, PlonkSpongeConstantsKimchi>(params, &mut state, round);
+    }
+    state[0]
+}
+
+/// Computes the commitment to a seed, out of circuit.
+pub fn commit_seed<F: PrimeField>(params: &ArithmeticSpongeParams<F>, seed: F) -> F {
+    poseidon_native(params, seed, F::zero())
+}
+
+/// Computes the `counter`-th pseudo-random value derived from a seed, out of circuit.
+pub fn prf<F: PrimeField>(params: &ArithmeticSpongeParams<F>, seed: F, counter: u64) -> F {
+    poseidon_native(params, seed, F::from(counter) + F::one())
+}
+
+/// A PRF keyed by a seed in the circuit.
+pub struct Prf<F>
+where
+    F: PrimeField,
+{
+    seed: FieldVar<F>,
+    commitment: FieldVar<F>,
+}
+
+impl<F> Prf<F>
+where
+    F: PrimeField,
+{
+    /// Creates a PRF from a seed, and computes the commitment to the seed.
+    pub fn new(sys: &mut RunState<F>, loc: Cow<'static, str>, seed: FieldVar<F>) -> Self {
+        let (commitment, _) = sys.poseidon(loc, (seed.clone(), FieldVar::zero()));
+        Self { seed, commitment }
+    }
+
+    /// The commitment to the seed.
+    /// Circuits should constrain it to a public input, so that the verifier knows which seed was used.
+    pub fn commitment(&self) -> &FieldVar<F> {
+        &self.commitment
+    }
+
+    /// Derives the `counter`-th pseudo-random value.
+    pub fn eval(&self, sys: &mut RunState<F>, loc: Cow<'static, str>, counter: u64) -> FieldVar<F> {
+        let input = FieldVar::constant(F::from(counter) + F::one());
+        sys.poseidon(loc, (self.seed.clone(), input)).0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{curve::KimchiCurve, loc, snarky::api::SnarkyCircuit, SnarkyResult};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Derives the third pseudo-random value from a seed committed in the public input.
+    struct TestCircuit {}
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = Fp;
+        type PublicInput = FieldVar<Fp>;
+        type PublicOutput = FieldVar<Fp>;
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            commitment: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let seed: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+
+            let prf = Prf::new(sys, loc!(), seed);
+            sys.assert_eq(None, loc!(), prf.commitment().clone(), commitment)?;
+
+            Ok(prf.eval(sys, loc!(), 3))
+        }
+    }
+
+    #[test]
+    fn snarky_prf() {
+        let params = Vesta::sponge_params();
+        let seed = Fp::from(42u64);
+        let commitment = commit_seed(params, seed);
+        assert_ne!(prf(params, seed, 0), prf(params, seed, 1));
+
+        let (mut prover_index, verifier_index) = TestCircuit {}.compile_to_indexes().unwrap();
+
+        let debug = true;
+        let (proof, public_output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>(commitment, seed, debug)
+            .unwrap();
+        assert_eq!(*public_output, prf(params, seed, 3));
+
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, commitment, *public_output);
+
+        // the seed must match the commitment
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>(commitment, seed + Fp::from(1u64), debug)
+            .is_err());
+    }
+}
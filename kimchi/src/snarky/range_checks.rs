@@ -1,4 +1,4 @@
-use super::{constraint_system::KimchiConstraint, runner::Constraint};
+use super::{boolean::Boolean, constraint_system::KimchiConstraint, runner::Constraint};
 use crate::{circuits::polynomial::COLUMNS, FieldVar, RunState, SnarkyResult};
 use ark_ff::{BigInteger, PrimeField};
 use itertools::Itertools;
@@ -154,11 +154,43 @@ pub fn range_check<F: PrimeField>(
     Ok(())
 }
 
+/// The number of bits that the values of a range check must fit in.
+const RANGE_CHECK_BITS: usize = 88;
+
+/// Constrains the 3 provided values to fit in 88 bits, using generic gates only
+/// (for backends that do not support the range check gates)
+pub fn range_check_generic<F: PrimeField>(
+    runner: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    v0: FieldVar<F>,
+    v1: FieldVar<F>,
+    v2: FieldVar<F>,
+) -> SnarkyResult<()> {
+    for v in [v0, v1, v2] {
+        let bits: [Boolean<F>; RANGE_CHECK_BITS] = runner.compute(loc.clone(), |w| {
+            let bits = w.read_var(&v).into_repr().to_bits_le();
+            std::array::from_fn(|i| bits[i])
+        })?;
+
+        let terms = bits
+            .iter()
+            .enumerate()
+            .map(|(i, bit)| (F::from(2u64).pow([i as u64]), bit.to_field_var()))
+            .collect_vec();
+        let recomposed = FieldVar::linear_combination(&terms);
+
+        runner.assert_eq(Some("Range check".into()), loc.clone(), recomposed, v)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
-        circuits::expr::constraints::ExprOps, loc, snarky::api::SnarkyCircuit, FieldVar, RunState,
-        SnarkyResult,
+        circuits::{capabilities::Capabilities, expr::constraints::ExprOps},
+        loc,
+        snarky::api::SnarkyCircuit,
+        FieldVar, RunState, SnarkyResult,
     };
     use mina_curves::pasta::{Fp, Vesta, VestaParameters};
     use mina_poseidon::{
@@ -248,4 +280,49 @@ mod test {
                 .unwrap();
         }
     }
+
+    /// Same as [TestCircuit], compiled for a backend without range check gates.
+    struct GenericTestCircuit {}
+
+    impl SnarkyCircuit for GenericTestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = Fp;
+        type PublicInput = ();
+        type PublicOutput = ();
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _public: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            TestCircuit {}.circuit(sys, (), private)
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities::generic_only(1 << 16)
+        }
+    }
+
+    #[test]
+    fn snarky_range_check_generic() {
+        let (mut prover_index, verifier_index) =
+            GenericTestCircuit {}.compile_to_indexes().unwrap();
+        assert!(!prover_index.asm().contains("RangeCheck"));
+
+        let private_input = Fp::from(2).pow(88) - Fp::from(1);
+        let debug = true;
+        let (proof, _public_output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), private_input, debug)
+            .unwrap();
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), ());
+
+        // a value that does not fit in 88 bits is rejected
+        let private_input = Fp::from(2).pow(88);
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>((), private_input, debug)
+            .is_err());
+    }
 }
@@ -0,0 +1,239 @@
+//! The Rescue-Prime permutation, as an alternative to Poseidon for commitment hashing.
+//!
+//! A round of Rescue-Prime is two half-rounds over the state: the first raises each element to `alpha`,
+//! the second to `1/alpha` (the inverse of `x^alpha`), each followed by the MDS matrix and the addition of round constants.
+//! Kimchi has no gate for it, so each power `x^7` costs 4 generic multiplications,
+//! and each inverse power is a hinted value `y` checked with `y^7 = x`, which costs as much.
+//! Rescue-Prime needs far fewer rounds than the 55 full rounds of kimchi's Poseidon,
+//! but every round is made of generic constraints instead of a dedicated gate:
+//! see [crate::bench::hashes] for the comparison.
+//!
+//! The round constants and the MDS matrix are generated with [crate::poseidon_params::generate].
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+
+use crate::{
+    poseidon_params::{generate, PoseidonConfig},
+    snarky::prelude::{FieldVar, RunState, SnarkyResult},
+};
+
+/// The power of the S-box.
+pub const RESCUE_ALPHA: u64 = 7;
+
+/// The number of rounds used for the comparison with Poseidon, over a state of 3 elements.
+pub const RESCUE_ROUNDS: usize = 8;
+
+/// The parameters of a Rescue-Prime permutation.
+#[derive(Clone, Debug)]
+pub struct RescueParams<F> {
+    /// The exponent of the inverse S-box, `1/alpha` modulo `p - 1`, in little-endian limbs.
+    pub alpha_inv: Vec<u64>,
+    /// The round constants of each half-round.
+    pub round_constants: Vec<Vec<F>>,
+    pub mds: Vec<Vec<F>>,
+}
+
+/// The inverse of `alpha` modulo `p - 1`, in little-endian limbs:
+/// the `(k (p - 1) + 1) / alpha` for the `k` that makes it exact.
+fn inverse_exponent<F: PrimeField>(alpha: u64) -> Vec<u64> {
+    let mut p_minus_one = F::characteristic().to_vec();
+    // the characteristic is odd
+    p_minus_one[0] -= 1;
+
+    for k in 1..alpha {
+        // k (p - 1) + 1
+        let mut limbs = Vec::with_capacity(p_minus_one.len() + 1);
+        let mut carry = 1u128;
+        for limb in &p_minus_one {
+            let value = u128::from(*limb) * u128::from(k) + carry;
+            limbs.push(value as u64);
+            carry = value >> 64;
+        }
+        limbs.push(carry as u64);
+
+        let mut remainder = 0u128;
+        let mut quotient = vec![0u64; limbs.len()];
+        for (i, limb) in limbs.iter().enumerate().rev() {
+            let value = (remainder << 64) | u128::from(*limb);
+            quotient[i] = (value / u128::from(alpha)) as u64;
+            remainder = value % u128::from(alpha);
+        }
+        if remainder == 0 {
+            return quotient;
+        }
+    }
+    panic!("x^{alpha} is not a permutation of the field");
+}
+
+impl<F: PrimeField> RescueParams<F> {
+    /// Generates the parameters of a permutation of `rounds` rounds over `width` elements from a seed.
+    pub fn new(width: usize, rounds: usize, seed: &[u8]) -> Self {
+        let config = PoseidonConfig {
+            width,
+            full_rounds: 2 * rounds,
+            partial_rounds: 0,
+            alpha: RESCUE_ALPHA,
+        };
+        let params = generate::<F>(&config, seed);
+        Self {
+            alpha_inv: inverse_exponent::<F>(RESCUE_ALPHA),
+            round_constants: params.round_constants,
+            mds: params.mds,
+        }
+    }
+
+    /// The parameters used for the comparison with Poseidon.
+    pub fn default_params() -> Self {
+        Self::new(3, RESCUE_ROUNDS, b"rescue-prime")
+    }
+}
+
+/// Applies the permutation to a state, out of circuit.
+pub fn rescue_native<F: PrimeField>(params: &RescueParams<F>, state: &mut [F]) {
+    for (half_round, constants) in params.round_constants.iter().enumerate() {
+        for x in state.iter_mut() {
+            *x = if half_round % 2 == 0 {
+                x.pow([RESCUE_ALPHA])
+            } else {
+                x.pow(&params.alpha_inv)
+            };
+        }
+        let mixed: Vec<F> = params
+            .mds
+            .iter()
+            .zip(constants)
+            .map(|(row, constant)| {
+                row.iter().zip(state.iter()).map(|(m, x)| *m * x).sum::<F>() + constant
+            })
+            .collect();
+        state.copy_from_slice(&mixed);
+    }
+}
+
+/// Computes the first output of the permutation of `[left, right, 0]`, out of circuit.
+pub fn rescue_hash_native<F: PrimeField>(params: &RescueParams<F>, left: F, right: F) -> F {
+    let mut state = [left, right, F::zero()];
+    rescue_native(params, &mut state);
+    state[0]
+}
+
+/// Raises a variable to [RESCUE_ALPHA] with 4 multiplications.
+fn pow7<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: &Cow<'static, str>,
+    x: &FieldVar<F>,
+) -> SnarkyResult<FieldVar<F>> {
+    let x2 = x.mul(x, None, loc.clone(), sys)?;
+    let x4 = x2.mul(&x2, None, loc.clone(), sys)?;
+    let x6 = x4.mul(&x2, None, loc.clone(), sys)?;
+    x6.mul(x, None, loc.clone(), sys)
+}
+
+/// Applies the permutation to a state.
+pub fn rescue<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    params: &RescueParams<F>,
+    state: &[FieldVar<F>],
+) -> SnarkyResult<Vec<FieldVar<F>>> {
+    let mut state = state.to_vec();
+    for (half_round, constants) in params.round_constants.iter().enumerate() {
+        let mut powered = Vec::with_capacity(state.len());
+        for x in &state {
+            if half_round % 2 == 0 {
+                powered.push(pow7(sys, &loc, x)?);
+            } else {
+                let alpha_inv = &params.alpha_inv;
+                let y: FieldVar<F> =
+                    sys.compute(loc.clone(), |env| env.read_var(x).pow(alpha_inv))?;
+                pow7(sys, &loc, &y)?.assert_equals(sys, loc.clone(), x)?;
+                powered.push(y);
+            }
+        }
+
+        // the linear layer is free
+        state = params
+            .mds
+            .iter()
+            .zip(constants)
+            .map(|(row, constant)| {
+                let terms: Vec<_> = row.iter().zip(&powered).map(|(m, x)| x.scale(*m)).collect();
+                FieldVar::sum_many(&terms) + FieldVar::constant(*constant)
+            })
+            .collect();
+    }
+    Ok(state)
+}
+
+/// Computes the first output of the permutation of `[left, right, 0]`.
+pub fn rescue_hash<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    params: &RescueParams<F>,
+    left: FieldVar<F>,
+    right: FieldVar<F>,
+) -> SnarkyResult<FieldVar<F>> {
+    let state = rescue(sys, loc, params, &[left, right, FieldVar::zero()])?;
+    Ok(state[0].clone())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Hashes the two private inputs.
+    struct TestCircuit {
+        params: RescueParams<Fp>,
+    }
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = (Fp, Fp);
+        type PublicInput = ();
+        type PublicOutput = FieldVar<Fp>;
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let (left, right): (FieldVar<Fp>, FieldVar<Fp>) =
+                sys.compute(loc!(), |_| *private.unwrap())?;
+            rescue_hash(sys, loc!(), &self.params, left, right)
+        }
+    }
+
+    #[test]
+    fn snarky_rescue() {
+        let params = RescueParams::<Fp>::default_params();
+
+        // the inverse S-box inverts the S-box
+        let x = Fp::from(42u64);
+        assert_eq!(x.pow([RESCUE_ALPHA]).pow(&params.alpha_inv), x);
+
+        let expected = rescue_hash_native(&params, Fp::from(1u64), Fp::from(2u64));
+        let circuit = TestCircuit { params };
+        let (mut prover_index, verifier_index) = circuit.compile_to_indexes().unwrap();
+        let debug = true;
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), (Fp::from(1u64), Fp::from(2u64)), debug)
+            .unwrap();
+        assert_eq!(*output, expected);
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+    }
+}
@@ -1,18 +1,26 @@
 //! The circuit-generation and witness-generation logic.
 
-use std::borrow::Cow;
+use std::{borrow::Cow, convert::Infallible};
 
 use super::{
     api::Witness,
     constants::Constants,
+    custom_gate::custom_gate,
     errors::{
         RealSnarkyError, SnarkyCompilationError, SnarkyError, SnarkyResult, SnarkyRuntimeResult,
     },
     poseidon::poseidon,
-    range_checks::range_check,
+    range_checks::{range_check, range_check_generic},
+    shard::{substitute, substitute_constraint},
+    witness_store::WitnessMatrix,
 };
 use crate::{
-    circuits::gate::CircuitGate,
+    circuits::{
+        argument::ArgumentWitness,
+        capabilities::Capabilities,
+        custom_gate::RegisteredGate,
+        gate::{CircuitGate, GateType},
+    },
     curve::KimchiCurve,
     snarky::{
         boolean::Boolean,
@@ -23,6 +31,7 @@ use crate::{
     },
 };
 use ark_ff::PrimeField;
+use rayon::prelude::*;
 
 impl<F> Constraint<F>
 where
@@ -100,6 +109,36 @@ where
     /// A map from a constraint index to a source location
     /// (usually a file name and line number).
     constraints_locations: Vec<Cow<'static, str>>,
+
+    /// The capabilities of the backend the circuit is compiled for,
+    /// used to pick the implementation of gadgets.
+    pub capabilities: Capabilities,
+
+    /// The constraints buffered by a shard when compiling, replayed by its parent (see [RunState::shard_map]).
+    shard_constraints: Option<Vec<(Constraint<F>, Cow<'static, str>)>>,
+
+    /// If set, compiling fails as soon as the circuit no longer fits a domain of `2^max_domain_log2` rows,
+    /// instead of failing later when creating the domains (see [RunState::with_layer]).
+    pub max_domain_log2: Option<usize>,
+
+    /// The layers started so far, with the number of rows when each started (see [RunState::with_layer]).
+    layer_starts: Vec<(Cow<'static, str>, usize)>,
+
+    /// The layer being built, if any.
+    current_layer: Option<Cow<'static, str>>,
+}
+
+/// The zero-knowledge rows of a circuit committed in a single chunk, which the domain must fit as well.
+const ZK_ROWS: usize = 3;
+
+/// The size of a circuit being built, see [RunState::stats].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitStats {
+    /// The rows of gates, see [RunState::num_rows].
+    pub rows: usize,
+    pub lookups: usize,
+    /// The size of the public input, public output included.
+    pub public_inputs: usize,
 }
 
 //
@@ -195,6 +234,11 @@ where
             labels_stack: vec![],
             constraints_counter: 0,
             constraints_locations: vec![],
+            capabilities: Capabilities::kimchi(),
+            shard_constraints: None,
+            max_domain_log2: None,
+            layer_starts: vec![],
+            current_layer: None,
         };
 
         // allocate the public inputs
@@ -404,6 +448,11 @@ where
             }
 
             if !env.has_witness {
+                if let Some(buffer) = &mut env.shard_constraints {
+                    buffer.push((constraint, loc));
+                    return Ok(());
+                }
+
                 // TODO: we should have a mode "don't create constraints" instead of having an option here
                 let cs = match &mut env.system {
                     Some(cs) => cs,
@@ -418,6 +467,7 @@ where
                         cs.add_constraint(&env.labels_stack, &loc, c);
                     }
                 }
+                env.check_domain_size()?;
             }
 
             Ok(())
@@ -567,6 +617,270 @@ where
         res
     }
 
+    /// The number of rows of gates created so far, not counting the public input rows,
+    /// or `None` if the constraint system is not being built.
+    /// A generic gate still waiting for its second half is not counted, as in [Self::with_budget].
+    pub fn num_rows(&self) -> Option<usize> {
+        match &self.system {
+            Some(cs) if !self.has_witness => Some(cs.get_rows_len()),
+            _ => None,
+        }
+    }
+
+    /// The number of lookups made by the gates created so far,
+    /// or `None` if the constraint system is not being built.
+    pub fn num_lookups(&self) -> Option<usize> {
+        match &self.system {
+            Some(cs) if !self.has_witness => Some(cs.num_lookups()),
+            _ => None,
+        }
+    }
+
+    /// The size of the circuit built so far, so that a circuit generator can make layout decisions
+    /// (such as splitting a circuit) while compiling, instead of after a failed proof.
+    /// Returns `None` if the constraint system is not being built.
+    pub fn stats(&self) -> Option<CircuitStats> {
+        Some(CircuitStats {
+            rows: self.num_rows()?,
+            lookups: self.num_lookups()?,
+            public_inputs: self.num_public_inputs,
+        })
+    }
+
+    /// Builds a layer of a model (labeled with `name`),
+    /// so that a circuit outgrowing [Self::max_domain_log2] names the layer that went past it,
+    /// and the layer to split the circuit at.
+    pub fn with_layer<FUNC, T>(&mut self, name: Cow<'static, str>, closure: FUNC) -> T
+    where
+        FUNC: FnOnce(&mut Self) -> T,
+    {
+        if let Some(rows) = self.num_rows() {
+            self.layer_starts.push((name.clone(), rows));
+        }
+        let outer = self.current_layer.replace(name.clone());
+        let res = self.with_label(Some(name), closure);
+        self.current_layer = outer;
+        res
+    }
+
+    /// Fails if the circuit, with its public input rows and its zero-knowledge rows,
+    /// no longer fits a domain of `2^max_domain_log2` rows.
+    /// The layer suggested to split the circuit at is the one that started closest to the middle of the circuit built so far,
+    /// so that both parts are about the same size.
+    fn check_domain_size(&self) -> SnarkyResult<()> {
+        let (log2, rows) = match (self.max_domain_log2, self.num_rows()) {
+            (Some(log2), Some(rows)) => (log2, rows),
+            _ => return Ok(()),
+        };
+        if self.num_public_inputs + rows + ZK_ROWS <= 1 << log2 {
+            return Ok(());
+        }
+
+        let layer = self.current_layer.as_ref().map_or_else(
+            || "outside of any layer".to_string(),
+            |layer| layer.to_string(),
+        );
+        let split = self
+            .layer_starts
+            .iter()
+            .filter(|(_, start)| *start > 0)
+            .min_by_key(|(_, start)| start.abs_diff(rows / 2))
+            .map_or_else(|| layer.clone(), |(name, _)| name.to_string());
+        Err(self.compilation_error(SnarkyCompilationError::DomainTooSmall(layer, log2, split)))
+    }
+
+    /// Runs a gadget (labeled with `label`) under a budget of rows,
+    /// so that a gadget growing past its expected size fails the compilation
+    /// instead of silently growing the circuit.
+    /// Budgets can be nested, and are only checked when compiling.
+    ///
+    /// The rows are counted as they are created,
+    /// so a generic gate shared with the next gadget is counted in the next gadget.
+    pub fn with_budget<FUNC, T>(
+        &mut self,
+        label: Cow<'static, str>,
+        max_rows: usize,
+        closure: FUNC,
+    ) -> SnarkyResult<T>
+    where
+        FUNC: FnOnce(&mut Self) -> SnarkyResult<T>,
+    {
+        self.with_label(Some(label.clone()), |env| {
+            let start = env.num_rows();
+            let res = closure(env)?;
+
+            if let (Some(start), Some(end)) = (start, env.num_rows()) {
+                let used = end - start;
+                if used > max_rows {
+                    return Err(
+                        env.compilation_error(SnarkyCompilationError::BudgetExceeded(
+                            label.to_string(),
+                            used,
+                            max_rows,
+                        )),
+                    );
+                }
+            }
+
+            Ok(res)
+        })
+    }
+
+    /// Creates a hint: a variable whose value is computed by `advice` during witness generation
+    /// but is not constrained by its type (see [Self::compute]),
+    /// along with the constraints that `justify` adds to tie it to the rest of the circuit
+    /// (for example, `x = q * d + r` and `r < d` for the quotient `q` and the remainder `r` of a division).
+    ///
+    /// A hint whose justification adds no constraint is refused,
+    /// as its value could be anything the prover likes.
+    pub fn hint<T, ADVICE, JUSTIFY>(
+        &mut self,
+        label: Cow<'static, str>,
+        loc: Cow<'static, str>,
+        advice: ADVICE,
+        justify: JUSTIFY,
+    ) -> SnarkyResult<T>
+    where
+        T: SnarkyType<F>,
+        ADVICE: FnOnce(&dyn WitnessGeneration<F>) -> T::OutOfCircuit,
+        JUSTIFY: FnOnce(&mut Self, &T) -> SnarkyResult<()>,
+    {
+        self.with_label(Some(label.clone()), |env| {
+            let value: T = env.compute_inner(false, loc, advice)?;
+
+            let start = env.constraints_counter;
+            justify(env, &value)?;
+            if env.constraints_counter == start {
+                return Err(
+                    env.compilation_error(SnarkyCompilationError::UnjustifiedHint(
+                        label.to_string(),
+                    )),
+                );
+            }
+
+            Ok(value)
+        })
+    }
+
+    /// Runs a gadget (labeled with `label`) on each input, in parallel,
+    /// and returns the outputs in the order of the inputs (see [crate::snarky::shard]).
+    ///
+    /// The gadget runs on a shard of the state, in which its input is made of fresh variables:
+    /// it must not use variables of the circuit other than its input,
+    /// and constants in the input are not folded as they would be by calling the gadget directly.
+    /// Budgets (see [Self::with_budget]) are not checked inside the gadget, but can be set around the call.
+    pub fn shard_map<I, R, G>(
+        &mut self,
+        label: Cow<'static, str>,
+        loc: Cow<'static, str>,
+        inputs: &[I],
+        gadget: G,
+    ) -> SnarkyResult<Vec<R>>
+    where
+        I: SnarkyType<F>,
+        I::Auxiliary: Send,
+        R: SnarkyType<F> + Send,
+        G: Fn(&mut Self, I) -> SnarkyResult<R> + Sync,
+    {
+        self.with_label(Some(label), |env| {
+            // the shards, and the variables and values of their inputs
+            let mut shards = Vec::with_capacity(inputs.len());
+            for input in inputs {
+                let (cvars, aux) = input.to_cvars();
+                let values: Option<Vec<F>> = env
+                    .has_witness
+                    .then(|| cvars.iter().map(|cvar| cvar.eval(env)).collect());
+                shards.push((env.new_shard(), cvars, values, aux));
+            }
+
+            let synthesized = shards
+                .into_par_iter()
+                .map(|(mut shard, cvars, values, aux)| {
+                    let vars: Vec<FieldVar<F>> = match values {
+                        Some(values) => values
+                            .into_iter()
+                            .map(|v| shard.store_field_elt(v))
+                            .collect(),
+                        None => cvars.iter().map(|_| shard.alloc_var()).collect(),
+                    };
+                    let output = gadget(&mut shard, I::from_cvars_unsafe(vars, aux))?;
+                    Ok((shard, cvars, output))
+                })
+                .collect::<SnarkyResult<Vec<_>>>()?;
+
+            let mut outputs = Vec::with_capacity(synthesized.len());
+            for (shard, cvars, output) in synthesized {
+                outputs.push(env.merge_shard(shard, &cvars, output)?);
+            }
+            Ok(outputs)
+        })
+    }
+
+    /// Creates an empty shard of the state, see [Self::shard_map].
+    fn new_shard(&self) -> Self {
+        let system = self
+            .system
+            .as_ref()
+            .map(|cs| SnarkyConstraintSystem::create(cs.constants().clone()));
+        Self {
+            system,
+            public_input: vec![],
+            public_output: vec![],
+            private_input: vec![],
+            eval_constraints: self.eval_constraints,
+            num_public_inputs: 0,
+            next_var: 0,
+            has_witness: self.has_witness,
+            as_prover: self.as_prover,
+            labels_stack: self.labels_stack.clone(),
+            constraints_counter: 0,
+            constraints_locations: vec![],
+            capabilities: self.capabilities.clone(),
+            shard_constraints: (!self.has_witness).then(Vec::new),
+            // the state replays the constraints of the shard, and checks the size of the circuit then
+            max_domain_log2: None,
+            layer_starts: vec![],
+            current_layer: self.current_layer.clone(),
+        }
+    }
+
+    /// Merges a shard whose input was `inputs`, and returns its output in the state.
+    fn merge_shard<R: SnarkyType<F>>(
+        &mut self,
+        mut shard: Self,
+        inputs: &[FieldVar<F>],
+        output: R,
+    ) -> SnarkyResult<R> {
+        // the first variables of the shard are its input, the others follow the variables of the state
+        let offset = self.next_var;
+        let map = |var: usize| match inputs.get(var) {
+            Some(input) => input.clone(),
+            None => FieldVar::Var(offset + var - inputs.len()),
+        };
+        self.next_var += shard.next_var - inputs.len();
+
+        if self.has_witness {
+            self.private_input
+                .extend_from_slice(&shard.private_input[inputs.len()..]);
+            self.constraints_counter += shard.constraints_counter;
+            self.constraints_locations
+                .append(&mut shard.constraints_locations);
+        } else {
+            for (constraint, constraint_loc) in shard.shard_constraints.take().unwrap() {
+                let constraint = substitute_constraint(constraint, &map).map_err(|name| {
+                    self.compilation_error(SnarkyCompilationError::UnsupportedInShard(
+                        name.to_string(),
+                    ))
+                })?;
+                self.add_constraint(constraint, None, constraint_loc)?;
+            }
+        }
+
+        let (cvars, aux) = output.to_cvars();
+        let cvars = cvars.iter().map(|cvar| substitute(cvar, &map)).collect();
+        Ok(R::from_cvars_unsafe(cvars, aux))
+    }
+
     /// Creates an [RealSnarkyError] using the current context.
     pub fn error(&self, error: SnarkyError) -> RealSnarkyError {
         let loc = if self.constraints_counter == 0 {
@@ -627,11 +941,29 @@ where
     /// Returns the public output generated after running the circuit,
     /// and the witness of the execution trace.
     pub fn generate_witness(&mut self) -> Witness<F> {
+        let witness = self
+            .generate_witness_into(|rows| {
+                Ok::<_, Infallible>(std::array::from_fn(|_| vec![F::zero(); rows]))
+            })
+            .unwrap_or_else(|never| match never {});
+        Witness(witness)
+    }
+
+    /// Same as [Self::generate_witness], but stores the witness in a matrix created by `create`,
+    /// given the number of rows of the witness (see [crate::snarky::witness_store]).
+    pub fn generate_witness_into<W, E>(
+        &mut self,
+        create: impl FnOnce(usize) -> Result<W, E>,
+    ) -> Result<W, E>
+    where
+        W: WitnessMatrix<F>,
+    {
         // TODO: asserting this is dumb.. what if there's no private input : D
         assert!(!self.private_input.is_empty());
 
         // TODO: do we really want to panic here?
         let system = self.system.as_mut().unwrap();
+        let mut witness = create(system.num_witness_rows())?;
 
         let get_one = |var_idx| {
             if var_idx < self.num_public_inputs {
@@ -643,14 +975,14 @@ where
 
         // compute witness
         // TODO: can we avoid passing a closure here? a reference to a Inputs struct would be better perhaps.
-        let witness = system.compute_witness(get_one);
+        system.compute_witness_into(get_one, &mut witness);
 
         // clear state (TODO: find better solution)
         self.public_input = vec![];
         self.next_var = self.num_public_inputs;
 
         // return public output and witness
-        Witness(witness)
+        Ok(witness)
     }
 
     pub(crate) fn poseidon_params(&self) -> mina_poseidon::poseidon::ArithmeticSpongeParams<F> {
@@ -666,6 +998,9 @@ where
         poseidon(self, loc, preimage)
     }
     ///constrains the 3 provided values to fit in 88 bits
+    ///
+    /// If the backend does not support the range check gates (see [Self::capabilities]),
+    /// the values are decomposed into bits with generic gates instead.
     pub fn range_check(
         &mut self,
         loc: Cow<'static, str>,
@@ -673,6 +1008,26 @@ where
         v1: FieldVar<F>,
         v2: FieldVar<F>,
     ) -> SnarkyResult<()> {
-        range_check(self, loc, v0, v1, v2)
+        if self
+            .capabilities
+            .supports(&[GateType::RangeCheck0, GateType::RangeCheck1])
+            && self.capabilities.max_lookup_width > 0
+        {
+            range_check(self, loc, v0, v1, v2)
+        } else {
+            range_check_generic(self, loc, v0, v1, v2)
+        }
+    }
+
+    /// Adds a registered custom gate, lowered to generic gates, and returns the cells of its rows.
+    /// The `inputs` are placed in the first cells of the current row.
+    pub fn custom_gate(
+        &mut self,
+        loc: Cow<'static, str>,
+        gate: &RegisteredGate<F>,
+        inputs: &[FieldVar<F>],
+        coeffs: &[F],
+    ) -> SnarkyResult<ArgumentWitness<FieldVar<F>>> {
+        custom_gate(self, loc, gate, inputs, coeffs)
     }
 }
@@ -0,0 +1,182 @@
+//! Parallel synthesis of independent gadgets (see [crate::snarky::runner::RunState::shard_map]).
+//!
+//! Each gadget runs on its own shard: a state whose variables are numbered from zero,
+//! starting with the variables of its input, and which buffers its constraints instead of creating gates.
+//! The shards are then merged in their original order:
+//! their variables are renumbered after the variables of the parent,
+//! and their constraints are replayed in the parent.
+//! This is exactly what running the gadgets one after the other would have done,
+//! so the circuit does not depend on the number of threads.
+//!
+//! Only the gadget logic and the witness computation run in parallel:
+//! the constraints are still turned into gates by the parent, one shard after the other.
+
+use ark_ff::PrimeField;
+
+use crate::snarky::{
+    constraint_system::{BasicInput, BasicSnarkyConstraint, KimchiConstraint, PoseidonInput},
+    cvar::FieldVar,
+    runner::Constraint,
+};
+
+/// Replaces the variables of a shard by the variables of its parent.
+pub(crate) fn substitute<F: PrimeField>(
+    var: &FieldVar<F>,
+    map: &impl Fn(usize) -> FieldVar<F>,
+) -> FieldVar<F> {
+    match var {
+        FieldVar::Constant(c) => FieldVar::Constant(*c),
+        FieldVar::Var(v) => map(*v),
+        FieldVar::Add(x, y) => {
+            FieldVar::Add(Box::new(substitute(x, map)), Box::new(substitute(y, map)))
+        }
+        FieldVar::Scale(s, x) => FieldVar::Scale(*s, Box::new(substitute(x, map))),
+    }
+}
+
+fn substitute_rows<F: PrimeField>(
+    rows: Vec<Vec<FieldVar<F>>>,
+    map: &impl Fn(usize) -> FieldVar<F>,
+) -> Vec<Vec<FieldVar<F>>> {
+    rows.iter()
+        .map(|row| row.iter().map(|var| substitute(var, map)).collect())
+        .collect()
+}
+
+/// Replaces the variables of a constraint created by a shard by the variables of its parent,
+/// or returns the name of the constraint if it cannot be created in a shard.
+pub(crate) fn substitute_constraint<F: PrimeField>(
+    constraint: Constraint<F>,
+    map: &impl Fn(usize) -> FieldVar<F>,
+) -> Result<Constraint<F>, &'static str> {
+    let sub = |var: &FieldVar<F>| substitute(var, map);
+
+    let constraint = match constraint {
+        Constraint::BasicSnarkyConstraint(c) => Constraint::BasicSnarkyConstraint(match c {
+            BasicSnarkyConstraint::Boolean(x) => BasicSnarkyConstraint::Boolean(sub(&x)),
+            BasicSnarkyConstraint::Equal(x, y) => BasicSnarkyConstraint::Equal(sub(&x), sub(&y)),
+            BasicSnarkyConstraint::Square(x, y) => BasicSnarkyConstraint::Square(sub(&x), sub(&y)),
+            BasicSnarkyConstraint::R1CS(x, y, z) => {
+                BasicSnarkyConstraint::R1CS(sub(&x), sub(&y), sub(&z))
+            }
+        }),
+        Constraint::KimchiConstraint(c) => Constraint::KimchiConstraint(match c {
+            KimchiConstraint::Basic(BasicInput { l, r, o, m, c }) => {
+                KimchiConstraint::Basic(BasicInput {
+                    l: (l.0, sub(&l.1)),
+                    r: (r.0, sub(&r.1)),
+                    o: (o.0, sub(&o.1)),
+                    m,
+                    c,
+                })
+            }
+            KimchiConstraint::Poseidon(rows) => {
+                KimchiConstraint::Poseidon(substitute_rows(rows, map))
+            }
+            KimchiConstraint::Poseidon2(PoseidonInput { states, last }) => {
+                KimchiConstraint::Poseidon2(PoseidonInput {
+                    states: substitute_rows(states, map),
+                    last: last.iter().map(sub).collect(),
+                })
+            }
+            KimchiConstraint::RangeCheck(rows) => {
+                KimchiConstraint::RangeCheck(substitute_rows(rows, map))
+            }
+            KimchiConstraint::EcAddComplete(_) => return Err("EcAddComplete"),
+            KimchiConstraint::EcScale(_) => return Err("EcScale"),
+            KimchiConstraint::EcEndoscale(_) => return Err("EcEndoscale"),
+            KimchiConstraint::EcEndoscalar(_) => return Err("EcEndoscalar"),
+        }),
+    };
+    Ok(constraint)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        loc,
+        snarky::{
+            api::SnarkyCircuit,
+            bits::{to_bits, Endianness},
+            prelude::{FieldVar, RunState, SnarkyResult},
+        },
+    };
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Squares a value, hashes it with the value, and range checks the square.
+    fn neuron(sys: &mut RunState<Fp>, x: FieldVar<Fp>) -> SnarkyResult<FieldVar<Fp>> {
+        let square = x.mul(&x, None, loc!(), sys)?;
+        to_bits(sys, loc!(), &square, 16, Endianness::Little)?;
+        Ok(sys.poseidon(loc!(), (square, x)).0)
+    }
+
+    /// Applies [neuron] to each of the private inputs, one after the other or in shards.
+    struct TestCircuit {
+        sharded: bool,
+    }
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = [Fp; 8];
+        type PublicInput = ();
+        type PublicOutput = [FieldVar<Fp>; 8];
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let x: [FieldVar<Fp>; 8] = sys.compute(loc!(), |_| *private.unwrap())?;
+            // the inputs are linear combinations, which the shards get as variables
+            let x = x.map(|x| x.scale(Fp::from(2u64)) + FieldVar::constant(Fp::from(1u64)));
+
+            let outputs = if self.sharded {
+                sys.shard_map("neurons".into(), loc!(), &x, neuron)?
+            } else {
+                x.into_iter()
+                    .map(|x| neuron(sys, x))
+                    .collect::<SnarkyResult<Vec<_>>>()?
+            };
+            Ok(outputs.try_into().unwrap())
+        }
+    }
+
+    #[test]
+    fn snarky_shard_map() {
+        let x = [0u64, 1, 2, 3, 4, 5, 6, 7].map(Fp::from);
+        let debug = true;
+
+        let (mut serial, _) = TestCircuit { sharded: false }.compile_to_indexes().unwrap();
+        let (_, expected) = serial
+            .prove::<BaseSponge, ScalarSponge>((), x, debug)
+            .unwrap();
+
+        // the circuit and the witness are the same as without shards
+        let (mut sharded, verifier_index) =
+            TestCircuit { sharded: true }.compile_to_indexes().unwrap();
+        assert_eq!(sharded.asm(), serial.asm());
+
+        let (proof, output) = sharded
+            .prove::<BaseSponge, ScalarSponge>((), x, debug)
+            .unwrap();
+        assert_eq!(output, expected);
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+
+        // the constraints of a shard are checked when proving
+        let too_large = [300u64, 1, 2, 3, 4, 5, 6, 7].map(Fp::from);
+        assert!(sharded
+            .prove::<BaseSponge, ScalarSponge>((), too_large, debug)
+            .is_err());
+    }
+}
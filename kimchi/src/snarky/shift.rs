@@ -0,0 +1,256 @@
+//! Shifts of fixed-point values, i.e. multiplications and divisions by powers of two.
+//!
+//! Requantizing a fixed-point value to a smaller scale is a division by a power of two,
+//! which can be constrained by decomposing the value into bits and dropping the low bits,
+//! instead of constraining a full division with a quotient and a remainder.
+//! Values are unsigned, and must fit in the width given to the gadgets;
+//! signed values are shifted by half of their range (see [shift_right_signed]).
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+
+use crate::snarky::{
+    bits::{from_bits, to_bits, Endianness},
+    prelude::{FieldVar, RunState, SnarkyResult},
+};
+
+/// Rounds a signed value to a fixed-point field element at `2^scale_bits`, negative values being negated in the field.
+pub fn to_fixed<F: PrimeField>(value: f64, scale_bits: usize) -> F {
+    let magnitude = F::from((value.abs() * (1u64 << scale_bits) as f64).round() as u64);
+    if value < 0.0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// The signed fixed-point value of `x`, read from the unsigned `x + 2^(bits - 1)`.
+pub fn to_signed<F: PrimeField>(x: F, bits: usize) -> i128 {
+    let half = 1i128 << (bits - 1);
+    let shifted = (x + F::from(half as u64)).into_repr();
+    i128::from(shifted.as_ref()[0]) - half
+}
+
+/// How the bits dropped by a right shift are rounded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Towards zero.
+    Floor,
+    /// To the nearest value, half-way values being rounded up.
+    Nearest,
+}
+
+/// Multiplies a value by `2^shift`.
+/// This does not create any constraint,
+/// so the caller must make sure that the result does not wrap around the field.
+pub fn shift_left<F: PrimeField>(value: &FieldVar<F>, shift: usize) -> FieldVar<F> {
+    value.scale(F::from(2u64).pow([shift as u64]))
+}
+
+/// Divides a value of `width` bits by `2^shift`, rounding as requested,
+/// and constrains the value to fit in `width` bits.
+///
+/// # Panics
+///
+/// Will panic if the width is not smaller than the size of the field (see [to_bits]).
+pub fn shift_right<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    value: &FieldVar<F>,
+    width: usize,
+    shift: usize,
+    rounding: Rounding,
+) -> SnarkyResult<FieldVar<F>> {
+    if shift == 0 {
+        return Ok(value.clone());
+    }
+
+    // rounding to the nearest value adds half of the divisor, which can carry into one more bit
+    let (value, width) = match rounding {
+        Rounding::Floor => (value.clone(), width),
+        Rounding::Nearest => {
+            // the rounded value is only checked in one more bit, so the value is checked on its own
+            to_bits(sys, loc.clone(), value, width, Endianness::Little)?;
+            let half = FieldVar::constant(F::from(2u64).pow([shift as u64 - 1]));
+            (value + half, width + 1)
+        }
+    };
+
+    let bits = to_bits(sys, loc, &value, width, Endianness::Little)?;
+    if shift >= width {
+        return Ok(FieldVar::zero());
+    }
+    Ok(from_bits(&bits[shift..], Endianness::Little))
+}
+
+/// Divides a signed value of `width` bits, sign included, by `2^shift`, rounding as requested,
+/// as the unsigned `value + 2^(width - 1)` divided by [shift_right], minus `2^(width - 1 - shift)`.
+///
+/// # Panics
+///
+/// Will panic if the shift is not smaller than the width, or as [shift_right].
+pub fn shift_right_signed<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    value: &FieldVar<F>,
+    width: usize,
+    shift: usize,
+    rounding: Rounding,
+) -> SnarkyResult<FieldVar<F>> {
+    assert!(shift < width, "cannot shift {width} bits by {shift} bits");
+    let half = |bits: usize| FieldVar::constant(F::from(2u64).pow([bits as u64]));
+    let unsigned = value + &half(width - 1);
+    let shifted = shift_right(sys, loc, &unsigned, width, shift, rounding)?;
+    Ok(shifted - &half(width - 1 - shift))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Shifts the private input of 8 bits by 3 bits, in both directions and with both roundings.
+    struct TestCircuit;
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = Fp;
+        type PublicInput = ();
+        type PublicOutput = [FieldVar<Fp>; 3];
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let x: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+
+            let floor = shift_right(sys, loc!(), &x, 8, 3, Rounding::Floor)?;
+            let nearest = shift_right(sys, loc!(), &x, 8, 3, Rounding::Nearest)?;
+            Ok([shift_left(&x, 3), floor, nearest])
+        }
+    }
+
+    /// Rounds the private input of 8 bits to the nearest multiple of 8, without any other gadget.
+    struct NearestCircuit;
+
+    impl SnarkyCircuit for NearestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = Fp;
+        type PublicInput = ();
+        type PublicOutput = FieldVar<Fp>;
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let x: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+            shift_right(sys, loc!(), &x, 8, 3, Rounding::Nearest)
+        }
+    }
+
+    /// Shifts the signed private input of 8 bits by 3 bits, with both roundings.
+    struct SignedCircuit;
+
+    impl SnarkyCircuit for SignedCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = Fp;
+        type PublicInput = ();
+        type PublicOutput = [FieldVar<Fp>; 2];
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let x: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+
+            let floor = shift_right_signed(sys, loc!(), &x, 8, 3, Rounding::Floor)?;
+            let nearest = shift_right_signed(sys, loc!(), &x, 8, 3, Rounding::Nearest)?;
+            Ok([floor, nearest])
+        }
+    }
+
+    #[test]
+    fn snarky_shift() {
+        let (mut prover_index, verifier_index) = TestCircuit.compile_to_indexes().unwrap();
+
+        let debug = true;
+        for (x, floor, nearest) in [(182u64, 22u64, 23u64), (179, 22, 22), (255, 31, 32)] {
+            let (proof, output) = prover_index
+                .prove::<BaseSponge, ScalarSponge>((), Fp::from(x), debug)
+                .unwrap();
+            assert_eq!(
+                *output,
+                [Fp::from(x * 8), Fp::from(floor), Fp::from(nearest)]
+            );
+            verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+        }
+
+        // the value must fit in its width
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>((), Fp::from(256u64), debug)
+            .is_err());
+
+        // the nearest rounding checks the width on its own
+        let (mut prover_index, _) = NearestCircuit.compile_to_indexes().unwrap();
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>((), Fp::from(255u64), debug)
+            .is_ok());
+        for x in [Fp::from(256u64), Fp::from(300u64), -Fp::from(1u64)] {
+            assert!(prover_index
+                .prove::<BaseSponge, ScalarSponge>((), x, debug)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn snarky_shift_signed() {
+        let (mut prover_index, verifier_index) = SignedCircuit.compile_to_indexes().unwrap();
+
+        let debug = true;
+        for (x, floor, nearest) in [
+            (-12i64, -2i64, -1i64),
+            (-13, -2, -2),
+            (12, 1, 2),
+            (-128, -16, -16),
+        ] {
+            let x = to_fixed::<Fp>(x as f64, 0);
+            let (proof, output) = prover_index
+                .prove::<BaseSponge, ScalarSponge>((), x, debug)
+                .unwrap();
+            assert_eq!(
+                (to_signed(output[0], 8), to_signed(output[1], 8)),
+                (floor.into(), nearest.into())
+            );
+            verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+        }
+
+        // the value must fit in its width, sign included
+        for x in [128.0, -129.0] {
+            assert!(prover_index
+                .prove::<BaseSponge, ScalarSponge>((), to_fixed(x, 0), debug)
+                .is_err());
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! Gadgets computing aggregate statistics of private values,
+//! so that a circuit can output the statistics of a batch of predictions without revealing the predictions.
+//!
+//! Values are unsigned and must fit in the width given to the gadgets,
+//! which constrain them to do so.
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+use num_bigint::BigUint;
+
+use crate::snarky::{
+    bits::{to_bits, Endianness},
+    boolean::Boolean,
+    prelude::{FieldVar, RunState, SnarkyResult},
+};
+
+/// Returns whether `value` is at least the constant `bound` (at most `2^width`).
+///
+/// `value + 2^width - bound` is decomposed in `width + 1` bits,
+/// and its most significant bit is set if and only if `value >= bound`.
+/// The value must be constrained to `width` bits by the caller, for the bit to be meaningful when set.
+pub fn at_least<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    value: &FieldVar<F>,
+    width: usize,
+    bound: u64,
+) -> SnarkyResult<Boolean<F>> {
+    let offset = F::from(2u64).pow([width as u64]) - F::from(bound);
+    let shifted = value + FieldVar::constant(offset);
+    let bits = to_bits(sys, loc, &shifted, width + 1, Endianness::Little)?;
+    Ok(bits[width].clone())
+}
+
+/// Computes the mean of the values, rounded down.
+///
+/// # Panics
+///
+/// Will panic if there are no values, or if the sum of the values may not fit in the field.
+pub fn mean<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    values: &[FieldVar<F>],
+    width: usize,
+) -> SnarkyResult<FieldVar<F>> {
+    assert!(!values.is_empty(), "the mean of no values");
+    let count = values.len() as u64;
+    let count_bits = (u64::BITS - (count - 1).leading_zeros()) as usize;
+    assert!(
+        width + count_bits < F::size_in_bits(),
+        "the sum may not fit in the field"
+    );
+
+    for value in values {
+        to_bits(sys, loc.clone(), value, width, Endianness::Little)?;
+    }
+    let sum = FieldVar::sum_many(values);
+
+    // sum = mean * count + remainder, with remainder < count
+    let advice_sum = sum.clone();
+    let (mean, _): (FieldVar<F>, FieldVar<F>) = sys.hint(
+        "statistics.mean".into(),
+        loc.clone(),
+        move |env| {
+            let sum: BigUint = env.read_var(&advice_sum).into();
+            let count = BigUint::from(count);
+            (F::from(&sum / &count), F::from(&sum % &count))
+        },
+        |sys, (mean, remainder)| {
+            check_mean(sys, loc.clone(), &sum, count, (mean, remainder), width)
+        },
+    )?;
+
+    Ok(mean)
+}
+
+/// Constrains `sum = mean * count + remainder` with `remainder < count`, the mean fitting in `width` bits.
+fn check_mean<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    sum: &FieldVar<F>,
+    count: u64,
+    (mean, remainder): (&FieldVar<F>, &FieldVar<F>),
+    width: usize,
+) -> SnarkyResult<()> {
+    let count_bits = (u64::BITS - (count - 1).leading_zeros()) as usize;
+    let recomposed = mean.scale(F::from(count)) + remainder;
+    sys.assert_eq(None, loc.clone(), recomposed, sum.clone())?;
+    // the mean is at most the maximum value, so it fits in the same width
+    to_bits(sys, loc.clone(), mean, width, Endianness::Little)?;
+    // the remainder must fit in `count_bits` bits for `at_least` to compare it
+    to_bits(sys, loc.clone(), remainder, count_bits, Endianness::Little)?;
+    let below = at_least(sys, loc.clone(), remainder, count_bits, count)?;
+    sys.assert_eq(None, loc, below.to_field_var(), FieldVar::zero())
+}
+
+/// Computes the maximum of the values.
+///
+/// # Panics
+///
+/// Will panic if there are no values.
+pub fn max<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    values: &[FieldVar<F>],
+    width: usize,
+) -> SnarkyResult<FieldVar<F>> {
+    assert!(!values.is_empty(), "the maximum of no values");
+
+    let advice = values.to_vec();
+    sys.hint(
+        "statistics.max".into(),
+        loc.clone(),
+        move |env| {
+            advice
+                .iter()
+                .map(|value| env.read_var(value))
+                .max_by_key(|value| value.into_repr())
+                .unwrap()
+        },
+        |sys, max: &FieldVar<F>| {
+            // the maximum is at least every value, and is one of them
+            let mut product = FieldVar::constant(F::one());
+            for value in values {
+                let difference = max - value;
+                to_bits(sys, loc.clone(), value, width, Endianness::Little)?;
+                to_bits(sys, loc.clone(), &difference, width, Endianness::Little)?;
+                product = product.mul(&difference, None, loc.clone(), sys)?;
+            }
+            sys.assert_eq(None, loc.clone(), product, FieldVar::zero())
+        },
+    )
+}
+
+/// Counts the values falling in each bucket of a histogram.
+/// The buckets are delimited by the ascending `boundaries`:
+/// the first bucket holds the values below the first boundary,
+/// bucket `i` the values in `[boundaries[i - 1], boundaries[i])`,
+/// and the last bucket the values from the last boundary up.
+///
+/// # Panics
+///
+/// Will panic if the boundaries are not ascending, or do not fit in `width` bits.
+pub fn histogram<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    values: &[FieldVar<F>],
+    width: usize,
+    boundaries: &[u64],
+) -> SnarkyResult<Vec<FieldVar<F>>> {
+    assert!(
+        boundaries.windows(2).all(|pair| pair[0] < pair[1]),
+        "the boundaries are not ascending"
+    );
+    assert!(
+        boundaries.iter().all(|b| (*b as u128) < (1u128 << width)),
+        "the boundaries do not fit in {width} bits"
+    );
+
+    for value in values {
+        to_bits(sys, loc.clone(), value, width, Endianness::Little)?;
+    }
+
+    // for each boundary, the number of values at least the boundary
+    let mut at_least_counts = vec![FieldVar::constant(F::from(values.len() as u64))];
+    for &boundary in boundaries {
+        let mut flags = Vec::with_capacity(values.len());
+        for value in values {
+            let flag = at_least(sys, loc.clone(), value, width, boundary)?;
+            flags.push(flag.to_field_var());
+        }
+        at_least_counts.push(FieldVar::sum_many(&flags));
+    }
+    at_least_counts.push(FieldVar::zero());
+
+    Ok(at_least_counts
+        .windows(2)
+        .map(|pair| &pair[0] - &pair[1])
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Outputs the mean, the maximum and a histogram of a private batch of 8-bit values.
+    struct TestCircuit;
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = [Fp; 5];
+        type PublicInput = ();
+        type PublicOutput = ((FieldVar<Fp>, FieldVar<Fp>), [FieldVar<Fp>; 3]);
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let values: [FieldVar<Fp>; 5] = sys.compute(loc!(), |_| *private.unwrap())?;
+
+            let mean = mean(sys, loc!(), &values, 8)?;
+            let max = max(sys, loc!(), &values, 8)?;
+            let histogram = histogram(sys, loc!(), &values, 8, &[10, 100])?;
+            Ok(((mean, max), histogram.try_into().unwrap()))
+        }
+    }
+
+    /// Checks a mean and a remainder given by the prover, for 5 values summing to the public input.
+    struct MeanCircuit;
+
+    impl SnarkyCircuit for MeanCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = [Fp; 2];
+        type PublicInput = FieldVar<Fp>;
+        type PublicOutput = ();
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            sum: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let [mean, remainder]: [FieldVar<Fp>; 2] =
+                sys.compute(loc!(), |_| *private.unwrap())?;
+            check_mean(sys, loc!(), &sum, 5, (&mean, &remainder), 8)
+        }
+    }
+
+    #[test]
+    fn snarky_statistics() {
+        let (mut prover_index, verifier_index) = TestCircuit.compile_to_indexes().unwrap();
+
+        let values = [3u64, 250, 42, 10, 99].map(Fp::from);
+        let debug = true;
+        let (proof, output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>((), values, debug)
+            .unwrap();
+        let ((mean, max), histogram) = *output;
+        assert_eq!(mean, Fp::from(80u64));
+        assert_eq!(max, Fp::from(250u64));
+        assert_eq!(histogram, [1u64, 3, 1].map(Fp::from));
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+
+        // the mean of values summing to 404 cannot be rounded up to 81 with a negative remainder
+        let (mut prover_index, _) = MeanCircuit.compile_to_indexes().unwrap();
+        let sum = Fp::from(404u64);
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>(sum, [Fp::from(80u64), Fp::from(4u64)], debug)
+            .is_ok());
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>(sum, [Fp::from(81u64), -Fp::from(1u64)], debug)
+            .is_err());
+    }
+}
@@ -4,11 +4,11 @@ use crate::{
         api::SnarkyCircuit,
         boolean::Boolean,
         cvar::FieldVar,
-        errors::{SnarkyError, SnarkyRuntimeError},
+        errors::{SnarkyCompilationError, SnarkyError, SnarkyRuntimeError},
         runner::RunState,
     },
 };
-use ark_ff::One;
+use ark_ff::{One, PrimeField};
 use mina_curves::pasta::{Fp, Vesta, VestaParameters};
 use mina_poseidon::{
     constants::PlonkSpongeConstantsKimchi,
@@ -141,3 +141,359 @@ fn test_simple_circuit() {
         }
     }
 }
+
+/// Multiplies the private input by itself several times, under a budget of rows.
+struct BudgetCircuit {
+    budget: usize,
+}
+
+impl SnarkyCircuit for BudgetCircuit {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = Fp;
+    type PublicInput = ();
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        _public: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let x: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+
+        sys.with_budget("powers".into(), self.budget, |sys| {
+            let mut acc = x.clone();
+            for _ in 0..10 {
+                acc = acc.mul(&x, None, loc!(), sys)?;
+            }
+            Ok(acc)
+        })
+    }
+}
+
+#[test]
+fn test_budget() {
+    let (mut prover_index, _) = BudgetCircuit { budget: 20 }.compile_to_indexes().unwrap();
+    let debug = true;
+    let (_, public_output) = prover_index
+        .prove::<BaseSponge, ScalarSponge>((), Fp::from(2), debug)
+        .unwrap();
+    assert_eq!(*public_output, Fp::from(2048));
+
+    let res = BudgetCircuit { budget: 2 }.compile_to_indexes();
+    match res {
+        Err(err) => match err.source {
+            SnarkyError::CompilationError(SnarkyCompilationError::BudgetExceeded(label, _, 2)) => {
+                assert_eq!(label, "powers");
+                assert_eq!(err.label_stack.unwrap(), vec!["powers"]);
+            }
+            err => panic!("not the err expected: {err}"),
+        },
+        Ok(_) => panic!("the budget should be exceeded"),
+    }
+}
+
+/// Divides the private input by 4 with a hint, as a rescaling gadget would.
+struct RescaleCircuit {
+    justified: bool,
+}
+
+impl SnarkyCircuit for RescaleCircuit {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = Fp;
+    type PublicInput = ();
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        _public: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let x: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+
+        let advice_x = x.clone();
+        let (q, _): (FieldVar<Fp>, FieldVar<Fp>) = sys.hint(
+            "rescale".into(),
+            loc!(),
+            move |env| {
+                let x = env.read_var(&advice_x).into_repr().as_ref()[0];
+                (Fp::from(x / 4), Fp::from(x % 4))
+            },
+            |sys, (q, r)| {
+                if !self.justified {
+                    return Ok(());
+                }
+                let scaled = FieldVar::linear_combination(&[
+                    (Fp::from(4u64), q.clone()),
+                    (Fp::one(), r.clone()),
+                ]);
+                sys.assert_eq(None, loc!(), scaled, x.clone())?;
+                Boolean::unpack(sys, loc!(), r, 2)?;
+                Ok(())
+            },
+        )?;
+
+        Ok(q)
+    }
+}
+
+#[test]
+fn test_hint() {
+    let (mut prover_index, _) = RescaleCircuit { justified: true }
+        .compile_to_indexes()
+        .unwrap();
+    let debug = true;
+    let (_, public_output) = prover_index
+        .prove::<BaseSponge, ScalarSponge>((), Fp::from(11), debug)
+        .unwrap();
+    assert_eq!(*public_output, Fp::from(2));
+
+    let res = RescaleCircuit { justified: false }.compile_to_indexes();
+    match res {
+        Err(err) => match err.source {
+            SnarkyError::CompilationError(SnarkyCompilationError::UnjustifiedHint(label)) => {
+                assert_eq!(label, "rescale");
+            }
+            err => panic!("not the err expected: {err}"),
+        },
+        Ok(_) => panic!("the hint should be refused"),
+    }
+}
+
+#[test]
+fn test_sum_many() {
+    let vs: Vec<FieldVar<Fp>> = (0..100)
+        .map(|i| FieldVar::Var(i).scale(Fp::from(i as u64 + 1)))
+        .collect();
+
+    let chain = vs.iter().fold(FieldVar::zero(), |acc, v| acc + v);
+    let tree = FieldVar::sum_many(&vs);
+
+    let (_, chain_terms) = chain.to_constant_and_terms();
+    let (_, tree_terms) = tree.to_constant_and_terms();
+    assert_eq!(chain_terms, tree_terms);
+
+    fn depth(v: &FieldVar<Fp>) -> usize {
+        match v {
+            FieldVar::Add(a, b) => 1 + depth(a).max(depth(b)),
+            FieldVar::Scale(_, v) => depth(v),
+            _ => 0,
+        }
+    }
+    assert_eq!(depth(&chain), 99);
+    assert_eq!(depth(&tree), 7);
+}
+
+/// Checks the inner product of the private input with constant weights against the public input,
+/// and outputs its inner product with private weights.
+struct DotProductCircuit;
+
+impl SnarkyCircuit for DotProductCircuit {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = ([Fp; 4], [Fp; 4]);
+    type PublicInput = FieldVar<Fp>;
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        expected: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let xs: [FieldVar<Fp>; 4] = sys.compute(loc!(), |_| private.unwrap().0)?;
+        let ws: [FieldVar<Fp>; 4] = sys.compute(loc!(), |_| private.unwrap().1)?;
+
+        // the scaled terms are summed with 3 generic gates (2 rows), and the equality is a wiring
+        sys.with_budget("constant weights".into(), 2, |sys| {
+            let weights = [1u64, 2, 3, 4].map(|w| FieldVar::constant(Fp::from(w)));
+            let dot = FieldVar::dot_product(&xs, &weights, None, loc!(), sys)?;
+            sys.assert_eq(None, loc!(), dot, expected)
+        })?;
+
+        FieldVar::dot_product(&xs, &ws, None, loc!(), sys)
+    }
+}
+
+#[test]
+fn test_dot_product() {
+    let (mut prover_index, verifier_index) = DotProductCircuit.compile_to_indexes().unwrap();
+
+    let xs = [1u64, 2, 3, 4].map(Fp::from);
+    let ws = [5u64, 6, 7, 8].map(Fp::from);
+    let debug = true;
+    let (proof, public_output) = prover_index
+        .prove::<BaseSponge, ScalarSponge>(Fp::from(30), (xs, ws), debug)
+        .unwrap();
+    assert_eq!(*public_output, Fp::from(70));
+    verifier_index.verify::<BaseSponge, ScalarSponge>(proof, Fp::from(30), *public_output);
+}
+
+/// Range checks the private input, checking the size of the circuit as it grows.
+struct StatsCircuit;
+
+impl SnarkyCircuit for StatsCircuit {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = Fp;
+    type PublicInput = FieldVar<Fp>;
+    type PublicOutput = ();
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        public: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let x: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+        let before = sys.stats();
+        sys.range_check(loc!(), x.clone(), x.clone(), x.clone())?;
+        let after = sys.stats();
+
+        // the stats are only known when compiling
+        if let (Some(before), Some(after)) = (before, after) {
+            assert_eq!(before.lookups, 0);
+            assert!(after.lookups > 0);
+            assert!(after.rows > before.rows);
+            assert_eq!(after.public_inputs, 1);
+        }
+        sys.assert_eq(None, loc!(), x, public)
+    }
+}
+
+#[test]
+fn test_stats() {
+    let (mut prover_index, _) = StatsCircuit.compile_to_indexes().unwrap();
+    let debug = true;
+    prover_index
+        .prove::<BaseSponge, ScalarSponge>(Fp::from(5), Fp::from(5), debug)
+        .unwrap();
+}
+
+/// Raises the private input to powers in two layers, the second much larger than the first.
+struct LayeredCircuit {
+    max_domain_log2: Option<usize>,
+}
+
+impl SnarkyCircuit for LayeredCircuit {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = Fp;
+    type PublicInput = ();
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        _public: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let x: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+        let mut acc = x.clone();
+        for (layer, powers) in [("small", 4), ("large", 30)] {
+            acc = sys.with_layer(layer.into(), |sys| {
+                let mut acc = acc;
+                for _ in 0..powers {
+                    acc = acc.mul(&x, None, loc!(), sys)?;
+                }
+                Ok(acc)
+            })?;
+        }
+        Ok(acc)
+    }
+
+    fn max_domain_log2(&self) -> Option<usize> {
+        self.max_domain_log2
+    }
+}
+
+#[test]
+fn test_max_domain_size() {
+    assert!(LayeredCircuit {
+        max_domain_log2: None
+    }
+    .compile_to_indexes()
+    .is_ok());
+
+    let res = LayeredCircuit {
+        max_domain_log2: Some(4),
+    }
+    .compile_to_indexes();
+    match res {
+        Err(err) => match err.source {
+            SnarkyError::CompilationError(SnarkyCompilationError::DomainTooSmall(
+                layer,
+                4,
+                split,
+            )) => {
+                assert_eq!(layer, "large");
+                assert_eq!(split, "large");
+            }
+            err => panic!("not the err expected: {err}"),
+        },
+        Ok(_) => panic!("the domain should be too small"),
+    }
+}
+
+/// Chains multiplications past the size of the SRS, of the given size.
+struct ChunkedCircuit(usize);
+
+impl SnarkyCircuit for ChunkedCircuit {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = Fp;
+    type PublicInput = ();
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        _public: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let x: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+        let mut acc = x.clone();
+        for _ in 0..40 {
+            acc = acc.mul(&x, None, loc!(), sys)?;
+        }
+        Ok(acc)
+    }
+
+    fn max_srs_size(&self) -> Option<usize> {
+        Some(self.0)
+    }
+}
+
+#[test]
+fn test_chunked_commitments() {
+    let (mut prover_index, verifier_index) = ChunkedCircuit(16).compile_to_indexes().unwrap();
+    assert!(prover_index.num_gates() > 16);
+
+    let x = Fp::from(2u64);
+    let debug = true;
+    let (proof, output) = prover_index
+        .prove::<BaseSponge, ScalarSponge>((), x, debug)
+        .unwrap();
+    assert_eq!(*output, Fp::from(2u64.pow(41)));
+    verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), *output);
+}
+
+#[test]
+fn test_empty_srs() {
+    match ChunkedCircuit(0).compile_to_indexes() {
+        Err(err) => assert!(matches!(
+            err.source,
+            SnarkyError::CompilationError(SnarkyCompilationError::EmptySrs(0))
+        )),
+        Ok(_) => panic!("an empty SRS should be rejected"),
+    }
+}
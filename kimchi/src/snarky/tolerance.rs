@@ -0,0 +1,97 @@
+//! Equality up to a tolerance, for values quantized independently.
+//!
+//! An output quantized by the model and the same output recomputed in the circuit from quantized inputs
+//! generally differ in their last bits, so asserting their equality makes honest proofs fail.
+//! [assert_close] only constrains their difference to be small.
+
+use std::borrow::Cow;
+
+use ark_ff::PrimeField;
+
+use crate::snarky::{
+    bits::{to_bits, Endianness},
+    prelude::{FieldVar, RunState, SnarkyResult},
+};
+
+/// Constrains `|a - b| <= eps`, the difference being taken as a signed value.
+///
+/// `a - b + eps` and `eps - (a - b)` are both decomposed in the number of bits of `2 * eps`:
+/// they sum to `2 * eps`, so they both fit if and only if they are both in `[0, 2 * eps]`.
+/// An `eps` of zero is an equality.
+pub fn assert_close<F: PrimeField>(
+    sys: &mut RunState<F>,
+    loc: Cow<'static, str>,
+    a: &FieldVar<F>,
+    b: &FieldVar<F>,
+    eps: u64,
+) -> SnarkyResult<()> {
+    let difference = a - b;
+    if eps == 0 {
+        return sys.assert_eq(None, loc, difference, FieldVar::zero());
+    }
+
+    let width = (u128::BITS - (2 * eps as u128).leading_zeros()) as usize;
+    let eps = FieldVar::constant(F::from(eps));
+    let above = &difference + eps.clone();
+    let below = &eps - &difference;
+    to_bits(sys, loc.clone(), &above, width, Endianness::Little)?;
+    to_bits(sys, loc, &below, width, Endianness::Little)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{loc, snarky::api::SnarkyCircuit};
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Asserts that the two private inputs are within 3 of each other.
+    struct TestCircuit;
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = (Fp, Fp);
+        type PublicInput = ();
+        type PublicOutput = ();
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            _: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let (a, b): (FieldVar<Fp>, FieldVar<Fp>) =
+                sys.compute(loc!(), |_| *private.unwrap())?;
+            assert_close(sys, loc!(), &a, &b, 3)
+        }
+    }
+
+    #[test]
+    fn snarky_assert_close() {
+        let (mut prover_index, verifier_index) = TestCircuit.compile_to_indexes().unwrap();
+
+        let debug = true;
+        for (a, b) in [(100u64, 100u64), (100, 103), (103, 100)] {
+            let (proof, _) = prover_index
+                .prove::<BaseSponge, ScalarSponge>((), (Fp::from(a), Fp::from(b)), debug)
+                .unwrap();
+            verifier_index.verify::<BaseSponge, ScalarSponge>(proof, (), ());
+        }
+
+        for (a, b) in [(100u64, 104u64), (104, 100)] {
+            assert!(prover_index
+                .prove::<BaseSponge, ScalarSponge>((), (Fp::from(a), Fp::from(b)), debug)
+                .is_err());
+        }
+    }
+}
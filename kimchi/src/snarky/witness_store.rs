@@ -0,0 +1,197 @@
+//! Storage of the witness matrix of a circuit.
+//!
+//! The witness is generated row by row (see [crate::snarky::runner::RunState::generate_witness_into]),
+//! and consumed by the prover one column at a time.
+//! By default it is kept in memory as one vector per column.
+//! It can instead be stored in a memory-mapped file ([MappedWitness], behind the `mmap_witness` feature),
+//! which keeps it out of the heap while it is generated.
+//! The layout of the file is column-major by default, so that reading a column reads contiguous pages;
+//! a row-major layout writes contiguous pages when the witness is generated instead.
+//!
+//! The prover takes its columns by value, so [MappedWitness::into_columns] reads the whole file back into memory
+//! before proving: the peak memory of a proof is that of the in-memory witness,
+//! and storing the witness adds a serialization and a deserialization of each cell.
+//! The file only pays off to inspect a witness, or to keep it after the proof.
+
+use crate::circuits::polynomial::COLUMNS;
+
+/// A witness matrix of `COLUMNS` columns, filled when generating the witness.
+/// Cells that are not set must be zero.
+pub trait WitnessMatrix<F> {
+    /// Sets the value of a cell.
+    fn set(&mut self, col: usize, row: usize, value: F);
+}
+
+impl<F> WitnessMatrix<F> for [Vec<F>; COLUMNS] {
+    fn set(&mut self, col: usize, row: usize, value: F) {
+        self[col][row] = value;
+    }
+}
+
+/// The order of the cells of a stored witness.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WitnessLayout {
+    /// The cells of a row are contiguous, in the order in which the witness is generated.
+    RowMajor,
+    /// The cells of a column are contiguous, in the order in which the prover reads the witness.
+    ColumnMajor,
+}
+
+#[cfg(feature = "mmap_witness")]
+pub use mapped::MappedWitness;
+
+#[cfg(feature = "mmap_witness")]
+mod mapped {
+    use std::{fs::OpenOptions, io, marker::PhantomData, path::Path};
+
+    use ark_ff::PrimeField;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use memmap2::MmapMut;
+
+    use super::{WitnessLayout, WitnessMatrix};
+    use crate::circuits::polynomial::COLUMNS;
+
+    /// A witness matrix stored in a memory-mapped file,
+    /// each cell being a serialized field element.
+    pub struct MappedWitness<F> {
+        map: MmapMut,
+        rows: usize,
+        layout: WitnessLayout,
+        /// The size of a serialized field element.
+        cell_size: usize,
+        _field: PhantomData<F>,
+    }
+
+    impl<F> MappedWitness<F>
+    where
+        F: PrimeField,
+    {
+        /// Creates a matrix of `rows` rows of zeros in the file at `path`, overwriting it.
+        pub fn create(path: &Path, rows: usize, layout: WitnessLayout) -> io::Result<Self> {
+            let cell_size = F::zero().serialized_size();
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(path)?;
+            // the file is filled with zeros, which deserialize to zero
+            file.set_len((COLUMNS * rows * cell_size) as u64)?;
+
+            // SAFETY: the file was just truncated for the witness and nothing else is expected to modify it
+            let map = unsafe { MmapMut::map_mut(&file)? };
+            Ok(Self {
+                map,
+                rows,
+                layout,
+                cell_size,
+                _field: PhantomData,
+            })
+        }
+
+        /// The number of rows of the matrix.
+        pub fn rows(&self) -> usize {
+            self.rows
+        }
+
+        fn offset(&self, col: usize, row: usize) -> usize {
+            let cell = match self.layout {
+                WitnessLayout::RowMajor => row * COLUMNS + col,
+                WitnessLayout::ColumnMajor => col * self.rows + row,
+            };
+            cell * self.cell_size
+        }
+
+        /// Returns the value of a cell.
+        pub fn get(&self, col: usize, row: usize) -> F {
+            let offset = self.offset(col, row);
+            F::deserialize(&self.map[offset..offset + self.cell_size])
+                .expect("the witness file only contains serialized field elements")
+        }
+
+        /// Reads a column.
+        pub fn column(&self, col: usize) -> Vec<F> {
+            (0..self.rows).map(|row| self.get(col, row)).collect()
+        }
+
+        /// Reads all the columns, in the form expected by the prover, which holds them all in memory.
+        pub fn into_columns(self) -> [Vec<F>; COLUMNS] {
+            std::array::from_fn(|col| self.column(col))
+        }
+    }
+
+    impl<F> WitnessMatrix<F> for MappedWitness<F>
+    where
+        F: PrimeField,
+    {
+        fn set(&mut self, col: usize, row: usize, value: F) {
+            let offset = self.offset(col, row);
+            value
+                .serialize(&mut self.map[offset..offset + self.cell_size])
+                .expect("a cell fits a serialized field element");
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mmap_witness"))]
+mod test {
+    use super::*;
+    use crate::{
+        loc,
+        snarky::{
+            api::SnarkyCircuit,
+            prelude::{FieldVar, RunState, SnarkyResult},
+        },
+    };
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::evaluation_proof::OpeningProof;
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    /// Outputs the square of the private input plus the public input.
+    struct TestCircuit;
+
+    impl SnarkyCircuit for TestCircuit {
+        type Curve = Vesta;
+        type Proof = OpeningProof<Self::Curve>;
+
+        type PrivateInput = Fp;
+        type PublicInput = FieldVar<Fp>;
+        type PublicOutput = FieldVar<Fp>;
+
+        fn circuit(
+            &self,
+            sys: &mut RunState<Fp>,
+            public: Self::PublicInput,
+            private: Option<&Self::PrivateInput>,
+        ) -> SnarkyResult<Self::PublicOutput> {
+            let x: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+            let square = x.mul(&x, None, loc!(), sys)?;
+            Ok(square + &public)
+        }
+    }
+
+    #[test]
+    fn snarky_mapped_witness() {
+        let (mut prover_index, verifier_index) = TestCircuit.compile_to_indexes().unwrap();
+        let public = Fp::from(1u64);
+        let private = Fp::from(3u64);
+        let debug = true;
+
+        for layout in [WitnessLayout::RowMajor, WitnessLayout::ColumnMajor] {
+            let path = std::env::temp_dir().join(format!("snarky_witness_{layout:?}"));
+            prover_index.store_witness_in(path.clone(), layout);
+            let (proof, output) = prover_index
+                .prove::<BaseSponge, ScalarSponge>(public, private, debug)
+                .unwrap();
+            assert_eq!(*output, Fp::from(10u64));
+            verifier_index.verify::<BaseSponge, ScalarSponge>(proof, public, *output);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! This module implements the [`TestOnly`] wrapper,
+//! which separates insecure proving artifacts from production ones.
+//!
+//! Indexes created from an [InsecureTestSRS] (or with the gate checks disabled),
+//! and the proofs created from these indexes, are wrapped in [TestOnly].
+//! The wrapper cannot be serialized and only exposes test helpers to prove and verify,
+//! so that these artifacts cannot be published or passed to [crate::verifier::verify] by accident.
+
+use std::sync::Arc;
+
+use ark_ff::PrimeField;
+use mina_poseidon::FqSponge;
+use poly_commitment::evaluation_proof::OpeningProof;
+
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem, lookup::runtime_tables::RuntimeTable, wires::COLUMNS,
+    },
+    curve::KimchiCurve,
+    error::{ProverError, VerifyError},
+    plonk_sponge::FrSponge,
+    precomputed_srs::InsecureTestSRS,
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    verifier::verify,
+    verifier_index::VerifierIndex,
+};
+
+/// A proving artifact that must only be used in tests.
+#[derive(Debug, Clone)]
+pub struct TestOnly<T>(T);
+
+impl<T> TestOnly<T> {
+    /// Marks an artifact as test-only.
+    /// Use this for artifacts that were obtained by skipping checks,
+    /// for example a [ConstraintSystem] built with `disable_gates_checks`.
+    pub fn new(artifact: T) -> Self {
+        Self(artifact)
+    }
+
+    /// Gives access to the wrapped artifact.
+    /// The name is deliberately loud: whatever is derived from it is just as insecure.
+    pub fn as_insecure(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<G: KimchiCurve> TestOnly<ProverIndex<G, OpeningProof<G>>>
+where
+    G::BaseField: PrimeField,
+{
+    /// Creates a test-only prover index from an [InsecureTestSRS].
+    pub fn create(cs: ConstraintSystem<G::ScalarField>, srs: InsecureTestSRS<G>) -> Self {
+        let mut srs = srs.into_inner_insecure();
+        srs.add_lagrange_basis(cs.domain.d1);
+
+        let &endo_q = G::other_curve_endo();
+        Self(ProverIndex::create(cs, endo_q, Arc::new(srs)))
+    }
+
+    /// Returns the test-only verifier index of this prover index.
+    pub fn verifier_index(&self) -> TestOnly<VerifierIndex<G, OpeningProof<G>>> {
+        TestOnly(self.0.verifier_index())
+    }
+
+    /// Produces a test-only proof.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the proof cannot be created (see [ProverProof::create]).
+    pub fn prove<
+        EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+        EFrSponge: FrSponge<G::ScalarField>,
+    >(
+        &self,
+        group_map: &G::Map,
+        witness: [Vec<G::ScalarField>; COLUMNS],
+        runtime_tables: &[RuntimeTable<G::ScalarField>],
+    ) -> Result<TestOnly<ProverProof<G, OpeningProof<G>>>, ProverError> {
+        ProverProof::create::<EFqSponge, EFrSponge>(group_map, witness, runtime_tables, &self.0)
+            .map(TestOnly)
+    }
+}
+
+impl<G: KimchiCurve> TestOnly<VerifierIndex<G, OpeningProof<G>>>
+where
+    G::BaseField: PrimeField,
+{
+    /// Verifies a test-only proof against this test-only verifier index.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the proof does not verify (see [crate::verifier::verify]).
+    pub fn verify<
+        EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+        EFrSponge: FrSponge<G::ScalarField>,
+    >(
+        &self,
+        group_map: &G::Map,
+        proof: &TestOnly<ProverProof<G, OpeningProof<G>>>,
+        public_input: &[G::ScalarField],
+    ) -> Result<(), VerifyError> {
+        verify::<G, EFqSponge, EFrSponge, OpeningProof<G>>(
+            group_map,
+            &self.0,
+            &proof.0,
+            public_input,
+        )
+    }
+}
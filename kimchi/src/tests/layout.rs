@@ -0,0 +1,98 @@
+use std::{array, time::Instant};
+
+use crate::circuits::{
+    gate::CircuitGate,
+    layout::pack_generic_rows,
+    polynomials::{generic::GenericGateSpec, xor},
+    wires::{Wire, COLUMNS},
+};
+use ark_ff::Zero;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+
+use super::framework::TestFramework;
+
+type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+#[test]
+fn test_pack_generic_rows_after_xor() {
+    // a 16-bit xor (its gate and the zero row it reads as its next row), then two constants
+    let mut gates = vec![];
+    let row = CircuitGate::<Fp>::extend_xor_gadget(&mut gates, 16);
+    for (i, constant) in [1u64, 2].into_iter().enumerate() {
+        gates.push(CircuitGate::create_generic_gadget(
+            Wire::for_row(row + i),
+            GenericGateSpec::Const(constant.into()),
+            None,
+        ));
+    }
+
+    let mut witness = xor::create_xor_witness(Fp::from(0x1234u64), Fp::from(0xabcdu64), 16);
+    for constant in [1u64, 2] {
+        witness[0].push(Fp::from(constant));
+        for column in &mut witness[1..] {
+            column.push(Fp::zero());
+        }
+    }
+
+    // the zero row of the xor is not a host; the second constant is packed into the first one
+    let (gates, packed) = pack_generic_rows(gates, 0);
+    assert_eq!(packed.kept, vec![0, 1, 2]);
+    assert_eq!(packed.packed, vec![None, None, Some(3)]);
+    packed.apply_to_witness(&mut witness);
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .setup()
+        .prove_and_verify::<BaseSponge, ScalarSponge>()
+        .unwrap();
+}
+
+#[test]
+fn test_pack_generic_rows_proving_time() {
+    // 200 constants take a domain of 256 rows, and 128 rows once packed two by two
+    let constants: Vec<Fp> = (1..=200u64).map(Fp::from).collect();
+    let gates: Vec<_> = constants
+        .iter()
+        .enumerate()
+        .map(|(row, constant)| {
+            CircuitGate::create_generic_gadget(
+                Wire::for_row(row),
+                GenericGateSpec::Const(*constant),
+                None,
+            )
+        })
+        .collect();
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); constants.len()]);
+    witness[0] = constants;
+
+    let (packed_gates, packed) = pack_generic_rows(gates.clone(), 0);
+    assert_eq!(packed_gates.len(), 100);
+    let mut packed_witness = witness.clone();
+    packed.apply_to_witness(&mut packed_witness);
+
+    // the domain size sets the cost of the prover, so packing halves it
+    let mut runs = vec![];
+    for (gates, witness) in [(gates, witness), (packed_gates, packed_witness)] {
+        let runner = TestFramework::<Vesta>::default()
+            .gates(gates)
+            .witness(witness)
+            .setup();
+        let domain = runner.prover_index().cs.domain.d1.size;
+        let start = Instant::now();
+        runner.prove::<BaseSponge, ScalarSponge>().unwrap();
+        runs.push((domain, start.elapsed()));
+    }
+    let [(domain, time), (packed_domain, packed_time)] = runs[..] else {
+        unreachable!()
+    };
+    println!(
+        "proved in {time:?} on {domain} rows before packing, and in {packed_time:?} on {packed_domain} rows after"
+    );
+    assert_eq!((domain, packed_domain), (256, 128));
+}
@@ -8,13 +8,16 @@ mod foreign_field_mul;
 mod framework;
 mod generic;
 mod keccak;
+mod layout;
 mod lookup;
 mod not;
 mod poseidon;
+mod proof_card;
 mod range_check;
 mod recursion;
 mod rot;
 mod serde;
+mod test_only;
 mod turshi;
 mod varbasemul;
 mod xor;
@@ -0,0 +1,70 @@
+use crate::{
+    circuits::{
+        polynomials::generic::testing::{create_circuit, fill_in_witness},
+        wires::COLUMNS,
+    },
+    error::ProofCardError,
+    proof::ProverProof,
+    proof_card::{ProofCard, QuantizationConfig},
+    prover_index::testing::new_index_for_test,
+};
+use ark_ff::Zero;
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Fq, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof};
+use std::{array, collections::BTreeMap};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+#[test]
+fn test_proof_card_roundtrip() {
+    let public = vec![Fp::from(3u8); 5];
+    let gates = create_circuit(0, public.len());
+
+    // create witness
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &public);
+
+    let index = new_index_for_test(gates, public.len());
+    let verifier_index = index.verifier_index();
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof =
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index).unwrap();
+
+    // bundle the proof in a card
+    let metrics = BTreeMap::from([("accuracy".to_string(), 0.97)]);
+    let card = ProofCard::new::<BaseSponge>(
+        "generic".to_string(),
+        &verifier_index,
+        Fp::from(42u8),
+        QuantizationConfig {
+            scale_bits: 16,
+            value_bits: 32,
+        },
+        metrics,
+        public,
+        proof,
+    );
+
+    // the card survives a JSON roundtrip
+    let json = card.to_json().unwrap();
+    let card: ProofCard<Vesta, OpeningProof<Vesta>> = ProofCard::from_json(&json).unwrap();
+    assert_eq!(card.metrics["accuracy"], 0.97);
+    card.verify::<BaseSponge, ScalarSponge>(&group_map, &verifier_index)
+        .unwrap();
+
+    // a tampered digest is rejected
+    let mut tampered = card;
+    tampered.verifier_index_digest += Fq::from(1u8);
+    assert!(matches!(
+        tampered.verify::<BaseSponge, ScalarSponge>(&group_map, &verifier_index),
+        Err(ProofCardError::VerifierIndexMismatch)
+    ));
+}
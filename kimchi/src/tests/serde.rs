@@ -93,4 +93,32 @@ mod tests {
         .unwrap();
         println!("- time to verify: {}ms", start.elapsed().as_millis());
     }
+
+    #[test]
+    fn test_verifier_index_from_file() {
+        let public = vec![Fp::from(3u8); 5];
+        let gates = create_circuit(0, public.len());
+        let index = new_index_for_test(gates, public.len());
+        let verifier_index = index.verifier_index();
+
+        let path = std::env::temp_dir().join(format!("verifier-index-{}", std::process::id()));
+        std::fs::File::create(&path).unwrap();
+        verifier_index.to_file(&path, Some(false)).unwrap();
+
+        // the index commits in one chunk, as does a larger SRS, but not a smaller one
+        let from_file = |size: usize| {
+            let srs = SRS::<GroupAffine<VestaParameters>>::create(size);
+            VerifierIndex::<Vesta, OpeningProof<Vesta>>::from_file(
+                std::sync::Arc::new(srs),
+                &path,
+                None,
+                verifier_index.endo,
+            )
+        };
+        let domain_size = verifier_index.domain.size as usize;
+        assert!(from_file(domain_size).is_ok());
+        assert!(from_file(2 * domain_size).is_ok());
+        assert!(from_file(domain_size / 2).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,50 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        polynomials::generic::testing::{create_circuit, fill_in_witness},
+        wires::COLUMNS,
+    },
+    precomputed_srs::InsecureTestSRS,
+    test_only::TestOnly,
+};
+use ark_ff::Zero;
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::commitment::CommitmentCurve;
+use std::array;
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+#[test]
+fn test_prove_and_verify_with_insecure_srs() {
+    let public = vec![Fp::from(3u8); 5];
+    let gates = create_circuit(0, public.len());
+
+    // create witness
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &public);
+
+    // create a test-only index
+    let cs = ConstraintSystem::<Fp>::create(gates)
+        .public(public.len())
+        .build()
+        .unwrap();
+    let srs = InsecureTestSRS::<Vesta>::create(cs.domain.d1.log_size_of_group);
+    let prover_index = TestOnly::create(cs, srs);
+    let verifier_index = prover_index.verifier_index();
+
+    // create and verify a test-only proof
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof = prover_index
+        .prove::<BaseSponge, ScalarSponge>(&group_map, witness, &[])
+        .unwrap();
+    verifier_index
+        .verify::<BaseSponge, ScalarSponge>(&group_map, &proof, &public)
+        .unwrap();
+}
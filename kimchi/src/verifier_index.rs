@@ -1,6 +1,8 @@
 //! This module implements the verifier index as [`VerifierIndex`].
 //! You can derive this struct from the [`ProverIndex`] struct.
 
+#[cfg(feature = "prover")]
+use crate::prover_index::ProverIndex;
 use crate::{
     alphas::Alphas,
     circuits::{
@@ -11,7 +13,6 @@ use crate::{
         wires::{COLUMNS, PERMUTS},
     },
     curve::KimchiCurve,
-    prover_index::ProverIndex,
 };
 use ark_ff::{One, PrimeField};
 use ark_poly::{univariate::DensePolynomial, Radix2EvaluationDomain as D};
@@ -152,6 +153,7 @@ pub struct VerifierIndex<G: KimchiCurve, OpeningProof: OpenProof<G>> {
 }
 //~spec:endcode
 
+#[cfg(feature = "prover")]
 impl<G: KimchiCurve, OpeningProof: OpenProof<G>> ProverIndex<G, OpeningProof>
 where
     G::BaseField: PrimeField,
@@ -363,6 +365,21 @@ impl<G: KimchiCurve, OpeningProof: OpenProof<G>> VerifierIndex<G, OpeningProof>
         let mut verifier_index = Self::deserialize(&mut rmp_serde::Deserializer::new(reader))
             .map_err(|e| e.to_string())?;
 
+        // the commitments of the index are chunked by the size of its SRS,
+        // so that another SRS must split the domain in as many chunks
+        let domain_size = verifier_index.domain.size as usize;
+        let chunks = |max_poly_size: usize| (domain_size + max_poly_size - 1) / max_poly_size;
+        if srs.max_poly_size() == 0
+            || chunks(srs.max_poly_size()) != chunks(verifier_index.max_poly_size)
+        {
+            return Err(format!(
+                "the verifier index was created with an SRS of size {}, in {} chunks, which an SRS of size {} does not match",
+                verifier_index.max_poly_size,
+                chunks(verifier_index.max_poly_size),
+                srs.max_poly_size()
+            ));
+        }
+
         // fill in the rest
         verifier_index.srs = srs;
         verifier_index.endo = endo;
@@ -0,0 +1,194 @@
+//! Streaming witnesses to the prover.
+//!
+//! When the inputs of a circuit arrive faster than they can be proven (for example over the network),
+//! buffering all of them exhausts memory. [prove_stream] reads the witnesses as a stream of [WitnessChunk]s,
+//! produced by an iterator on another thread, and proves each witness as soon as its last row arrives.
+//! At most `capacity` chunks are buffered between the producer and the prover:
+//! once the buffer is full, the producer blocks until the prover catches up,
+//! pushing the backpressure back to the source of the inputs.
+
+use std::{array, sync::mpsc, thread};
+
+use ark_ff::{PrimeField, Zero};
+use mina_poseidon::FqSponge;
+use poly_commitment::OpenProof;
+
+use crate::{
+    circuits::wires::COLUMNS,
+    curve::KimchiCurve,
+    error::{ProverError, WitnessStreamError},
+    plonk_sponge::FrSponge,
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    verifier_index::VerifierIndex,
+};
+
+/// Consecutive rows of a witness, starting at row `start`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WitnessChunk<F> {
+    pub start: usize,
+    pub rows: Vec<[F; COLUMNS]>,
+}
+
+/// Proves the witnesses streamed by `chunks`, buffering at most `capacity` chunks, and gives each proof to `on_proof`.
+/// Returns the number of witnesses proven.
+///
+/// The chunks of a witness must come in order, and a witness is complete once it has as many rows as the circuit,
+/// the next chunk starting the next witness.
+/// A witness that does not prove is given to `on_proof` as an error, without stopping the stream.
+///
+/// # Errors
+///
+/// Will give error if a chunk is out of order or overflows the circuit, or if the stream ends in the middle of a witness.
+/// The producer then stops at its next chunk.
+pub fn prove_stream<G, OpeningProof, EFqSponge, EFrSponge, I, FUNC>(
+    group_map: &G::Map,
+    index: &ProverIndex<G, OpeningProof>,
+    chunks: I,
+    capacity: usize,
+    mut on_proof: FUNC,
+) -> Result<usize, WitnessStreamError>
+where
+    G: KimchiCurve,
+    G::BaseField: PrimeField,
+    OpeningProof: OpenProof<G>,
+    VerifierIndex<G, OpeningProof>: Clone,
+    EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+    EFrSponge: FrSponge<G::ScalarField>,
+    I: IntoIterator<Item = WitnessChunk<G::ScalarField>>,
+    I::IntoIter: Send,
+    FUNC: FnMut(Result<ProverProof<G, OpeningProof>, ProverError>),
+{
+    let rows = index.cs.gates.len();
+    let empty = || array::from_fn(|_| vec![G::ScalarField::zero(); rows]);
+    let (sender, receiver) = mpsc::sync_channel(capacity);
+    let chunks = chunks.into_iter();
+
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            for chunk in chunks {
+                // the prover stopped
+                if sender.send(chunk).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut proofs = 0;
+        let mut witness: [Vec<_>; COLUMNS] = empty();
+        let mut next = 0;
+        for chunk in receiver {
+            if chunk.start != next {
+                return Err(WitnessStreamError::OutOfOrder(next, chunk.start));
+            }
+            if chunk.start + chunk.rows.len() > rows {
+                return Err(WitnessStreamError::Overflow(
+                    chunk.start,
+                    chunk.rows.len(),
+                    rows,
+                ));
+            }
+            next += chunk.rows.len();
+            for (row, cells) in chunk.rows.into_iter().enumerate() {
+                for (col, cell) in cells.into_iter().enumerate() {
+                    witness[col][chunk.start + row] = cell;
+                }
+            }
+
+            if next == rows {
+                let complete = std::mem::replace(&mut witness, empty());
+                on_proof(ProverProof::create::<EFqSponge, EFrSponge>(
+                    group_map,
+                    complete,
+                    &[],
+                    index,
+                ));
+                proofs += 1;
+                next = 0;
+            }
+        }
+
+        if next != 0 {
+            return Err(WitnessStreamError::Incomplete(next, rows));
+        }
+        Ok(proofs)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        circuits::{gate::CircuitGate, polynomials::generic::GenericGateSpec, wires::Wire},
+        prover_index::testing::new_index_for_test,
+        verifier::verify,
+    };
+    use groupmap::GroupMap;
+    use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+    use mina_poseidon::{
+        constants::PlonkSpongeConstantsKimchi,
+        sponge::{DefaultFqSponge, DefaultFrSponge},
+    };
+    use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof};
+
+    type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+    type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+    #[test]
+    fn test_prove_stream() {
+        let rows = 22;
+        let gates = (0..rows)
+            .map(|row| {
+                CircuitGate::create_generic_gadget(
+                    Wire::for_row(row),
+                    GenericGateSpec::Const(1u32.into()),
+                    None,
+                )
+            })
+            .collect();
+        let index = new_index_for_test::<Vesta>(gates, 0);
+        let verifier_index = index.verifier_index();
+        let group_map = <Vesta as CommitmentCurve>::Map::setup();
+
+        // 3 witnesses, in chunks of 5 rows and a last chunk of 2 rows
+        let chunks = (0..3).flat_map(move |_| {
+            (0..rows).step_by(5).map(move |start| WitnessChunk {
+                start,
+                rows: vec![[Fp::from(1u32); COLUMNS]; (rows - start).min(5)],
+            })
+        });
+        let mut proofs = vec![];
+        let proven = prove_stream::<_, _, BaseSponge, ScalarSponge, _, _>(
+            &group_map,
+            &index,
+            chunks,
+            1,
+            |proof| proofs.push(proof.unwrap()),
+        )
+        .unwrap();
+        assert_eq!(proven, 3);
+        for proof in &proofs {
+            verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+                &group_map,
+                &verifier_index,
+                proof,
+                &[],
+            )
+            .unwrap();
+        }
+
+        // a stream ending in the middle of a witness
+        let truncated = vec![WitnessChunk {
+            start: 0,
+            rows: vec![[Fp::from(1u32); COLUMNS]; 5],
+        }];
+        let res = prove_stream::<_, _, BaseSponge, ScalarSponge, _, _>(
+            &group_map,
+            &index,
+            truncated,
+            1,
+            |_| unreachable!(),
+        );
+        assert!(matches!(res, Err(WitnessStreamError::Incomplete(5, 22))));
+    }
+}
@@ -0,0 +1,85 @@
+"""Trains the reference LeNet-5 on MNIST and exports it for `LeNet5Weights::load`.
+
+    $ python scripts/export_lenet5.py [--out lenet5]
+
+The model is the one of `create_lenet5_circuit`: two 5x5 convolutions, each followed by a ReLU and a 2x2 average pooling,
+and three dense layers, on MNIST digits padded to 32x32.
+Training is seeded and deterministic on CPU, so every run exports the same weights.
+The script writes
+
+- `<out>_weights.txt`, the parameters in the order of `model.parameters()`, as `LeNet5Weights::load` reads them,
+- `<out>_golden.txt`, the first test image (1024 values) followed by its 10 logits, to check the circuit against,
+
+and prints the SHA-256 of both files, to pin them next to the benchmark.
+"""
+
+import argparse
+import hashlib
+
+import numpy as np
+import torch
+from torch import nn
+from torchvision import datasets, transforms
+
+
+class LeNet5(nn.Module):
+    def __init__(self):
+        super().__init__()
+        self.conv1 = nn.Conv2d(1, 6, 5)
+        self.conv2 = nn.Conv2d(6, 16, 5)
+        self.fc1 = nn.Linear(16 * 5 * 5, 120)
+        self.fc2 = nn.Linear(120, 84)
+        self.fc3 = nn.Linear(84, 10)
+        self.pool = nn.AvgPool2d(2, 2)
+
+    def forward(self, x):
+        x = self.pool(torch.relu(self.conv1(x)))
+        x = self.pool(torch.relu(self.conv2(x)))
+        x = x.flatten(1)
+        x = torch.relu(self.fc1(x))
+        x = torch.relu(self.fc2(x))
+        return self.fc3(x)
+
+
+def sha256(path):
+    with open(path, "rb") as f:
+        return hashlib.sha256(f.read()).hexdigest()
+
+
+def main():
+    parser = argparse.ArgumentParser()
+    parser.add_argument("--out", default="lenet5")
+    parser.add_argument("--epochs", type=int, default=2)
+    args = parser.parse_args()
+
+    torch.manual_seed(0)
+    torch.use_deterministic_algorithms(True)
+    transform = transforms.Compose([transforms.Pad(2), transforms.ToTensor()])
+    train = datasets.MNIST("data", train=True, download=True, transform=transform)
+    test = datasets.MNIST("data", train=False, download=True, transform=transform)
+    loader = torch.utils.data.DataLoader(train, batch_size=64, shuffle=True)
+
+    model = LeNet5()
+    optimizer = torch.optim.Adam(model.parameters(), lr=1e-3)
+    for _ in range(args.epochs):
+        for images, labels in loader:
+            optimizer.zero_grad()
+            nn.functional.cross_entropy(model(images), labels).backward()
+            optimizer.step()
+
+    model.eval()
+    weights = f"{args.out}_weights.txt"
+    np.savetxt(weights, np.concatenate([p.detach().numpy().ravel() for p in model.parameters()]))
+
+    image, _ = test[0]
+    with torch.no_grad():
+        logits = model(image.unsqueeze(0))[0]
+    golden = f"{args.out}_golden.txt"
+    np.savetxt(golden, np.concatenate([image.numpy().ravel(), logits.numpy()]))
+
+    for path in [weights, golden]:
+        print(f"{sha256(path)}  {path}")
+
+
+if __name__ == "__main__":
+    main()
@@ -1,6 +1,6 @@
 mod sample_circuit;
 
-use sample_circuit::{LinearRegressionCircuit, create_linear_regression_circuit};
+use sample_circuit::{LinearRegressionCircuit, create_linear_regression_circuit, lint_tensor};
 
 fn main() {
     // Example usage
@@ -9,11 +9,17 @@ fn main() {
     let b = 0.5;
     let y = x.iter().zip(w.iter()).map(|(&xi, &wi)| xi * wi).sum::<f64>() + b;
 
+    // Fail fast on inputs that would not make satisfiable witnesses
+    for (name, values) in [("x", &x), ("w", &w)] {
+        let stats = lint_tensor(name, values, 32).expect("invalid input");
+        println!("{name}: {stats}");
+    }
+
     let circuit = create_linear_regression_circuit(
         x.map(|v| F::from_f64(v)),
-        w.map(|v| F::from_f64(v)),
-        F::from_f64(b),
-        F::from_f64(y),
+        [w.map(|v| F::from_f64(v))],
+        [F::from_f64(b)],
+        [F::from_f64(y)],
     );
 
     // Here you would typically run the circuit proving and verification
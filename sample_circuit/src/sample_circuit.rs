@@ -9,75 +9,2299 @@ use zkcircuit::{
   };
 
   const SCALE_FACTOR: u64 = 1 << 16; // 2^16
-  const N: usize = 10; // Number of features
+  const TOLERANCE: u64 = 1; // Maximum deviation of the output, in units of its last bit
+  const TANH_SATURATION_BITS: usize = 19; // tanh(x) rounds to 1 at SCALE_FACTOR from x = 2^19 / SCALE_FACTOR = 8
+  const GEMM_BLOCK: usize = 8; // Block size of the matrix products of the convolutions
 
-  pub struct LinearRegressionCircuit<F: Field> {
+  /// A multi-output linear regression `y = W x + b` of `M` outputs over `N` features, `W` being given by its rows.
+  /// The rows share the scaling and unscaling tables, and the input is scaled once for all of them.
+  pub struct LinearRegressionCircuit<F: Field, const N: usize, const M: usize> {
       x: [Witness<F>; N],
-      w: [Witness<F>; N],
-      b: Witness<F>,
-      y: Witness<F>,
+      w: [[Witness<F>; N]; M],
+      b: [Witness<F>; M],
+      y: [Witness<F>; M],
       scale_lookup: LookupTable<F>,
       unscale_lookup: LookupTable<F>,
   }
 
-  impl<F: Field> Circuit<F> for LinearRegressionCircuit<F> {
+  impl<F: Field, const N: usize, const M: usize> Circuit<F> for LinearRegressionCircuit<F, N, M> {
       fn synthesize(&self, builder: &mut CircuitBuilder<F>) -> anyhow::Result<()> {
-          // 1. Scaling Layer
-          let mut scaled_x = [Witness::default(); N];
-          let mut scaled_w = [Witness::default(); N];
-          for i in 0..N {
-              scaled_x[i] = builder.mul(self.x[i], F::from(SCALE_FACTOR));
-              scaled_w[i] = builder.mul(self.w[i], F::from(SCALE_FACTOR));
-              builder.lookup(&self.scale_lookup, self.x[i], scaled_x[i])?;
-              builder.lookup(&self.scale_lookup, self.w[i], scaled_w[i])?;
+          // 1. Scaling and Inner Product Layers
+          let rows: Vec<&[Witness<F>]> = self.w.iter().map(|row| row.as_slice()).collect();
+          let z = matvec(builder, &self.scale_lookup, &rows, &self.x)?;
+
+          for ((z, b), y) in z.into_iter().zip(&self.b).zip(&self.y) {
+              let scaled_b = builder.mul(*b, F::from(SCALE_FACTOR * SCALE_FACTOR));
+              builder.lookup(&self.scale_lookup, *b, scaled_b)?;
+
+              // 2. Bias Addition Layer
+              let z_with_bias = builder.add(z, scaled_b);
+
+              // 3. Unscaling Layer
+              let y_scaled = builder.div(z_with_bias, F::from(SCALE_FACTOR * SCALE_FACTOR));
+              builder.lookup(&self.unscale_lookup, y_scaled, *y)?;
+
+              // Constraint: Check if y is correctly calculated, up to the quantization error of y
+              assert_close(builder, y_scaled, *y, TOLERANCE);
           }
+
+          Ok(())
+      }
+  }
+
+  /// A logistic regression over `N` features, classifying the input as `1` if `sigmoid(w x + b) >= 1/2`, and `0` otherwise.
+  /// The probability is approximated with a table created by [sigmoid_table] for signed fixed-point logits of `bits` bits,
+  /// rounded to `granularity_bits`.
+  pub struct LogisticRegressionCircuit<F: Field, const N: usize> {
+      x: [Witness<F>; N],
+      w: [Witness<F>; N],
+      b: Witness<F>,
+      label: Witness<F>,
+      bits: usize,
+      granularity_bits: usize,
+      scale_lookup: LookupTable<F>,
+      sigmoid_table: LookupTable<F>,
+  }
+
+  impl<F: Field, const N: usize> Circuit<F> for LogisticRegressionCircuit<F, N> {
+      fn synthesize(&self, builder: &mut CircuitBuilder<F>) -> anyhow::Result<()> {
+          // 1. Scaling and Inner Product Layers
+          let z = matvec(builder, &self.scale_lookup, &[self.w.as_slice()], &self.x)?[0];
           let scaled_b = builder.mul(self.b, F::from(SCALE_FACTOR * SCALE_FACTOR));
           builder.lookup(&self.scale_lookup, self.b, scaled_b)?;
 
-          // 2. Inner Product Layer
-          let mut z = builder.zero();
-          for i in 0..N {
-              let product = builder.mul(scaled_x[i], scaled_w[i]);
-              z = builder.add(z, product);
-          }
+          // 2. Bias Addition Layer, with the logit brought back to SCALE_FACTOR
+          let z_with_bias = builder.add(z, scaled_b);
+          let logit = builder.div(z_with_bias, F::from(SCALE_FACTOR));
+
+          // 3. Sigmoid Layer
+          let probability = sigmoid(builder, &self.sigmoid_table, logit, self.bits, self.granularity_bits)?;
+
+          // 4. Thresholding Layer: the probability is in [0, SCALE_FACTOR], so its difference with 1/2 fits in the bits of SCALE_FACTOR
+          let half = builder.constant(F::from(SCALE_FACTOR / 2));
+          let centered = builder.sub(probability, half);
+          let threshold_bits = SCALE_FACTOR.trailing_zeros() as usize + 1;
+          let predicted = non_negative(builder, centered, threshold_bits);
+
+          // Constraint: Check if the label is the predicted class
+          builder.assert_eq(predicted, self.label);
+
+          Ok(())
+      }
+  }
 
-          // 3. Bias Addition Layer
+  /// The decision function of an [SvmCircuit], whose parameters are witnesses.
+  pub enum SvmKernel<F: Field, const N: usize> {
+      /// `w x + b`.
+      Linear { w: [Witness<F>; N] },
+      /// `sum_i coefficients_i exp(-gamma |x - support_vectors_i|^2) + b`, the coefficients being the dual coefficients
+      /// times the labels, and `gamma` a constant at `SCALE_FACTOR`.
+      /// The exponentials are approximated with a table created by [exp_table].
+      Rbf {
+          support_vectors: Vec<[Witness<F>; N]>,
+          coefficients: Vec<Witness<F>>,
+          gamma: u64,
+          exp_table: LookupTable<F>,
+      },
+  }
+
+  /// A support vector machine over `N` features, classifying the input as `1` if its decision function is non-negative,
+  /// and `0` otherwise (the class `-1` of the SVM).
+  /// The decision function is computed at `SCALE_FACTOR` as a signed fixed-point value of `bits` bits, sign included.
+  pub struct SvmCircuit<F: Field, const N: usize> {
+      x: [Witness<F>; N],
+      kernel: SvmKernel<F, N>,
+      b: Witness<F>,
+      label: Witness<F>,
+      bits: usize,
+      granularity_bits: usize,
+      scale_lookup: LookupTable<F>,
+  }
+
+  /// Computes the RBF kernel `exp(-gamma |x - sv|^2)` at `SCALE_FACTOR`, for `x` and `sv` scaled to `SCALE_FACTOR`.
+  /// `gamma |x - sv|^2` is range checked to `bits` bits, and rounded down to a multiple of `2^granularity_bits` for the lookup.
+  fn rbf_kernel<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      table: &LookupTable<F>,
+      x: &[Witness<F>],
+      sv: &[Witness<F>],
+      gamma: u64,
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<Witness<F>> {
+      let squares: Vec<_> = x
+          .iter()
+          .zip(sv)
+          .map(|(x, sv)| {
+              let difference = builder.sub(*x, *sv);
+              builder.mul(difference, difference)
+          })
+          .collect();
+      let distance = sum_many(builder, &squares);
+      let distance = builder.div(distance, F::from(SCALE_FACTOR));
+      let exponent = builder.mul(distance, F::from(gamma));
+      let exponent = builder.div(exponent, F::from(SCALE_FACTOR));
+      builder.range_check(exponent, bits);
+
+      let k = builder.div(exponent, F::from(1u64 << granularity_bits));
+      let kernel = builder.witness(table.eval(builder.value(k)));
+      builder.lookup(table, k, kernel)?;
+      Ok(kernel)
+  }
+
+  impl<F: Field, const N: usize> Circuit<F> for SvmCircuit<F, N> {
+      fn synthesize(&self, builder: &mut CircuitBuilder<F>) -> anyhow::Result<()> {
+          // 1. Kernel Layer, at SCALE_FACTOR^2
+          let z = match &self.kernel {
+              SvmKernel::Linear { w } => matvec(builder, &self.scale_lookup, &[w.as_slice()], &self.x)?[0],
+              SvmKernel::Rbf { support_vectors, coefficients, gamma, exp_table } => {
+                  anyhow::ensure!(
+                      support_vectors.len() == coefficients.len(),
+                      "{} support vectors for {} coefficients",
+                      support_vectors.len(),
+                      coefficients.len()
+                  );
+                  let scaled_x = scale_all(builder, &self.scale_lookup, &self.x)?;
+                  let scaled_coefficients = scale_all(builder, &self.scale_lookup, coefficients)?;
+                  let mut terms = Vec::with_capacity(support_vectors.len());
+                  for (sv, coefficient) in support_vectors.iter().zip(scaled_coefficients) {
+                      let scaled_sv = scale_all(builder, &self.scale_lookup, sv)?;
+                      let kernel = rbf_kernel(
+                          builder,
+                          exp_table,
+                          &scaled_x,
+                          &scaled_sv,
+                          *gamma,
+                          self.bits,
+                          self.granularity_bits,
+                      )?;
+                      terms.push(builder.mul(coefficient, kernel));
+                  }
+                  sum_many(builder, &terms)
+              }
+          };
+          let scaled_b = builder.mul(self.b, F::from(SCALE_FACTOR * SCALE_FACTOR));
+          builder.lookup(&self.scale_lookup, self.b, scaled_b)?;
+
+          // 2. Bias Addition Layer, with the decision function brought back to SCALE_FACTOR
           let z_with_bias = builder.add(z, scaled_b);
+          let decision = builder.div(z_with_bias, F::from(SCALE_FACTOR));
 
-          // 4. Unscaling Layer
-          let y_scaled = builder.div(z_with_bias, F::from(SCALE_FACTOR * SCALE_FACTOR));
-          builder.lookup(&self.unscale_lookup, y_scaled, self.y)?;
+          // 3. Sign Layer
+          let predicted = non_negative(builder, decision, self.bits);
 
-          // Constraint: Check if y is correctly calculated
-          builder.assert_eq(y_scaled, self.y);
+          // Constraint: Check if the label is the predicted class
+          builder.assert_eq(predicted, self.label);
 
           Ok(())
       }
   }
 
-  pub fn create_linear_regression_circuit<F: Field>(
+  /// The activation applied to the outputs of a layer of an [MlpCircuit] or a [LeNet5Circuit].
+  #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+  pub enum Activation {
+      Identity,
+      Relu,
+      /// A [leaky_relu] with a constant slope, at `SCALE_FACTOR`.
+      LeakyRelu(u64),
+      Sigmoid,
+      Tanh,
+      Gelu,
+  }
+
+  /// The lookup tables of the activations, for signed fixed-point values of `bits` bits, sign included,
+  /// those with a table rounding their inputs to `granularity_bits`.
+  pub struct Activations<F: Field> {
+      bits: usize,
+      granularity_bits: usize,
+      sigmoid_table: LookupTable<F>,
+      tanh_table: LookupTable<F>,
+      gelu_table: LookupTable<F>,
+  }
+
+  impl<F: Field> Activations<F> {
+      pub fn new(bits: usize, granularity_bits: usize) -> Self {
+          Activations {
+              bits,
+              granularity_bits,
+              sigmoid_table: sigmoid_table(bits, granularity_bits),
+              tanh_table: tanh_table(granularity_bits),
+              gelu_table: gelu_table(bits, granularity_bits),
+          }
+      }
+
+      /// Adds `bias` to the output `z` of a layer, at `SCALE_FACTOR^2`, and applies `activation` at `SCALE_FACTOR`,
+      /// as the activation gadgets expect. The bias is scaled with `scale_lookup`.
+      fn apply(
+          &self,
+          builder: &mut CircuitBuilder<F>,
+          scale_lookup: &LookupTable<F>,
+          activation: Activation,
+          z: Witness<F>,
+          bias: Witness<F>,
+      ) -> anyhow::Result<Witness<F>> {
+          let scaled_bias = builder.mul(bias, F::from(SCALE_FACTOR * SCALE_FACTOR));
+          builder.lookup(scale_lookup, bias, scaled_bias)?;
+          let z_with_bias = builder.add(z, scaled_bias);
+          let x = builder.div(z_with_bias, F::from(SCALE_FACTOR));
+
+          let (bits, granularity_bits) = (self.bits, self.granularity_bits);
+          Ok(match activation {
+              Activation::Identity => x,
+              Activation::Relu => relu(builder, x, bits),
+              Activation::LeakyRelu(slope) => leaky_relu(builder, x, &NegativeSlope::Constant(slope), bits),
+              Activation::Sigmoid => sigmoid(builder, &self.sigmoid_table, x, bits, granularity_bits)?,
+              Activation::Tanh => tanh(builder, &self.tanh_table, x, bits, granularity_bits)?,
+              Activation::Gelu => gelu(builder, &self.gelu_table, x, bits, granularity_bits)?,
+          })
+      }
+  }
+
+  /// A fully connected layer, whose weights are given by their rows, one per output.
+  pub struct DenseLayer<F: Field> {
+      weights: Vec<Vec<Witness<F>>>,
+      biases: Vec<Witness<F>>,
+      activation: Activation,
+  }
+
+  /// Creates a [DenseLayer] of `inputs` inputs from its weights, a row of `inputs` weights per output, and its biases.
+  fn dense_layer<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      inputs: usize,
+      weights: &[Vec<F>],
+      biases: &[F],
+      activation: Activation,
+  ) -> anyhow::Result<DenseLayer<F>> {
+      anyhow::ensure!(
+          weights.iter().all(|row| row.len() == inputs),
+          "the weights of a dense layer are not {}x{inputs}",
+          weights.len()
+      );
+      anyhow::ensure!(biases.len() == weights.len(), "{} biases for {} outputs", biases.len(), weights.len());
+      Ok(DenseLayer {
+          weights: weights
+              .iter()
+              .map(|row| row.iter().map(|v| builder.witness(*v)).collect())
+              .collect(),
+          biases: biases.iter().map(|v| builder.witness(*v)).collect(),
+          activation,
+      })
+  }
+
+  /// Applies a sequence of dense layers to `x`, each output being brought back to the scale of the inputs for the next layer.
+  fn dense_forward<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      scale_lookup: &LookupTable<F>,
+      activations: &Activations<F>,
+      layers: &[DenseLayer<F>],
+      x: &[Witness<F>],
+  ) -> anyhow::Result<Vec<Witness<F>>> {
+      let mut h = x.to_vec();
+      for layer in layers {
+          // 1. Inner products, at SCALE_FACTOR^2
+          let rows: Vec<&[Witness<F>]> = layer.weights.iter().map(|row| row.as_slice()).collect();
+          let z = matvec(builder, scale_lookup, &rows, &h)?;
+
+          let mut outputs = Vec::with_capacity(z.len());
+          for (z, b) in z.into_iter().zip(&layer.biases) {
+              // 2. Bias and activation, at SCALE_FACTOR
+              let activated = activations.apply(builder, scale_lookup, layer.activation, z, *b)?;
+
+              // 3. Unscaling, to the scale of the inputs
+              outputs.push(builder.div(activated, F::from(SCALE_FACTOR)));
+          }
+          h = outputs;
+      }
+      Ok(h)
+  }
+
+  /// A multi-layer perceptron: a sequence of fully connected layers, each followed by its activation.
+  /// The outputs of each layer are computed at `SCALE_FACTOR` for the activation,
+  /// and brought back to the scale of the inputs for the next layer.
+  pub struct MlpCircuit<F: Field> {
+      x: Vec<Witness<F>>,
+      layers: Vec<DenseLayer<F>>,
+      y: Vec<Witness<F>>,
+      scale_lookup: LookupTable<F>,
+      activations: Activations<F>,
+  }
+
+  impl<F: Field> Circuit<F> for MlpCircuit<F> {
+      fn synthesize(&self, builder: &mut CircuitBuilder<F>) -> anyhow::Result<()> {
+          let h = dense_forward(builder, &self.scale_lookup, &self.activations, &self.layers, &self.x)?;
+
+          // Constraint: Check if y is correctly calculated, up to its quantization error
+          for (output, y) in h.into_iter().zip(&self.y) {
+              assert_close(builder, output, *y, TOLERANCE);
+          }
+          Ok(())
+      }
+  }
+
+  /// A convolutional layer of a [LeNet5Circuit], with a kernel of `in_channels x k x k` weights and a bias per output channel,
+  /// followed by its activation and a `2 x 2` average pooling.
+  pub struct ConvLayer<F: Field> {
+      kernels: Vec<FeatureMap<F>>,
+      biases: Vec<Witness<F>>,
+      activation: Activation,
+  }
+
+  impl<F: Field> ConvLayer<F> {
+      /// Applies the layer to `input`, at the scale of the inputs, which the output is brought back to.
+      fn forward(
+          &self,
+          builder: &mut CircuitBuilder<F>,
+          scale_lookup: &LookupTable<F>,
+          activations: &Activations<F>,
+          input: &[Vec<Vec<Witness<F>>>],
+      ) -> anyhow::Result<FeatureMap<F>> {
+          // 1. Convolution, at SCALE_FACTOR^2
+          let z = conv2d(builder, scale_lookup, input, &self.kernels, 1, GEMM_BLOCK)?;
+
+          // 2. Bias and activation, at SCALE_FACTOR
+          let mut activated = Vec::with_capacity(z.len());
+          for (channel, b) in z.into_iter().zip(&self.biases) {
+              let mut rows = Vec::with_capacity(channel.len());
+              for row in channel {
+                  rows.push(
+                      row.into_iter()
+                          .map(|z| activations.apply(builder, scale_lookup, self.activation, z, *b))
+                          .collect::<anyhow::Result<Vec<_>>>()?,
+                  );
+              }
+              activated.push(rows);
+          }
+
+          // 3. Pooling, then unscaling to the scale of the inputs
+          let pooled = avg_pool2d(builder, &activated, 2, 2, activations.bits)?;
+          Ok(pooled
+              .into_iter()
+              .map(|channel| {
+                  channel
+                      .into_iter()
+                      .map(|row| row.into_iter().map(|x| builder.div(x, F::from(SCALE_FACTOR))).collect())
+                      .collect()
+              })
+              .collect())
+      }
+  }
+
+  /// Creates a [ConvLayer] of `5 x 5` kernels from its kernels and its biases.
+  fn conv_layer<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      (kernels, biases): &(Vec<Vec<Vec<Vec<F>>>>, Vec<F>),
+      in_channels: usize,
+      out_channels: usize,
+      activation: Activation,
+  ) -> anyhow::Result<ConvLayer<F>> {
+      anyhow::ensure!(
+          kernels.len() == out_channels
+              && kernels.iter().all(|kernel| kernel.len() == in_channels
+                  && kernel.iter().all(|channel| channel.len() == 5 && channel.iter().all(|row| row.len() == 5))),
+          "the kernels of a convolution are not {out_channels}x{in_channels}x5x5"
+      );
+      anyhow::ensure!(biases.len() == out_channels, "{} biases for {out_channels} channels", biases.len());
+      Ok(ConvLayer {
+          kernels: kernels
+              .iter()
+              .map(|kernel| {
+                  kernel
+                      .iter()
+                      .map(|channel| channel.iter().map(|row| row.iter().map(|v| builder.witness(*v)).collect()).collect())
+                      .collect()
+              })
+              .collect(),
+          biases: biases.iter().map(|v| builder.witness(*v)).collect(),
+          activation,
+      })
+  }
+
+  /// LeNet-5 on a `32 x 32` grayscale image (a `28 x 28` MNIST digit padded by 2):
+  /// two convolutions of `5 x 5` kernels (6, then 16 channels), each followed by its activation and a `2 x 2` average pooling,
+  /// and a classifier of three dense layers (120, 84 and 10 outputs) on the `16 x 5 x 5` features.
+  /// The 10 outputs are the logits of the digits, checked against `y` up to their quantization error.
+  pub struct LeNet5Circuit<F: Field> {
+      image: FeatureMap<F>,
+      conv1: ConvLayer<F>,
+      conv2: ConvLayer<F>,
+      classifier: Vec<DenseLayer<F>>,
+      y: Vec<Witness<F>>,
+      scale_lookup: LookupTable<F>,
+      activations: Activations<F>,
+  }
+
+  impl<F: Field> Circuit<F> for LeNet5Circuit<F> {
+      fn synthesize(&self, builder: &mut CircuitBuilder<F>) -> anyhow::Result<()> {
+          // 1. Feature extraction: 1x32x32 -> 6x14x14 -> 16x5x5
+          let features = self.conv1.forward(builder, &self.scale_lookup, &self.activations, &self.image)?;
+          let features = self.conv2.forward(builder, &self.scale_lookup, &self.activations, &features)?;
+
+          // 2. Classification of the 400 features, channel by channel
+          let flattened: Vec<_> = features.into_iter().flatten().flatten().collect();
+          let logits = dense_forward(builder, &self.scale_lookup, &self.activations, &self.classifier, &flattened)?;
+
+          // Constraint: Check if the logits are correctly calculated, up to their quantization error
+          for (logit, y) in logits.into_iter().zip(&self.y) {
+              assert_close(builder, logit, *y, TOLERANCE);
+          }
+          Ok(())
+      }
+  }
+
+  /// A self-attention head on a sequence of `n` tokens of `d_model` features:
+  /// the tokens are projected to queries, keys and values of `d_head` entries by `w_q`, `w_k` and `w_v`
+  /// (`d_model x d_head` matrices, given by their rows), and the output of the [attention] gadget,
+  /// `n x d_head` values at the scale of the inputs, is checked against `y` up to its quantization error.
+  pub struct AttentionCircuit<F: Field> {
+      x: Vec<Vec<Witness<F>>>,
+      w_q: Vec<Vec<Witness<F>>>,
+      w_k: Vec<Vec<Witness<F>>>,
+      w_v: Vec<Vec<Witness<F>>>,
+      y: Vec<Vec<Witness<F>>>,
+      bits: usize,
+      granularity_bits: usize,
+      scale_lookup: LookupTable<F>,
+      exp_table: LookupTable<F>,
+  }
+
+  impl<F: Field> Circuit<F> for AttentionCircuit<F> {
+      fn synthesize(&self, builder: &mut CircuitBuilder<F>) -> anyhow::Result<()> {
+          // 1. Projection Layers, brought back to the scale of the inputs
+          let q = project(builder, &self.scale_lookup, &self.x, &self.w_q)?;
+          let k = project(builder, &self.scale_lookup, &self.x, &self.w_k)?;
+          let v = project(builder, &self.scale_lookup, &self.x, &self.w_v)?;
+
+          // 2. Attention Layer
+          let output = attention(
+              builder,
+              &self.scale_lookup,
+              &self.exp_table,
+              (&rows(&q), &rows(&k), &rows(&v)),
+              self.bits,
+              self.granularity_bits,
+          )?;
+
+          // Constraint: Check if the output is correctly calculated, up to its quantization error
+          for (output, y) in output.into_iter().flatten().zip(self.y.iter().flatten()) {
+              assert_close(builder, output, *y, TOLERANCE);
+          }
+          Ok(())
+      }
+  }
+
+  /// A layer normalization `gamma * (x - mean(x)) / sqrt(var(x) + eps) + beta` of the features of a token.
+  pub struct LayerNorm<F: Field> {
+      gamma: Vec<Witness<F>>,
+      beta: Vec<Witness<F>>,
+      /// `eps`, at `SCALE_FACTOR^2`; it must not be zero, so that the deviation is not.
+      eps: F,
+  }
+
+  impl<F: Field> LayerNorm<F> {
+      /// Normalizes `x`, at the scale of the inputs, which the output is brought back to.
+      /// The computation is at `SCALE_FACTOR`, on signed fixed-point values of `bits` bits, sign included,
+      /// which the features scaled to `SCALE_FACTOR` and the normalized features (up to `sqrt(n)` at `SCALE_FACTOR`) must fit in.
+      /// The mean and the variance are rounded down by constrained divisions,
+      /// and the deviation is hinted as the integer square root of the variance plus `eps`, checked with two range checks.
+      /// The hints are computed on `u64`s, so the sum of the squares of the centered features,
+      /// of `2 * (bits + 1) + log2(n)` bits, must fit in 64 bits.
+      fn forward(
+          &self,
+          builder: &mut CircuitBuilder<F>,
+          scale_lookup: &LookupTable<F>,
+          x: &[Witness<F>],
+          bits: usize,
+      ) -> anyhow::Result<Vec<Witness<F>>> {
+          let n = x.len() as u64;
+          anyhow::ensure!(n > 0, "the normalization of no features");
+          anyhow::ensure!(
+              self.gamma.len() == x.len() && self.beta.len() == x.len(),
+              "a layer normalization of {} parameters for {n} features",
+              self.gamma.len()
+          );
+          let n_bits = (u64::BITS - n.leading_zeros()) as usize;
+          anyhow::ensure!(
+              2 * (bits + 1) + n_bits <= u64::BITS as usize,
+              "a layer normalization of {n} features of {bits} bits, whose sum of squares does not fit in 64 bits"
+          );
+          let count = builder.constant(F::from(n));
+
+          // 1. Mean, at SCALE_FACTOR, with the offset of avg_pool2d for the signed values
+          let scaled = scale_all(builder, scale_lookup, x)?;
+          let sum = sum_many(builder, &scaled);
+          let offset = builder.constant(F::from(n << (bits - 1)));
+          let unoffset = builder.constant(F::from(1u64 << (bits - 1)));
+          let sum = builder.add(sum, offset);
+          let mean = div_rem(builder, sum, count, n_bits, bits);
+          let mean = builder.sub(mean, unoffset);
+
+          // 2. Variance plus eps, at SCALE_FACTOR^2
+          let centered: Vec<_> = scaled.iter().map(|x| builder.sub(*x, mean)).collect();
+          let squares: Vec<_> = centered.iter().map(|c| builder.mul(*c, *c)).collect();
+          let sum = sum_many(builder, &squares);
+          let variance = div_rem(builder, sum, count, n_bits, 2 * bits);
+          let variance = builder.add(variance, self.eps);
+
+          // 3. Deviation, at SCALE_FACTOR: s^2 <= variance < (s + 1)^2, so both gaps are at most 2s
+          let deviation = builder.witness(F::from(builder.value(variance).to_u64().isqrt()));
+          builder.range_check(deviation, bits);
+          let square = builder.mul(deviation, deviation);
+          let below = builder.sub(variance, square);
+          builder.range_check(below, bits + 1);
+          let twice = builder.add(deviation, deviation);
+          let next_square = builder.add(square, twice);
+          let above = builder.sub(next_square, variance);
+          builder.range_check(above, bits + 1);
+
+          // 4. Normalization, at SCALE_FACTOR, offset by a multiple of the deviation for the signed values
+          let half_range = F::from(1u64 << (bits - 1));
+          let offset = builder.mul(deviation, half_range);
+          let unoffset = builder.constant(half_range);
+          let mut output = Vec::with_capacity(x.len());
+          for ((c, gamma), beta) in centered.into_iter().zip(&self.gamma).zip(&self.beta) {
+              let numerator = builder.mul(c, F::from(SCALE_FACTOR));
+              let numerator = builder.add(numerator, offset);
+              let normalized = div_rem(builder, numerator, deviation, bits, bits);
+              let normalized = builder.sub(normalized, unoffset);
+
+              // 5. Affine transform, at SCALE_FACTOR^2, brought back to the scale of the inputs
+              let scaled_gamma = builder.mul(*gamma, F::from(SCALE_FACTOR));
+              builder.lookup(scale_lookup, *gamma, scaled_gamma)?;
+              let scaled_beta = builder.mul(*beta, F::from(SCALE_FACTOR * SCALE_FACTOR));
+              builder.lookup(scale_lookup, *beta, scaled_beta)?;
+              let product = builder.mul(scaled_gamma, normalized);
+              let z = builder.add(product, scaled_beta);
+              output.push(builder.div(z, F::from(SCALE_FACTOR * SCALE_FACTOR)));
+          }
+          Ok(output)
+      }
+  }
+
+  /// A pre-normalization transformer encoder block on a sequence of `n` tokens of `d_model` features:
+  /// `h = x + MultiHead(LayerNorm(x))`, then `y = h + Mlp(LayerNorm(h))`.
+  /// The multi-head attention splits the projections `w_q`, `w_k` and `w_v` (`d_model x d_model`, given by their rows)
+  /// into `heads` heads of the [attention] gadget, whose outputs are concatenated and projected by `w_o`;
+  /// the MLP is two dense layers, a hidden one with a GELU and an output one of `d_model` features.
+  /// All the activations are at the scale of the inputs, and the output is checked against `y` up to its quantization error.
+  pub struct EncoderBlockCircuit<F: Field> {
+      x: Vec<Vec<Witness<F>>>,
+      heads: usize,
+      norm1: LayerNorm<F>,
+      w_q: Vec<Vec<Witness<F>>>,
+      w_k: Vec<Vec<Witness<F>>>,
+      w_v: Vec<Vec<Witness<F>>>,
+      w_o: Vec<Vec<Witness<F>>>,
+      norm2: LayerNorm<F>,
+      mlp: Vec<DenseLayer<F>>,
+      y: Vec<Vec<Witness<F>>>,
+      scale_lookup: LookupTable<F>,
+      exp_table: LookupTable<F>,
+      activations: Activations<F>,
+  }
+
+  impl<F: Field> EncoderBlockCircuit<F> {
+      /// Normalizes each token with `norm`.
+      fn normalize(
+          &self,
+          builder: &mut CircuitBuilder<F>,
+          norm: &LayerNorm<F>,
+          x: &[Vec<Witness<F>>],
+      ) -> anyhow::Result<Vec<Vec<Witness<F>>>> {
+          x.iter()
+              .map(|token| norm.forward(builder, &self.scale_lookup, token, self.activations.bits))
+              .collect()
+      }
+  }
+
+  /// Adds the residual connection `x` to the output `z` of a sublayer.
+  fn residual<F: Field>(builder: &mut CircuitBuilder<F>, x: &[Vec<Witness<F>>], z: Vec<Vec<Witness<F>>>) -> Vec<Vec<Witness<F>>> {
+      x.iter()
+          .zip(z)
+          .map(|(x, z)| x.iter().zip(z).map(|(x, z)| builder.add(*x, z)).collect())
+          .collect()
+  }
+
+  /// Adds two tensors of activations of the same shape, at the scales `SCALE_FACTOR^scale_a` and `SCALE_FACTOR^scale_b`,
+  /// as a skip connection between layers that left them at different scales, and returns the sum with its scale.
+  /// The tensor at the lower scale is brought to the higher one with a scaling lookup per level, so that no precision is lost;
+  /// `scale_lookup` must cover the values it rescales.
+  pub fn add_residual<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      scale_lookup: &LookupTable<F>,
+      (a, scale_a): (&[Witness<F>], u32),
+      (b, scale_b): (&[Witness<F>], u32),
+  ) -> anyhow::Result<(Vec<Witness<F>>, u32)> {
+      anyhow::ensure!(a.len() == b.len(), "a residual connection of {} values to a tensor of {}", a.len(), b.len());
+      let scale = scale_a.max(scale_b);
+      let mut rescale = |values: &[Witness<F>], from: u32| -> anyhow::Result<Vec<Witness<F>>> {
+          let mut values = values.to_vec();
+          for _ in from..scale {
+              values = scale_all(builder, scale_lookup, &values)?;
+          }
+          Ok(values)
+      };
+      let a = rescale(a, scale_a)?;
+      let b = rescale(b, scale_b)?;
+      let sum = a.into_iter().zip(b).map(|(a, b)| builder.add(a, b)).collect();
+      Ok((sum, scale))
+  }
+
+  impl<F: Field> Circuit<F> for EncoderBlockCircuit<F> {
+      fn synthesize(&self, builder: &mut CircuitBuilder<F>) -> anyhow::Result<()> {
+          let (bits, granularity_bits) = (self.activations.bits, self.activations.granularity_bits);
+
+          // 1. Multi-head attention sublayer
+          let normalized = self.normalize(builder, &self.norm1, &self.x)?;
+          let q = project(builder, &self.scale_lookup, &normalized, &self.w_q)?;
+          let k = project(builder, &self.scale_lookup, &normalized, &self.w_k)?;
+          let v = project(builder, &self.scale_lookup, &normalized, &self.w_v)?;
+          let d_head = self.w_q.first().map_or(0, |row| row.len()) / self.heads;
+          let columns = |matrix: &[Vec<Witness<F>>], h: usize| -> Vec<Vec<Witness<F>>> {
+              matrix.iter().map(|row| row[h * d_head..(h + 1) * d_head].to_vec()).collect()
+          };
+          let mut concatenated = vec![Vec::with_capacity(d_head * self.heads); self.x.len()];
+          for h in 0..self.heads {
+              let (q, k, v) = (columns(&q, h), columns(&k, h), columns(&v, h));
+              let head = attention(
+                  builder,
+                  &self.scale_lookup,
+                  &self.exp_table,
+                  (&rows(&q), &rows(&k), &rows(&v)),
+                  bits,
+                  granularity_bits,
+              )?;
+              for (token, output) in concatenated.iter_mut().zip(head) {
+                  token.extend(output);
+              }
+          }
+          let attended = project(builder, &self.scale_lookup, &concatenated, &self.w_o)?;
+          let h = residual(builder, &self.x, attended);
+
+          // 2. MLP sublayer
+          let normalized = self.normalize(builder, &self.norm2, &h)?;
+          let transformed = normalized
+              .iter()
+              .map(|token| dense_forward(builder, &self.scale_lookup, &self.activations, &self.mlp, token))
+              .collect::<anyhow::Result<Vec<_>>>()?;
+          let output = residual(builder, &h, transformed);
+
+          // Constraint: Check if the output is correctly calculated, up to its quantization error
+          for (output, y) in output.into_iter().flatten().zip(self.y.iter().flatten()) {
+              assert_close(builder, output, *y, TOLERANCE);
+          }
+          Ok(())
+      }
+  }
+
+  /// An LSTM cell of `hidden` units on inputs of `n` features, with four gates in the order input, forget, cell and output:
+  /// `w` has a row of `n` weights and `u` a row of `hidden` weights for each of the `4 * hidden` gate units, and `b` a bias each.
+  pub struct LstmCell<F: Field> {
+      w: Vec<Vec<Witness<F>>>,
+      u: Vec<Vec<Witness<F>>>,
+      b: Vec<Witness<F>>,
+  }
+
+  impl<F: Field> LstmCell<F> {
+      /// The number of hidden units.
+      pub fn hidden(&self) -> usize {
+          self.b.len() / 4
+      }
+
+      /// Runs a step of the cell on `x`, from the hidden and cell states `(h, c)`, and returns the next states.
+      /// The inputs and the states are at the scale of the inputs, and so are the returned states:
+      /// `i, f, o = sigmoid(...)` and `g = tanh(...)` at `SCALE_FACTOR`, `c' = f c + i g` and `h' = o tanh(c')`.
+      pub fn step(
+          &self,
+          builder: &mut CircuitBuilder<F>,
+          scale_lookup: &LookupTable<F>,
+          activations: &Activations<F>,
+          x: &[Witness<F>],
+          (h, c): (&[Witness<F>], &[Witness<F>]),
+      ) -> anyhow::Result<(Vec<Witness<F>>, Vec<Witness<F>>)> {
+          let hidden = self.hidden();
+          anyhow::ensure!(h.len() == hidden && c.len() == hidden, "states of {} and {} units for a cell of {hidden}", h.len(), c.len());
+
+          // 1. Gates, at SCALE_FACTOR
+          let wx = matvec(builder, scale_lookup, &rows(&self.w), x)?;
+          let uh = matvec(builder, scale_lookup, &rows(&self.u), h)?;
+          let mut gates = Vec::with_capacity(4 * hidden);
+          for (unit, ((wx, uh), b)) in wx.into_iter().zip(uh).zip(&self.b).enumerate() {
+              let z = builder.add(wx, uh);
+              let activation = if unit / hidden == 2 { Activation::Tanh } else { Activation::Sigmoid };
+              gates.push(activations.apply(builder, scale_lookup, activation, z, *b)?);
+          }
+          let (i, rest) = gates.split_at(hidden);
+          let (f, rest) = rest.split_at(hidden);
+          let (g, o) = rest.split_at(hidden);
+
+          // 2. State carry, at SCALE_FACTOR: f c is at SCALE_FACTOR, and i g at SCALE_FACTOR^2
+          let mut next_h = Vec::with_capacity(hidden);
+          let mut next_c = Vec::with_capacity(hidden);
+          for unit in 0..hidden {
+              let kept = builder.mul(f[unit], c[unit]);
+              let written = builder.mul(i[unit], g[unit]);
+              let written = builder.div(written, F::from(SCALE_FACTOR));
+              let cell = builder.add(kept, written);
+
+              // 3. Output, brought back to the scale of the inputs
+              let squashed = tanh(builder, &activations.tanh_table, cell, activations.bits, activations.granularity_bits)?;
+              let output = builder.mul(o[unit], squashed);
+              next_h.push(builder.div(output, F::from(SCALE_FACTOR * SCALE_FACTOR)));
+              next_c.push(builder.div(cell, F::from(SCALE_FACTOR)));
+          }
+          Ok((next_h, next_c))
+      }
+  }
+
+  /// Unrolls an LSTM cell over a sequence of fixed length, from the states `initial`,
+  /// and returns the hidden state after each step and the final cell state.
+  pub fn unroll_lstm<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      scale_lookup: &LookupTable<F>,
+      activations: &Activations<F>,
+      cell: &LstmCell<F>,
+      sequence: &[Vec<Witness<F>>],
+      (h, c): (Vec<Witness<F>>, Vec<Witness<F>>),
+  ) -> anyhow::Result<(Vec<Vec<Witness<F>>>, Vec<Witness<F>>)> {
+      let (mut h, mut c) = (h, c);
+      let mut outputs = Vec::with_capacity(sequence.len());
+      for x in sequence {
+          (h, c) = cell.step(builder, scale_lookup, activations, x, (&h, &c))?;
+          outputs.push(h.clone());
+      }
+      Ok((outputs, c))
+  }
+
+  /// An LSTM over a sequence of tokens, from zero states,
+  /// whose hidden state after the last token is checked against `y` up to its quantization error.
+  pub struct LstmCircuit<F: Field> {
+      sequence: Vec<Vec<Witness<F>>>,
+      cell: LstmCell<F>,
+      y: Vec<Witness<F>>,
+      scale_lookup: LookupTable<F>,
+      activations: Activations<F>,
+  }
+
+  impl<F: Field> Circuit<F> for LstmCircuit<F> {
+      fn synthesize(&self, builder: &mut CircuitBuilder<F>) -> anyhow::Result<()> {
+          let zeros: Vec<_> = (0..self.cell.hidden()).map(|_| builder.constant(F::from(0u64))).collect();
+          let (outputs, _) = unroll_lstm(
+              builder,
+              &self.scale_lookup,
+              &self.activations,
+              &self.cell,
+              &self.sequence,
+              (zeros.clone(), zeros),
+          )?;
+
+          // Constraint: Check if the last hidden state is correctly calculated, up to its quantization error
+          let last = outputs.last().cloned().unwrap_or_default();
+          for (output, y) in last.into_iter().zip(&self.y) {
+              assert_close(builder, output, *y, TOLERANCE);
+          }
+          Ok(())
+      }
+  }
+
+  /// A GRU cell of `hidden` units on inputs of `n` features, with three gates in the order update, reset and candidate,
+  /// laid out as the weights of an [LstmCell]. It keeps no cell state, and costs three activations per unit instead of five.
+  pub struct GruCell<F: Field> {
+      w: Vec<Vec<Witness<F>>>,
+      u: Vec<Vec<Witness<F>>>,
+      b: Vec<Witness<F>>,
+  }
+
+  impl<F: Field> GruCell<F> {
+      /// The number of hidden units.
+      pub fn hidden(&self) -> usize {
+          self.b.len() / 3
+      }
+
+      /// Runs a step of the cell on `x`, from the hidden state `h`, and returns the next hidden state.
+      /// The inputs and the states are at the scale of the inputs: `z, r = sigmoid(...)` at `SCALE_FACTOR`,
+      /// `n = tanh(W_n x + U_n (r h) + b_n)` at `SCALE_FACTOR` and `h' = (1 - z) h + z n`.
+      pub fn step(
+          &self,
+          builder: &mut CircuitBuilder<F>,
+          scale_lookup: &LookupTable<F>,
+          activations: &Activations<F>,
+          x: &[Witness<F>],
+          h: &[Witness<F>],
+      ) -> anyhow::Result<Vec<Witness<F>>> {
+          let hidden = self.hidden();
+          anyhow::ensure!(h.len() == hidden, "a state of {} units for a cell of {hidden}", h.len());
+
+          // 1. Update and reset gates, at SCALE_FACTOR
+          let wx = matvec(builder, scale_lookup, &rows(&self.w), x)?;
+          let uh = matvec(builder, scale_lookup, &rows(&self.u[..2 * hidden]), h)?;
+          let mut gates = Vec::with_capacity(2 * hidden);
+          for ((wx, uh), b) in wx.iter().zip(uh).zip(&self.b) {
+              let z = builder.add(*wx, uh);
+              gates.push(activations.apply(builder, scale_lookup, Activation::Sigmoid, z, *b)?);
+          }
+          let (z, r) = gates.split_at(hidden);
+
+          // 2. Candidate state, at SCALE_FACTOR, from the reset hidden state brought back to the scale of the inputs
+          let reset: Vec<_> = r
+              .iter()
+              .zip(h)
+              .map(|(r, h)| {
+                  let product = builder.mul(*r, *h);
+                  builder.div(product, F::from(SCALE_FACTOR))
+              })
+              .collect();
+          let ur = matvec(builder, scale_lookup, &rows(&self.u[2 * hidden..]), &reset)?;
+          let mut candidate = Vec::with_capacity(hidden);
+          for ((wx, ur), b) in wx[2 * hidden..].iter().zip(ur).zip(&self.b[2 * hidden..]) {
+              let z = builder.add(*wx, ur);
+              candidate.push(activations.apply(builder, scale_lookup, Activation::Tanh, z, *b)?);
+          }
+
+          // 3. Interpolation, at SCALE_FACTOR, brought back to the scale of the inputs
+          let one = builder.constant(F::from(SCALE_FACTOR));
+          let mut next_h = Vec::with_capacity(hidden);
+          for unit in 0..hidden {
+              let retain = builder.sub(one, z[unit]);
+              let kept = builder.mul(retain, h[unit]);
+              let written = builder.mul(z[unit], candidate[unit]);
+              let written = builder.div(written, F::from(SCALE_FACTOR));
+              let state = builder.add(kept, written);
+              next_h.push(builder.div(state, F::from(SCALE_FACTOR)));
+          }
+          Ok(next_h)
+      }
+  }
+
+  /// Unrolls a GRU cell over a sequence of fixed length, from the hidden state `h`,
+  /// and returns the hidden state after each step.
+  pub fn unroll_gru<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      scale_lookup: &LookupTable<F>,
+      activations: &Activations<F>,
+      cell: &GruCell<F>,
+      sequence: &[Vec<Witness<F>>],
+      h: Vec<Witness<F>>,
+  ) -> anyhow::Result<Vec<Vec<Witness<F>>>> {
+      let mut h = h;
+      let mut outputs = Vec::with_capacity(sequence.len());
+      for x in sequence {
+          h = cell.step(builder, scale_lookup, activations, x, &h)?;
+          outputs.push(h.clone());
+      }
+      Ok(outputs)
+  }
+
+  /// A GRU over a sequence of tokens, from a zero state,
+  /// whose hidden state after the last token is checked against `y` up to its quantization error.
+  pub struct GruCircuit<F: Field> {
+      sequence: Vec<Vec<Witness<F>>>,
+      cell: GruCell<F>,
+      y: Vec<Witness<F>>,
+      scale_lookup: LookupTable<F>,
+      activations: Activations<F>,
+  }
+
+  impl<F: Field> Circuit<F> for GruCircuit<F> {
+      fn synthesize(&self, builder: &mut CircuitBuilder<F>) -> anyhow::Result<()> {
+          let zeros: Vec<_> = (0..self.cell.hidden()).map(|_| builder.constant(F::from(0u64))).collect();
+          let outputs = unroll_gru(builder, &self.scale_lookup, &self.activations, &self.cell, &self.sequence, zeros)?;
+
+          // Constraint: Check if the last hidden state is correctly calculated, up to its quantization error
+          let last = outputs.last().cloned().unwrap_or_default();
+          for (output, y) in last.into_iter().zip(&self.y) {
+              assert_close(builder, output, *y, TOLERANCE);
+          }
+          Ok(())
+      }
+  }
+
+  /// Borrows the rows of a matrix, as the matrix products take them.
+  fn rows<F: Field>(matrix: &[Vec<Witness<F>>]) -> Vec<&[Witness<F>]> {
+      matrix.iter().map(|row| row.as_slice()).collect()
+  }
+
+  /// Brings the outputs of a matrix product, at `SCALE_FACTOR^2`, back to the scale of the inputs.
+  fn unscale_all<F: Field>(builder: &mut CircuitBuilder<F>, z: Vec<Vec<Witness<F>>>) -> Vec<Vec<Witness<F>>> {
+      z.into_iter()
+          .map(|row| row.into_iter().map(|z| builder.div(z, F::from(SCALE_FACTOR * SCALE_FACTOR))).collect())
+          .collect()
+  }
+
+  /// Projects the tokens `x` by the matrix `w`, given by its rows, back to the scale of the inputs.
+  fn project<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      scale_lookup: &LookupTable<F>,
+      x: &[Vec<Witness<F>>],
+      w: &[Vec<Witness<F>>],
+  ) -> anyhow::Result<Vec<Vec<Witness<F>>>> {
+      let z = gemm(builder, scale_lookup, &rows(x), &rows(w), GEMM_BLOCK)?;
+      Ok(unscale_all(builder, z))
+  }
+
+  /// Scales the values to `SCALE_FACTOR`, each scaling being checked against `scale_lookup`.
+  fn scale_all<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      scale_lookup: &LookupTable<F>,
+      values: &[Witness<F>],
+  ) -> anyhow::Result<Vec<Witness<F>>> {
+      let mut scaled = Vec::with_capacity(values.len());
+      for value in values {
+          let scaled_value = builder.mul(*value, F::from(SCALE_FACTOR));
+          builder.lookup(scale_lookup, *value, scaled_value)?;
+          scaled.push(scaled_value);
+      }
+      Ok(scaled)
+  }
+
+  /// Computes the product `W x` of a matrix, given by its rows, with a vector,
+  /// both scaled to `SCALE_FACTOR` with `scale_lookup`, so the outputs are at `SCALE_FACTOR^2`.
+  /// The vector is scaled once for all the rows, and the scaling lookups of each row are made together,
+  /// before the row's inner product.
+  pub fn matvec<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      scale_lookup: &LookupTable<F>,
+      w: &[&[Witness<F>]],
+      x: &[Witness<F>],
+  ) -> anyhow::Result<Vec<Witness<F>>> {
+      let scaled_x = scale_all(builder, scale_lookup, x)?;
+      let mut outputs = Vec::with_capacity(w.len());
+      for row in w {
+          anyhow::ensure!(row.len() == x.len(), "a row of {} weights for {} inputs", row.len(), x.len());
+          let scaled_row = scale_all(builder, scale_lookup, row)?;
+          let products: Vec<_> = scaled_row
+              .iter()
+              .zip(&scaled_x)
+              .map(|(w, x)| builder.mul(*w, *x))
+              .collect();
+          outputs.push(sum_many(builder, &products));
+      }
+      Ok(outputs)
+  }
+
+  /// Computes the product `A B` of an `m x n` matrix and an `n x p` matrix, given by their rows,
+  /// both scaled to `SCALE_FACTOR` with `scale_lookup`, so the outputs are at `SCALE_FACTOR^2`.
+  /// Every entry is scaled once, however many products it is used in.
+  /// The output is computed by blocks of `block x block` entries, each accumulating its inner products
+  /// by blocks of `block` terms, so that a block only uses `block` rows of `A` and `block` columns of `B` at a time.
+  pub fn gemm<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      scale_lookup: &LookupTable<F>,
+      a: &[&[Witness<F>]],
+      b: &[&[Witness<F>]],
+      block: usize,
+  ) -> anyhow::Result<Vec<Vec<Witness<F>>>> {
+      anyhow::ensure!(block > 0, "the block size must not be zero");
+      let (m, n) = (a.len(), b.len());
+      let p = b.first().map_or(0, |row| row.len());
+      anyhow::ensure!(a.iter().all(|row| row.len() == n), "the rows of A do not have {n} entries");
+      anyhow::ensure!(b.iter().all(|row| row.len() == p), "the rows of B do not have {p} entries");
+
+      let scaled_a = a
+          .iter()
+          .map(|row| scale_all(builder, scale_lookup, row))
+          .collect::<anyhow::Result<Vec<_>>>()?;
+      let scaled_b = b
+          .iter()
+          .map(|row| scale_all(builder, scale_lookup, row))
+          .collect::<anyhow::Result<Vec<_>>>()?;
+
+      let mut outputs: Vec<Vec<Option<Witness<F>>>> = vec![vec![None; p]; m];
+      for i0 in (0..m).step_by(block) {
+          for j0 in (0..p).step_by(block) {
+              for k0 in (0..n).step_by(block) {
+                  for i in i0..(i0 + block).min(m) {
+                      for j in j0..(j0 + block).min(p) {
+                          let products: Vec<_> = (k0..(k0 + block).min(n))
+                              .map(|k| builder.mul(scaled_a[i][k], scaled_b[k][j]))
+                              .collect();
+                          let partial = sum_many(builder, &products);
+                          outputs[i][j] = Some(match outputs[i][j] {
+                              Some(sum) => builder.add(sum, partial),
+                              None => partial,
+                          });
+                      }
+                  }
+              }
+          }
+      }
+
+      // an empty inner dimension gives zeros
+      Ok(outputs
+          .into_iter()
+          .map(|row| row.into_iter().map(|output| output.unwrap_or_else(|| builder.zero())).collect())
+          .collect())
+  }
+
+  /// A feature map of `channels x height x width` values.
+  pub type FeatureMap<F> = Vec<Vec<Vec<Witness<F>>>>;
+
+  /// Convolves each channel of `input` with its own `k x k` kernel, without padding and with a stride of `stride`,
+  /// both scaled to `SCALE_FACTOR` with `scale_lookup`, so the outputs are at `SCALE_FACTOR^2`.
+  /// Unlike a full convolution, channels are never mixed, so it takes `k^2` multiplications per output
+  /// instead of `k^2 * channels`; [pointwise_conv2d] then mixes the channels.
+  pub fn depthwise_conv2d<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      scale_lookup: &LookupTable<F>,
+      input: &[Vec<Vec<Witness<F>>>],
+      kernels: &[Vec<Vec<Witness<F>>>],
+      stride: usize,
+  ) -> anyhow::Result<FeatureMap<F>> {
+      anyhow::ensure!(stride > 0, "the stride must not be zero");
+      anyhow::ensure!(
+          kernels.len() == input.len(),
+          "{} kernels for {} channels",
+          kernels.len(),
+          input.len()
+      );
+
+      let mut output = Vec::with_capacity(input.len());
+      for (channel, kernel) in input.iter().zip(kernels) {
+          let (height, width) = (channel.len(), channel.first().map_or(0, |row| row.len()));
+          let k = kernel.len();
+          anyhow::ensure!(k > 0 && kernel.iter().all(|row| row.len() == k), "a kernel is not square");
+          anyhow::ensure!(channel.iter().all(|row| row.len() == width), "the rows of a channel do not have {width} values");
+          anyhow::ensure!(height >= k && width >= k, "a {height}x{width} channel for a {k}x{k} kernel");
+
+          let scaled_channel = channel
+              .iter()
+              .map(|row| scale_all(builder, scale_lookup, row))
+              .collect::<anyhow::Result<Vec<_>>>()?;
+          let scaled_kernel = kernel
+              .iter()
+              .map(|row| scale_all(builder, scale_lookup, row))
+              .collect::<anyhow::Result<Vec<_>>>()?;
+
+          let mut maps = vec![];
+          for i in (0..=height - k).step_by(stride) {
+              let mut row = vec![];
+              for j in (0..=width - k).step_by(stride) {
+                  let products: Vec<_> = (0..k * k)
+                      .map(|t| {
+                          let (di, dj) = (t / k, t % k);
+                          builder.mul(scaled_kernel[di][dj], scaled_channel[i + di][j + dj])
+                      })
+                      .collect();
+                  row.push(sum_many(builder, &products));
+              }
+              maps.push(row);
+          }
+          output.push(maps);
+      }
+      Ok(output)
+  }
+
+  /// Mixes the channels of `input` with a `1 x 1` convolution: each output channel is a weighted sum of the input channels,
+  /// `weights` giving the weights of each output channel, both scaled to `SCALE_FACTOR` with `scale_lookup`,
+  /// so the outputs are at `SCALE_FACTOR^2`.
+  /// This is the product of the weights with the `channels x (height * width)` matrix of the input, computed by [gemm].
+  pub fn pointwise_conv2d<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      scale_lookup: &LookupTable<F>,
+      input: &[Vec<Vec<Witness<F>>>],
+      weights: &[&[Witness<F>]],
+      block: usize,
+  ) -> anyhow::Result<FeatureMap<F>> {
+      let (height, width) = input
+          .first()
+          .map_or((0, 0), |channel| (channel.len(), channel.first().map_or(0, |row| row.len())));
+      anyhow::ensure!(
+          input.iter().all(|channel| channel.len() == height && channel.iter().all(|row| row.len() == width)),
+          "the channels are not all {height}x{width}"
+      );
+
+      let pixels: Vec<Vec<_>> = input.iter().map(|channel| channel.concat()).collect();
+      let pixels: Vec<&[_]> = pixels.iter().map(Vec::as_slice).collect();
+      let outputs = gemm(builder, scale_lookup, weights, &pixels, block)?;
+      Ok(outputs
+          .into_iter()
+          .map(|channel| channel.chunks(width.max(1)).map(<[_]>::to_vec).collect())
+          .collect())
+  }
+
+  /// Convolves `input` with a `channels x k x k` kernel per output channel, without padding and with a stride of `stride`,
+  /// both scaled to `SCALE_FACTOR` with `scale_lookup`, so the outputs are at `SCALE_FACTOR^2`.
+  /// The patches of the input are laid out as the columns of a `(channels * k * k) x (outputs)` matrix,
+  /// which the [gemm] by blocks of `block` multiplies by the flattened kernels,
+  /// so an input value is scaled once per patch it belongs to.
+  pub fn conv2d<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      scale_lookup: &LookupTable<F>,
+      input: &[Vec<Vec<Witness<F>>>],
+      kernels: &[FeatureMap<F>],
+      stride: usize,
+      block: usize,
+  ) -> anyhow::Result<FeatureMap<F>> {
+      anyhow::ensure!(stride > 0, "the stride must not be zero");
+      let (height, width) = input
+          .first()
+          .map_or((0, 0), |channel| (channel.len(), channel.first().map_or(0, |row| row.len())));
+      anyhow::ensure!(
+          input.iter().all(|channel| channel.len() == height && channel.iter().all(|row| row.len() == width)),
+          "the channels are not all {height}x{width}"
+      );
+      let k = kernels.first().and_then(|kernel| kernel.first()).map_or(0, |channel| channel.len());
+      anyhow::ensure!(
+          k > 0 && kernels.iter().all(|kernel| kernel.len() == input.len()
+              && kernel.iter().all(|channel| channel.len() == k && channel.iter().all(|row| row.len() == k))),
+          "the kernels are not all {}x{k}x{k}",
+          input.len()
+      );
+      anyhow::ensure!(height >= k && width >= k, "a {height}x{width} input for a {k}x{k} kernel");
+
+      let positions: Vec<_> = (0..=height - k)
+          .step_by(stride)
+          .flat_map(|i| (0..=width - k).step_by(stride).map(move |j| (i, j)))
+          .collect();
+      let patches: Vec<Vec<_>> = (0..input.len() * k * k)
+          .map(|t| {
+              let (c, di, dj) = (t / (k * k), t / k % k, t % k);
+              positions.iter().map(|(i, j)| input[c][i + di][j + dj]).collect()
+          })
+          .collect();
+      let patches: Vec<&[_]> = patches.iter().map(Vec::as_slice).collect();
+      let flattened: Vec<Vec<_>> = kernels.iter().map(|kernel| kernel.concat().concat()).collect();
+      let flattened: Vec<&[_]> = flattened.iter().map(Vec::as_slice).collect();
+
+      let out_width = (width - k) / stride + 1;
+      let outputs = gemm(builder, scale_lookup, &flattened, &patches, block)?;
+      Ok(outputs
+          .into_iter()
+          .map(|channel| channel.chunks(out_width).map(<[_]>::to_vec).collect())
+          .collect())
+  }
+
+  /// Sums the values with a balanced tree of additions instead of a chain,
+  /// so that the depth of the sum is logarithmic in the number of values.
+  pub fn sum_many<F: Field>(builder: &mut CircuitBuilder<F>, values: &[Witness<F>]) -> Witness<F> {
+      match values {
+          [] => builder.zero(),
+          [value] => *value,
+          _ => {
+              let (left, right) = values.split_at(values.len() / 2);
+              let left = sum_many(builder, left);
+              let right = sum_many(builder, right);
+              builder.add(left, right)
+          }
+      }
+  }
+
+  /// Constrains `|a - b| <= eps`: `a - b + eps` and `eps - (a - b)` sum to `2 * eps`,
+  /// so range checking both to the bits of `2 * eps` bounds them to `[0, 2 * eps]`.
+  pub fn assert_close<F: Field>(builder: &mut CircuitBuilder<F>, a: Witness<F>, b: Witness<F>, eps: u64) {
+      let bits = (u64::BITS - (2 * eps).leading_zeros()) as usize;
+      let eps = builder.constant(F::from(eps));
+      let difference = builder.sub(a, b);
+      let above = builder.add(difference, eps);
+      let below = builder.sub(eps, difference);
+      builder.range_check(above, bits);
+      builder.range_check(below, bits);
+  }
+
+  /// Constrains `y = max(0, x)` for a signed fixed-point `x` of `bits` bits, sign included.
+  /// `x + 2^(bits - 1)` is decomposed in `bits` bits, which also range checks `x`,
+  /// and its most significant bit is set if and only if `x >= 0`, so `y = msb * x`.
+  pub fn relu<F: Field>(builder: &mut CircuitBuilder<F>, x: Witness<F>, bits: usize) -> Witness<F> {
+      let non_negative = non_negative(builder, x, bits);
+      builder.mul(non_negative, x)
+  }
+
+  /// Returns a bit set if and only if the signed `x` of `bits` bits is non-negative, as in [relu].
+  fn non_negative<F: Field>(builder: &mut CircuitBuilder<F>, x: Witness<F>, bits: usize) -> Witness<F> {
+      let offset = builder.constant(F::from(1u64 << (bits - 1)));
+      let shifted = builder.add(x, offset);
+      let shifted_bits = builder.decompose(shifted, bits);
+      shifted_bits[bits - 1]
+  }
+
+  /// The slope of a [leaky_relu] for negative inputs, at `SCALE_FACTOR`.
+  pub enum NegativeSlope<F: Field> {
+      /// A slope fixed in the circuit, as in LeakyReLU.
+      Constant(u64),
+      /// A slope that is a weight of the model, as in PReLU.
+      Witnessed(Witness<F>),
+  }
+
+  /// Constrains `y = x` for a non-negative `x`, and `y = slope * x` otherwise,
+  /// for a signed fixed-point `x` (at `SCALE_FACTOR`) of `bits` bits, sign included.
+  /// The sign is computed as in [relu], and `slope * x` is brought back to `SCALE_FACTOR`.
+  pub fn leaky_relu<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      x: Witness<F>,
+      slope: &NegativeSlope<F>,
+      bits: usize,
+  ) -> Witness<F> {
+      let non_negative = non_negative(builder, x, bits);
+      let sloped = match slope {
+          NegativeSlope::Constant(slope) => builder.mul(x, F::from(*slope)),
+          NegativeSlope::Witnessed(slope) => builder.mul(x, *slope),
+      };
+      let sloped = builder.div(sloped, F::from(SCALE_FACTOR));
+
+      // y = sloped + non_negative * (x - sloped)
+      let difference = builder.sub(x, sloped);
+      let correction = builder.mul(non_negative, difference);
+      builder.add(sloped, correction)
+  }
+
+  /// A lookup table of `sigmoid(x) = 1 / (1 + e^-x)` for the [sigmoid] gadget, with outputs at `SCALE_FACTOR`.
+  /// Entry `k` is the sigmoid of the fixed-point value `k * 2^granularity_bits - 2^(bits - 1)`,
+  /// so the table has `2^(bits - granularity_bits)` entries:
+  /// each bit of granularity halves the table, at the cost of a coarser approximation.
+  pub fn sigmoid_table<F: Field>(bits: usize, granularity_bits: usize) -> LookupTable<F> {
+      let step = (1u64 << granularity_bits) as f64;
+      let offset = (1u64 << (bits - 1)) as f64;
+      LookupTable::new(move |k: F| {
+          let x = (k.to_f64() * step - offset) / SCALE_FACTOR as f64;
+          F::from((SCALE_FACTOR as f64 / (1.0 + (-x).exp())).round() as u64)
+      })
+  }
+
+  /// Approximates `sigmoid(x)` for a signed fixed-point `x` (at `SCALE_FACTOR`) of `bits` bits, sign included,
+  /// with a table created by [sigmoid_table] for the same `bits` and `granularity_bits`.
+  /// `x` is range checked to `bits` bits, and rounded down to a multiple of `2^granularity_bits` for the lookup.
+  pub fn sigmoid<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      table: &LookupTable<F>,
+      x: Witness<F>,
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<Witness<F>> {
+      signed_lookup(builder, table, x, bits, granularity_bits)
+  }
+
+  /// A lookup table of the standard normal CDF `Φ(x)` for the [gelu] gadget, with outputs at `SCALE_FACTOR`,
+  /// laid out as the table of [sigmoid_table] for the same `bits` and `granularity_bits`.
+  /// `Φ(x)` is computed with the tanh approximation `(1 + tanh(sqrt(2 / π) * (x + 0.044715 * x^3))) / 2`.
+  pub fn gelu_table<F: Field>(bits: usize, granularity_bits: usize) -> LookupTable<F> {
+      let step = (1u64 << granularity_bits) as f64;
+      let offset = (1u64 << (bits - 1)) as f64;
+      LookupTable::new(move |k: F| {
+          let x = (k.to_f64() * step - offset) / SCALE_FACTOR as f64;
+          let cdf = 0.5 * (1.0 + ((2.0 / std::f64::consts::PI).sqrt() * (x + 0.044715 * x.powi(3))).tanh());
+          F::from((SCALE_FACTOR as f64 * cdf).round() as u64)
+      })
+  }
+
+  /// Approximates `gelu(x) = x * Φ(x)` for a signed fixed-point `x` (at `SCALE_FACTOR`) of `bits` bits, sign included,
+  /// with a table created by [gelu_table] for the same `bits` and `granularity_bits`.
+  /// `Φ(x)` is looked up as in [sigmoid], and the product is brought back to `SCALE_FACTOR`.
+  pub fn gelu<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      table: &LookupTable<F>,
+      x: Witness<F>,
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<Witness<F>> {
+      let cdf = signed_lookup(builder, table, x, bits, granularity_bits)?;
+      let product = builder.mul(x, cdf);
+      Ok(builder.div(product, F::from(SCALE_FACTOR)))
+  }
+
+  /// Looks up a signed fixed-point `x` of `bits` bits, sign included, in a table laid out as [sigmoid_table].
+  fn signed_lookup<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      table: &LookupTable<F>,
+      x: Witness<F>,
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<Witness<F>> {
+      let offset = builder.constant(F::from(1u64 << (bits - 1)));
+      let shifted = builder.add(x, offset);
+      builder.range_check(shifted, bits);
+
+      let k = builder.div(shifted, F::from(1u64 << granularity_bits));
+      let y = builder.witness(table.eval(builder.value(k)));
+      builder.lookup(table, k, y)?;
+      Ok(y)
+  }
+
+  /// A lookup table of `tanh(x)` for the [tanh] gadget, with outputs at `SCALE_FACTOR`.
+  /// Entry `k` is the tanh of the fixed-point value `k * 2^granularity_bits`,
+  /// so the table has `2^(TANH_SATURATION_BITS - granularity_bits)` entries, for non-negative inputs only.
+  pub fn tanh_table<F: Field>(granularity_bits: usize) -> LookupTable<F> {
+      let step = (1u64 << granularity_bits) as f64;
+      LookupTable::new(move |k: F| {
+          let x = k.to_f64() * step / SCALE_FACTOR as f64;
+          F::from((SCALE_FACTOR as f64 * x.tanh()).round() as u64)
+      })
+  }
+
+  /// Approximates `tanh(x)` for a signed fixed-point `x` (at `SCALE_FACTOR`) of `bits` bits, sign included,
+  /// with a table created by [tanh_table] for the same `granularity_bits` (at most `TANH_SATURATION_BITS`).
+  /// The input is split in three segments: `-1` up to `-2^TANH_SATURATION_BITS`, `1` from `2^TANH_SATURATION_BITS`,
+  /// and the table in between, looked up with the magnitude of `x` since `tanh(-x) = -tanh(x)`.
+  /// `x` is range checked to `bits` bits, and its magnitude rounded down to a multiple of `2^granularity_bits` for the lookup.
+  /// Fails if `granularity_bits` is more than `bits` or than `TANH_SATURATION_BITS`, as the table would have no key bits.
+  pub fn tanh<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      table: &LookupTable<F>,
+      x: Witness<F>,
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<Witness<F>> {
+      let table_end = TANH_SATURATION_BITS.min(bits);
+      anyhow::ensure!(
+          granularity_bits <= table_end,
+          "a tanh granularity of {granularity_bits} bits, past the {table_end} bits of its table"
+      );
+
+      // the sign of x, as in [relu], is 1 or -1
+      let offset = builder.constant(F::from(1u64 << (bits - 1)));
+      let shifted = builder.add(x, offset);
+      let shifted_bits = builder.decompose(shifted, bits);
+      let non_negative = shifted_bits[bits - 1];
+      let one = builder.constant(F::from(1u64));
+      let twice = builder.add(non_negative, non_negative);
+      let sign = builder.sub(twice, one);
+      let magnitude = builder.mul(sign, x);
+
+      // the magnitude saturates if any of its bits above the table is set
+      let magnitude_bits = builder.decompose(magnitude, bits);
+      let mut in_table = one;
+      for bit in &magnitude_bits[table_end..] {
+          let cleared = builder.sub(one, *bit);
+          in_table = builder.mul(in_table, cleared);
+      }
+
+      // the key is made of the bits of the magnitude between the granularity and the saturation
+      let key_bits: Vec<_> = magnitude_bits[granularity_bits..table_end]
+          .iter()
+          .enumerate()
+          .map(|(i, bit)| builder.mul(*bit, F::from(1u64 << i)))
+          .collect();
+      let k = sum_many(builder, &key_bits);
+      let y = builder.witness(table.eval(builder.value(k)));
+      builder.lookup(table, k, y)?;
+
+      // |tanh(x)| = 1 + in_table * (y - 1)
+      let saturated = builder.constant(F::from(SCALE_FACTOR));
+      let below_saturation = builder.sub(y, saturated);
+      let correction = builder.mul(in_table, below_saturation);
+      let magnitude_y = builder.add(saturated, correction);
+      Ok(builder.mul(sign, magnitude_y))
+  }
+
+  /// A lookup table of `exp(-x)` for the [softmax] gadget, with outputs at `SCALE_FACTOR`.
+  /// Entry `k` is the exponential of the fixed-point value `-k * 2^granularity_bits`,
+  /// so the table has `2^(bits - granularity_bits)` entries for differences of `bits` bits.
+  pub fn exp_table<F: Field>(granularity_bits: usize) -> LookupTable<F> {
+      let step = (1u64 << granularity_bits) as f64;
+      LookupTable::new(move |k: F| {
+          let x = k.to_f64() * step / SCALE_FACTOR as f64;
+          F::from((SCALE_FACTOR as f64 * (-x).exp()).round() as u64)
+      })
+  }
+
+  /// Constrains `numerator = quotient * divisor + remainder` with `0 <= remainder < divisor`,
+  /// for a non-zero `divisor` of at most `bits` bits, and returns the quotient, range checked to `quotient_bits` bits.
+  pub fn div_rem<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      numerator: Witness<F>,
+      divisor: Witness<F>,
+      bits: usize,
+      quotient_bits: usize,
+  ) -> Witness<F> {
+      let n = builder.value(numerator).to_u64();
+      let d = builder.value(divisor).to_u64();
+      let quotient = builder.witness(F::from(n / d));
+      let remainder = builder.witness(F::from(n % d));
+
+      let product = builder.mul(quotient, divisor);
+      let recomposed = builder.add(product, remainder);
+      builder.assert_eq(recomposed, numerator);
+      builder.range_check(quotient, quotient_bits);
+
+      // remainder < divisor, as divisor - remainder - 1 >= 0
+      let one = builder.constant(F::from(1u64));
+      let gap = builder.sub(divisor, remainder);
+      let gap = builder.sub(gap, one);
+      builder.range_check(remainder, bits);
+      builder.range_check(gap, bits);
+      quotient
+  }
+
+  /// Returns one of the signed fixed-point values of `bits` bits, sign included, hinted to be their maximum.
+  /// It is only constrained to be one of the values: the product of the differences is zero.
+  fn max_hint<F: Field>(builder: &mut CircuitBuilder<F>, values: &[Witness<F>], bits: usize) -> Witness<F> {
+      let offset = F::from(1u64 << (bits - 1));
+      let argmax = (0..values.len())
+          .max_by_key(|&i| (builder.value(values[i]) + offset).to_u64())
+          .expect("the maximum of no values");
+      let max = builder.witness(builder.value(values[argmax]));
+
+      let mut product = builder.constant(F::from(1u64));
+      for value in values {
+          let difference = builder.sub(max, *value);
+          product = builder.mul(product, difference);
+      }
+      let zero = builder.zero();
+      builder.assert_eq(product, zero);
+      max
+  }
+
+  /// Returns the maximum of signed fixed-point values of `bits` bits, sign included:
+  /// one of the values, whose differences with all the values are range checked to `bits` bits.
+  pub fn max_many<F: Field>(builder: &mut CircuitBuilder<F>, values: &[Witness<F>], bits: usize) -> Witness<F> {
+      let max = max_hint(builder, values, bits);
+      for value in values {
+          let difference = builder.sub(max, *value);
+          builder.range_check(difference, bits);
+      }
+      max
+  }
+
+  /// The statistics of a tensor of floats ingested as witnesses, as reported by [lint_tensor].
+  #[derive(Clone, Debug, PartialEq)]
+  pub struct TensorStats {
+      pub len: usize,
+      /// The minimum, maximum and mean of the finite values.
+      pub min: f64,
+      pub max: f64,
+      pub mean: f64,
+      pub nan: usize,
+      pub infinite: usize,
+      /// The values too large to be rounded to fixed point at `SCALE_FACTOR` at all.
+      pub out_of_range: usize,
+      /// The values that do not fit the signed range of `bits` bits at `SCALE_FACTOR` the gadgets check them against.
+      pub saturated: usize,
+      /// The non-zero values that round to zero at `SCALE_FACTOR`.
+      pub underflowed: usize,
+  }
+
+  impl std::fmt::Display for TensorStats {
+      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+          write!(
+              f,
+              "{} values in [{}, {}], mean {}, {} NaN, {} infinite, {} out of range, {} saturated, {} underflowed",
+              self.len, self.min, self.max, self.mean, self.nan, self.infinite, self.out_of_range, self.saturated, self.underflowed
+          )
+      }
+  }
+
+  /// Checks a tensor of floats before it is rounded to witnesses at `SCALE_FACTOR`, and returns its statistics.
+  /// A NaN, an infinity or an out-of-range value would otherwise be rounded silently to a wrong field element,
+  /// and a value saturating the `bits` bits of the range checks would only surface as an unsatisfiable circuit.
+  pub fn lint_tensor(name: &str, values: &[f64], bits: usize) -> anyhow::Result<TensorStats> {
+      let limit = (1u64 << (bits - 1)) as f64 / SCALE_FACTOR as f64;
+      let resolution = 0.5 / SCALE_FACTOR as f64;
+      let finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+      let count = |predicate: &dyn Fn(f64) -> bool| finite.iter().filter(|v| predicate(**v)).count();
+      let stats = TensorStats {
+          len: values.len(),
+          min: finite.iter().copied().fold(f64::INFINITY, f64::min),
+          max: finite.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+          mean: finite.iter().sum::<f64>() / finite.len().max(1) as f64,
+          nan: values.iter().filter(|v| v.is_nan()).count(),
+          infinite: values.iter().filter(|v| v.is_infinite()).count(),
+          out_of_range: count(&|v| v.abs() * SCALE_FACTOR as f64 >= u64::MAX as f64),
+          saturated: count(&|v| v.abs() >= limit),
+          underflowed: count(&|v| v != 0.0 && v.abs() < resolution),
+      };
+
+      anyhow::ensure!(stats.nan == 0 && stats.infinite == 0, "tensor {name} is not finite: {stats}");
+      anyhow::ensure!(stats.out_of_range == 0, "tensor {name} does not fit fixed point at scale {SCALE_FACTOR}: {stats}");
+      anyhow::ensure!(
+          stats.saturated == 0,
+          "tensor {name} saturates the {bits}-bit range of magnitude {limit}: {stats}"
+      );
+      Ok(stats)
+  }
+
+  /// Rounds a tensor of floats to fixed-point field elements at `SCALE_FACTOR`, after checking it with [lint_tensor].
+  pub fn quantize_tensor<F: Field>(name: &str, values: &[f64], bits: usize) -> anyhow::Result<Vec<F>> {
+      lint_tensor(name, values, bits)?;
+      Ok(values.iter().map(|v| to_fixed(*v, SCALE_FACTOR as f64)).collect())
+  }
+
+  /// The parameters of a batch normalization, one value per channel, as imported from a float model.
+  pub struct BatchNorm {
+      pub gamma: Vec<f64>,
+      pub beta: Vec<f64>,
+      pub mean: Vec<f64>,
+      pub var: Vec<f64>,
+      pub eps: f64,
+  }
+
+  /// The affine transform `scale * x + shift` of each channel a batch normalization folds into at inference,
+  /// with the scales at `SCALE_FACTOR` and the shifts at `SCALE_FACTOR^2`.
+  pub struct FoldedBatchNorm<F: Field> {
+      pub scale: Vec<F>,
+      pub shift: Vec<F>,
+  }
+
+  /// Rounds a signed value to a fixed-point field element at `scale`, negative values being negated in the field.
+  fn to_fixed<F: Field>(value: f64, scale: f64) -> F {
+      let magnitude = F::from((value.abs() * scale).round() as u64);
+      if value < 0.0 {
+          F::from(0u64) - magnitude
+      } else {
+          magnitude
+      }
+  }
+
+  impl BatchNorm {
+      /// Folds the normalization `gamma * (x - mean) / sqrt(var + eps) + beta` into `scale * x + shift`,
+      /// with `scale = gamma / sqrt(var + eps)` and `shift = beta - mean * scale`, rounded to fixed point.
+      pub fn fold<F: Field>(&self) -> anyhow::Result<FoldedBatchNorm<F>> {
+          let channels = self.gamma.len();
+          anyhow::ensure!(
+              self.beta.len() == channels && self.mean.len() == channels && self.var.len() == channels,
+              "the parameters do not all have {channels} channels"
+          );
+
+          let mut folded = FoldedBatchNorm { scale: vec![], shift: vec![] };
+          for c in 0..channels {
+              let deviation = self.var[c] + self.eps;
+              anyhow::ensure!(deviation > 0.0, "the variance of channel {c} plus eps is not positive");
+              let scale = self.gamma[c] / deviation.sqrt();
+              let shift = self.beta[c] - self.mean[c] * scale;
+              folded.scale.push(to_fixed(scale, SCALE_FACTOR as f64));
+              folded.shift.push(to_fixed(shift, (SCALE_FACTOR * SCALE_FACTOR) as f64));
+          }
+          Ok(folded)
+      }
+  }
+
+  /// Applies a folded batch normalization to each channel of `input`, at `SCALE_FACTOR`, so the outputs are at `SCALE_FACTOR^2`.
+  /// The scales and shifts are constants of the circuit: each value costs a multiplication by a constant and an addition.
+  pub fn batch_norm<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      input: &[Vec<Vec<Witness<F>>>],
+      folded: &FoldedBatchNorm<F>,
+  ) -> anyhow::Result<FeatureMap<F>> {
+      anyhow::ensure!(
+          folded.scale.len() == input.len() && folded.shift.len() == input.len(),
+          "a batch normalization of {} channels for {} channels",
+          folded.scale.len(),
+          input.len()
+      );
+
+      let mut output = Vec::with_capacity(input.len());
+      for (c, channel) in input.iter().enumerate() {
+          let shift = builder.constant(folded.shift[c]);
+          let normalized = channel
+              .iter()
+              .map(|row| {
+                  row.iter()
+                      .map(|x| {
+                          let scaled = builder.mul(*x, folded.scale[c]);
+                          builder.add(scaled, shift)
+                      })
+                      .collect()
+              })
+              .collect();
+          output.push(normalized);
+      }
+      Ok(output)
+  }
+
+  /// Max-pools each channel of `input` over windows of `window x window` values (2 or 3 in most models),
+  /// taken every `stride` values without padding, for signed fixed-point values of `bits` bits, sign included.
+  /// Each output is the [max_many] of its window, so it costs `window^2` range checks of `bits` bits.
+  pub fn max_pool2d<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      input: &[Vec<Vec<Witness<F>>>],
+      window: usize,
+      stride: usize,
+      bits: usize,
+  ) -> anyhow::Result<FeatureMap<F>> {
+      anyhow::ensure!(window > 0 && stride > 0, "the window and the stride must not be zero");
+
+      let mut output = Vec::with_capacity(input.len());
+      for channel in input {
+          let (height, width) = (channel.len(), channel.first().map_or(0, |row| row.len()));
+          anyhow::ensure!(channel.iter().all(|row| row.len() == width), "the rows of a channel do not have {width} values");
+          anyhow::ensure!(height >= window && width >= window, "a {height}x{width} channel for a {window}x{window} window");
+
+          let mut pooled = vec![];
+          for i in (0..=height - window).step_by(stride) {
+              let mut row = vec![];
+              for j in (0..=width - window).step_by(stride) {
+                  let values: Vec<_> = channel[i..i + window]
+                      .iter()
+                      .flat_map(|row| &row[j..j + window])
+                      .copied()
+                      .collect();
+                  row.push(max_many(builder, &values, bits));
+              }
+              pooled.push(row);
+          }
+          output.push(pooled);
+      }
+      Ok(output)
+  }
+
+  /// Average-pools each channel of `input` over windows of `window x window` values, taken every `stride` values without padding,
+  /// for signed fixed-point values of `bits` bits, sign included.
+  /// Each output is the sum of its window divided by `window^2`, rounded down, with [div_rem]:
+  /// the sum is offset by `window^2 * 2^(bits - 1)` to be non-negative, so the offset quotient has `bits` bits,
+  /// and the offset is removed from it.
+  pub fn avg_pool2d<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      input: &[Vec<Vec<Witness<F>>>],
+      window: usize,
+      stride: usize,
+      bits: usize,
+  ) -> anyhow::Result<FeatureMap<F>> {
+      anyhow::ensure!(window > 0 && stride > 0, "the window and the stride must not be zero");
+      let size = (window * window) as u64;
+      let size_bits = (u64::BITS - size.leading_zeros()) as usize;
+      let divisor = builder.constant(F::from(size));
+      let offset = builder.constant(F::from(size << (bits - 1)));
+      let unoffset = builder.constant(F::from(1u64 << (bits - 1)));
+
+      let mut output = Vec::with_capacity(input.len());
+      for channel in input {
+          let (height, width) = (channel.len(), channel.first().map_or(0, |row| row.len()));
+          anyhow::ensure!(channel.iter().all(|row| row.len() == width), "the rows of a channel do not have {width} values");
+          anyhow::ensure!(height >= window && width >= window, "a {height}x{width} channel for a {window}x{window} window");
+
+          let mut pooled = vec![];
+          for i in (0..=height - window).step_by(stride) {
+              let mut row = vec![];
+              for j in (0..=width - window).step_by(stride) {
+                  let values: Vec<_> = channel[i..i + window]
+                      .iter()
+                      .flat_map(|row| &row[j..j + window])
+                      .copied()
+                      .collect();
+                  let sum = sum_many(builder, &values);
+                  let sum = builder.add(sum, offset);
+                  let average = div_rem(builder, sum, divisor, size_bits, bits);
+                  row.push(builder.sub(average, unoffset));
+              }
+              pooled.push(row);
+          }
+          output.push(pooled);
+      }
+      Ok(output)
+  }
+
+  /// Computes `softmax(logits)` with the max-subtraction trick,
+  /// for signed fixed-point logits (at `SCALE_FACTOR`) of `bits` bits, sign included:
+  /// the exponentials are taken of the logits minus their maximum, which are non-positive,
+  /// so they stay below `SCALE_FACTOR` and the largest does not underflow.
+  /// See [softmax_from] for the table and the outputs.
+  pub fn softmax<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      table: &LookupTable<F>,
+      logits: &[Witness<F>],
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<Vec<Witness<F>>> {
+      // softmax_from checks that the maximum is at least every logit
+      let max = max_hint(builder, logits, bits);
+      softmax_from(builder, table, logits, max, bits, granularity_bits)
+  }
+
+  /// Computes `softmax(logits)` as the exponentials of `logits - reference`, divided by their sum,
+  /// with a table created by [exp_table] for the same `granularity_bits`.
+  /// The differences `reference - logits` are range checked to `bits` bits, so `reference` must be at least every logit,
+  /// and rounded down to a multiple of `2^granularity_bits` for the lookups.
+  /// The outputs are probabilities at `SCALE_FACTOR`, rounded down by a division whose remainder is constrained;
+  /// the sum of the exponentials must not be zero, which the maximum of the logits as `reference` ensures.
+  pub fn softmax_from<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      table: &LookupTable<F>,
+      logits: &[Witness<F>],
+      reference: Witness<F>,
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<Vec<Witness<F>>> {
+      let mut exps = Vec::with_capacity(logits.len());
+      for logit in logits {
+          let difference = builder.sub(reference, *logit);
+          builder.range_check(difference, bits);
+
+          let k = builder.div(difference, F::from(1u64 << granularity_bits));
+          let exp = builder.witness(table.eval(builder.value(k)));
+          builder.lookup(table, k, exp)?;
+          exps.push(exp);
+      }
+      let sum = sum_many(builder, &exps);
+
+      // each exponential is at most SCALE_FACTOR, and so are the probabilities
+      let scale_bits = SCALE_FACTOR.trailing_zeros() as usize + 1;
+      let sum_bits = scale_bits + (usize::BITS - logits.len().leading_zeros()) as usize;
+      let probabilities = exps
+          .into_iter()
+          .map(|exp| {
+              let scaled = builder.mul(exp, F::from(SCALE_FACTOR));
+              div_rem(builder, scaled, sum, sum_bits, scale_bits)
+          })
+          .collect();
+      Ok(probabilities)
+  }
+
+  /// Computes the scaled dot-product attention `softmax(Q K^T / sqrt(d)) V` of `n` queries against `m` keys and values,
+  /// the queries and keys having `d` entries, all at the scale of the inputs, as the output.
+  /// Both products are [gemm]s scaled with `scale_lookup`. The scores are brought to `SCALE_FACTOR`
+  /// and scaled by `1 / sqrt(d)`, rounded at `SCALE_FACTOR`, to be the logits of a [softmax] per query,
+  /// for signed fixed-point logits of `bits` bits, sign included, with a table created by [exp_table] for `granularity_bits`.
+  /// The probabilities are brought back to the scale of the inputs to weight the values.
+  pub fn attention<F: Field>(
+      builder: &mut CircuitBuilder<F>,
+      scale_lookup: &LookupTable<F>,
+      exp_table: &LookupTable<F>,
+      (q, k, v): (&[&[Witness<F>]], &[&[Witness<F>]], &[&[Witness<F>]]),
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<Vec<Vec<Witness<F>>>> {
+      let d = q.first().map_or(0, |row| row.len());
+      anyhow::ensure!(k.len() == v.len(), "{} keys for {} values", k.len(), v.len());
+      anyhow::ensure!(k.iter().all(|row| row.len() == d), "the keys do not have the {d} entries of the queries");
+
+      // 1. Scores Q K^T, at SCALE_FACTOR^2
+      let k_t: Vec<Vec<Witness<F>>> = (0..d).map(|j| k.iter().map(|row| row[j]).collect()).collect();
+      let scores = gemm(builder, scale_lookup, q, &rows(&k_t), GEMM_BLOCK)?;
+
+      // 2. Scaling by 1 / sqrt(d), at SCALE_FACTOR
+      let inv_sqrt_d = (SCALE_FACTOR as f64 / (d.max(1) as f64).sqrt()).round() as u64;
+      let mut weights = Vec::with_capacity(scores.len());
+      for row in scores {
+          let logits: Vec<_> = row
+              .into_iter()
+              .map(|score| {
+                  let score = builder.div(score, F::from(SCALE_FACTOR));
+                  let scaled = builder.mul(score, F::from(inv_sqrt_d));
+                  builder.div(scaled, F::from(SCALE_FACTOR))
+              })
+              .collect();
+
+          // 3. Softmax, brought back to the scale of the inputs
+          let probabilities = softmax(builder, exp_table, &logits, bits, granularity_bits)?;
+          weights.push(
+              probabilities
+                  .into_iter()
+                  .map(|p| builder.div(p, F::from(SCALE_FACTOR)))
+                  .collect::<Vec<_>>(),
+          );
+      }
+
+      // 4. Value weighting, brought back to the scale of the inputs
+      let output = gemm(builder, scale_lookup, &rows(&weights), v, GEMM_BLOCK)?;
+      Ok(unscale_all(builder, output))
+  }
+
+  pub fn create_linear_regression_circuit<F: Field, const N: usize, const M: usize>(
       x: [F; N],
-      w: [F; N],
-      b: F,
-      y: F,
-  ) -> LinearRegressionCircuit<F> {
+      w: [[F; N]; M],
+      b: [F; M],
+      y: [F; M],
+  ) -> LinearRegressionCircuit<F, N, M> {
       let mut builder = CircuitBuilder::new();
       let x_witnesses = x.map(|v| builder.witness(v));
-      let w_witnesses = w.map(|v| builder.witness(v));
-      let b_witness = builder.witness(b);
-      let y_witness = builder.witness(y);
+      let w_witnesses = w.map(|row| row.map(|v| builder.witness(v)));
+      let b_witnesses = b.map(|v| builder.witness(v));
+      let y_witnesses = y.map(|v| builder.witness(v));
 
-      // Create lookup tables for scaling and unscaling
+      // Create lookup tables for scaling and unscaling, shared by all the outputs
       let scale_lookup = LookupTable::new(|x| x * F::from(SCALE_FACTOR));
       let unscale_lookup = LookupTable::new(|x| x / F::from(SCALE_FACTOR * SCALE_FACTOR));
 
       LinearRegressionCircuit {
           x: x_witnesses,
           w: w_witnesses,
-          b: b_witness,
-          y: y_witness,
+          b: b_witnesses,
+          y: y_witnesses,
           scale_lookup,
           unscale_lookup,
       }
   }
 
+  pub fn create_logistic_regression_circuit<F: Field, const N: usize>(
+      x: [F; N],
+      w: [F; N],
+      b: F,
+      label: bool,
+      bits: usize,
+      granularity_bits: usize,
+  ) -> LogisticRegressionCircuit<F, N> {
+      let mut builder = CircuitBuilder::new();
+      let x_witnesses = x.map(|v| builder.witness(v));
+      let w_witnesses = w.map(|v| builder.witness(v));
+      let b_witness = builder.witness(b);
+      let label_witness = builder.witness(F::from(label as u64));
+
+      LogisticRegressionCircuit {
+          x: x_witnesses,
+          w: w_witnesses,
+          b: b_witness,
+          label: label_witness,
+          bits,
+          granularity_bits,
+          scale_lookup: LookupTable::new(|x| x * F::from(SCALE_FACTOR)),
+          sigmoid_table: sigmoid_table(bits, granularity_bits),
+      }
+  }
+
+  /// Creates an [SvmCircuit] with a linear kernel, for a label `true` if the decision function is non-negative.
+  pub fn create_linear_svm_circuit<F: Field, const N: usize>(
+      x: [F; N],
+      w: [F; N],
+      b: F,
+      label: bool,
+      bits: usize,
+  ) -> SvmCircuit<F, N> {
+      let mut builder = CircuitBuilder::new();
+      let x_witnesses = x.map(|v| builder.witness(v));
+      let w_witnesses = w.map(|v| builder.witness(v));
+      let b_witness = builder.witness(b);
+      let label_witness = builder.witness(F::from(label as u64));
+
+      SvmCircuit {
+          x: x_witnesses,
+          kernel: SvmKernel::Linear { w: w_witnesses },
+          b: b_witness,
+          label: label_witness,
+          bits,
+          granularity_bits: 0,
+          scale_lookup: LookupTable::new(|x| x * F::from(SCALE_FACTOR)),
+      }
+  }
+
+  /// Creates an [SvmCircuit] with an RBF kernel of the given support vectors, each with its coefficient,
+  /// `gamma` being at `SCALE_FACTOR`, for a label `true` if the decision function is non-negative.
+  pub fn create_rbf_svm_circuit<F: Field, const N: usize>(
+      x: [F; N],
+      support_vectors: &[([F; N], F)],
+      gamma: u64,
+      b: F,
+      label: bool,
+      bits: usize,
+      granularity_bits: usize,
+  ) -> SvmCircuit<F, N> {
+      let mut builder = CircuitBuilder::new();
+      let x_witnesses = x.map(|v| builder.witness(v));
+      let (support_vectors, coefficients) = support_vectors
+          .iter()
+          .map(|(sv, coefficient)| (sv.map(|v| builder.witness(v)), builder.witness(*coefficient)))
+          .unzip();
+      let b_witness = builder.witness(b);
+      let label_witness = builder.witness(F::from(label as u64));
+
+      SvmCircuit {
+          x: x_witnesses,
+          kernel: SvmKernel::Rbf {
+              support_vectors,
+              coefficients,
+              gamma,
+              exp_table: exp_table(granularity_bits),
+          },
+          b: b_witness,
+          label: label_witness,
+          bits,
+          granularity_bits,
+          scale_lookup: LookupTable::new(|x| x * F::from(SCALE_FACTOR)),
+      }
+  }
+
+  /// Creates an [MlpCircuit] with layers of the given sizes, inputs first, and the activation of each layer.
+  /// The parameters of layer `l` are its weights, a row of `sizes[l]` weights for each of its `sizes[l + 1]` outputs,
+  /// and a bias for each output.
+  pub fn create_mlp_circuit<F: Field>(
+      sizes: &[usize],
+      activations: &[Activation],
+      x: &[F],
+      parameters: &[(Vec<Vec<F>>, Vec<F>)],
+      y: &[F],
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<MlpCircuit<F>> {
+      let layers = activations.len();
+      anyhow::ensure!(sizes.len() == layers + 1, "{} layer sizes for {layers} layers", sizes.len());
+      anyhow::ensure!(parameters.len() == layers, "the parameters of {} layers for {layers} layers", parameters.len());
+      anyhow::ensure!(x.len() == sizes[0], "{} inputs for a layer of {}", x.len(), sizes[0]);
+      anyhow::ensure!(y.len() == sizes[layers], "{} outputs for a layer of {}", y.len(), sizes[layers]);
+
+      let mut builder = CircuitBuilder::new();
+      let x_witnesses = x.iter().map(|v| builder.witness(*v)).collect();
+      let mut dense_layers = Vec::with_capacity(layers);
+      for (l, (weights, biases)) in parameters.iter().enumerate() {
+          let outputs = sizes[l + 1];
+          anyhow::ensure!(weights.len() == outputs, "{} rows of weights for the {outputs} outputs of layer {l}", weights.len());
+          dense_layers.push(dense_layer(&mut builder, sizes[l], weights, biases, activations[l])?);
+      }
+      let y_witnesses = y.iter().map(|v| builder.witness(*v)).collect();
+
+      Ok(MlpCircuit {
+          x: x_witnesses,
+          layers: dense_layers,
+          y: y_witnesses,
+          scale_lookup: LookupTable::new(|x| x * F::from(SCALE_FACTOR)),
+          activations: Activations::new(bits, granularity_bits),
+      })
+  }
+
+  /// The parameters of a [LeNet5Circuit]: the kernels (`out_channels x in_channels x 5 x 5`) and biases of its convolutions,
+  /// and the weights (a row per output) and biases of its dense layers.
+  /// Trained MNIST weights, exported from a reference implementation and loaded with [LeNet5Weights::load],
+  /// make the benchmark comparable across backends.
+  /// No weights ship with the crate: `scripts/export_lenet5.py` trains the reference model with a fixed seed,
+  /// exports its weights and a golden image with its logits (see [load_lenet5_golden]), and prints their SHA-256.
+  pub struct LeNet5Weights<F: Field> {
+      pub conv1: (Vec<Vec<Vec<Vec<F>>>>, Vec<F>),
+      pub conv2: (Vec<Vec<Vec<Vec<F>>>>, Vec<F>),
+      pub dense: [(Vec<Vec<F>>, Vec<F>); 3],
+  }
+
+  /// The tensors of a [LeNet5Weights], in the order and with the shapes of the parameters of the reference PyTorch model.
+  const LENET5_TENSORS: [(&str, &[usize]); 10] = [
+      ("conv1.weight", &[6, 1, 5, 5]),
+      ("conv1.bias", &[6]),
+      ("conv2.weight", &[16, 6, 5, 5]),
+      ("conv2.bias", &[16]),
+      ("fc1.weight", &[120, 400]),
+      ("fc1.bias", &[120]),
+      ("fc2.weight", &[84, 120]),
+      ("fc2.bias", &[84]),
+      ("fc3.weight", &[10, 84]),
+      ("fc3.bias", &[10]),
+  ];
+
+  impl<F: Field> LeNet5Weights<F> {
+      /// Quantizes the parameters of a float model with [quantize_tensor], checking them against the `bits` bits of the range checks.
+      /// The parameters are flattened tensor after tensor, in the order of PyTorch (`conv1.weight`, `conv1.bias`, ..., `fc3.bias`),
+      /// and row-major within each tensor.
+      pub fn from_floats(values: &[f64], bits: usize) -> anyhow::Result<Self> {
+          let total: usize = LENET5_TENSORS.iter().map(|(_, shape)| shape.iter().product::<usize>()).sum();
+          anyhow::ensure!(values.len() == total, "{} parameters for the {total} of LeNet-5", values.len());
+
+          let mut rest = values;
+          let mut tensors = Vec::with_capacity(LENET5_TENSORS.len());
+          for (name, shape) in LENET5_TENSORS {
+              let (tensor, next) = rest.split_at(shape.iter().product());
+              tensors.push(quantize_tensor::<F>(name, tensor, bits)?);
+              rest = next;
+          }
+
+          let rows = |tensor: &[F], len: usize| -> Vec<Vec<F>> { tensor.chunks(len).map(<[F]>::to_vec).collect() };
+          let kernels = |tensor: &[F], in_channels: usize| -> Vec<Vec<Vec<Vec<F>>>> {
+              tensor
+                  .chunks(in_channels * 25)
+                  .map(|kernel| kernel.chunks(25).map(|channel| rows(channel, 5)).collect())
+                  .collect()
+          };
+          let [conv1, conv1_bias, conv2, conv2_bias, fc1, fc1_bias, fc2, fc2_bias, fc3, fc3_bias] = tensors.as_slice() else {
+              unreachable!("LeNet-5 has {} tensors", LENET5_TENSORS.len())
+          };
+          Ok(LeNet5Weights {
+              conv1: (kernels(conv1, 1), conv1_bias.clone()),
+              conv2: (kernels(conv2, 6), conv2_bias.clone()),
+              dense: [
+                  (rows(fc1, 16 * 5 * 5), fc1_bias.clone()),
+                  (rows(fc2, 120), fc2_bias.clone()),
+                  (rows(fc3, 84), fc3_bias.clone()),
+              ],
+          })
+      }
+
+      /// Loads the parameters of a float model from a text file of their values separated by whitespace,
+      /// in the order of [LeNet5Weights::from_floats], as written by
+      /// `np.savetxt(path, np.concatenate([p.detach().numpy().ravel() for p in model.parameters()]))`.
+      pub fn load(path: &std::path::Path, bits: usize) -> anyhow::Result<Self> {
+          let text = std::fs::read_to_string(path)?;
+          let values = text
+              .split_whitespace()
+              .map(str::parse::<f64>)
+              .collect::<Result<Vec<_>, _>>()?;
+          Self::from_floats(&values, bits)
+      }
+  }
+
+  /// Loads the golden output written by `scripts/export_lenet5.py`: a `32 x 32` image followed by its 10 logits,
+  /// separated by whitespace, quantized with [quantize_tensor] for [create_lenet5_circuit].
+  /// The circuit built from the exported weights, the image and the logits must be satisfiable,
+  /// up to the rounding of the quantization.
+  pub fn load_lenet5_golden<F: Field>(path: &std::path::Path, bits: usize) -> anyhow::Result<(Vec<Vec<F>>, Vec<F>)> {
+      let text = std::fs::read_to_string(path)?;
+      let values = text
+          .split_whitespace()
+          .map(str::parse::<f64>)
+          .collect::<Result<Vec<_>, _>>()?;
+      anyhow::ensure!(values.len() == 32 * 32 + 10, "{} values for a 32x32 image and 10 logits", values.len());
+
+      let (image, logits) = values.split_at(32 * 32);
+      let image = quantize_tensor::<F>("image", image, bits)?;
+      Ok((
+          image.chunks(32).map(<[F]>::to_vec).collect(),
+          quantize_tensor::<F>("logits", logits, bits)?,
+      ))
+  }
+
+  /// Creates a [LeNet5Circuit] classifying a `32 x 32` image, with the same `activation` after every layer but the last,
+  /// and the expected logits `y`.
+  pub fn create_lenet5_circuit<F: Field>(
+      image: &[Vec<F>],
+      weights: &LeNet5Weights<F>,
+      activation: Activation,
+      y: &[F],
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<LeNet5Circuit<F>> {
+      anyhow::ensure!(
+          image.len() == 32 && image.iter().all(|row| row.len() == 32),
+          "the image is not 32x32"
+      );
+      anyhow::ensure!(y.len() == 10, "{} logits for 10 digits", y.len());
+
+      let mut builder = CircuitBuilder::new();
+      let image_witnesses = vec![image
+          .iter()
+          .map(|row| row.iter().map(|v| builder.witness(*v)).collect())
+          .collect()];
+      let conv1 = conv_layer(&mut builder, &weights.conv1, 1, 6, activation)?;
+      let conv2 = conv_layer(&mut builder, &weights.conv2, 6, 16, activation)?;
+
+      let sizes = [16 * 5 * 5, 120, 84, 10];
+      let mut classifier = Vec::with_capacity(3);
+      for (l, (layer_weights, biases)) in weights.dense.iter().enumerate() {
+          let outputs = sizes[l + 1];
+          anyhow::ensure!(layer_weights.len() == outputs, "{} rows of weights for the {outputs} outputs of dense layer {l}", layer_weights.len());
+          let activation = if l == 2 { Activation::Identity } else { activation };
+          classifier.push(dense_layer(&mut builder, sizes[l], layer_weights, biases, activation)?);
+      }
+      let y_witnesses = y.iter().map(|v| builder.witness(*v)).collect();
+
+      Ok(LeNet5Circuit {
+          image: image_witnesses,
+          conv1,
+          conv2,
+          classifier,
+          y: y_witnesses,
+          scale_lookup: LookupTable::new(|x| x * F::from(SCALE_FACTOR)),
+          activations: Activations::new(bits, granularity_bits),
+      })
+  }
+
+  /// Creates an [AttentionCircuit] on the tokens `x`, with the projections `w_q`, `w_k` and `w_v`
+  /// (a row of `d_head` weights for each of the `d_model` features) and the expected output `y`.
+  pub fn create_attention_circuit<F: Field>(
+      x: &[Vec<F>],
+      (w_q, w_k, w_v): (&[Vec<F>], &[Vec<F>], &[Vec<F>]),
+      y: &[Vec<F>],
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<AttentionCircuit<F>> {
+      let d_model = x.first().map_or(0, |token| token.len());
+      let d_head = w_q.first().map_or(0, |row| row.len());
+      anyhow::ensure!(x.iter().all(|token| token.len() == d_model), "the tokens do not all have {d_model} features");
+      for w in [w_q, w_k, w_v] {
+          anyhow::ensure!(
+              w.len() == d_model && w.iter().all(|row| row.len() == d_head),
+              "a projection is not {d_model}x{d_head}"
+          );
+      }
+      anyhow::ensure!(
+          y.len() == x.len() && y.iter().all(|row| row.len() == d_head),
+          "the output is not {}x{d_head}",
+          x.len()
+      );
+
+      let mut builder = CircuitBuilder::new();
+      let mut witnesses = |matrix: &[Vec<F>]| -> Vec<Vec<Witness<F>>> {
+          matrix
+              .iter()
+              .map(|row| row.iter().map(|v| builder.witness(*v)).collect())
+              .collect()
+      };
+      Ok(AttentionCircuit {
+          x: witnesses(x),
+          w_q: witnesses(w_q),
+          w_k: witnesses(w_k),
+          w_v: witnesses(w_v),
+          y: witnesses(y),
+          bits,
+          granularity_bits,
+          scale_lookup: LookupTable::new(|x| x * F::from(SCALE_FACTOR)),
+          exp_table: exp_table(granularity_bits),
+      })
+  }
+
+  /// The parameters of an [EncoderBlockCircuit] of `d_model` features.
+  pub struct EncoderBlockWeights<F: Field> {
+      pub heads: usize,
+      /// The `gamma` and `beta` of the normalizations before the attention and before the MLP.
+      pub norm1: (Vec<F>, Vec<F>),
+      pub norm2: (Vec<F>, Vec<F>),
+      /// The `eps` of the normalizations.
+      pub eps: f64,
+      /// The projections `w_q`, `w_k`, `w_v` and `w_o`, each a row of `d_model` weights for each of the `d_model` features.
+      pub attention: [Vec<Vec<F>>; 4],
+      /// The weights (a row per output) and biases of the hidden and output layers of the MLP.
+      pub mlp: [(Vec<Vec<F>>, Vec<F>); 2],
+  }
+
+  /// Creates an [EncoderBlockCircuit] on the tokens `x`, with the expected output `y`.
+  /// The model dimension is the number of features of the tokens, and must be a multiple of the number of heads.
+  pub fn create_encoder_block_circuit<F: Field>(
+      x: &[Vec<F>],
+      weights: &EncoderBlockWeights<F>,
+      y: &[Vec<F>],
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<EncoderBlockCircuit<F>> {
+      let d_model = x.first().map_or(0, |token| token.len());
+      let heads = weights.heads;
+      anyhow::ensure!(x.iter().all(|token| token.len() == d_model), "the tokens do not all have {d_model} features");
+      anyhow::ensure!(heads > 0 && d_model % heads == 0, "{heads} heads for a model dimension of {d_model}");
+      for w in &weights.attention {
+          anyhow::ensure!(
+              w.len() == d_model && w.iter().all(|row| row.len() == d_model),
+              "a projection is not {d_model}x{d_model}"
+          );
+      }
+      anyhow::ensure!(
+          y.len() == x.len() && y.iter().all(|token| token.len() == d_model),
+          "the output is not {}x{d_model}",
+          x.len()
+      );
+      let eps: F = to_fixed(weights.eps, (SCALE_FACTOR * SCALE_FACTOR) as f64);
+      anyhow::ensure!(eps != F::from(0u64), "the eps of the normalizations rounds to zero");
+
+      let mut builder = CircuitBuilder::new();
+      let mut norm = |(gamma, beta): &(Vec<F>, Vec<F>)| -> anyhow::Result<LayerNorm<F>> {
+          anyhow::ensure!(
+              gamma.len() == d_model && beta.len() == d_model,
+              "a normalization of {} parameters for {d_model} features",
+              gamma.len()
+          );
+          Ok(LayerNorm {
+              gamma: gamma.iter().map(|v| builder.witness(*v)).collect(),
+              beta: beta.iter().map(|v| builder.witness(*v)).collect(),
+              eps,
+          })
+      };
+      let (norm1, norm2) = (norm(&weights.norm1)?, norm(&weights.norm2)?);
+
+      let witnesses = |builder: &mut CircuitBuilder<F>, matrix: &[Vec<F>]| -> Vec<Vec<Witness<F>>> {
+          matrix
+              .iter()
+              .map(|row| row.iter().map(|v| builder.witness(*v)).collect())
+              .collect()
+      };
+      let x_witnesses = witnesses(&mut builder, x);
+      let [w_q, w_k, w_v, w_o] = weights.attention.each_ref().map(|w| witnesses(&mut builder, w));
+
+      let [(hidden_weights, hidden_biases), (output_weights, output_biases)] = &weights.mlp;
+      anyhow::ensure!(output_weights.len() == d_model, "{} rows of weights for the {d_model} outputs of the MLP", output_weights.len());
+      let mlp = vec![
+          dense_layer(&mut builder, d_model, hidden_weights, hidden_biases, Activation::Gelu)?,
+          dense_layer(&mut builder, hidden_weights.len(), output_weights, output_biases, Activation::Identity)?,
+      ];
+      let y_witnesses = witnesses(&mut builder, y);
+
+      Ok(EncoderBlockCircuit {
+          x: x_witnesses,
+          heads,
+          norm1,
+          w_q,
+          w_k,
+          w_v,
+          w_o,
+          norm2,
+          mlp,
+          y: y_witnesses,
+          scale_lookup: LookupTable::new(|x| x * F::from(SCALE_FACTOR)),
+          exp_table: exp_table(granularity_bits),
+          activations: Activations::new(bits, granularity_bits),
+      })
+  }
+
+  /// Creates an [LstmCircuit] on a sequence of tokens of `n` features, with the parameters `(w, u, b)` of an [LstmCell]
+  /// of `hidden` units and the expected last hidden state `y`.
+  pub fn create_lstm_circuit<F: Field>(
+      sequence: &[Vec<F>],
+      (w, u, b): (&[Vec<F>], &[Vec<F>], &[F]),
+      y: &[F],
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<LstmCircuit<F>> {
+      anyhow::ensure!(!sequence.is_empty(), "an empty sequence");
+      let n = sequence[0].len();
+      let hidden = y.len();
+      anyhow::ensure!(sequence.iter().all(|x| x.len() == n), "the tokens do not all have {n} features");
+      anyhow::ensure!(b.len() == 4 * hidden, "{} biases for the {} gate units of {hidden} hidden units", b.len(), 4 * hidden);
+      anyhow::ensure!(
+          w.len() == 4 * hidden && w.iter().all(|row| row.len() == n),
+          "the input weights are not {}x{n}",
+          4 * hidden
+      );
+      anyhow::ensure!(
+          u.len() == 4 * hidden && u.iter().all(|row| row.len() == hidden),
+          "the hidden weights are not {}x{hidden}",
+          4 * hidden
+      );
+
+      let mut builder = CircuitBuilder::new();
+      let mut witnesses = |matrix: &[Vec<F>]| -> Vec<Vec<Witness<F>>> {
+          matrix
+              .iter()
+              .map(|row| row.iter().map(|v| builder.witness(*v)).collect())
+              .collect()
+      };
+      let sequence_witnesses = witnesses(sequence);
+      let cell = LstmCell {
+          w: witnesses(w),
+          u: witnesses(u),
+          b: witnesses(&[b.to_vec()]).remove(0),
+      };
+      let y_witnesses = witnesses(&[y.to_vec()]).remove(0);
+
+      Ok(LstmCircuit {
+          sequence: sequence_witnesses,
+          cell,
+          y: y_witnesses,
+          scale_lookup: LookupTable::new(|x| x * F::from(SCALE_FACTOR)),
+          activations: Activations::new(bits, granularity_bits),
+      })
+  }
+
+  /// Creates a [GruCircuit] on a sequence of tokens of `n` features, with the parameters `(w, u, b)` of a [GruCell]
+  /// of `hidden` units and the expected last hidden state `y`, to compare with the [LstmCircuit] of the same shape.
+  pub fn create_gru_circuit<F: Field>(
+      sequence: &[Vec<F>],
+      (w, u, b): (&[Vec<F>], &[Vec<F>], &[F]),
+      y: &[F],
+      bits: usize,
+      granularity_bits: usize,
+  ) -> anyhow::Result<GruCircuit<F>> {
+      anyhow::ensure!(!sequence.is_empty(), "an empty sequence");
+      let n = sequence[0].len();
+      let hidden = y.len();
+      anyhow::ensure!(sequence.iter().all(|x| x.len() == n), "the tokens do not all have {n} features");
+      anyhow::ensure!(b.len() == 3 * hidden, "{} biases for the {} gate units of {hidden} hidden units", b.len(), 3 * hidden);
+      anyhow::ensure!(
+          w.len() == 3 * hidden && w.iter().all(|row| row.len() == n),
+          "the input weights are not {}x{n}",
+          3 * hidden
+      );
+      anyhow::ensure!(
+          u.len() == 3 * hidden && u.iter().all(|row| row.len() == hidden),
+          "the hidden weights are not {}x{hidden}",
+          3 * hidden
+      );
+
+      let mut builder = CircuitBuilder::new();
+      let mut witnesses = |matrix: &[Vec<F>]| -> Vec<Vec<Witness<F>>> {
+          matrix
+              .iter()
+              .map(|row| row.iter().map(|v| builder.witness(*v)).collect())
+              .collect()
+      };
+      let sequence_witnesses = witnesses(sequence);
+      let cell = GruCell {
+          w: witnesses(w),
+          u: witnesses(u),
+          b: witnesses(&[b.to_vec()]).remove(0),
+      };
+      let y_witnesses = witnesses(&[y.to_vec()]).remove(0);
+
+      Ok(GruCircuit {
+          sequence: sequence_witnesses,
+          cell,
+          y: y_witnesses,
+          scale_lookup: LookupTable::new(|x| x * F::from(SCALE_FACTOR)),
+          activations: Activations::new(bits, granularity_bits),
+      })
+  }
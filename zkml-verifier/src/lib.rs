@@ -0,0 +1,71 @@
+//! The verifier of the benchmark proofs, for applications that only need to check them.
+//!
+//! This crate depends on `kimchi` without its `prover` feature,
+//! so that the prover, the snarky frontend and the benchmark harness are not built.
+//! It exposes the verifier keys, the proof types and the verification functions,
+//! along with the types of the Pasta curves on which the benchmark proofs are created.
+//!
+//! ```ignore
+//! let index = zkml_verifier::load_verifier_index(srs, path)?;
+//! zkml_verifier::verify_proof(&index, &proof, &public_input)?;
+//! ```
+
+use std::{path::Path, sync::Arc};
+
+pub use kimchi::{
+    curve::KimchiCurve,
+    error::VerifyError,
+    groupmap, mina_curves, mina_poseidon, poly_commitment,
+    proof::ProverProof,
+    proof_card::ProofCard,
+    verifier::{batch_verify, verify, Context},
+    verifier_index::VerifierIndex,
+};
+
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof, srs::SRS};
+
+/// The sponge over the base field used by the benchmark proofs.
+pub type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+
+/// The sponge over the scalar field used by the benchmark proofs.
+pub type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+/// A benchmark proof.
+pub type Proof = ProverProof<Vesta, OpeningProof<Vesta>>;
+
+/// The verifier key of a benchmark circuit.
+pub type VerifierKey = VerifierIndex<Vesta, OpeningProof<Vesta>>;
+
+/// Loads a verifier key written with [VerifierIndex::to_file].
+///
+/// # Errors
+///
+/// Will give error if the file cannot be read or deserialized.
+pub fn load_verifier_index(srs: Arc<SRS<Vesta>>, path: &Path) -> Result<VerifierKey, String> {
+    VerifierKey::from_file(srs, path, None, *Vesta::other_curve_endo())
+}
+
+/// Verifies a benchmark proof against its public input.
+///
+/// # Errors
+///
+/// Will give error if the proof does not verify.
+pub fn verify_proof(
+    verifier_index: &VerifierKey,
+    proof: &Proof,
+    public_input: &[Fp],
+) -> Result<(), VerifyError> {
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        verifier_index,
+        proof,
+        public_input,
+    )
+}